@@ -0,0 +1,11 @@
+//! Compiles the engine's small amount of C: the Lua hook that aborts scripts which run past their
+//! budget (see `scripting::budget`).
+
+extern crate cc;
+
+fn main() {
+    println!("cargo:rerun-if-changed=src/scripting/budget_hook.c");
+    cc::Build::new()
+        .file("src/scripting/budget_hook.c")
+        .compile("budget_hook");
+}
@@ -0,0 +1,15 @@
+-- Character Info player/info.lua
+
+-- The player's character. STATS are their stats at level 1, and GROWTH how
+-- some of them rise from level to level; see src/game_state/growth.rs.
+NAME = "Hero"
+STATS = { max_hp = 40, max_mp = 12, attack = 12, defense = 6, magic = 8, speed = 11 }
+SKILLS = { "fire", "cure" }
+MAX_LEVEL = 30
+EXP_CURVE = "10 * (level - 1) ^ 2"
+GROWTH = {
+    max_hp = "40 + (level - 1) * 7",
+    max_mp = "12 + (level - 1) * 2",
+    attack = "12 + math.floor((level - 1) * 1.5)",
+    defense = { 6, 7, 8, 8, 9, 10, 11, 11, 12, 13 },
+}
@@ -0,0 +1,72 @@
+-- Database items.lua
+
+-- The items the party can carry, by the names scripts refer to them by. kind
+-- is one of "consumable", "key", "weapon" or "armor"; armor is worn in a slot:
+-- "shield", "head", "body" or "accessory". bonus is added to the stats of
+-- whoever wears the item. price is what shops charge for the item; they pay
+-- half that for it, and nothing for key items or items without a price.
+ITEMS = {
+    potion = {
+        name = "Potion",
+        description = "Restores 50 HP.",
+        kind = "consumable",
+        hp = 50,
+        price = 40,
+    },
+    ether = {
+        name = "Ether",
+        description = "Restores 20 MP.",
+        kind = "consumable",
+        mp = 20,
+        price = 120,
+    },
+    antidote = {
+        name = "Antidote",
+        description = "Cures poison.",
+        kind = "consumable",
+        remove_states = { "poison" },
+        price = 20,
+    },
+    old_key = {
+        name = "Old Key",
+        description = "A rusted key. It must open something.",
+        kind = "key",
+        stack_limit = 1,
+    },
+    bronze_sword = {
+        name = "Bronze Sword",
+        description = "A plain, dependable blade.",
+        kind = "weapon",
+        bonus = { attack = 5 },
+        price = 100,
+    },
+    wooden_shield = {
+        name = "Wooden Shield",
+        kind = "armor",
+        slot = "shield",
+        bonus = { defense = 2 },
+        price = 60,
+    },
+    leather_cap = {
+        name = "Leather Cap",
+        kind = "armor",
+        slot = "head",
+        bonus = { defense = 1 },
+        price = 40,
+    },
+    traveler_coat = {
+        name = "Traveler's Coat",
+        kind = "armor",
+        slot = "body",
+        bonus = { defense = 3, max_hp = 10 },
+        price = 150,
+    },
+    swift_ring = {
+        name = "Swift Ring",
+        description = "Light on the finger, and lighter on the feet.",
+        kind = "armor",
+        slot = "accessory",
+        bonus = { speed = 4, defense = -1 },
+        price = 500,
+    },
+}
@@ -0,0 +1,30 @@
+-- Database skills.lua
+
+-- The skills combatants can use, by the names characters' SKILLS lists refer to. Formulas are
+-- Lua expressions, where a is the user and b is the target.
+SKILLS = {
+    fire = {
+        name = "Fire",
+        description = "Burns an enemy with magic fire.",
+        mp_cost = 4,
+        effect = "damage",
+        formula = "a.magic * 3 - b.defense",
+        add_states = { "burn" },
+    },
+    cure = {
+        name = "Cure",
+        description = "Restores an ally's HP.",
+        mp_cost = 3,
+        effect = "heal",
+        formula = "a.magic * 2 + 10",
+        remove_states = { "burn", "poison" },
+    },
+    sleep = {
+        name = "Sleep",
+        description = "Puts an enemy to sleep.",
+        mp_cost = 5,
+        effect = "damage",
+        formula = "0",
+        add_states = { "sleep" },
+    },
+}
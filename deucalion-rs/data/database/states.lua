@@ -0,0 +1,9 @@
+-- Database states.lua
+
+-- The status effects skills can inflict. Turns count the sufferer's turns; 0 lasts until the
+-- battle is over. damage_per_turn is a formula, where both a and b are the sufferer.
+STATES = {
+    burn = { name = "Burn", turns = 3, damage_per_turn = "b.max_hp / 20" },
+    poison = { name = "Poison", turns = 0, damage_per_turn = "b.max_hp / 10" },
+    sleep = { name = "Sleep", turns = 2, skip_turn = true, modifiers = { defense = 0.5 } },
+}
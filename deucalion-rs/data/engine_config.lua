@@ -2,7 +2,36 @@
 
 --- This file holds all configuration that is not set by the developer, such as
 --	resolution, framerate, savegame directory
+--  Any value left out here takes its default.
 SCREEN_WIDTH = 640
 SCREEN_HEIGHT = 480
 
 MAXIMUM_FRAMERATE = 60
+
+-- One of "windowed", "fullscreen" or "borderless"
+WINDOW_MODE = "windowed"
+VSYNC = true
+
+-- The resolution the game is drawn at, before being scaled to the window
+RENDER_WIDTH = 640
+RENDER_HEIGHT = 480
+-- One of "integer" (pixel-perfect), "letterbox" or "stretch"
+SCALING_MODE = "integer"
+
+-- Volumes, from 0 to 100
+MASTER_VOLUME = 100
+MUSIC_VOLUME = 80
+SFX_VOLUME = 80
+
+LANGUAGE = "en"
+
+-- The keys bound to each action. Key names are SFML's, e.g. "A", "Num1", "Return"
+KEY_BINDINGS = {
+	up = { "Up", "W" },
+	down = { "Down", "S" },
+	left = { "Left", "A" },
+	right = { "Right", "D" },
+	confirm = { "Z", "Return", "Space" },
+	cancel = { "X", "Escape" },
+	menu = { "C", "Tab" },
+}
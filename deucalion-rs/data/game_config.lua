@@ -5,3 +5,63 @@
 
 TITLE = "Deucalion Engine"
 STARTING_MAP = "map001"
+
+-- A new game starts on STARTING_MAP with the player's character, followed by
+-- the characters in STARTING_PARTY, carrying STARTING_GOLD. on_new_game, in
+-- init.lua, can set up the rest.
+STARTING_PARTY = {}
+STARTING_GOLD = 0
+
+-- The title screen's commands, in order: any of "new_game", "continue",
+-- "options" and "quit". Its background is a picture in data/pictures, and its
+-- music is in data/music; leave them out for a plain, silent title screen.
+TITLE_COMMANDS = { "new_game", "continue", "options", "quit" }
+-- TITLE_BACKGROUND = "title"
+-- TITLE_MUSIC = "title"
+
+-- The game over screen, shown when the party loses a battle that can't be
+-- lost, has a picture and music of its own in the same way.
+-- GAME_OVER_BACKGROUND = "game_over"
+-- GAME_OVER_MUSIC = "game_over"
+
+-- The Lua standard libraries game scripts may use. Leaving this out allows
+-- everything except "io" and "debug", which give scripts access to the
+-- player's computer; only add them if you trust every script in the game.
+SCRIPT_LIBRARIES = { "base", "coroutine", "table", "string", "math", "bit32", "os" }
+
+-- How long a single script (such as an event) may run before it is aborted, so
+-- that a mistake like an endless loop doesn't freeze the game. 0 means no limit.
+SCRIPT_INSTRUCTION_LIMIT = 10000000
+-- In milliseconds
+SCRIPT_TIME_LIMIT = 1000
+
+-- Developer mode shows script errors on screen. Turn it off before release.
+DEVELOPER_MODE = true
+
+-- Directories, relative to the data directory, where mods keep their script
+-- modules. These are searched before data/scripts when a script uses require.
+-- They can't be absolute or use '..' to leave the data directory.
+MOD_ROOTS = {}
+
+-- How characters move around maps: "grid" steps a tile at a time, "free"
+-- moves by the pixel and follows analog sticks. Maps can override this, and
+-- DIAGONAL_MOVEMENT, with the custom properties movement_mode and
+-- diagonal_movement.
+MOVEMENT_MODE = "grid"
+DIAGONAL_MOVEMENT = false
+
+-- How turns come around in battles: "turns" has everyone act once a round,
+-- fastest first; "atb" (active time) has everyone act whenever their gauge
+-- fills, which it does faster the faster they are.
+BATTLE_MODE = "turns"
+
+-- Random encounters are rolled from this seed, so that a playtest can be
+-- repeated exactly. 0 rolls different encounters every time.
+ENCOUNTER_SEED = 0
+
+-- How many slots the player can save the game in from the menu.
+SAVE_SLOTS = 3
+
+-- The windowskin, in data/windowskins, that the UI's windows are drawn with.
+-- Leave it out for plain windows.
+WINDOWSKIN = "default"
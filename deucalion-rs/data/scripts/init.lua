@@ -0,0 +1,38 @@
+-- Game Entry Point init.lua
+
+-- This script is run once when the game starts, before the first map is
+-- loaded. Modules in data/scripts can be loaded with require; for example,
+-- require("battle.formulas") runs data/scripts/battle/formulas.lua and
+-- returns whatever it returns.
+
+-- on_new_game, if it's defined, is called when the player starts a new game,
+-- once the starting party has joined, to set up the rest of the game: giving
+-- the party items, moving the player, and so on.
+function on_new_game()
+end
+
+-- on_battle_end, if it's defined, is called after every battle with the name
+-- of the troop fought and how it went: "victory", "defeat" or "escaped". A
+-- defeat is followed by the game over screen unless the battle was started
+-- with start_battle(troop, can_flee, true).
+function on_battle_end(troop, outcome)
+end
+
+-- on_encounter, if it's defined, is called for every step the player takes in
+-- an encounter region, with the region's name and whether an encounter is due.
+-- Return false to prevent one, true to have one straight away, or the name of
+-- a troop to fight it straight away; return nothing to leave it to chance.
+function on_encounter(region, due)
+end
+
+-- on_level_up, if it's defined, is called whenever a party member reaches a
+-- new level, with their character's name and the level.
+function on_level_up(character, level)
+end
+
+-- on_main_menu, if it's defined, is called each time the main menu opens, with
+-- the names of its commands. Return the list of commands to show: those names,
+-- in any order, and tables like { label = "Quests", call = "show_quests" } for
+-- commands that call a function of this script's. Return nothing to keep them.
+function on_main_menu(commands)
+end
@@ -0,0 +1,7 @@
+-- Troop slimes.lua
+
+-- A pair of slimes, for trying out battles with start_battle("slimes").
+ENEMIES = {
+    { name = "Slime", stats = { max_hp = 18, attack = 7, defense = 2, speed = 6 }, exp = 6 },
+    { name = "Slime", stats = { max_hp = 18, attack = 7, defense = 2, speed = 6 }, exp = 6 },
+}
@@ -0,0 +1,6 @@
+-- Windowskin Info default/info.lua
+
+-- The image windows are drawn with, cut into nine slices BORDER pixels in from
+-- each edge; see src/ui/skin.rs.
+IMAGE = "skin.png"
+BORDER = 8
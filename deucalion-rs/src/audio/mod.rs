@@ -0,0 +1,102 @@
+//! Sound and music playback, and the player's volume settings.
+
+use sfml::audio::{listener, SoundSource};
+
+use config::engine_config::EngineConfig;
+use error::DeucalionError;
+use resource::music::LoadedMusic;
+
+/// Keeps track of the configured volumes. The master volume is applied globally; music and sound
+/// effect volumes are applied by whoever plays a sound, using `music_volume` and `sfx_volume`.
+#[derive(Debug, Clone)]
+pub struct Mixer {
+    master_volume: u32,
+    music_volume: u32,
+    sfx_volume: u32,
+}
+
+impl Mixer {
+    /// Create a mixer with the configured volumes, and apply the master volume.
+    pub fn new(config: &EngineConfig) -> Mixer {
+        let mut mixer = Mixer {
+            master_volume: 0,
+            music_volume: 0,
+            sfx_volume: 0,
+        };
+        mixer.apply_config(config);
+        mixer
+    }
+
+    /// Take on the volumes from a (possibly changed) configuration.
+    pub fn apply_config(&mut self, config: &EngineConfig) {
+        self.master_volume = config.master_volume;
+        self.music_volume = config.music_volume;
+        self.sfx_volume = config.sfx_volume;
+        listener::set_global_volume(self.master_volume as f32);
+        debug!(
+            "Volumes set to master {}, music {}, sound effects {}",
+            self.master_volume, self.music_volume, self.sfx_volume
+        );
+    }
+
+    /// The volume, as SFML expects it, to play music at.
+    pub fn music_volume(&self) -> f32 {
+        self.music_volume as f32
+    }
+
+    /// The volume, as SFML expects it, to play sound effects at.
+    pub fn sfx_volume(&self) -> f32 {
+        self.sfx_volume as f32
+    }
+}
+
+/// Plays one piece of music at a time, on a loop, at the mixer's music volume.
+pub struct MusicPlayer {
+    // The music playing, with its name.
+    playing: Option<(String, LoadedMusic)>,
+}
+
+impl MusicPlayer {
+    /// Create a player with nothing playing.
+    pub fn new() -> MusicPlayer {
+        MusicPlayer { playing: None }
+    }
+
+    /// The name of the music playing, if any.
+    pub fn playing(&self) -> Option<&str> {
+        self.playing.as_ref().map(|&(ref name, _)| name.as_str())
+    }
+
+    /// Play a piece of music, by name, on a loop, in place of whatever was playing. If it's
+    /// already playing, it carries on rather than starting over. An empty name stops the music.
+    pub fn play(&mut self, name: &str, mixer: &Mixer) -> Result<(), DeucalionError> {
+        if name.is_empty() {
+            self.stop();
+            return Ok(());
+        }
+        if self.playing() == Some(name) {
+            return Ok(());
+        }
+        self.stop();
+        let mut loaded = LoadedMusic::by_name(name)?;
+        loaded.music.set_looping(true);
+        loaded.music.set_volume(mixer.music_volume());
+        loaded.music.play();
+        self.playing = Some((String::from(name), loaded));
+        Ok(())
+    }
+
+    /// Stop the music, if any is playing.
+    pub fn stop(&mut self) {
+        if let Some((_, mut loaded)) = self.playing.take() {
+            loaded.music.stop();
+        }
+    }
+
+    /// Take on a (possibly changed) music volume.
+    pub fn apply_volume(&mut self, mixer: &Mixer) {
+        if let Some((_, ref mut loaded)) = self.playing {
+            loaded.music.set_volume(mixer.music_volume());
+        }
+    }
+}
@@ -0,0 +1,248 @@
+//! The characters and enemies taking part in a battle, and the stats they fight with.
+
+use hlua::AnyLuaValue;
+
+use config::schema::ConfigValue;
+use scripting::convert::get_table_field;
+
+/// The numbers a character or enemy fights with.
+///
+/// In Lua, stats are a table with any of the fields `max_hp`, `max_mp`, `attack`, `defense`,
+/// `magic` and `speed`; missing fields keep their defaults.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct Stats {
+    pub max_hp: u32,
+    pub max_mp: u32,
+    /// How hard physical attacks hit.
+    pub attack: u32,
+    /// How much damage is taken away from hits.
+    pub defense: u32,
+    /// How strong skills are.
+    pub magic: u32,
+    /// How soon turns come around: the fastest acts first each round, and ATB gauges fill in
+    /// proportion to it.
+    pub speed: u32,
+}
+
+impl Default for Stats {
+    fn default() -> Stats {
+        Stats {
+            max_hp: 30,
+            max_mp: 10,
+            attack: 10,
+            defense: 5,
+            magic: 5,
+            speed: 10,
+        }
+    }
+}
+
+impl ConfigValue for Stats {
+    fn type_name() -> String {
+        String::from(
+            "stats (a table with any of max_hp, max_mp, attack, defense, magic and speed)",
+        )
+    }
+
+    fn from_lua(value: AnyLuaValue) -> Option<Stats> {
+        match value {
+            AnyLuaValue::LuaArray(_) => {}
+            _ => return None,
+        }
+        // Missing fields keep their defaults, but a field of the wrong type spoils the table.
+        let stat = |name: &str, default: u32| match get_table_field(&value, name) {
+            AnyLuaValue::LuaNil => Some(default),
+            field => u32::from_lua(field),
+        };
+        let defaults = Stats::default();
+        let stats = Stats {
+            max_hp: stat("max_hp", defaults.max_hp)?,
+            max_mp: stat("max_mp", defaults.max_mp)?,
+            attack: stat("attack", defaults.attack)?,
+            defense: stat("defense", defaults.defense)?,
+            magic: stat("magic", defaults.magic)?,
+            speed: stat("speed", defaults.speed)?,
+        };
+        if stats.max_hp == 0 {
+            return None;
+        }
+        Some(stats)
+    }
+}
+
+impl Stats {
+    /// The stats with a bonus added, but none below zero, and a max HP of at least 1.
+    pub fn with_bonus(self, bonus: &StatBonus) -> Stats {
+        let add = |stat: u32, bonus: i32| (i64::from(stat) + i64::from(bonus)).max(0) as u32;
+        Stats {
+            max_hp: add(self.max_hp, bonus.max_hp).max(1),
+            max_mp: add(self.max_mp, bonus.max_mp),
+            attack: add(self.attack, bonus.attack),
+            defense: add(self.defense, bonus.defense),
+            magic: add(self.magic, bonus.magic),
+            speed: add(self.speed, bonus.speed),
+        }
+    }
+
+    /// How much each stat goes up, or down, from these stats to `other`.
+    pub fn difference(self, other: Stats) -> StatBonus {
+        let difference = |from: u32, to: u32| {
+            let difference = i64::from(to) - i64::from(from);
+            difference.max(i64::from(i32::min_value())).min(i64::from(i32::max_value())) as i32
+        };
+        StatBonus {
+            max_hp: difference(self.max_hp, other.max_hp),
+            max_mp: difference(self.max_mp, other.max_mp),
+            attack: difference(self.attack, other.attack),
+            defense: difference(self.defense, other.defense),
+            magic: difference(self.magic, other.magic),
+            speed: difference(self.speed, other.speed),
+        }
+    }
+}
+
+/// Amounts added to stats, or taken away, such as by equipment.
+///
+/// In Lua, a bonus is a table with any of the fields of `Stats`, each a whole number, which may
+/// be negative; missing fields are 0.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+pub struct StatBonus {
+    pub max_hp: i32,
+    pub max_mp: i32,
+    pub attack: i32,
+    pub defense: i32,
+    pub magic: i32,
+    pub speed: i32,
+}
+
+impl ConfigValue for StatBonus {
+    fn type_name() -> String {
+        String::from(
+            "stat bonus (a table with any of max_hp, max_mp, attack, defense, magic and speed)",
+        )
+    }
+
+    fn from_lua(value: AnyLuaValue) -> Option<StatBonus> {
+        match value {
+            AnyLuaValue::LuaArray(_) => {}
+            _ => return None,
+        }
+        let bonus = |name: &str| match get_table_field(&value, name) {
+            AnyLuaValue::LuaNil => Some(0),
+            field => i32::from_lua(field),
+        };
+        Some(StatBonus {
+            max_hp: bonus("max_hp")?,
+            max_mp: bonus("max_mp")?,
+            attack: bonus("attack")?,
+            defense: bonus("defense")?,
+            magic: bonus("magic")?,
+            speed: bonus("speed")?,
+        })
+    }
+}
+
+/// Which side of a battle a combatant is on.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum Side {
+    /// The player's party.
+    Party,
+    /// The enemies.
+    Troop,
+}
+
+impl Side {
+    /// The side fighting this one.
+    pub fn opponent(self) -> Side {
+        match self {
+            Side::Party => Side::Troop,
+            Side::Troop => Side::Party,
+        }
+    }
+}
+
+/// A status effect a combatant is suffering.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ActiveState {
+    /// The status effect's name in the database.
+    pub id: String,
+    /// How many more of the sufferer's turns it lasts for, or None if it lasts until the battle
+    /// is over.
+    pub turns_left: Option<u32>,
+}
+
+/// A character or enemy in a battle.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Combatant {
+    /// The name shown to the player.
+    pub name: String,
+    pub side: Side,
+    pub stats: Stats,
+    pub hp: u32,
+    pub mp: u32,
+    /// The skills the combatant can use, by name.
+    pub skills: Vec<String>,
+    /// Whether the combatant is guarding, which halves the damage they take until their next
+    /// turn.
+    pub guarding: bool,
+    /// In ATB mode, how full their gauge is, from 0 to 1; they act when it's full.
+    pub gauge: f32,
+    /// The status effects the combatant is suffering, in the order they were inflicted.
+    pub states: Vec<ActiveState>,
+    /// For an enemy, the EXP each surviving party member earns when the party wins.
+    pub exp: u32,
+}
+
+impl Combatant {
+    /// Create a combatant at full HP and MP.
+    pub fn new(name: &str, side: Side, stats: Stats) -> Combatant {
+        Combatant {
+            name: String::from(name),
+            side: side,
+            stats: stats,
+            hp: stats.max_hp,
+            mp: stats.max_mp,
+            skills: Vec::new(),
+            guarding: false,
+            gauge: 0.0,
+            states: Vec::new(),
+            exp: 0,
+        }
+    }
+
+    /// Give the combatant skills to use, by name.
+    pub fn with_skills(mut self, skills: Vec<String>) -> Combatant {
+        self.skills = skills;
+        self
+    }
+
+    /// Give an enemy EXP to reward the party with for winning.
+    pub fn with_exp(mut self, exp: u32) -> Combatant {
+        self.exp = exp;
+        self
+    }
+
+    /// Whether the combatant can still fight.
+    pub fn is_alive(&self) -> bool {
+        self.hp > 0
+    }
+
+    /// Whether the combatant is suffering a status effect, by its name in the database.
+    pub fn has_state(&self, id: &str) -> bool {
+        self.states.iter().any(|state| state.id == id)
+    }
+
+    /// Take away HP, but not below zero. Returns how much was taken.
+    pub fn take_damage(&mut self, amount: u32) -> u32 {
+        let taken = amount.min(self.hp);
+        self.hp -= taken;
+        taken
+    }
+
+    /// Give back HP, but not above the maximum. Returns how much was given.
+    pub fn heal(&mut self, amount: u32) -> u32 {
+        let healed = amount.min(self.stats.max_hp - self.hp);
+        self.hp += healed;
+        healed
+    }
+}
@@ -0,0 +1,690 @@
+//! The database: the skills combatants use, the status effects they suffer and the items the
+//! party carries, defined by the game in Lua rather than in the engine.
+//!
+//! Skills are read from the `SKILLS` table of data/database/skills.lua, keyed by the name that
+//! characters' `SKILLS` lists know them by, e.g.:
+//!
+//! ```lua
+//! SKILLS = {
+//!     fire = {
+//!         name = "Fire",
+//!         mp_cost = 4,
+//!         effect = "damage",
+//!         formula = "a.magic * 3 - b.defense",
+//!         add_states = { "burn" },
+//!     },
+//! }
+//! ```
+//!
+//! Status effects are read from the `STATES` table of data/database/states.lua in the same way,
+//! e.g. `burn = { name = "Burn", turns = 3, damage_per_turn = "b.max_hp / 10" }`. Formulas are
+//! Lua expressions; see `battle::formula::FormulaEnvironment` for what they're given.
+//!
+//! Items are read from the `ITEMS` table of data/database/items.lua, e.g.
+//! `potion = { name = "Potion", kind = "consumable", hp = 50, price = 40 }` or
+//! `cap = { name = "Leather Cap", kind = "armor", slot = "head", bonus = { defense = 2 } }`.
+//!
+//! The whole database is checked when it's loaded, including that every formula runs and that
+//! skills and items only refer to status effects that exist. An entry with a problem is left
+//! out, and each of its problems is reported as a `DeucalionError::DatabaseError` naming the
+//! entry, e.g. "skills.fire". Any of the files may be left out by a game that doesn't need it.
+
+use std::collections::BTreeMap;
+
+use hlua::{AnyLuaValue, Lua};
+
+use battle::combatant::{Combatant, Side, StatBonus, Stats};
+use battle::formula::{FormulaEnvironment, VARIANCE};
+use config::schema::{ConfigValue, Field, Schema};
+use error::DeucalionError;
+use resource::loading;
+use resource::ResourceKind;
+use scripting::budget::ScriptBudget;
+use scripting::convert::get_table_field;
+use scripting::execute_script_from_reader;
+use scripting::modules::get_module_roots;
+use scripting::sandbox::{get_default_script_libraries, get_sandboxed_environment};
+
+/// The database table, and the global in it, that skills are read from.
+pub const SKILLS_TABLE: (&'static str, &'static str) = ("skills", "SKILLS");
+
+/// The database table, and the global in it, that status effects are read from.
+pub const STATES_TABLE: (&'static str, &'static str) = ("states", "STATES");
+
+/// The database table, and the global in it, that items are read from.
+pub const ITEMS_TABLE: (&'static str, &'static str) = ("items", "ITEMS");
+
+/// The most of an item the party can carry at once, unless the item says otherwise.
+pub const DEFAULT_STACK_LIMIT: u32 = 99;
+
+/// What a skill does to its target.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum EffectKind {
+    /// Take the formula's result from an opponent's HP.
+    Damage,
+    /// Give the formula's result back to an ally's HP.
+    Heal,
+}
+
+impl EffectKind {
+    /// Parse an effect from the name used for it in the database.
+    pub fn from_name(name: &str) -> Option<EffectKind> {
+        match name {
+            "damage" => Some(EffectKind::Damage),
+            "heal" => Some(EffectKind::Heal),
+            _ => None,
+        }
+    }
+
+    /// The side a user of the effect may target: their opponents for damage, their own for
+    /// healing.
+    pub fn target_side(self, user: Side) -> Side {
+        match self {
+            EffectKind::Damage => user.opponent(),
+            EffectKind::Heal => user,
+        }
+    }
+}
+
+impl ConfigValue for EffectKind {
+    fn type_name() -> String {
+        String::from("one of damage or heal")
+    }
+
+    fn from_lua(value: AnyLuaValue) -> Option<EffectKind> {
+        String::from_lua(value).and_then(|name| EffectKind::from_name(&name))
+    }
+}
+
+/// Something a combatant knows how to do, for a cost in MP.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Skill {
+    /// The name shown to the player; the skill's name in the database if it isn't given.
+    pub name: String,
+    pub description: String,
+    pub mp_cost: u32,
+    pub effect: EffectKind,
+    /// How much damage or healing the skill does, before variance.
+    pub formula: String,
+    /// How far the result may stray from the formula's, as a fraction of it, either way.
+    pub variance: f64,
+    /// The status effects the skill inflicts on its target, by name.
+    pub add_states: Vec<String>,
+    /// The status effects the skill cures its target of, by name.
+    pub remove_states: Vec<String>,
+}
+
+impl Default for Skill {
+    fn default() -> Skill {
+        Skill {
+            name: String::new(),
+            description: String::new(),
+            mp_cost: 0,
+            effect: EffectKind::Damage,
+            formula: String::new(),
+            variance: VARIANCE,
+            add_states: Vec::new(),
+            remove_states: Vec::new(),
+        }
+    }
+}
+
+/// How a status effect changes the stats of whoever suffers it, as multipliers.
+///
+/// In Lua, a table with any of the fields `attack`, `defense`, `magic` and `speed`; missing
+/// fields are 1, which leaves the stat alone.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct StatModifiers {
+    pub attack: f64,
+    pub defense: f64,
+    pub magic: f64,
+    pub speed: f64,
+}
+
+impl StatModifiers {
+    /// Change stats by the modifiers, rounding to the nearest whole number.
+    pub fn apply(&self, stats: Stats) -> Stats {
+        let modify = |stat: u32, modifier: f64| {
+            (f64::from(stat) * modifier)
+                .round()
+                .max(0.0)
+                .min(f64::from(u32::max_value())) as u32
+        };
+        Stats {
+            attack: modify(stats.attack, self.attack),
+            defense: modify(stats.defense, self.defense),
+            magic: modify(stats.magic, self.magic),
+            speed: modify(stats.speed, self.speed),
+            ..stats
+        }
+    }
+}
+
+impl Default for StatModifiers {
+    fn default() -> StatModifiers {
+        StatModifiers {
+            attack: 1.0,
+            defense: 1.0,
+            magic: 1.0,
+            speed: 1.0,
+        }
+    }
+}
+
+impl ConfigValue for StatModifiers {
+    fn type_name() -> String {
+        String::from(
+            "stat modifiers (a table with any of attack, defense, magic and speed, as \
+             non-negative multipliers)",
+        )
+    }
+
+    fn from_lua(value: AnyLuaValue) -> Option<StatModifiers> {
+        match value {
+            AnyLuaValue::LuaArray(_) => {}
+            _ => return None,
+        }
+        let modifier = |name: &str| match get_table_field(&value, name) {
+            AnyLuaValue::LuaNil => Some(1.0),
+            field => f64::from_lua(field).and_then(|m| if m >= 0.0 { Some(m) } else { None }),
+        };
+        Some(StatModifiers {
+            attack: modifier("attack")?,
+            defense: modifier("defense")?,
+            magic: modifier("magic")?,
+            speed: modifier("speed")?,
+        })
+    }
+}
+
+/// A condition a combatant can suffer in battle, such as poison or sleep.
+#[derive(PartialEq, Debug, Clone)]
+pub struct StatusEffect {
+    /// The name shown to the player; the status effect's name in the database if it isn't given.
+    pub name: String,
+    /// How many of the sufferer's turns it lasts, or 0 to last until the battle is over.
+    pub turns: u32,
+    /// Whether the sufferer loses their turns while it lasts.
+    pub skip_turn: bool,
+    /// How much HP the sufferer loses at the start of each of their turns, with the sufferer as
+    /// both `a` and `b`; a negative result heals them instead. Empty for none.
+    pub damage_per_turn: String,
+    pub modifiers: StatModifiers,
+}
+
+impl Default for StatusEffect {
+    fn default() -> StatusEffect {
+        StatusEffect {
+            name: String::new(),
+            turns: 0,
+            skip_turn: false,
+            damage_per_turn: String::new(),
+            modifiers: StatModifiers::default(),
+        }
+    }
+}
+
+/// What sort of thing an item is, which decides what can be done with it.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum ItemKind {
+    /// Used up to restore HP or MP, or cure status effects.
+    Consumable,
+    /// Needed for the story, such as a key; it can't be used up.
+    Key,
+    /// Held in the weapon slot.
+    Weapon,
+    /// Worn in one of the other equipment slots.
+    Armor,
+}
+
+impl ItemKind {
+    /// Parse a kind from the name used for it in the database.
+    pub fn from_name(name: &str) -> Option<ItemKind> {
+        match name {
+            "consumable" => Some(ItemKind::Consumable),
+            "key" => Some(ItemKind::Key),
+            "weapon" => Some(ItemKind::Weapon),
+            "armor" => Some(ItemKind::Armor),
+            _ => None,
+        }
+    }
+}
+
+impl ConfigValue for ItemKind {
+    fn type_name() -> String {
+        String::from("one of consumable, key, weapon or armor")
+    }
+
+    fn from_lua(value: AnyLuaValue) -> Option<ItemKind> {
+        String::from_lua(value).and_then(|name| ItemKind::from_name(&name))
+    }
+}
+
+/// Where a piece of equipment is worn. A party member wears one item in each slot.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Copy, Clone)]
+pub enum EquipSlot {
+    Weapon,
+    Shield,
+    Head,
+    Body,
+    Accessory,
+}
+
+impl EquipSlot {
+    /// Every slot, in the order they're shown to the player.
+    pub const ALL: [EquipSlot; 5] = [
+        EquipSlot::Weapon,
+        EquipSlot::Shield,
+        EquipSlot::Head,
+        EquipSlot::Body,
+        EquipSlot::Accessory,
+    ];
+
+    /// Parse a slot from the name used for it in the database and in scripts.
+    pub fn from_name(name: &str) -> Option<EquipSlot> {
+        match name {
+            "weapon" => Some(EquipSlot::Weapon),
+            "shield" => Some(EquipSlot::Shield),
+            "head" => Some(EquipSlot::Head),
+            "body" => Some(EquipSlot::Body),
+            "accessory" => Some(EquipSlot::Accessory),
+            _ => None,
+        }
+    }
+
+    /// The name used for the slot in the database and in scripts.
+    pub fn name(self) -> &'static str {
+        match self {
+            EquipSlot::Weapon => "weapon",
+            EquipSlot::Shield => "shield",
+            EquipSlot::Head => "head",
+            EquipSlot::Body => "body",
+            EquipSlot::Accessory => "accessory",
+        }
+    }
+
+    /// The name shown to the player for the slot.
+    pub fn label(self) -> &'static str {
+        match self {
+            EquipSlot::Weapon => "Weapon",
+            EquipSlot::Shield => "Shield",
+            EquipSlot::Head => "Head",
+            EquipSlot::Body => "Body",
+            EquipSlot::Accessory => "Accessory",
+        }
+    }
+}
+
+impl ConfigValue for EquipSlot {
+    fn type_name() -> String {
+        String::from("one of weapon, shield, head, body or accessory")
+    }
+
+    fn from_lua(value: AnyLuaValue) -> Option<EquipSlot> {
+        String::from_lua(value).and_then(|name| EquipSlot::from_name(&name))
+    }
+}
+
+impl ConfigValue for BTreeMap<EquipSlot, String> {
+    fn type_name() -> String {
+        String::from("table of equipment slots to items")
+    }
+
+    fn from_lua(value: AnyLuaValue) -> Option<BTreeMap<EquipSlot, String>> {
+        let entries = match value {
+            AnyLuaValue::LuaArray(entries) => entries,
+            _ => return None,
+        };
+        let mut equipment = BTreeMap::new();
+        for (slot, item) in entries {
+            equipment.insert(EquipSlot::from_lua(slot)?, String::from_lua(item)?);
+        }
+        Some(equipment)
+    }
+}
+
+/// Something the party can carry: something to use up, a key item, or equipment.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Item {
+    /// The name shown to the player; the item's name in the database if it isn't given.
+    pub name: String,
+    pub description: String,
+    pub kind: ItemKind,
+    /// For armor, the slot it's worn in. Weapons are always held in the weapon slot.
+    pub slot: Option<EquipSlot>,
+    /// The most of the item the party can carry at once.
+    pub stack_limit: u32,
+    /// What the item costs in a shop, unless the shop sets its own price. Items sell for half
+    /// this; key items, and items without a price, can't be sold.
+    pub price: u32,
+    /// For equipment, what it adds to its wearer's stats, or takes away.
+    pub bonus: StatBonus,
+    /// For a consumable, how much HP it restores.
+    pub hp: u32,
+    /// For a consumable, how much MP it restores.
+    pub mp: u32,
+    /// For a consumable, the status effects it cures, by name.
+    pub remove_states: Vec<String>,
+}
+
+impl Item {
+    /// The slot the item is worn in, or None if it isn't equipment.
+    pub fn equip_slot(&self) -> Option<EquipSlot> {
+        match self.kind {
+            ItemKind::Weapon => Some(EquipSlot::Weapon),
+            ItemKind::Armor => self.slot,
+            ItemKind::Consumable | ItemKind::Key => None,
+        }
+    }
+
+    /// Whether the item can be used from the menu, outside battle: a consumable that restores
+    /// HP or MP. Curing status effects only matters in battle, where they last.
+    pub fn is_usable_outside_battle(&self) -> bool {
+        self.kind == ItemKind::Consumable && (self.hp > 0 || self.mp > 0)
+    }
+}
+
+impl Default for Item {
+    fn default() -> Item {
+        Item {
+            name: String::new(),
+            description: String::new(),
+            kind: ItemKind::Consumable,
+            slot: None,
+            stack_limit: DEFAULT_STACK_LIMIT,
+            price: 0,
+            bonus: StatBonus::default(),
+            hp: 0,
+            mp: 0,
+            remove_states: Vec::new(),
+        }
+    }
+}
+
+/// Describe how each field of a skill is read from its table.
+pub fn get_skill_schema() -> Schema<Skill> {
+    Schema::new()
+        .field(Field::new("name", |s: &mut Skill| &mut s.name))
+        .field(Field::new("description", |s: &mut Skill| &mut s.description))
+        .field(Field::new("mp_cost", |s: &mut Skill| &mut s.mp_cost))
+        .field(Field::new("effect", |s: &mut Skill| &mut s.effect).required())
+        .field(Field::new("formula", |s: &mut Skill| &mut s.formula).required())
+        .field(Field::new("variance", |s: &mut Skill| &mut s.variance).range(0.0, 1.0))
+        .field(Field::new("add_states", |s: &mut Skill| &mut s.add_states))
+        .field(Field::new("remove_states", |s: &mut Skill| &mut s.remove_states))
+}
+
+/// Describe how each field of a status effect is read from its table.
+pub fn get_status_effect_schema() -> Schema<StatusEffect> {
+    Schema::new()
+        .field(Field::new("name", |s: &mut StatusEffect| &mut s.name))
+        .field(Field::new("turns", |s: &mut StatusEffect| &mut s.turns))
+        .field(Field::new("skip_turn", |s: &mut StatusEffect| &mut s.skip_turn))
+        .field(Field::new("damage_per_turn", |s: &mut StatusEffect| {
+            &mut s.damage_per_turn
+        }))
+        .field(Field::new("modifiers", |s: &mut StatusEffect| &mut s.modifiers))
+}
+
+/// Describe how each field of an item is read from its table.
+pub fn get_item_schema() -> Schema<Item> {
+    Schema::new()
+        .field(Field::new("name", |i: &mut Item| &mut i.name))
+        .field(Field::new("description", |i: &mut Item| &mut i.description))
+        .field(Field::new("kind", |i: &mut Item| &mut i.kind).required())
+        .field(Field::new("slot", |i: &mut Item| &mut i.slot))
+        .field(Field::new("stack_limit", |i: &mut Item| &mut i.stack_limit).range(1, 9999))
+        .field(Field::new("price", |i: &mut Item| &mut i.price))
+        .field(Field::new("bonus", |i: &mut Item| &mut i.bonus))
+        .field(Field::new("hp", |i: &mut Item| &mut i.hp))
+        .field(Field::new("mp", |i: &mut Item| &mut i.mp))
+        .field(Field::new("remove_states", |i: &mut Item| &mut i.remove_states))
+}
+
+/// Every skill, status effect and item in the game, by the names the game refers to them by.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct Database {
+    pub skills: BTreeMap<String, Skill>,
+    pub status_effects: BTreeMap<String, StatusEffect>,
+    pub items: BTreeMap<String, Item>,
+}
+
+impl Database {
+    /// Load the database from data/database/, returning every problem found with it alongside
+    /// whatever of it is valid.
+    pub fn load() -> (Database, Vec<DeucalionError>) {
+        // The tables are read like a character's info, in an environment of their own.
+        let mut environment =
+            get_sandboxed_environment(&get_default_script_libraries(), &get_module_roots(&[]));
+        let mut errors = Vec::new();
+        for &(table, _) in [SKILLS_TABLE, STATES_TABLE, ITEMS_TABLE].iter() {
+            if let Err(e) = run_table_script(&mut environment, table) {
+                errors.push(e);
+            }
+        }
+        let (database, table_errors) = Database::from_environment(&mut environment);
+        errors.extend(table_errors);
+        (database, errors)
+    }
+
+    /// Read the database from the `SKILLS`, `STATES` and `ITEMS` globals of an environment its
+    /// scripts have been run in, checking every entry.
+    pub fn from_environment(environment: &mut Lua) -> (Database, Vec<DeucalionError>) {
+        let mut errors = Vec::new();
+        let mut database = Database::default();
+        for (id, entry) in get_entries(environment, STATES_TABLE, &mut errors) {
+            let entry_name = format!("{}.{}", STATES_TABLE.0, id);
+            let (mut status_effect, entry_errors) =
+                read_entry(&get_status_effect_schema(), &entry_name, &entry);
+            if !entry_errors.is_empty() {
+                errors.extend(entry_errors);
+                continue;
+            }
+            if status_effect.name.is_empty() {
+                status_effect.name = id.clone();
+            }
+            database.status_effects.insert(id, status_effect);
+        }
+        for (id, entry) in get_entries(environment, SKILLS_TABLE, &mut errors) {
+            let entry_name = format!("{}.{}", SKILLS_TABLE.0, id);
+            let (mut skill, entry_errors) = read_entry(&get_skill_schema(), &entry_name, &entry);
+            if !entry_errors.is_empty() {
+                errors.extend(entry_errors);
+                continue;
+            }
+            if skill.name.is_empty() {
+                skill.name = id.clone();
+            }
+            database.skills.insert(id, skill);
+        }
+        for (id, entry) in get_entries(environment, ITEMS_TABLE, &mut errors) {
+            let entry_name = format!("{}.{}", ITEMS_TABLE.0, id);
+            let (mut item, entry_errors) = read_entry(&get_item_schema(), &entry_name, &entry);
+            if !entry_errors.is_empty() {
+                errors.extend(entry_errors);
+                continue;
+            }
+            if item.name.is_empty() {
+                item.name = id.clone();
+            }
+            database.items.insert(id, item);
+        }
+        database.check(&mut errors);
+        (database, errors)
+    }
+
+    /// Find a skill by the name the game refers to it by.
+    pub fn skill(&self, id: &str) -> Option<&Skill> {
+        self.skills.get(id)
+    }
+
+    /// Find a status effect by the name the game refers to it by.
+    pub fn status_effect(&self, id: &str) -> Option<&StatusEffect> {
+        self.status_effects.get(id)
+    }
+
+    /// Find an item by the name the game refers to it by.
+    pub fn item(&self, id: &str) -> Option<&Item> {
+        self.items.get(id)
+    }
+
+    /// The name of an item shown to the player, or its id if it isn't in the database.
+    pub fn item_name(&self, id: &str) -> String {
+        self.item(id).map_or(String::from(id), |item| item.name.clone())
+    }
+
+    // Check what can't be checked entry by entry: that formulas run, that skills and items refer
+    //  to status effects that exist, and that armor has a slot. Entries that fail are removed.
+    fn check(&mut self, errors: &mut Vec<DeucalionError>) {
+        let mut formulas = FormulaEnvironment::new();
+        let mut check_formula = |entry: &str, formula: &str| {
+            let combatant = Combatant::new("", Side::Party, Stats::default());
+            let stats = combatant.stats;
+            formulas
+                .evaluate(formula, (&combatant, &stats), (&combatant, &stats))
+                .map(|_| ())
+                .map_err(|e| DeucalionError::DatabaseError(String::from(entry), e.to_string()))
+        };
+
+        let mut invalid = Vec::new();
+        for (id, status_effect) in self.status_effects.iter() {
+            if status_effect.damage_per_turn.is_empty() {
+                continue;
+            }
+            let entry = format!("{}.{}", STATES_TABLE.0, id);
+            if let Err(e) = check_formula(&entry, &status_effect.damage_per_turn) {
+                errors.push(e);
+                invalid.push(id.clone());
+            }
+        }
+        for id in invalid.drain(..) {
+            self.status_effects.remove(&id);
+        }
+
+        for (id, skill) in self.skills.iter() {
+            let entry = format!("{}.{}", SKILLS_TABLE.0, id);
+            let mut valid = true;
+            for state in skill.add_states.iter().chain(skill.remove_states.iter()) {
+                if !self.status_effects.contains_key(state) {
+                    errors.push(DeucalionError::DatabaseError(
+                        entry.clone(),
+                        format!("there is no status effect named '{}'", state),
+                    ));
+                    valid = false;
+                }
+            }
+            if let Err(e) = check_formula(&entry, &skill.formula) {
+                errors.push(e);
+                valid = false;
+            }
+            if !valid {
+                invalid.push(id.clone());
+            }
+        }
+        for id in invalid.drain(..) {
+            self.skills.remove(&id);
+        }
+
+        for (id, item) in self.items.iter() {
+            let entry = format!("{}.{}", ITEMS_TABLE.0, id);
+            let mut valid = true;
+            for state in item.remove_states.iter() {
+                if !self.status_effects.contains_key(state) {
+                    errors.push(DeucalionError::DatabaseError(
+                        entry.clone(),
+                        format!("there is no status effect named '{}'", state),
+                    ));
+                    valid = false;
+                }
+            }
+            let slot_problem = match (item.kind, item.slot) {
+                (ItemKind::Armor, None) | (ItemKind::Armor, Some(EquipSlot::Weapon)) => {
+                    Some("armor needs a slot: one of shield, head, body or accessory")
+                }
+                (ItemKind::Armor, Some(_)) | (_, None) => None,
+                (_, Some(_)) => Some("only armor has a slot"),
+            };
+            if let Some(problem) = slot_problem {
+                errors.push(DeucalionError::DatabaseError(entry, String::from(problem)));
+                valid = false;
+            }
+            if !valid {
+                invalid.push(id.clone());
+            }
+        }
+        for id in invalid {
+            self.items.remove(&id);
+        }
+    }
+}
+
+// Run data/database/<table>.lua, if the game has it.
+fn run_table_script(environment: &mut Lua, table: &str) -> Result<(), DeucalionError> {
+    let path = loading::get_resource_relative_path_by_name(ResourceKind::Database, table)?;
+    if !loading::data_file_exists(&path) {
+        info!("There is no {} in the database at {}", table, path.display());
+        return Ok(());
+    }
+    let reader = loading::open_data_file(&path)?;
+    execute_script_from_reader(environment, reader, &path, &ScriptBudget::for_config())?;
+    Ok(())
+}
+
+// Get the entries of a table of the database by name, in order. A missing table has none.
+fn get_entries(
+    environment: &mut Lua,
+    (table, global): (&str, &str),
+    errors: &mut Vec<DeucalionError>,
+) -> BTreeMap<String, AnyLuaValue> {
+    let mut entries = BTreeMap::new();
+    let fields = match environment.get::<AnyLuaValue, _>(global) {
+        Some(AnyLuaValue::LuaArray(fields)) => fields,
+        Some(AnyLuaValue::LuaNil) | None => return entries,
+        Some(_) => {
+            errors.push(DeucalionError::LuaGetFailed(
+                String::from(global),
+                format!("table of {}, by name", table),
+            ));
+            return entries;
+        }
+    };
+    for (key, entry) in fields {
+        match key {
+            AnyLuaValue::LuaString(id) => {
+                entries.insert(id, entry);
+            }
+            key => errors.push(DeucalionError::DatabaseError(
+                format!("{}[{:?}]", table, key),
+                String::from("entries must be named by strings"),
+            )),
+        }
+    }
+    entries
+}
+
+// Read an entry of the database with a schema, describing each problem with it.
+fn read_entry<T: Default + 'static>(
+    schema: &Schema<T>,
+    entry_name: &str,
+    entry: &AnyLuaValue,
+) -> (T, Vec<DeucalionError>) {
+    let database_error = |description: String| {
+        DeucalionError::DatabaseError(String::from(entry_name), description)
+    };
+    match *entry {
+        AnyLuaValue::LuaArray(_) => {}
+        _ => return (T::default(), vec![database_error(String::from("should be a table"))]),
+    }
+    let (value, errors) =
+        schema.read_from(|identifier| Some(get_table_field(entry, identifier)), T::default());
+    let errors = errors
+        .into_iter()
+        .map(|e| match e {
+            DeucalionError::LuaGetFailed(field, expected) => {
+                database_error(format!("expected {} for {}", expected, field))
+            }
+            e => database_error(e.to_string()),
+        })
+        .collect();
+    (value, errors)
+}
@@ -0,0 +1,164 @@
+//! How much damage hits do and how likely the party is to get away. The built-in formulas, used
+//! for plain attacks and items, are deliberately simple, so that the numbers are easy to reason
+//! about when balancing a game; skills and status effects have formulas of their own, written in
+//! Lua in the database (see `battle::database`) and run by a `FormulaEnvironment`.
+
+use std::path::PathBuf;
+
+use hlua::{AnyLuaValue, Lua};
+
+use battle::combatant::{Combatant, Stats};
+use error::DeucalionError;
+use scripting::budget::ScriptBudget;
+use scripting::convert::make_table;
+use scripting::execute_script_from_reader;
+use scripting::modules::get_module_roots;
+use scripting::sandbox::{get_sandboxed_environment, LuaLibrary};
+use utility::random::Random;
+
+/// How far damage and healing may stray from the formula's result, as a fraction of it, either
+/// way.
+pub const VARIANCE: f64 = 0.1;
+
+/// How much more likely the party is to get away for each attempt that has failed.
+pub const FLEE_CHANCE_PER_ATTEMPT: f64 = 0.1;
+
+/// How many Lua instructions a formula may run. Formulas are run for every hit, so one that
+/// runs away should be stopped long before it could be noticed.
+pub const FORMULA_INSTRUCTIONS: u64 = 100_000;
+
+/// Work out the damage of a hit before variance: twice the attacker's attack plus the item's
+/// power, if it's an item, less the target's defense, but always at least 1. Guarding halves it.
+pub fn get_base_damage(attack: u32, power: u32, defense: u32, guarding: bool) -> u32 {
+    let damage = (i64::from(attack) * 2 + i64::from(power) - i64::from(defense)).max(1);
+    let damage = damage.min(i64::from(u32::max_value())) as u32;
+    if guarding {
+        guard(damage)
+    } else {
+        damage
+    }
+}
+
+/// Halve the damage of a hit on a guarding target, but leave at least 1.
+pub fn guard(damage: u32) -> u32 {
+    (damage / 2).max(1)
+}
+
+/// Round the result of a formula to a whole amount of damage or healing, which may be negative.
+pub fn round_amount(amount: f64) -> i64 {
+    amount
+        .round()
+        .max(-f64::from(u32::max_value()))
+        .min(f64::from(u32::max_value())) as i64
+}
+
+/// Spread an amount of damage or healing randomly, by up to `VARIANCE` either way, but always
+/// at least 1.
+pub fn vary(amount: u32, random: &mut Random) -> u32 {
+    vary_by(amount, VARIANCE, random)
+}
+
+/// Spread an amount randomly like `vary`, by up to the given fraction of it either way.
+pub fn vary_by(amount: u32, variance: f64, random: &mut Random) -> u32 {
+    let factor = 1.0 + variance * (random.fraction() * 2.0 - 1.0);
+    (f64::from(amount) * factor)
+        .round()
+        .max(1.0)
+        .min(f64::from(u32::max_value())) as u32
+}
+
+/// Work out how likely the party is to get away, from 0 to 1: the share of the total speed of
+/// both sides that's the party's, plus a little for each attempt that has already failed.
+pub fn get_flee_chance(party_speed: u32, troop_speed: u32, failed_attempts: u32) -> f64 {
+    let total = f64::from(party_speed) + f64::from(troop_speed);
+    let share = if total > 0.0 {
+        f64::from(party_speed) / total
+    } else {
+        0.5
+    };
+    (share + FLEE_CHANCE_PER_ATTEMPT * f64::from(failed_attempts)).min(1.0)
+}
+
+// Describe a combatant to a formula: their stats, as they are with any status effects, and how
+//  much HP and MP they have left.
+fn combatant_table(combatant: &Combatant, stats: &Stats) -> AnyLuaValue {
+    let number = |n: u32| AnyLuaValue::LuaNumber(f64::from(n));
+    make_table(vec![
+        ("max_hp", number(stats.max_hp)),
+        ("max_mp", number(stats.max_mp)),
+        ("attack", number(stats.attack)),
+        ("defense", number(stats.defense)),
+        ("magic", number(stats.magic)),
+        ("speed", number(stats.speed)),
+        ("hp", number(combatant.hp)),
+        ("mp", number(combatant.mp)),
+    ])
+}
+
+/// A sandboxed Lua environment that runs the formulas of the database, such as
+/// `"a.magic * 3 - b.defense"`. A formula is a Lua expression, which is given the user as `a`
+/// and the target as `b`, each a table of their stats plus `hp` and `mp`, and which must come
+/// to a number. Only the base and math libraries are open.
+pub struct FormulaEnvironment {
+    environment: Lua<'static>,
+}
+
+impl FormulaEnvironment {
+    /// Create an environment to run formulas in.
+    pub fn new() -> FormulaEnvironment {
+        let libraries = [LuaLibrary::Base, LuaLibrary::Math];
+        FormulaEnvironment {
+            environment: get_sandboxed_environment(&libraries, &get_module_roots(&[])),
+        }
+    }
+
+    /// Work out a formula for a user and a target, with the stats they have at the moment.
+    pub fn evaluate(
+        &mut self,
+        formula: &str,
+        user: (&Combatant, &Stats),
+        target: (&Combatant, &Stats),
+    ) -> Result<f64, DeucalionError> {
+        let globals = vec![
+            ("a", combatant_table(user.0, user.1)),
+            ("b", combatant_table(target.0, target.1)),
+        ];
+        self.evaluate_with(formula, globals)
+    }
+
+    /// Work out a formula that's given other globals, such as a growth curve's `level`.
+    pub fn evaluate_with(
+        &mut self,
+        formula: &str,
+        globals: Vec<(&str, AnyLuaValue)>,
+    ) -> Result<f64, DeucalionError> {
+        for (name, value) in globals {
+            self.environment.set(name, value);
+        }
+        // The newlines keep a comment at the end of the formula from swallowing the bracket.
+        let source = format!("return (\n{}\n)", formula);
+        let path = PathBuf::from(format!("<formula {}>", formula));
+        let result = execute_script_from_reader(
+            &mut self.environment,
+            source.as_bytes(),
+            &path,
+            &ScriptBudget {
+                instructions: Some(FORMULA_INSTRUCTIONS),
+                time: None,
+            },
+        )?;
+        match result {
+            AnyLuaValue::LuaNumber(n) if n.is_finite() => Ok(n),
+            other => Err(DeucalionError::from(format!(
+                "the formula {} came to {:?} rather than a number",
+                formula, other
+            ))),
+        }
+    }
+}
+
+impl Default for FormulaEnvironment {
+    fn default() -> FormulaEnvironment {
+        FormulaEnvironment::new()
+    }
+}
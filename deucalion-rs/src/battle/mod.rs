@@ -0,0 +1,19 @@
+//! Turn-based battles between the player's party and a troop of enemies. The battle itself is a
+//! state machine, kept apart from the battle scene that draws it, so that battles can be played
+//! through in tests.
+
+pub mod combatant;
+pub mod database;
+pub mod formula;
+pub mod state;
+pub mod troop;
+#[cfg(test)]
+mod test_combatant;
+#[cfg(test)]
+mod test_database;
+#[cfg(test)]
+mod test_formula;
+#[cfg(test)]
+mod test_state;
+#[cfg(test)]
+pub mod test_support;
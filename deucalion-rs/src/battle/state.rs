@@ -0,0 +1,676 @@
+//! The battle state machine: who acts when, what their commands do, and when the battle is over.
+//!
+//! A battle knows nothing of drawing or input, so it can be played through in tests. The battle
+//! scene calls `advance` every frame, which runs enemy turns until a party member's turn comes
+//! around; the battle then waits for `submit` to give that member's command. Everything that
+//! happens is recorded as `BattleEvent`s, for the scene to show.
+//!
+//! In turn mode, each round every combatant acts once, fastest first. In ATB (active time)
+//! mode, each combatant has a gauge that fills over time, faster the higher their speed, and
+//! they act whenever it's full. Time stands still while a party member's command is chosen.
+//!
+//! Skills and status effects come from the database (see `battle::database`). Status effects
+//! take hold at the start of each of their sufferer's turns, when they may hurt them, take the
+//! turn away, or wear off.
+
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use hlua::AnyLuaValue;
+
+use battle::combatant::{ActiveState, Combatant, Side, Stats};
+use battle::database::{Database, EffectKind, Item, ItemKind};
+use battle::formula::{self, FormulaEnvironment};
+use config::schema::ConfigValue;
+use utility::random::Random;
+
+/// How long, in seconds, a combatant with a speed of `ATB_REFERENCE_SPEED` takes to fill their
+/// gauge in ATB mode.
+pub const ATB_FILL_TIME: f32 = 3.0;
+
+/// The speed that fills an ATB gauge in exactly `ATB_FILL_TIME`.
+pub const ATB_REFERENCE_SPEED: f32 = 10.0;
+
+/// How turns come around.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum TurnMode {
+    /// Everyone acts once a round, fastest first.
+    Turns,
+    /// Everyone acts when their gauge fills, which it does faster the faster they are.
+    ActiveTime,
+}
+
+impl TurnMode {
+    /// Parse a turn mode from the name used for it in game_config.lua.
+    pub fn from_name(name: &str) -> Option<TurnMode> {
+        match name {
+            "turns" => Some(TurnMode::Turns),
+            "atb" => Some(TurnMode::ActiveTime),
+            _ => None,
+        }
+    }
+
+    /// The name used for this turn mode in game_config.lua.
+    pub fn name(self) -> &'static str {
+        match self {
+            TurnMode::Turns => "turns",
+            TurnMode::ActiveTime => "atb",
+        }
+    }
+}
+
+impl ConfigValue for TurnMode {
+    fn type_name() -> String {
+        String::from("one of turns or atb")
+    }
+
+    fn from_lua(value: AnyLuaValue) -> Option<TurnMode> {
+        String::from_lua(value).and_then(|name| TurnMode::from_name(&name))
+    }
+}
+
+/// What an item does to its target. Items use the engine's built-in formulas.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum Effect {
+    /// Hurt an opponent, with the given power.
+    Damage(u32),
+    /// Restore HP to an ally, with the given power.
+    Heal(u32),
+}
+
+impl Effect {
+    /// The side a user of the effect may target: their opponents for damage, their own for
+    /// healing.
+    pub fn target_side(self, user: Side) -> Side {
+        match self {
+            Effect::Damage(_) => user.opponent(),
+            Effect::Heal(_) => user,
+        }
+    }
+}
+
+/// A stack of items the party can use in battle.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct BattleItem {
+    pub name: String,
+    /// How many the party has.
+    pub count: u32,
+    pub effect: Effect,
+}
+
+impl BattleItem {
+    /// The party's stack of an item from the database, by its name there, if it's one they can
+    /// use in battle: a consumable that restores HP.
+    pub fn from_item(id: &str, item: &Item, count: u32) -> Option<BattleItem> {
+        if item.kind != ItemKind::Consumable || item.hp == 0 {
+            return None;
+        }
+        Some(BattleItem {
+            name: String::from(id),
+            count: count,
+            effect: Effect::Heal(item.hp),
+        })
+    }
+}
+
+/// What a party member does on their turn. Targets are indices into the battle's combatants.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum BattleCommand {
+    /// Hit an opponent.
+    Attack(usize),
+    /// Use one of the member's skills, by its name in the database.
+    Skill { skill: String, target: usize },
+    /// Use one of the party's items, by name.
+    Item { item: String, target: usize },
+    /// Take half damage until their next turn.
+    Guard,
+    /// Try to get away from the battle.
+    Flee,
+}
+
+/// How a battle ended.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum BattleOutcome {
+    /// Every enemy was defeated.
+    Victory,
+    /// Every party member was defeated.
+    Defeat,
+    /// The party got away.
+    Escaped,
+}
+
+impl BattleOutcome {
+    /// The name scripts are given for the outcome.
+    pub fn name(self) -> &'static str {
+        match self {
+            BattleOutcome::Victory => "victory",
+            BattleOutcome::Defeat => "defeat",
+            BattleOutcome::Escaped => "escaped",
+        }
+    }
+}
+
+/// What a battle is doing.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum BattlePhase {
+    /// Turns are being taken; `advance` moves the battle on.
+    Running,
+    /// The party member at this index is waiting for a command to be submitted.
+    WaitingForCommand(usize),
+    /// The battle is over.
+    Finished(BattleOutcome),
+}
+
+/// Something that happened in a battle. Combatants are indices into the battle's combatants.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum BattleEvent {
+    Attacked { user: usize, target: usize },
+    UsedSkill { user: usize, skill: String, target: usize },
+    UsedItem { user: usize, item: String, target: usize },
+    Guarded(usize),
+    Damaged { target: usize, amount: u32 },
+    Healed { target: usize, amount: u32 },
+    /// A combatant's HP reached zero.
+    Defeated(usize),
+    /// A combatant began suffering a status effect, by its name in the database.
+    StateAdded { target: usize, state: String },
+    /// A status effect wore off or was cured.
+    StateRemoved { target: usize, state: String },
+    /// A status effect kept a combatant from acting on their turn.
+    TurnSkipped(usize),
+    /// The party tried to get away, but couldn't.
+    FleeFailed,
+    Ended(BattleOutcome),
+}
+
+/// A battle between the player's party and a troop of enemies.
+pub struct Battle {
+    /// Everyone in the battle: the party first, then the troop.
+    pub combatants: Vec<Combatant>,
+    pub mode: TurnMode,
+    /// Whether the party may flee.
+    pub can_flee: bool,
+    database: Rc<Database>,
+    formulas: FormulaEnvironment,
+    items: Vec<BattleItem>,
+    phase: BattlePhase,
+    // Who is due to act, in order: the rest of the round in turn mode, or whoever's gauges have
+    //  filled in ATB mode.
+    ready: VecDeque<usize>,
+    failed_flees: u32,
+    events: Vec<BattleEvent>,
+    random: Random,
+}
+
+impl Battle {
+    /// Start a battle between a party and a troop, taking turns in the given way.
+    pub fn new(
+        party: Vec<Combatant>,
+        troop: Vec<Combatant>,
+        mode: TurnMode,
+        random: Random,
+    ) -> Battle {
+        let mut combatants = Vec::with_capacity(party.len() + troop.len());
+        for mut member in party {
+            member.side = Side::Party;
+            combatants.push(member);
+        }
+        for mut enemy in troop {
+            enemy.side = Side::Troop;
+            combatants.push(enemy);
+        }
+        let mut battle = Battle {
+            combatants: combatants,
+            mode: mode,
+            can_flee: true,
+            database: Rc::new(Database::default()),
+            formulas: FormulaEnvironment::new(),
+            items: Vec::new(),
+            phase: BattlePhase::Running,
+            ready: VecDeque::new(),
+            failed_flees: 0,
+            events: Vec::new(),
+            random: random,
+        };
+        // A battle against nobody, or with nobody, is over before it starts.
+        battle.check_end();
+        battle
+    }
+
+    /// Give the battle the database its skills and status effects come from. Combatants can
+    /// only use the skills they know.
+    pub fn with_database(mut self, database: Rc<Database>) -> Battle {
+        self.database = database;
+        self
+    }
+
+    /// Give the party items to use.
+    pub fn with_items(mut self, items: Vec<BattleItem>) -> Battle {
+        self.items = items;
+        self
+    }
+
+    /// What the battle is doing.
+    pub fn phase(&self) -> BattlePhase {
+        self.phase
+    }
+
+    /// The database the battle's skills and status effects come from.
+    pub fn database(&self) -> &Database {
+        &self.database
+    }
+
+    /// A combatant's stats as they are at the moment, changed by any status effects.
+    pub fn stats(&self, index: usize) -> Stats {
+        let combatant = &self.combatants[index];
+        let mut stats = combatant.stats;
+        for state in combatant.states.iter() {
+            if let Some(status_effect) = self.database.status_effect(&state.id) {
+                stats = status_effect.modifiers.apply(stats);
+            }
+        }
+        stats
+    }
+
+    /// The party's items, including any that have run out.
+    pub fn items(&self) -> &[BattleItem] {
+        &self.items
+    }
+
+    /// The indices of the combatants on a side that can still fight.
+    pub fn living(&self, side: Side) -> Vec<usize> {
+        (0..self.combatants.len())
+            .filter(|i| self.combatants[*i].side == side && self.combatants[*i].is_alive())
+            .collect()
+    }
+
+    /// The EXP each surviving party member earns for the enemies that have been defeated.
+    pub fn exp_reward(&self) -> u32 {
+        self.combatants
+            .iter()
+            .filter(|combatant| combatant.side == Side::Troop && !combatant.is_alive())
+            .fold(0u32, |total, enemy| total.saturating_add(enemy.exp))
+    }
+
+    /// Remove and return what has happened since this was last called, oldest first.
+    pub fn take_events(&mut self) -> Vec<BattleEvent> {
+        self.events.drain(..).collect()
+    }
+
+    /// Move the battle on by `delta` seconds: fill ATB gauges, and take turns until a party
+    /// member needs a command or the battle is over. In turn mode, time makes no difference.
+    pub fn advance(&mut self, delta: f32) {
+        let mut filled = false;
+        while self.phase == BattlePhase::Running {
+            let user = match self.ready.pop_front() {
+                Some(user) => user,
+                None => {
+                    if !self.queue_turns(delta, &mut filled) {
+                        return;
+                    }
+                    continue;
+                }
+            };
+            if !self.combatants[user].is_alive() {
+                continue;
+            }
+            // Guarding lasts until the guard's next turn.
+            self.combatants[user].guarding = false;
+            if !self.start_turn(user) {
+                self.end_turn(user);
+                continue;
+            }
+            match self.combatants[user].side {
+                Side::Party => self.phase = BattlePhase::WaitingForCommand(user),
+                Side::Troop => {
+                    let command = self.choose_enemy_command(user);
+                    if let Some(command) = command {
+                        if let Err(e) = self.perform(user, &command) {
+                            warn!("An enemy couldn't carry out {:?}: {}", command, e);
+                        }
+                    }
+                    self.end_turn(user);
+                }
+            }
+        }
+    }
+
+    /// Give the command of the party member whose turn it is. Returns why not if it can't be
+    /// carried out, such as a skill the member can't afford, in which case the member is still
+    /// waiting for a command.
+    pub fn submit(&mut self, command: BattleCommand) -> Result<(), String> {
+        let user = match self.phase {
+            BattlePhase::WaitingForCommand(user) => user,
+            _ => return Err(String::from("no one is waiting for a command")),
+        };
+        self.perform(user, &command)?;
+        if self.phase == BattlePhase::WaitingForCommand(user) {
+            self.phase = BattlePhase::Running;
+        }
+        self.end_turn(user);
+        Ok(())
+    }
+
+    // Work out who acts next: in turn mode, everyone still standing, fastest first; in ATB mode,
+    //  whoever's gauges fill in `delta`, which is only spent once per call to advance. Returns
+    //  whether anyone was queued.
+    fn queue_turns(&mut self, delta: f32, filled: &mut bool) -> bool {
+        match self.mode {
+            TurnMode::Turns => {
+                let speeds = self.speeds();
+                let mut order: Vec<usize> = (0..self.combatants.len())
+                    .filter(|i| self.combatants[*i].is_alive())
+                    .collect();
+                // The sort is stable, so the party goes first in a tie.
+                order.sort_by(|a, b| speeds[*b].cmp(&speeds[*a]));
+                self.ready.extend(order);
+            }
+            TurnMode::ActiveTime => {
+                if *filled {
+                    return false;
+                }
+                *filled = true;
+                let speeds = self.speeds();
+                for (i, combatant) in self.combatants.iter_mut().enumerate() {
+                    if !combatant.is_alive() || combatant.gauge >= 1.0 {
+                        continue;
+                    }
+                    let speed = speeds[i].max(1) as f32;
+                    combatant.gauge += delta * speed / ATB_REFERENCE_SPEED / ATB_FILL_TIME;
+                    if combatant.gauge >= 1.0 {
+                        combatant.gauge = 1.0;
+                        self.ready.push_back(i);
+                    }
+                }
+            }
+        }
+        !self.ready.is_empty()
+    }
+
+    // Everyone's speed at the moment, in order.
+    fn speeds(&self) -> Vec<u32> {
+        (0..self.combatants.len())
+            .map(|i| self.stats(i).speed)
+            .collect()
+    }
+
+    // Let a combatant's status effects take hold at the start of their turn: hurt them, wear
+    //  off, and take the turn away. Returns whether they can still act.
+    fn start_turn(&mut self, user: usize) -> bool {
+        let mut skip = false;
+        let states = self.combatants[user].states.clone();
+        for state in states {
+            let status_effect = match self.database.status_effect(&state.id) {
+                Some(status_effect) => status_effect.clone(),
+                None => continue,
+            };
+            skip = skip || status_effect.skip_turn;
+            if status_effect.damage_per_turn.is_empty() || !self.combatants[user].is_alive() {
+                continue;
+            }
+            match self.evaluate(&status_effect.damage_per_turn, user, user) {
+                Ok(amount) => match formula::round_amount(amount) {
+                    amount if amount > 0 => self.hurt(user, amount as u32),
+                    amount if amount < 0 => self.restore(user, (-amount) as u32),
+                    _ => {}
+                },
+                Err(e) => warn!("The damage per turn of {} failed: {}", state.id, e),
+            }
+        }
+        if !self.combatants[user].is_alive() {
+            return false;
+        }
+        if skip {
+            self.events.push(BattleEvent::TurnSkipped(user));
+        }
+
+        let mut expired = Vec::new();
+        for state in self.combatants[user].states.iter_mut() {
+            if let Some(turns) = state.turns_left {
+                state.turns_left = Some(turns.saturating_sub(1));
+                if turns <= 1 {
+                    expired.push(state.id.clone());
+                }
+            }
+        }
+        for id in expired {
+            self.remove_state(user, &id);
+        }
+        !skip
+    }
+
+    // Enemies attack someone on the other side at random.
+    fn choose_enemy_command(&mut self, user: usize) -> Option<BattleCommand> {
+        let targets = self.living(self.combatants[user].side.opponent());
+        self.random
+            .choose(&targets)
+            .map(|target| BattleCommand::Attack(*target))
+    }
+
+    // Finish a combatant's turn, and see whether that was the end of the battle.
+    fn end_turn(&mut self, user: usize) {
+        self.combatants[user].gauge = 0.0;
+        self.check_end();
+    }
+
+    // Check a command can be carried out, then carry it out.
+    fn perform(&mut self, user: usize, command: &BattleCommand) -> Result<(), String> {
+        let side = self.combatants[user].side;
+        match *command {
+            BattleCommand::Attack(target) => {
+                self.check_target(target, side.opponent())?;
+                self.events.push(BattleEvent::Attacked {
+                    user: user,
+                    target: target,
+                });
+                let attack = self.stats(user).attack;
+                self.damage(target, attack, 0);
+            }
+            BattleCommand::Skill { ref skill, target } => {
+                if !self.combatants[user].skills.contains(skill) {
+                    return Err(format!("{} doesn't know {}", self.combatants[user].name, skill));
+                }
+                let definition = match self.database.skill(skill) {
+                    Some(definition) => definition.clone(),
+                    None => return Err(format!("there is no skill named {}", skill)),
+                };
+                if self.combatants[user].mp < definition.mp_cost {
+                    return Err(format!("not enough MP for {}", definition.name));
+                }
+                self.check_target(target, definition.effect.target_side(side))?;
+                // The formula is worked out first, so that a broken one costs nothing.
+                let amount = self
+                    .evaluate(&definition.formula, user, target)
+                    .map_err(|e| format!("{} failed: {}", definition.name, e))?;
+                self.combatants[user].mp -= definition.mp_cost;
+                self.events.push(BattleEvent::UsedSkill {
+                    user: user,
+                    skill: skill.clone(),
+                    target: target,
+                });
+                // A skill whose formula comes to nothing, such as one that only inflicts a
+                //  status effect, neither hurts nor heals.
+                let amount = formula::round_amount(amount);
+                if amount > 0 {
+                    let amount =
+                        formula::vary_by(amount as u32, definition.variance, &mut self.random);
+                    match definition.effect {
+                        EffectKind::Damage if self.combatants[target].guarding => {
+                            self.hurt(target, formula::guard(amount))
+                        }
+                        EffectKind::Damage => self.hurt(target, amount),
+                        EffectKind::Heal => self.restore(target, amount),
+                    }
+                }
+                if self.combatants[target].is_alive() {
+                    for id in definition.remove_states.iter() {
+                        self.remove_state(target, id);
+                    }
+                    for id in definition.add_states.iter() {
+                        self.add_state(target, id);
+                    }
+                }
+            }
+            BattleCommand::Item { ref item, target } => {
+                let index = match self.items.iter().position(|i| i.name == *item && i.count > 0) {
+                    Some(index) => index,
+                    None => return Err(format!("the party has no {}", item)),
+                };
+                let effect = self.items[index].effect;
+                self.check_target(target, effect.target_side(side))?;
+                self.items[index].count -= 1;
+                self.events.push(BattleEvent::UsedItem {
+                    user: user,
+                    item: item.clone(),
+                    target: target,
+                });
+                // Items work the same whoever uses them.
+                match effect {
+                    Effect::Damage(power) => self.damage(target, 0, power),
+                    Effect::Heal(power) => self.heal(target, power),
+                }
+            }
+            BattleCommand::Guard => {
+                self.combatants[user].guarding = true;
+                self.events.push(BattleEvent::Guarded(user));
+            }
+            BattleCommand::Flee => {
+                if !self.can_flee {
+                    return Err(String::from("there's no escaping this battle"));
+                }
+                let party_speed = self.total_speed(Side::Party);
+                let troop_speed = self.total_speed(Side::Troop);
+                let chance = formula::get_flee_chance(party_speed, troop_speed, self.failed_flees);
+                if self.random.chance(chance) {
+                    self.finish(BattleOutcome::Escaped);
+                } else {
+                    self.failed_flees += 1;
+                    self.events.push(BattleEvent::FleeFailed);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Check a target is someone on the given side who can still fight.
+    fn check_target(&self, target: usize, side: Side) -> Result<(), String> {
+        match self.combatants.get(target) {
+            Some(combatant) if combatant.side != side => {
+                Err(format!("{} can't be targeted by that", combatant.name))
+            }
+            Some(combatant) if !combatant.is_alive() => {
+                Err(format!("{} has already been defeated", combatant.name))
+            }
+            Some(_) => Ok(()),
+            None => Err(format!("there's no combatant {} to target", target)),
+        }
+    }
+
+    // Work out a formula of the database for a user and a target.
+    fn evaluate(&mut self, formula: &str, user: usize, target: usize) -> Result<f64, String> {
+        let user_stats = self.stats(user);
+        let target_stats = self.stats(target);
+        self.formulas
+            .evaluate(
+                formula,
+                (&self.combatants[user], &user_stats),
+                (&self.combatants[target], &target_stats),
+            )
+            .map_err(|e| e.to_string())
+    }
+
+    // Hit a target with the built-in damage formula.
+    fn damage(&mut self, target: usize, attack: u32, power: u32) {
+        let defense = self.stats(target).defense;
+        let guarding = self.combatants[target].guarding;
+        let base = formula::get_base_damage(attack, power, defense, guarding);
+        let amount = formula::vary(base, &mut self.random);
+        self.hurt(target, amount);
+    }
+
+    // Heal a target with the built-in formula.
+    fn heal(&mut self, target: usize, power: u32) {
+        let amount = formula::vary(power, &mut self.random);
+        self.restore(target, amount);
+    }
+
+    // Take HP from a target. The defeated are rid of their status effects.
+    fn hurt(&mut self, target: usize, amount: u32) {
+        let amount = self.combatants[target].take_damage(amount);
+        self.events.push(BattleEvent::Damaged {
+            target: target,
+            amount: amount,
+        });
+        if !self.combatants[target].is_alive() {
+            self.combatants[target].states.clear();
+            self.events.push(BattleEvent::Defeated(target));
+        }
+    }
+
+    // Give HP back to a target.
+    fn restore(&mut self, target: usize, amount: u32) {
+        let amount = self.combatants[target].heal(amount);
+        self.events.push(BattleEvent::Healed {
+            target: target,
+            amount: amount,
+        });
+    }
+
+    // Inflict a status effect on a target, or start it over if they're already suffering it.
+    fn add_state(&mut self, target: usize, id: &str) {
+        let turns_left = match self.database.status_effect(id) {
+            Some(status_effect) if status_effect.turns > 0 => Some(status_effect.turns),
+            Some(_) => None,
+            None => return,
+        };
+        let states = &mut self.combatants[target].states;
+        match states.iter().position(|state| state.id == id) {
+            Some(i) => states[i].turns_left = turns_left,
+            None => {
+                states.push(ActiveState {
+                    id: String::from(id),
+                    turns_left: turns_left,
+                });
+                self.events.push(BattleEvent::StateAdded {
+                    target: target,
+                    state: String::from(id),
+                });
+            }
+        }
+    }
+
+    // Rid a target of a status effect, if they're suffering it.
+    fn remove_state(&mut self, target: usize, id: &str) {
+        if !self.combatants[target].has_state(id) {
+            return;
+        }
+        self.combatants[target].states.retain(|state| state.id != id);
+        self.events.push(BattleEvent::StateRemoved {
+            target: target,
+            state: String::from(id),
+        });
+    }
+
+    // The speed of everyone on a side who can still fight, together.
+    fn total_speed(&self, side: Side) -> u32 {
+        self.living(side).iter().map(|i| self.stats(*i).speed).sum()
+    }
+
+    // End the battle if either side has been wiped out.
+    fn check_end(&mut self) {
+        if let BattlePhase::Finished(_) = self.phase {
+            return;
+        }
+        if self.living(Side::Party).is_empty() {
+            self.finish(BattleOutcome::Defeat);
+        } else if self.living(Side::Troop).is_empty() {
+            self.finish(BattleOutcome::Victory);
+        }
+    }
+
+    fn finish(&mut self, outcome: BattleOutcome) {
+        self.phase = BattlePhase::Finished(outcome);
+        self.ready.clear();
+        self.events.push(BattleEvent::Ended(outcome));
+    }
+}
@@ -0,0 +1,55 @@
+use hlua::AnyLuaValue;
+
+use battle::combatant::*;
+use config::schema::ConfigValue;
+use scripting::convert::make_table;
+
+#[test]
+// Stats tables may leave out fields, but not get them wrong.
+fn test_stats_from_lua() {
+    let stats = Stats::from_lua(make_table(vec![
+        ("max_hp", AnyLuaValue::LuaNumber(50.0)),
+        ("speed", AnyLuaValue::LuaNumber(12.0)),
+    ])).unwrap();
+    assert_eq!(stats.max_hp, 50);
+    assert_eq!(stats.speed, 12);
+    assert_eq!(stats.attack, Stats::default().attack);
+
+    let wrong = make_table(vec![("attack", AnyLuaValue::LuaString(String::from("lots")))]);
+    assert_eq!(Stats::from_lua(wrong), None);
+    let dead = make_table(vec![("max_hp", AnyLuaValue::LuaNumber(0.0))]);
+    assert_eq!(Stats::from_lua(dead), None);
+    assert_eq!(Stats::from_lua(AnyLuaValue::LuaNumber(10.0)), None);
+}
+
+#[test]
+// HP should stay between zero and the maximum.
+fn test_damage_and_healing() {
+    let mut slime = Combatant::new("Slime", Side::Troop, Stats::default());
+    assert_eq!(slime.hp, 30);
+    assert_eq!(slime.take_damage(10), 10);
+    assert_eq!(slime.heal(50), 10);
+    assert_eq!(slime.hp, 30);
+    assert_eq!(slime.take_damage(100), 30);
+    assert!(!slime.is_alive());
+}
+
+#[test]
+// The difference between stats should be negative where they go down.
+fn test_stats_difference() {
+    let from = Stats::default();
+    let to = Stats {
+        attack: from.attack + 5,
+        speed: from.speed - 1,
+        ..from
+    };
+    assert_eq!(
+        from.difference(to),
+        StatBonus {
+            attack: 5,
+            speed: -1,
+            ..StatBonus::default()
+        }
+    );
+    assert_eq!(to.difference(from).attack, -5);
+}
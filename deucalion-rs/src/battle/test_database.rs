@@ -0,0 +1,196 @@
+use battle::combatant::Stats;
+use battle::database::*;
+use error::DeucalionError;
+use scripting::modules::get_module_roots;
+use scripting::sandbox::{get_default_script_libraries, get_sandboxed_environment};
+
+// Read a database from Lua source defining SKILLS and STATES.
+fn read_database(source: &str) -> (Database, Vec<DeucalionError>) {
+    let mut environment =
+        get_sandboxed_environment(&get_default_script_libraries(), &get_module_roots(&[]));
+    environment.execute::<()>(source).unwrap();
+    Database::from_environment(&mut environment)
+}
+
+// The entries the errors are about, in order.
+fn error_entries(errors: &[DeucalionError]) -> Vec<String> {
+    errors
+        .iter()
+        .map(|e| match *e {
+            DeucalionError::DatabaseError(ref entry, _) => entry.clone(),
+            ref e => panic!("Expected a database error, not {:?}", e),
+        })
+        .collect()
+}
+
+#[test]
+// Valid entries should be read, with names defaulting to the entry's.
+fn test_read_database() {
+    let (database, errors) = read_database(
+        r#"
+        SKILLS = {
+            fire = {
+                name = "Fire",
+                mp_cost = 4,
+                effect = "damage",
+                formula = "a.magic * 3 - b.defense",
+                add_states = { "burn" },
+            },
+            cure = { effect = "heal", formula = "a.magic + 10", variance = 0 },
+        }
+        STATES = {
+            burn = { name = "Burn", turns = 3, damage_per_turn = "b.max_hp / 10" },
+            sleep = { turns = 2, skip_turn = true, modifiers = { defense = 0.5 } },
+        }
+        "#,
+    );
+    assert!(errors.is_empty(), "Unexpected errors {:?}", errors);
+
+    let fire = database.skill("fire").unwrap();
+    assert_eq!(fire.name, "Fire");
+    assert_eq!(fire.mp_cost, 4);
+    assert_eq!(fire.effect, EffectKind::Damage);
+    assert_eq!(fire.add_states, vec![String::from("burn")]);
+    let cure = database.skill("cure").unwrap();
+    assert_eq!(cure.name, "cure");
+    assert_eq!(cure.effect, EffectKind::Heal);
+    assert_eq!(cure.variance, 0.0);
+
+    let sleep = database.status_effect("sleep").unwrap();
+    assert_eq!(sleep.name, "sleep");
+    assert!(sleep.skip_turn);
+    assert_eq!(sleep.modifiers.defense, 0.5);
+    assert_eq!(sleep.modifiers.attack, 1.0);
+    assert_eq!(database.status_effect("burn").unwrap().turns, 3);
+}
+
+#[test]
+// Every problem should be reported against its entry, and only the valid entries kept.
+fn test_invalid_entries() {
+    let (database, errors) = read_database(
+        r#"
+        SKILLS = {
+            fine = { effect = "damage", formula = "10" },
+            costly = { effect = "damage", formula = "10", mp_cost = -1 },
+            vague = { effect = "damage" },
+            muddled = { effect = "confuse", formula = "10" },
+            cursed = { effect = "damage", formula = "1", add_states = { "curse" } },
+            broken = { effect = "damage", formula = "a.magic +" },
+            wordy = { effect = "damage", formula = "'lots'" },
+            missing = { effect = "damage", formula = "a.luck * 2" },
+            [1] = { effect = "damage", formula = "10" },
+        }
+        STATES = {
+            poison = { damage_per_turn = "b.max_hp /" },
+            slow = { modifiers = { speed = -1 } },
+            stop = "frozen",
+        }
+        "#,
+    );
+    assert_eq!(database.skills.keys().collect::<Vec<_>>(), vec!["fine"]);
+    assert!(database.status_effects.is_empty());
+
+    let mut entries = error_entries(&errors);
+    entries.sort();
+    assert_eq!(
+        entries,
+        vec![
+            "skills.broken",
+            "skills.costly",
+            "skills.cursed",
+            "skills.missing",
+            "skills.muddled",
+            "skills.vague",
+            "skills.wordy",
+            "skills[LuaNumber(1.0)]",
+            "states.poison",
+            "states.slow",
+            "states.stop",
+        ]
+    );
+}
+
+#[test]
+// A database with no tables is empty, but a table that isn't a table is wrong.
+fn test_missing_tables() {
+    let (database, errors) = read_database("");
+    assert!(errors.is_empty());
+    assert_eq!(database, Database::default());
+
+    let (_, errors) = read_database("SKILLS = 'fire'");
+    assert_eq!(errors.len(), 1);
+    match errors[0] {
+        DeucalionError::LuaGetFailed(ref identifier, _) => assert_eq!(identifier, "SKILLS"),
+        ref e => panic!("Expected SKILLS to be wrong, not {:?}", e),
+    }
+}
+
+#[test]
+// Modifiers should scale the stats they name, and leave the rest alone.
+fn test_stat_modifiers() {
+    let modifiers = StatModifiers {
+        attack: 0.5,
+        speed: 1.5,
+        ..StatModifiers::default()
+    };
+    let stats = modifiers.apply(Stats::default());
+    assert_eq!(stats.attack, 5);
+    assert_eq!(stats.speed, 15);
+    assert_eq!(stats.defense, Stats::default().defense);
+    assert_eq!(stats.max_hp, Stats::default().max_hp);
+}
+
+#[test]
+// Items should be read with their kind and slot, and armor without a proper slot, or items
+// curing status effects that don't exist, left out.
+fn test_items() {
+    let (database, errors) = read_database(
+        r#"
+        STATES = { poison = { damage_per_turn = "b.max_hp / 10" } }
+        ITEMS = {
+            potion = { name = "Potion", kind = "consumable", hp = 50, stack_limit = 20 },
+            antidote = { kind = "consumable", remove_states = { "poison" } },
+            sword = { kind = "weapon", bonus = { attack = 6, speed = -1 } },
+            cap = { kind = "armor", slot = "head", bonus = { defense = 2 } },
+            key = { kind = "key" },
+            cloak = { kind = "armor" },
+            gauntlet = { kind = "armor", slot = "weapon" },
+            sharp_key = { kind = "key", slot = "head" },
+            elixir = { kind = "consumable", remove_states = { "doom" } },
+            pebble = { kind = "rock" },
+            heap = { kind = "consumable", stack_limit = 0 },
+        }
+        "#,
+    );
+    assert_eq!(
+        database.items.keys().collect::<Vec<_>>(),
+        vec!["antidote", "cap", "key", "potion", "sword"]
+    );
+    let potion = database.item("potion").unwrap();
+    assert_eq!(potion.name, "Potion");
+    assert_eq!(potion.kind, ItemKind::Consumable);
+    assert_eq!((potion.hp, potion.stack_limit), (50, 20));
+    assert_eq!(potion.equip_slot(), None);
+    let sword = database.item("sword").unwrap();
+    assert_eq!(sword.name, "sword");
+    assert_eq!(sword.equip_slot(), Some(EquipSlot::Weapon));
+    assert_eq!((sword.bonus.attack, sword.bonus.speed), (6, -1));
+    assert_eq!(sword.stack_limit, DEFAULT_STACK_LIMIT);
+    assert_eq!(database.item("cap").unwrap().equip_slot(), Some(EquipSlot::Head));
+    assert_eq!(database.item_name("potion"), "Potion");
+    assert_eq!(database.item_name("cloak"), "cloak");
+
+    let mut entries = error_entries(&errors);
+    entries.sort();
+    assert_eq!(
+        entries,
+        vec![
+            "items.cloak",
+            "items.elixir",
+            "items.gauntlet",
+            "items.heap",
+            "items.pebble",
+            "items.sharp_key",
+        ]
+    );
+}
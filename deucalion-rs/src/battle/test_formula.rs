@@ -0,0 +1,54 @@
+use battle::combatant::{Combatant, Side, Stats};
+use battle::formula::*;
+use utility::random::Random;
+
+#[test]
+// Damage should be twice the attack plus the power, less defense, never below 1.
+fn test_base_damage() {
+    assert_eq!(get_base_damage(10, 0, 5, false), 15);
+    assert_eq!(get_base_damage(10, 4, 5, false), 19);
+    assert_eq!(get_base_damage(10, 0, 5, true), 7);
+    assert_eq!(get_base_damage(1, 0, 50, false), 1);
+    assert_eq!(get_base_damage(1, 0, 50, true), 1);
+}
+
+#[test]
+// Varied amounts should stay within the variance.
+fn test_variance() {
+    let mut random = Random::with_seed(3);
+    for _ in 0..1000 {
+        let amount = vary(100, &mut random);
+        assert!(amount >= 90 && amount <= 110);
+        assert_eq!(vary(0, &mut random), 1);
+    }
+}
+
+#[test]
+// Fleeing should be likelier the faster the party is, and after failed attempts.
+fn test_flee_chance() {
+    assert_eq!(get_flee_chance(10, 10, 0), 0.5);
+    assert_eq!(get_flee_chance(30, 10, 0), 0.75);
+    assert!(get_flee_chance(10, 10, 2) > 0.5);
+    assert_eq!(get_flee_chance(10, 30, 10), 1.0);
+    assert_eq!(get_flee_chance(0, 0, 0), 0.5);
+}
+
+#[test]
+// Formulas should be given the user and target, and must come to a number.
+fn test_formula_environment() {
+    let mut formulas = FormulaEnvironment::new();
+    let mut hero = Combatant::new("Hero", Side::Party, Stats::default());
+    hero.hp = 12;
+    let slime = Combatant::new("Slime", Side::Troop, Stats::default());
+    let mut evaluate = |formula: &str| {
+        formulas.evaluate(formula, (&hero, &hero.stats), (&slime, &slime.stats))
+    };
+    assert_eq!(evaluate("a.attack * 2 - b.defense").unwrap(), 15.0);
+    assert_eq!(evaluate("math.max(a.hp, b.mp) -- a comment").unwrap(), 12.0);
+    assert!(evaluate("'lots'").is_err());
+    assert!(evaluate("a.luck * 2").is_err());
+    assert!(evaluate("a.attack +").is_err());
+    // Only the base and math libraries are open.
+    assert!(evaluate("os.time()").is_err());
+    assert!(evaluate("(function() while true do end end)()").is_err());
+}
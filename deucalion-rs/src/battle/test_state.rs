@@ -0,0 +1,400 @@
+use std::rc::Rc;
+
+use battle::combatant::{Combatant, Side, Stats};
+use battle::database::{Database, EffectKind, Item, ItemKind, Skill, StatModifiers,
+                       StatusEffect};
+use battle::state::*;
+use utility::random::Random;
+
+fn stats(max_hp: u32, attack: u32, defense: u32, speed: u32) -> Stats {
+    Stats {
+        max_hp: max_hp,
+        max_mp: 10,
+        attack: attack,
+        defense: defense,
+        magic: 5,
+        speed: speed,
+    }
+}
+
+fn hero(stats: Stats) -> Combatant {
+    Combatant::new("Hero", Side::Party, stats)
+}
+
+fn slime(stats: Stats) -> Combatant {
+    Combatant::new("Slime", Side::Troop, stats)
+}
+
+fn skill(mp_cost: u32, effect: EffectKind, formula: &str) -> Skill {
+    Skill {
+        mp_cost: mp_cost,
+        effect: effect,
+        formula: String::from(formula),
+        ..Skill::default()
+    }
+}
+
+fn new_battle(hero: Combatant, slime: Combatant, mode: TurnMode) -> Battle {
+    Battle::new(vec![hero], vec![slime], mode, Random::with_seed(1))
+}
+
+#[test]
+// The faster side should act first, and defeating every enemy should win the battle.
+fn test_victory() {
+    let mut battle = new_battle(
+        hero(stats(100, 20, 5, 20)),
+        slime(stats(10, 1, 0, 5)),
+        TurnMode::Turns,
+    );
+    assert!(battle.submit(BattleCommand::Guard).is_err());
+    battle.advance(0.0);
+    assert_eq!(battle.phase(), BattlePhase::WaitingForCommand(0));
+    assert!(battle.take_events().is_empty());
+
+    battle.submit(BattleCommand::Attack(1)).unwrap();
+    assert_eq!(
+        battle.take_events(),
+        vec![
+            BattleEvent::Attacked { user: 0, target: 1 },
+            BattleEvent::Damaged {
+                target: 1,
+                amount: 10,
+            },
+            BattleEvent::Defeated(1),
+            BattleEvent::Ended(BattleOutcome::Victory),
+        ]
+    );
+    assert_eq!(
+        battle.phase(),
+        BattlePhase::Finished(BattleOutcome::Victory)
+    );
+}
+
+#[test]
+// A faster enemy should act before the party, and losing every member should lose the battle.
+fn test_defeat() {
+    let mut battle = new_battle(
+        hero(stats(100, 20, 5, 5)),
+        slime(stats(100, 1, 0, 30)),
+        TurnMode::Turns,
+    );
+    battle.advance(0.0);
+    assert_eq!(
+        battle.take_events(),
+        vec![
+            BattleEvent::Attacked { user: 1, target: 0 },
+            BattleEvent::Damaged {
+                target: 0,
+                amount: 1,
+            },
+        ]
+    );
+    assert_eq!(battle.phase(), BattlePhase::WaitingForCommand(0));
+
+    let mut battle = new_battle(
+        hero(stats(1, 20, 5, 5)),
+        slime(stats(100, 50, 0, 30)),
+        TurnMode::Turns,
+    );
+    battle.advance(0.0);
+    assert_eq!(battle.phase(), BattlePhase::Finished(BattleOutcome::Defeat));
+    assert!(battle.submit(BattleCommand::Attack(1)).is_err());
+}
+
+#[test]
+// Guarding should halve the damage taken until the guard's next turn.
+fn test_guard() {
+    let mut battle = new_battle(
+        hero(stats(100, 1, 0, 20)),
+        slime(stats(1000, 10, 0, 5)),
+        TurnMode::Turns,
+    );
+    battle.advance(0.0);
+    battle.submit(BattleCommand::Guard).unwrap();
+    battle.advance(0.0);
+    let events = battle.take_events();
+    assert_eq!(events[0], BattleEvent::Guarded(0));
+    match events[2] {
+        BattleEvent::Damaged { target: 0, amount } => assert!(amount >= 9 && amount <= 11),
+        ref event => panic!("Expected the hero to be damaged, not {:?}", event),
+    }
+    // The hero's next turn has come around, so they're no longer guarding.
+    assert_eq!(battle.phase(), BattlePhase::WaitingForCommand(0));
+    assert!(!battle.combatants[0].guarding);
+}
+
+#[test]
+// Commands that can't be carried out should be refused, leaving the member to choose again.
+fn test_invalid_commands() {
+    let mut database = Database::default();
+    database.skills.insert(
+        String::from("fire"),
+        skill(4, EffectKind::Damage, "a.magic * 2 + 10 - b.defense"),
+    );
+    database
+        .skills
+        .insert(String::from("cure"), skill(3, EffectKind::Heal, "a.magic + 5"));
+    database
+        .skills
+        .insert(String::from("glitch"), skill(0, EffectKind::Damage, "a.luck"));
+    let items = vec![BattleItem {
+        name: String::from("potion"),
+        count: 1,
+        effect: Effect::Heal(20),
+    }];
+    let member = hero(stats(100, 1, 5, 20)).with_skills(vec![
+        String::from("fire"),
+        String::from("cure"),
+        String::from("quake"),
+        String::from("glitch"),
+    ]);
+    let mut battle = new_battle(member, slime(stats(1000, 1, 0, 5)), TurnMode::Turns)
+        .with_database(Rc::new(database))
+        .with_items(items);
+    battle.can_flee = false;
+    battle.advance(0.0);
+
+    let fire = |target| BattleCommand::Skill {
+        skill: String::from("fire"),
+        target: target,
+    };
+    let potion = || BattleCommand::Item {
+        item: String::from("potion"),
+        target: 0,
+    };
+    assert!(battle.submit(BattleCommand::Attack(0)).is_err());
+    assert!(battle.submit(BattleCommand::Attack(7)).is_err());
+    assert!(battle.submit(fire(0)).is_err());
+    assert!(battle
+        .submit(BattleCommand::Skill {
+            skill: String::from("ice"),
+            target: 1,
+        })
+        .is_err());
+    assert!(battle
+        .submit(BattleCommand::Skill {
+            skill: String::from("quake"),
+            target: 1,
+        })
+        .is_err());
+    // A formula that fails refuses the skill.
+    assert!(battle
+        .submit(BattleCommand::Skill {
+            skill: String::from("glitch"),
+            target: 1,
+        })
+        .is_err());
+    assert!(battle.submit(BattleCommand::Flee).is_err());
+    assert_eq!(battle.phase(), BattlePhase::WaitingForCommand(0));
+    assert!(battle.take_events().is_empty());
+
+    battle.submit(fire(1)).unwrap();
+    assert_eq!(battle.combatants[0].mp, 6);
+    assert!(battle.combatants[1].hp < 1000);
+    battle.advance(0.0);
+    battle.submit(fire(1)).unwrap();
+    battle.advance(0.0);
+    // Two MP isn't enough for cure.
+    assert!(battle
+        .submit(BattleCommand::Skill {
+            skill: String::from("cure"),
+            target: 0,
+        })
+        .is_err());
+    battle.submit(potion()).unwrap();
+    assert_eq!(battle.items()[0].count, 0);
+    battle.advance(0.0);
+    assert!(battle.submit(potion()).is_err());
+}
+
+#[test]
+// Status effects should hurt their sufferer, take their turns and change their stats at the
+// start of their turns, then wear off.
+fn test_status_effects() {
+    let mut database = Database::default();
+    let inflict = |state: &str| Skill {
+        add_states: vec![String::from(state)],
+        ..skill(0, EffectKind::Damage, "0")
+    };
+    database
+        .skills
+        .insert(String::from("poison"), inflict("poison"));
+    database.skills.insert(String::from("sleep"), inflict("sleep"));
+    database.skills.insert(String::from("weaken"), inflict("weak"));
+    database.status_effects.insert(
+        String::from("poison"),
+        StatusEffect {
+            turns: 2,
+            damage_per_turn: String::from("b.max_hp / 100"),
+            ..StatusEffect::default()
+        },
+    );
+    database.status_effects.insert(
+        String::from("sleep"),
+        StatusEffect {
+            turns: 1,
+            skip_turn: true,
+            ..StatusEffect::default()
+        },
+    );
+    database.status_effects.insert(
+        String::from("weak"),
+        StatusEffect {
+            modifiers: StatModifiers {
+                attack: 0.5,
+                ..StatModifiers::default()
+            },
+            ..StatusEffect::default()
+        },
+    );
+    let member = hero(stats(100, 1, 5, 20)).with_skills(vec![
+        String::from("poison"),
+        String::from("sleep"),
+        String::from("weaken"),
+    ]);
+    let mut battle = new_battle(member, slime(stats(1000, 10, 0, 5)), TurnMode::Turns)
+        .with_database(Rc::new(database));
+    let use_skill = |battle: &mut Battle, name: &str| {
+        battle
+            .submit(BattleCommand::Skill {
+                skill: String::from(name),
+                target: 1,
+            })
+            .unwrap()
+    };
+    battle.advance(0.0);
+
+    use_skill(&mut battle, "poison");
+    assert_eq!(
+        battle.take_events()[1],
+        BattleEvent::StateAdded {
+            target: 1,
+            state: String::from("poison"),
+        }
+    );
+    battle.advance(0.0);
+    assert_eq!(
+        battle.take_events()[0],
+        BattleEvent::Damaged {
+            target: 1,
+            amount: 10,
+        }
+    );
+
+    // The slime sleeps through its turn, and poison wears off after its second.
+    use_skill(&mut battle, "sleep");
+    battle.take_events();
+    battle.advance(0.0);
+    assert_eq!(
+        battle.take_events(),
+        vec![
+            BattleEvent::Damaged {
+                target: 1,
+                amount: 10,
+            },
+            BattleEvent::TurnSkipped(1),
+            BattleEvent::StateRemoved {
+                target: 1,
+                state: String::from("poison"),
+            },
+            BattleEvent::StateRemoved {
+                target: 1,
+                state: String::from("sleep"),
+            },
+        ]
+    );
+    assert_eq!(battle.combatants[1].hp, 980);
+    assert!(battle.combatants[1].states.is_empty());
+
+    // Weakness lasts for the rest of the battle.
+    use_skill(&mut battle, "weaken");
+    assert_eq!(battle.stats(1).attack, 5);
+    battle.advance(0.0);
+    assert!(battle.combatants[1].has_state("weak"));
+}
+
+#[test]
+// The party should always get away in the end, since each failure makes it likelier.
+fn test_flee() {
+    let mut battle = new_battle(
+        hero(stats(100, 1, 5, 1)),
+        slime(stats(1000, 1, 0, 1000)),
+        TurnMode::Turns,
+    );
+    for _ in 0..12 {
+        battle.advance(0.0);
+        if battle.phase() != BattlePhase::WaitingForCommand(0) {
+            break;
+        }
+        battle.submit(BattleCommand::Flee).unwrap();
+    }
+    assert_eq!(
+        battle.phase(),
+        BattlePhase::Finished(BattleOutcome::Escaped)
+    );
+}
+
+#[test]
+// In ATB mode, combatants should act when their gauges fill, and time should stand still while
+// a command is chosen.
+fn test_active_time() {
+    // The hero's gauge fills in two seconds, and the slime's in one.
+    let mut battle = new_battle(
+        hero(stats(100, 1, 5, 15)),
+        slime(stats(1000, 1, 0, 30)),
+        TurnMode::ActiveTime,
+    );
+    battle.advance(0.5);
+    assert_eq!(battle.phase(), BattlePhase::Running);
+    assert!(battle.take_events().is_empty());
+    assert_eq!(battle.combatants[0].gauge, 0.25);
+
+    battle.advance(0.5);
+    assert_eq!(battle.phase(), BattlePhase::Running);
+    assert_eq!(battle.take_events()[0], BattleEvent::Attacked { user: 1, target: 0 });
+    assert_eq!(battle.combatants[1].gauge, 0.0);
+
+    // Both gauges fill together; the hero is first in line.
+    battle.advance(1.0);
+    assert_eq!(battle.phase(), BattlePhase::WaitingForCommand(0));
+    battle.advance(5.0);
+    assert_eq!(battle.phase(), BattlePhase::WaitingForCommand(0));
+    assert!(battle.take_events().is_empty());
+
+    battle.submit(BattleCommand::Guard).unwrap();
+    assert_eq!(battle.combatants[0].gauge, 0.0);
+    battle.advance(0.0);
+    assert_eq!(battle.phase(), BattlePhase::Running);
+    assert_eq!(
+        battle.take_events()[1],
+        BattleEvent::Attacked { user: 1, target: 0 }
+    );
+}
+
+#[test]
+// Only consumables that restore HP should be usable in battle.
+fn test_battle_items() {
+    let potion = Item {
+        kind: ItemKind::Consumable,
+        hp: 50,
+        ..Item::default()
+    };
+    assert_eq!(
+        BattleItem::from_item("potion", &potion, 3),
+        Some(BattleItem {
+            name: String::from("potion"),
+            count: 3,
+            effect: Effect::Heal(50),
+        })
+    );
+    let antidote = Item {
+        hp: 0,
+        ..potion.clone()
+    };
+    assert_eq!(BattleItem::from_item("antidote", &antidote, 1), None);
+    let sword = Item {
+        kind: ItemKind::Weapon,
+        ..potion.clone()
+    };
+    assert_eq!(BattleItem::from_item("sword", &sword, 1), None);
+}
@@ -0,0 +1,74 @@
+//! Helpers shared by tests that need a database.
+
+use battle::combatant::StatBonus;
+use battle::database::{Database, EquipSlot, Item, ItemKind};
+
+/// A database of items for tests to buy, sell, carry, use and equip:
+///
+/// - a 50 gold potion restoring 20 HP;
+/// - a 100 gold sword adding 5 attack, and a 300 gold spear adding 8;
+/// - a helmet, carried one at a time, and a crown adding 20 max HP, both worn on the head;
+/// - a key, and a worthless rock, neither of which a shop will buy.
+pub fn item_database() -> Database {
+    let item = |name: &str, kind: ItemKind, price: u32| Item {
+        name: String::from(name),
+        kind: kind,
+        price: price,
+        ..Item::default()
+    };
+    let items = vec![
+        (
+            "potion",
+            Item {
+                hp: 20,
+                ..item("Potion", ItemKind::Consumable, 50)
+            },
+        ),
+        (
+            "sword",
+            Item {
+                bonus: StatBonus {
+                    attack: 5,
+                    ..StatBonus::default()
+                },
+                ..item("Sword", ItemKind::Weapon, 100)
+            },
+        ),
+        (
+            "spear",
+            Item {
+                bonus: StatBonus {
+                    attack: 8,
+                    ..StatBonus::default()
+                },
+                ..item("Spear", ItemKind::Weapon, 300)
+            },
+        ),
+        (
+            "helmet",
+            Item {
+                slot: Some(EquipSlot::Head),
+                stack_limit: 1,
+                ..item("Helmet", ItemKind::Armor, 0)
+            },
+        ),
+        (
+            "crown",
+            Item {
+                slot: Some(EquipSlot::Head),
+                bonus: StatBonus {
+                    max_hp: 20,
+                    ..StatBonus::default()
+                },
+                ..item("Crown", ItemKind::Armor, 0)
+            },
+        ),
+        ("key", item("Key", ItemKind::Key, 10)),
+        ("rock", item("Rock", ItemKind::Consumable, 0)),
+    ];
+    let mut database = Database::default();
+    for (id, item) in items {
+        database.items.insert(String::from(id), item);
+    }
+    database
+}
@@ -0,0 +1,113 @@
+//! Troops: the groups of enemies the party battles together.
+//!
+//! A troop is described by data/troops/<name>.lua, e.g.:
+//!
+//! ```lua
+//! ENEMIES = {
+//!     { name = "Slime", stats = { max_hp = 20, attack = 6, speed = 8 }, exp = 4 },
+//!     { name = "Slime", stats = { max_hp = 20, attack = 6, speed = 8 }, exp = 4 },
+//! }
+//! ```
+//!
+//! See `battle::combatant::Stats` for the stats an enemy may have. `exp` is the EXP the party
+//! earns for defeating the enemy, and may be left out.
+
+use hlua::AnyLuaValue;
+
+use battle::combatant::{Combatant, Side, Stats};
+use config::schema::{ConfigValue, Field, Schema};
+use error::DeucalionError;
+use resource::loading;
+use resource::ResourceKind;
+use scripting::budget::ScriptBudget;
+use scripting::convert::get_table_field;
+use scripting::execute_script_from_reader;
+use scripting::modules::get_module_roots;
+use scripting::sandbox::{get_default_script_libraries, get_sandboxed_environment};
+
+/// One enemy of a troop.
+#[derive(PartialEq, Debug, Clone)]
+pub struct EnemyInfo {
+    /// The enemy's name, as shown to the player.
+    pub name: String,
+    pub stats: Stats,
+    /// The EXP each surviving party member earns when the troop is defeated.
+    pub exp: u32,
+}
+
+impl ConfigValue for EnemyInfo {
+    fn type_name() -> String {
+        String::from("enemy (a table with name and, optionally, stats and exp)")
+    }
+
+    fn from_lua(value: AnyLuaValue) -> Option<EnemyInfo> {
+        let name = String::from_lua(get_table_field(&value, "name"))?;
+        let stats = match get_table_field(&value, "stats") {
+            AnyLuaValue::LuaNil => Stats::default(),
+            stats => Stats::from_lua(stats)?,
+        };
+        let exp = match get_table_field(&value, "exp") {
+            AnyLuaValue::LuaNil => 0,
+            exp => u32::from_lua(exp)?,
+        };
+        Some(EnemyInfo {
+            name: name,
+            stats: stats,
+            exp: exp,
+        })
+    }
+}
+
+/// A troop's enemies.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct Troop {
+    pub enemies: Vec<EnemyInfo>,
+}
+
+impl Troop {
+    /// Read a troop from data/troops/<name>.lua. A troop must have at least one enemy.
+    pub fn by_name(name: &str) -> Result<Troop, DeucalionError> {
+        let path = loading::get_resource_relative_path_by_name(ResourceKind::Troop, name)?;
+        // Like a character's info, it gets an environment of its own, which is thrown away once
+        //  it's been read.
+        let mut environment =
+            get_sandboxed_environment(&get_default_script_libraries(), &get_module_roots(&[]));
+        let reader = loading::open_data_file(&path)?;
+        execute_script_from_reader(&mut environment, reader, &path, &ScriptBudget::for_config())?;
+        let (troop, mut errors) = get_troop_schema().read(&mut environment, Troop::default());
+        match errors.pop() {
+            Some(e) => Err(e),
+            None => Ok(troop),
+        }
+    }
+
+    /// The troop's enemies, ready for battle. Enemies with the same name are told apart by a
+    /// letter after it, e.g. "Slime A" and "Slime B".
+    pub fn combatants(&self) -> Vec<Combatant> {
+        self.enemies
+            .iter()
+            .enumerate()
+            .map(|(i, enemy)| {
+                let same: Vec<usize> = (0..self.enemies.len())
+                    .filter(|j| self.enemies[*j].name == enemy.name)
+                    .collect();
+                let name = if same.len() > 1 {
+                    let letter = same.iter().position(|j| *j == i).unwrap_or(0);
+                    format!("{} {}", enemy.name, (b'A' + (letter % 26) as u8) as char)
+                } else {
+                    enemy.name.clone()
+                };
+                Combatant::new(&name, Side::Troop, enemy.stats).with_exp(enemy.exp)
+            })
+            .collect()
+    }
+}
+
+/// Describe how each field of a Troop is read from its script.
+pub fn get_troop_schema() -> Schema<Troop> {
+    Schema::new().field(
+        Field::new("ENEMIES", |t: &mut Troop| &mut t.enemies)
+            .required()
+            .check("with at least one enemy", |e| !e.is_empty()),
+    )
+}
@@ -0,0 +1,93 @@
+//! deucalion-pack: bundle a game's data directory into a single archive file.
+//!
+//! Usage: `deucalion-pack [data directory] [output archive]`. By default, `./data` is packed into
+//! `./data.pak`, which is where the engine looks for an archive when there is no data directory.
+
+#![allow(dead_code)]
+
+extern crate flate2;
+extern crate hlua;
+#[macro_use]
+extern crate log;
+extern crate env_logger;
+extern crate tiled;
+
+// The packer shares the engine's archive code, but none of the rest of it.
+#[path = "../error.rs"]
+mod error;
+#[path = "../resource/archive.rs"]
+mod archive;
+
+use std::env;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+
+fn fake_main() -> i32 {
+    env_logger::init().unwrap();
+
+    let mut args = env::args().skip(1);
+    let data_directory = PathBuf::from(args.next().unwrap_or_else(|| String::from("data")));
+    let output = PathBuf::from(args.next().unwrap_or_else(|| {
+        format!("{}.{}", "data", archive::ARCHIVE_EXTENSION)
+    }));
+
+    let builder = match archive::ArchiveBuilder::from_directory(&data_directory) {
+        Ok(b) => b,
+        Err(e) => {
+            error!("Failed to read the data directory {}: {}", data_directory.display(), e);
+            return 1;
+        }
+    };
+
+    // The map loader reads external tilesets straight from the filesystem, so they can't be read
+    //  out of an archive. Refuse to pack them rather than write an archive whose maps won't load.
+    let external: Vec<&str> = builder
+        .files()
+        .filter(|&(name, contents)| name.ends_with(".tmx") && uses_external_tileset(contents))
+        .map(|(name, _)| name)
+        .collect();
+    if !external.is_empty() {
+        error!(
+            "These maps use external (.tsx) tilesets, which can't be read from an archive; embed \
+             their tilesets in the maps instead: {}",
+            external.join(", ")
+        );
+        return 1;
+    }
+
+    let mut writer = match File::create(&output) {
+        Ok(f) => BufWriter::new(f),
+        Err(e) => {
+            error!("Failed to create the archive {}: {}", output.display(), e);
+            return 1;
+        }
+    };
+    if let Err(e) = builder.write(&mut writer) {
+        error!("Failed to write the archive {}: {}", output.display(), e);
+        return 1;
+    }
+
+    info!(
+        "Packed {} files from {} into {}",
+        builder.len(),
+        data_directory.display(),
+        output.display()
+    );
+    return 0;
+}
+
+// Whether a TMX map refers to a tileset in another file, which Tiled writes as a `<tileset>`
+//  element with a `source` attribute.
+fn uses_external_tileset(map: &[u8]) -> bool {
+    let map = String::from_utf8_lossy(map);
+    map.split("<tileset").skip(1).any(|element| {
+        let tag = element.split('>').next().unwrap_or("");
+        tag.split_whitespace()
+            .any(|attribute| attribute.starts_with("source="))
+    })
+}
+
+fn main() {
+    std::process::exit(fake_main());
+}
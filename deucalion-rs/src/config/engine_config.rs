@@ -1,11 +1,106 @@
 //! Functions for managing and acquiring engine configuration
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use dirs;
+use hlua::AnyLuaValue;
+
+use config::game_config::GameConfig;
 use error::DeucalionError;
-use scripting::{execute_script, get_value_by_identifier, Lua};
+use config::schema::{ConfigValue, Field, Schema};
+use scripting::{execute_script, execute_script_from_reader, Lua};
+use scripting::budget::ScriptBudget;
+use scripting::serialize::{quote_string, to_lua_source};
 use resource;
-// TODO: Make this work with the Lua subsystem.
+
+/// How the game's window is presented.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum WindowMode {
+    /// A normal, decorated window of the configured size.
+    Windowed,
+    /// Exclusive fullscreen at the configured size.
+    Fullscreen,
+    /// An undecorated window covering the whole desktop, at the desktop's resolution.
+    Borderless,
+}
+
+impl WindowMode {
+    /// Parse a window mode from the name used for it in engine_config.lua.
+    pub fn from_name(name: &str) -> Option<WindowMode> {
+        match name {
+            "windowed" => Some(WindowMode::Windowed),
+            "fullscreen" => Some(WindowMode::Fullscreen),
+            "borderless" => Some(WindowMode::Borderless),
+            _ => None,
+        }
+    }
+
+    /// The name used for this window mode in engine_config.lua.
+    pub fn name(self) -> &'static str {
+        match self {
+            WindowMode::Windowed => "windowed",
+            WindowMode::Fullscreen => "fullscreen",
+            WindowMode::Borderless => "borderless",
+        }
+    }
+}
+
+impl ConfigValue for WindowMode {
+    fn type_name() -> String {
+        String::from("one of windowed, fullscreen or borderless")
+    }
+
+    fn from_lua(value: AnyLuaValue) -> Option<WindowMode> {
+        String::from_lua(value).and_then(|name| WindowMode::from_name(&name))
+    }
+}
+
+/// How the internal render resolution is scaled up to fill the window.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum ScalingMode {
+    /// Scale by the largest whole number that fits, keeping pixels crisp, and letterbox the rest.
+    Integer,
+    /// Scale as large as possible while keeping the aspect ratio, and letterbox the rest.
+    Letterbox,
+    /// Stretch to fill the whole window, ignoring the aspect ratio.
+    Stretch,
+}
+
+impl ScalingMode {
+    /// Parse a scaling mode from the name used for it in engine_config.lua.
+    pub fn from_name(name: &str) -> Option<ScalingMode> {
+        match name {
+            "integer" => Some(ScalingMode::Integer),
+            "letterbox" => Some(ScalingMode::Letterbox),
+            "stretch" => Some(ScalingMode::Stretch),
+            _ => None,
+        }
+    }
+
+    /// The name used for this scaling mode in engine_config.lua.
+    pub fn name(self) -> &'static str {
+        match self {
+            ScalingMode::Integer => "integer",
+            ScalingMode::Letterbox => "letterbox",
+            ScalingMode::Stretch => "stretch",
+        }
+    }
+}
+
+impl ConfigValue for ScalingMode {
+    fn type_name() -> String {
+        String::from("one of integer, letterbox or stretch")
+    }
+
+    fn from_lua(value: AnyLuaValue) -> Option<ScalingMode> {
+        String::from_lua(value).and_then(|name| ScalingMode::from_name(&name))
+    }
+}
 
 /// A datastructure containing configuration details for the engine
-#[derive(PartialEq, Eq, Debug)]
+#[derive(PartialEq, Eq, Debug, Clone)]
 pub struct EngineConfig {
     /// The width of the window in which the engine will run.
     pub screen_width: u32,
@@ -13,84 +108,272 @@ pub struct EngineConfig {
     pub screen_height: u32,
     /// The maximum framerate at which the engine should attempt to run.
     pub maximum_framerate: u32,
+    /// Whether the window is windowed, fullscreen or borderless.
+    pub window_mode: WindowMode,
+    /// Whether to synchronize frames with the monitor's refresh rate.
+    pub vsync: bool,
+    /// How the render resolution is scaled to fit the window.
+    pub scaling_mode: ScalingMode,
+    /// The width at which the game is rendered, before scaling.
+    pub render_width: u32,
+    /// The height at which the game is rendered, before scaling.
+    pub render_height: u32,
+    /// The volume of all sound, from 0 to 100.
+    pub master_volume: u32,
+    /// The volume of music, from 0 to 100, relative to the master volume.
+    pub music_volume: u32,
+    /// The volume of sound effects, from 0 to 100, relative to the master volume.
+    pub sfx_volume: u32,
+    /// The language to display the game in, as a language code like "en".
+    pub language: String,
+    /// The keys bound to each input action, by action name and then key name. See `input`.
+    pub key_bindings: BTreeMap<String, Vec<String>>,
 }
 
-/// Acquire the engine's configuration. If acquiring it from data/engine_config.lua fails,
-/// this function will return the default configuration, which will typically work for every
-/// configuration.
-pub fn get_engine_config(environment: &mut Lua) -> EngineConfig {
-    let path = match resource::loading::get_resource_path_by_name(
+/// Acquire the engine's configuration. The shipped data/engine_config.lua is run first, then the
+/// player's own configuration at `user_config_path` (as written by the options menu), if it
+/// exists, so that the player's settings override the shipped ones. Anything neither of them sets
+/// correctly takes its default value.
+pub fn get_engine_config(environment: &mut Lua, user_config_path: Option<&Path>) -> EngineConfig {
+    match resource::loading::get_resource_reader_and_path_by_name(
         resource::ResourceKind::EngineConfig,
         "",
     ) {
-        Ok(v) => v,
+        // The reader may come from the data directory or a data archive; either way, the path
+        //  is only used for logging.
+        Ok((reader, path)) => match execute_script_from_reader(
+            environment,
+            reader,
+            &path,
+            &ScriptBudget::for_config(),
+        ) {
+            Ok(_) => info!("Ran engine config script at {}", path.to_string_lossy()),
+            Err(e) => error!(
+                "Failed to run engine config script at {}: {}",
+                path.to_string_lossy(),
+                e
+            ),
+        },
         Err(e) => {
-            // If the engine config's path can't be generated, it certainly can't be loaded.
+            // If the engine config can't be opened, it certainly can't be loaded.
             error!(
-                "Failed to generate the path for the engine configuration script: {}",
+                "Failed to open the engine configuration script: {}",
                 e
             );
-            return get_default_engine_config();
         }
-    };
-
-    // Syntax monster explaination for the &* in the second argument to execute_script:
-    //  to_string_lossy returns a Cow<str>, which we dereference to str and then borrow to &str
-    match execute_script(environment, &*path.to_string_lossy()) {
-        Ok(_) => {
-            match get_engine_config_from_environment(environment) {
-                Ok(w) => {
-                    info!(
-                        "Succesfully acquired engine config at {}",
-                        path.to_string_lossy()
-                    );
-                    trace!("Engine config is {:?}", w);
-                    w // This is the valid EngineConfig struct built from the script
-                }
-                Err(e) => {
-                    error!(
-                        "Failed to acquire engine config from script at {}: {}",
-                        path.to_string_lossy(),
-                        e
-                    );
-                    get_default_engine_config()
-                }
+    }
+
+    if let Some(user_config_path) = user_config_path {
+        if user_config_path.is_file() {
+            match execute_script(
+                environment,
+                &*user_config_path.to_string_lossy(),
+                &ScriptBudget::for_config(),
+            ) {
+                Ok(_) => info!(
+                    "Ran the player's engine config script at {}",
+                    user_config_path.display()
+                ),
+                Err(e) => error!(
+                    "Failed to run the player's engine config script at {}: {}",
+                    user_config_path.display(),
+                    e
+                ),
             }
         }
-        Err(e) => {
-            error!(
-                "Failed to run engine config script at {}: {}",
-                path.to_string_lossy(),
-                e
-            );
-            get_default_engine_config()
-        }
     }
+
+    // Values missing from the scripts are individually replaced by their defaults.
+    let config = get_engine_config_from_environment(environment);
+    trace!("Engine config is {:?}", config);
+    config
 }
 
-/// Check variables in the Lua environment, bringing their values into an EngineConfig struct
-fn get_engine_config_from_environment(
-    environment: &mut Lua,
-) -> Result<EngineConfig, DeucalionError> {
-    // Here, we have a set of match expressions that attempt to fetch the global config variables.
-    // At some point, this action (extract Lua global or error) should be abstracted into a macro
-    let screen_width = get_value_by_identifier(environment, "SCREEN_WIDTH")?;
-    let screen_height = get_value_by_identifier(environment, "SCREEN_HEIGHT")?;
-    let maximum_framerate = get_value_by_identifier(environment, "MAXIMUM_FRAMERATE")?;
-    // Simply build the EngineConfig struct. Making it to this point means the config is O.K.
-    Ok(EngineConfig {
-        screen_height: screen_height,
-        screen_width: screen_width,
-        maximum_framerate: maximum_framerate,
+/// Get the path of the player's engine configuration for a game, in the per-user configuration
+/// directory (e.g. ~/.config/deucalion/<title>/engine_config.lua on Linux). Returns None if the
+/// platform has no such directory.
+pub fn get_user_engine_config_path(game_config: &GameConfig) -> Option<PathBuf> {
+    dirs::config_dir().map(|directory| {
+        directory
+            .join("deucalion")
+            .join(get_game_directory_name(game_config))
+            .join("engine_config.lua")
     })
 }
 
+/// Get the name of the directory a game's per-user files, such as the player's engine
+/// configuration and saves, are kept in: its title, with any characters that aren't allowed in
+/// file names replaced.
+pub fn get_game_directory_name(game_config: &GameConfig) -> String {
+    game_config
+        .title
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' { c } else { '_' })
+        .collect()
+}
+
+/// Write the configuration to the player's engine configuration file, creating its directory if
+/// needed.
+pub fn save_user_engine_config(config: &EngineConfig, path: &Path) -> Result<(), DeucalionError> {
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+    let mut file = File::create(path)?;
+    file.write_all(engine_config_to_lua(config).as_bytes())?;
+    info!("Saved the player's engine config to {}", path.display());
+    Ok(())
+}
+
+/// Write the configuration as a Lua script that sets the same globals engine_config.lua does.
+pub fn engine_config_to_lua(config: &EngineConfig) -> String {
+    let key_bindings = AnyLuaValue::LuaArray(
+        config
+            .key_bindings
+            .iter()
+            .map(|(action, keys)| {
+                let keys = keys.iter()
+                    .enumerate()
+                    .map(|(i, key)| {
+                        (
+                            AnyLuaValue::LuaNumber((i + 1) as f64),
+                            AnyLuaValue::LuaString(key.clone()),
+                        )
+                    })
+                    .collect();
+                (
+                    AnyLuaValue::LuaString(action.clone()),
+                    AnyLuaValue::LuaArray(keys),
+                )
+            })
+            .collect(),
+    );
+
+    let mut source = String::from(
+        "-- Player Engine Configuration\n\
+         -- This file was written by the options menu, and overrides the game's engine_config.lua\n",
+    );
+    source.push_str(&format!("SCREEN_WIDTH = {}\n", config.screen_width));
+    source.push_str(&format!("SCREEN_HEIGHT = {}\n", config.screen_height));
+    source.push_str(&format!("MAXIMUM_FRAMERATE = {}\n", config.maximum_framerate));
+    source.push_str(&format!(
+        "WINDOW_MODE = {}\n",
+        quote_string(config.window_mode.name())
+    ));
+    source.push_str(&format!("VSYNC = {}\n", config.vsync));
+    source.push_str(&format!("RENDER_WIDTH = {}\n", config.render_width));
+    source.push_str(&format!("RENDER_HEIGHT = {}\n", config.render_height));
+    source.push_str(&format!(
+        "SCALING_MODE = {}\n",
+        quote_string(config.scaling_mode.name())
+    ));
+    source.push_str(&format!("MASTER_VOLUME = {}\n", config.master_volume));
+    source.push_str(&format!("MUSIC_VOLUME = {}\n", config.music_volume));
+    source.push_str(&format!("SFX_VOLUME = {}\n", config.sfx_volume));
+    source.push_str(&format!("LANGUAGE = {}\n", quote_string(&config.language)));
+    source.push_str(&format!(
+        "KEY_BINDINGS = {}\n",
+        to_lua_source(&key_bindings, 0)
+    ));
+    source
+}
+
+/// Describe how each field of an EngineConfig is read from engine_config.lua.
+pub fn get_engine_config_schema() -> Schema<EngineConfig> {
+    Schema::new()
+        .field(
+            Field::new("SCREEN_WIDTH", |c: &mut EngineConfig| &mut c.screen_width)
+                .range(1, 16384),
+        )
+        .field(
+            Field::new("SCREEN_HEIGHT", |c: &mut EngineConfig| &mut c.screen_height)
+                .range(1, 16384),
+        )
+        .field(
+            Field::new("MAXIMUM_FRAMERATE", |c: &mut EngineConfig| &mut c.maximum_framerate)
+                .range(1, 1000),
+        )
+        .field(Field::new("WINDOW_MODE", |c: &mut EngineConfig| &mut c.window_mode))
+        .field(Field::new("VSYNC", |c: &mut EngineConfig| &mut c.vsync))
+        .field(Field::new("SCALING_MODE", |c: &mut EngineConfig| &mut c.scaling_mode))
+        // The render resolution defaults to the window's size, so that games which don't care
+        //  about scaling get one screen pixel per game pixel.
+        .field(
+            Field::new("RENDER_WIDTH", |c: &mut EngineConfig| &mut c.render_width)
+                .range(1, 16384)
+                .default_from(|c| c.screen_width),
+        )
+        .field(
+            Field::new("RENDER_HEIGHT", |c: &mut EngineConfig| &mut c.render_height)
+                .range(1, 16384)
+                .default_from(|c| c.screen_height),
+        )
+        // Volumes are percentages.
+        .field(
+            Field::new("MASTER_VOLUME", |c: &mut EngineConfig| &mut c.master_volume)
+                .range(0, 100),
+        )
+        .field(
+            Field::new("MUSIC_VOLUME", |c: &mut EngineConfig| &mut c.music_volume)
+                .range(0, 100),
+        )
+        .field(
+            Field::new("SFX_VOLUME", |c: &mut EngineConfig| &mut c.sfx_volume)
+                .range(0, 100),
+        )
+        .field(Field::new("LANGUAGE", |c: &mut EngineConfig| &mut c.language))
+        .field(Field::new("KEY_BINDINGS", |c: &mut EngineConfig| &mut c.key_bindings))
+}
+
+/// Check variables in the Lua environment, bringing their values into an EngineConfig struct.
+/// Any value that is missing or invalid is replaced by its default, and each invalid one is
+/// reported to the developer.
+fn get_engine_config_from_environment(environment: &mut Lua) -> EngineConfig {
+    let (config, errors) =
+        get_engine_config_schema().read(environment, get_default_engine_config());
+    for e in errors.iter() {
+        warn!("Invalid engine config value, using its default: {}", e);
+    }
+    config
+}
+
+/// Get the default keys bound to each action.
+pub fn get_default_key_bindings() -> BTreeMap<String, Vec<String>> {
+    let defaults: [(&str, &[&str]); 7] = [
+        ("up", &["Up", "W"]),
+        ("down", &["Down", "S"]),
+        ("left", &["Left", "A"]),
+        ("right", &["Right", "D"]),
+        ("confirm", &["Z", "Return", "Space"]),
+        ("cancel", &["X", "Escape"]),
+        ("menu", &["C", "Tab"]),
+    ];
+    defaults
+        .iter()
+        .map(|&(action, keys)| {
+            (
+                String::from(action),
+                keys.iter().map(|k| String::from(*k)).collect(),
+            )
+        })
+        .collect()
+}
+
 /// Get the engine's default configuration state. This cannot fail.
-fn get_default_engine_config() -> EngineConfig {
+pub fn get_default_engine_config() -> EngineConfig {
     EngineConfig {
         screen_width: 640,
         screen_height: 480,
         maximum_framerate: 60,
+        window_mode: WindowMode::Windowed,
+        vsync: true,
+        scaling_mode: ScalingMode::Integer,
+        render_width: 640,
+        render_height: 480,
+        master_volume: 100,
+        music_volume: 80,
+        sfx_volume: 80,
+        language: String::from("en"),
+        key_bindings: get_default_key_bindings(),
     }
 }
 
@@ -104,11 +387,15 @@ mod tests {
         env.execute::<()>("SCREEN_WIDTH=1\nSCREEN_HEIGHT=2\nMAXIMUM_FRAMERATE=3")
             .unwrap();
         // Extract the values
-        let cfg = super::get_engine_config_from_environment(&mut env).unwrap();
+        let cfg = super::get_engine_config_from_environment(&mut env);
         let desired_cfg = super::EngineConfig {
             screen_width: 1,
             screen_height: 2,
             maximum_framerate: 3,
+            // The render resolution follows the window size unless it's set
+            render_width: 1,
+            render_height: 2,
+            ..super::get_default_engine_config()
         };
         assert_eq!(
             cfg,
@@ -118,4 +405,56 @@ mod tests {
             cfg
         );
     }
+
+    #[test]
+    fn test_engine_config_extended_values() {
+        use scripting::get_scripting_environment;
+        use super::{ScalingMode, WindowMode};
+        let mut env = get_scripting_environment();
+        env.execute::<()>(
+            "WINDOW_MODE='borderless'\nVSYNC=false\nSCALING_MODE='stretch'\n\
+             RENDER_WIDTH=320\nRENDER_HEIGHT=240\nMUSIC_VOLUME=150\n\
+             KEY_BINDINGS={confirm={'Return'}, cancel='Escape'}",
+        ).unwrap();
+        let cfg = super::get_engine_config_from_environment(&mut env);
+        assert_eq!(cfg.window_mode, WindowMode::Borderless);
+        assert_eq!(cfg.vsync, false);
+        assert_eq!(cfg.scaling_mode, ScalingMode::Stretch);
+        assert_eq!((cfg.render_width, cfg.render_height), (320, 240));
+        // Out-of-range volumes keep their default
+        assert_eq!(cfg.music_volume, super::get_default_engine_config().music_volume);
+        assert_eq!(cfg.key_bindings["confirm"], vec![String::from("Return")]);
+        assert_eq!(cfg.key_bindings["cancel"], vec![String::from("Escape")]);
+    }
+
+    #[test]
+    fn test_engine_config_invalid_values_use_defaults() {
+        use scripting::get_scripting_environment;
+        let mut env = get_scripting_environment();
+        // An empty environment should produce the default config, field by field
+        let cfg = super::get_engine_config_from_environment(&mut env);
+        assert_eq!(cfg, super::get_default_engine_config());
+        // And a single bad value shouldn't affect the others
+        env.execute::<()>("SCREEN_WIDTH=800\nWINDOW_MODE='sideways'").unwrap();
+        let cfg = super::get_engine_config_from_environment(&mut env);
+        assert_eq!(cfg.screen_width, 800);
+        assert_eq!(cfg.window_mode, super::get_default_engine_config().window_mode);
+    }
+
+    #[test]
+    fn test_engine_config_lua_round_trip() {
+        use scripting::get_scripting_environment;
+        use super::{ScalingMode, WindowMode};
+        let mut cfg = super::get_default_engine_config();
+        cfg.window_mode = WindowMode::Fullscreen;
+        cfg.scaling_mode = ScalingMode::Letterbox;
+        cfg.sfx_volume = 30;
+        cfg.language = String::from("pt-BR");
+        cfg.key_bindings.insert(String::from("menu"), vec![String::from("M")]);
+
+        // Reading back the written config should give the same config
+        let mut env = get_scripting_environment();
+        env.execute::<()>(&super::engine_config_to_lua(&cfg)).unwrap();
+        assert_eq!(super::get_engine_config_from_environment(&mut env), cfg);
+    }
 }
@@ -1,57 +1,174 @@
 //! Functions for managing and acquiring game configuration
-use error::DeucalionError;
-use scripting::{execute_script, Lua};
+use std::time::Duration;
+
+use hlua::AnyLuaValue;
+
+use config::schema::{ConfigValue, Field, Schema};
+use scripting::{execute_script_from_reader, Lua};
+use scripting::budget::ScriptBudget;
+use scripting::modules;
+use scripting::sandbox::{get_default_script_libraries, LuaLibrary};
+use world::movement::MovementMode;
+use battle::state::TurnMode;
+use game_state::MAX_GOLD;
 use resource;
+use utility::random::Random;
+
+/// A command on the title screen. See `scene::title`.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum TitleCommand {
+    NewGame,
+    Continue,
+    Options,
+    Quit,
+}
+
+impl TitleCommand {
+    /// The commands shown unless the game config says otherwise, in order.
+    pub fn defaults() -> Vec<TitleCommand> {
+        vec![
+            TitleCommand::NewGame,
+            TitleCommand::Continue,
+            TitleCommand::Options,
+            TitleCommand::Quit,
+        ]
+    }
+
+    /// Look up a command by the name the game config uses for it.
+    pub fn from_name(name: &str) -> Option<TitleCommand> {
+        match name {
+            "new_game" => Some(TitleCommand::NewGame),
+            "continue" => Some(TitleCommand::Continue),
+            "options" => Some(TitleCommand::Options),
+            "quit" => Some(TitleCommand::Quit),
+            _ => None,
+        }
+    }
+
+    /// The text shown for the command.
+    pub fn label(&self) -> &'static str {
+        match *self {
+            TitleCommand::NewGame => "New Game",
+            TitleCommand::Continue => "Continue",
+            TitleCommand::Options => "Options",
+            TitleCommand::Quit => "Quit",
+        }
+    }
+}
+
+impl ConfigValue for TitleCommand {
+    fn type_name() -> String {
+        String::from("one of new_game, continue, options or quit")
+    }
+
+    fn from_lua(value: AnyLuaValue) -> Option<TitleCommand> {
+        String::from_lua(value).and_then(|name| TitleCommand::from_name(&name))
+    }
+}
 
 /// A datastructure containing configuration details for the game
-#[derive(PartialEq, Eq, Debug)]
+#[derive(PartialEq, Eq, Debug, Clone)]
 pub struct GameConfig {
     /// The game's title
     pub title: String,
     /// The first map to load when starting the game
     pub starting_map: String,
+    /// The characters following the player when a new game starts, who join the party with them.
+    pub starting_party: Vec<String>,
+    /// How much gold the party starts a new game with.
+    pub starting_gold: u32,
+    /// The picture shown behind the title screen, or empty for a plain background.
+    pub title_background: String,
+    /// The music played on the title screen, or empty for silence.
+    pub title_music: String,
+    /// The commands on the title screen, in order.
+    pub title_commands: Vec<TitleCommand>,
+    /// The picture shown on the game over screen, or empty for a plain background.
+    pub game_over_background: String,
+    /// The music played on the game over screen, or empty for silence.
+    pub game_over_music: String,
+    /// The Lua standard libraries game scripts may use. See `scripting::sandbox`.
+    pub script_libraries: Vec<LuaLibrary>,
+    /// Directories, relative to the data directory, that mods keep their script modules in.
+    /// They are searched before data/scripts. See `scripting::modules`.
+    pub mod_roots: Vec<String>,
+    /// How many Lua instructions a single game script invocation may run, or 0 for no limit.
+    pub script_instruction_limit: u32,
+    /// How many milliseconds a single game script invocation may run for, or 0 for no limit.
+    pub script_time_limit: u32,
+    /// Whether the game is being developed, rather than played: script errors are shown on
+    /// screen, among other things.
+    pub developer_mode: bool,
+    /// How actors move, unless a map says otherwise. See `world::movement`.
+    pub movement_mode: MovementMode,
+    /// Whether actors may move diagonally, unless a map says otherwise.
+    pub diagonal_movement: bool,
+    /// How turns come around in battles. See `battle::state`.
+    pub battle_mode: TurnMode,
+    /// What random encounters are rolled from, so that playtests can be repeated, or 0 for
+    /// different encounters every time. See `world::encounter`.
+    pub encounter_seed: u32,
+    /// How many save slots the player can save the game in.
+    pub save_slots: u32,
+    /// The windowskin the UI's windows are drawn with, or empty for plain windows. See
+    /// `ui::skin`.
+    pub windowskin: String,
+}
+
+impl GameConfig {
+    /// The budget each game script invocation runs within.
+    pub fn script_budget(&self) -> ScriptBudget {
+        ScriptBudget {
+            instructions: match self.script_instruction_limit {
+                0 => None,
+                limit => Some(u64::from(limit)),
+            },
+            time: match self.script_time_limit {
+                0 => None,
+                limit => Some(Duration::from_millis(u64::from(limit))),
+            },
+        }
+    }
+
+    /// The generator random encounters are rolled with.
+    pub fn encounter_random(&self) -> Random {
+        match self.encounter_seed {
+            0 => Random::from_time(),
+            seed => Random::with_seed(u64::from(seed)),
+        }
+    }
 }
 
 /// Acquire the game's configuration. If acquiring it from data/game_config.lua fails,
 /// this function will return the default configuration, which is for debugging.
 pub fn get_game_config(environment: &mut Lua) -> GameConfig {
-    let path = match resource::loading::get_resource_path_by_name(
+    let (reader, path) = match resource::loading::get_resource_reader_and_path_by_name(
         resource::ResourceKind::GameConfig,
         "",
     ) {
         Ok(v) => v,
         Err(e) => {
-            // If the game config's path can't be generated, it certainly can't be loaded.
+            // If the game config can't be opened, it certainly can't be loaded.
             error!(
-                "Failed to generate the path for the game configuration script: {}",
+                "Failed to open the game configuration script: {}",
                 e
             );
             return get_default_game_config();
         }
     };
 
-    // Syntax monster explaination for the &* in the second argument to execute_script:
-    //  to_string_lossy returns a Cow<str>, which we dereference to str and then borrow to &str
-    match execute_script(environment, &*path.to_string_lossy()) {
+    // The reader may come from the data directory or a data archive; either way, the path is
+    //  only used for logging.
+    match execute_script_from_reader(environment, reader, &path, &ScriptBudget::for_config()) {
         Ok(_) => {
-            match get_game_config_from_environment(environment) {
-                Ok(w) => {
-                    info!(
-                        "Succesfully acquired game config at {}",
-                        path.to_string_lossy()
-                    );
-                    trace!("Game config is {:?}", w);
-                    w // This is the valid EngineConfig struct built from the script
-                }
-                Err(e) => {
-                    error!(
-                        "Failed to acquire game config from script at {}: {}",
-                        path.to_string_lossy(),
-                        e
-                    );
-                    get_default_game_config()
-                }
-            }
+            // Values missing from the script are individually replaced by their defaults.
+            let config = get_game_config_from_environment(environment);
+            info!(
+                "Succesfully acquired game config at {}",
+                path.to_string_lossy()
+            );
+            trace!("Game config is {:?}", config);
+            config
         }
         Err(e) => {
             error!(
@@ -64,37 +181,80 @@ pub fn get_game_config(environment: &mut Lua) -> GameConfig {
     }
 }
 
-/// Check variables in the Lua environment, bringing their values into a GameConfig struct
-fn get_game_config_from_environment(environment: &mut Lua) -> Result<GameConfig, DeucalionError> {
-    // Here, we have a set of match expressions that attempt to fetch the global config variables.
-    // At some point, this action (extract Lua global or error) should be abstracted into a macro
-    let title: String = match environment.get("TITLE") {
-        Some(v) => v,
-        None => {
-            return Err(DeucalionError::from(
-                "TITLE is not defined or is the wrong type",
-            ));
-        }
-    };
-    let starting_map: String = match environment.get("STARTING_MAP") {
-        Some(v) => v,
-        None => {
-            return Err(DeucalionError::from(
-                "STARTING_MAP is not defined or is the wrong type",
-            ));
-        }
-    };
-    // Simply build the EngineConfig struct. Making it to this point means the config is O.K.
-    Ok(GameConfig {
-        title: title,
-        starting_map: starting_map,
-    })
+/// Describe how each field of a GameConfig is read from game_config.lua.
+pub fn get_game_config_schema() -> Schema<GameConfig> {
+    Schema::new()
+        .field(Field::new("TITLE", |c: &mut GameConfig| &mut c.title).required())
+        .field(Field::new("STARTING_MAP", |c: &mut GameConfig| &mut c.starting_map).required())
+        .field(Field::new("STARTING_PARTY", |c: &mut GameConfig| &mut c.starting_party))
+        .field(
+            Field::new("STARTING_GOLD", |c: &mut GameConfig| &mut c.starting_gold)
+                .range(0, MAX_GOLD),
+        )
+        .field(Field::new("TITLE_BACKGROUND", |c: &mut GameConfig| &mut c.title_background))
+        .field(Field::new("TITLE_MUSIC", |c: &mut GameConfig| &mut c.title_music))
+        .field(
+            Field::new("TITLE_COMMANDS", |c: &mut GameConfig| &mut c.title_commands)
+                .check("not empty", |commands| !commands.is_empty()),
+        )
+        .field(Field::new(
+            "GAME_OVER_BACKGROUND",
+            |c: &mut GameConfig| &mut c.game_over_background,
+        ))
+        .field(Field::new("GAME_OVER_MUSIC", |c: &mut GameConfig| &mut c.game_over_music))
+        .field(Field::new("SCRIPT_LIBRARIES", |c: &mut GameConfig| &mut c.script_libraries))
+        .field(
+            Field::new("MOD_ROOTS", |c: &mut GameConfig| &mut c.mod_roots)
+                .check("relative to the data directory, without '..'", |roots| {
+                    roots.iter().all(|root| modules::is_valid_mod_root(root))
+                }),
+        )
+        .field(Field::new(
+            "SCRIPT_INSTRUCTION_LIMIT",
+            |c: &mut GameConfig| &mut c.script_instruction_limit,
+        ))
+        .field(Field::new("SCRIPT_TIME_LIMIT", |c: &mut GameConfig| &mut c.script_time_limit))
+        .field(Field::new("DEVELOPER_MODE", |c: &mut GameConfig| &mut c.developer_mode))
+        .field(Field::new("MOVEMENT_MODE", |c: &mut GameConfig| &mut c.movement_mode))
+        .field(Field::new("DIAGONAL_MOVEMENT", |c: &mut GameConfig| &mut c.diagonal_movement))
+        .field(Field::new("BATTLE_MODE", |c: &mut GameConfig| &mut c.battle_mode))
+        .field(Field::new("ENCOUNTER_SEED", |c: &mut GameConfig| &mut c.encounter_seed))
+        .field(Field::new("SAVE_SLOTS", |c: &mut GameConfig| &mut c.save_slots).range(1, 99))
+        .field(Field::new("WINDOWSKIN", |c: &mut GameConfig| &mut c.windowskin))
+}
+
+/// Check variables in the Lua environment, bringing their values into a GameConfig struct.
+/// Each value that is invalid, or missing but required, is reported and replaced by its default.
+fn get_game_config_from_environment(environment: &mut Lua) -> GameConfig {
+    let (config, errors) = get_game_config_schema().read(environment, get_default_game_config());
+    for e in errors.iter() {
+        error!("Invalid game config value, using its default: {}", e);
+    }
+    config
 }
 
-/// Get the engine's default configuration state. This cannot fail.
+/// Get the game's default configuration state. This cannot fail.
 fn get_default_game_config() -> GameConfig {
     GameConfig {
         title: String::from("Untitled"),
         starting_map: String::from("map001"),
+        starting_party: Vec::new(),
+        starting_gold: 0,
+        title_background: String::new(),
+        title_music: String::new(),
+        title_commands: TitleCommand::defaults(),
+        game_over_background: String::new(),
+        game_over_music: String::new(),
+        script_libraries: get_default_script_libraries(),
+        mod_roots: Vec::new(),
+        script_instruction_limit: 10_000_000,
+        script_time_limit: 1000,
+        developer_mode: false,
+        movement_mode: MovementMode::Grid,
+        diagonal_movement: false,
+        battle_mode: TurnMode::Turns,
+        encounter_seed: 0,
+        save_slots: 3,
+        windowskin: String::new(),
     }
 }
@@ -1,3 +1,6 @@
 //! This module provides all necessary datatypes and functions to acquire configuration data.
 pub mod engine_config;
 pub mod game_config;
+pub mod schema;
+#[cfg(test)]
+mod test_schema;
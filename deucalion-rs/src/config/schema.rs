@@ -0,0 +1,319 @@
+//! Declarative descriptions of how a configuration struct is read from the globals of a Lua
+//! environment. A `Schema` lists each field's Lua identifier, where it lives in the struct, and
+//! how it is validated; reading it checks every field, keeps the default for each one that is
+//! invalid, and reports all of the problems at once rather than stopping at the first.
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use hlua::{AnyLuaValue, Lua};
+
+use error::DeucalionError;
+
+/// A type that a configuration value can be read as.
+pub trait ConfigValue: Sized {
+    /// A description of the type, for error messages, e.g. "string" or "integer".
+    fn type_name() -> String;
+
+    /// Convert a Lua value to this type, or return None if it can't be.
+    fn from_lua(value: AnyLuaValue) -> Option<Self>;
+}
+
+impl ConfigValue for bool {
+    fn type_name() -> String {
+        String::from("boolean")
+    }
+
+    fn from_lua(value: AnyLuaValue) -> Option<bool> {
+        match value {
+            AnyLuaValue::LuaBoolean(b) => Some(b),
+            _ => None,
+        }
+    }
+}
+
+impl ConfigValue for String {
+    fn type_name() -> String {
+        String::from("string")
+    }
+
+    fn from_lua(value: AnyLuaValue) -> Option<String> {
+        match value {
+            AnyLuaValue::LuaString(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+impl ConfigValue for f64 {
+    fn type_name() -> String {
+        String::from("number")
+    }
+
+    fn from_lua(value: AnyLuaValue) -> Option<f64> {
+        match value {
+            AnyLuaValue::LuaNumber(n) => Some(n),
+            _ => None,
+        }
+    }
+}
+
+impl ConfigValue for u32 {
+    fn type_name() -> String {
+        String::from("non-negative integer")
+    }
+
+    fn from_lua(value: AnyLuaValue) -> Option<u32> {
+        match value {
+            AnyLuaValue::LuaNumber(n)
+                if n.fract() == 0.0 && n >= 0.0 && n <= u32::max_value() as f64 =>
+            {
+                Some(n as u32)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl ConfigValue for i32 {
+    fn type_name() -> String {
+        String::from("integer")
+    }
+
+    fn from_lua(value: AnyLuaValue) -> Option<i32> {
+        match value {
+            AnyLuaValue::LuaNumber(n)
+                if n.fract() == 0.0 && n >= i32::min_value() as f64
+                    && n <= i32::max_value() as f64 =>
+            {
+                Some(n as i32)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl ConfigValue for AnyLuaValue {
+    fn type_name() -> String {
+        String::from("any value")
+    }
+
+    fn from_lua(value: AnyLuaValue) -> Option<AnyLuaValue> {
+        Some(value)
+    }
+}
+
+impl<V: ConfigValue> ConfigValue for Option<V> {
+    fn type_name() -> String {
+        V::type_name()
+    }
+
+    // Nil leaves the field alone, so this only reads a value that's given.
+    fn from_lua(value: AnyLuaValue) -> Option<Option<V>> {
+        V::from_lua(value).map(Some)
+    }
+}
+
+impl<V: ConfigValue> ConfigValue for Vec<V> {
+    fn type_name() -> String {
+        format!("list of {} values", V::type_name())
+    }
+
+    fn from_lua(value: AnyLuaValue) -> Option<Vec<V>> {
+        let mut entries = match value {
+            AnyLuaValue::LuaArray(entries) => entries,
+            _ => return None,
+        };
+        // Tables don't come out of Lua in any particular order, so sort by index.
+        let mut indexed = Vec::with_capacity(entries.len());
+        for (key, value) in entries.drain(..) {
+            match key {
+                AnyLuaValue::LuaNumber(i) => indexed.push((i, V::from_lua(value)?)),
+                _ => return None,
+            }
+        }
+        indexed.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(::std::cmp::Ordering::Equal));
+        Some(indexed.into_iter().map(|(_, v)| v).collect())
+    }
+}
+
+impl ConfigValue for BTreeMap<String, Vec<String>> {
+    fn type_name() -> String {
+        String::from("table of names to lists of strings")
+    }
+
+    fn from_lua(value: AnyLuaValue) -> Option<BTreeMap<String, Vec<String>>> {
+        let entries = match value {
+            AnyLuaValue::LuaArray(entries) => entries,
+            _ => return None,
+        };
+        let mut map = BTreeMap::new();
+        for (name, list) in entries {
+            let name = match name {
+                AnyLuaValue::LuaString(name) => name,
+                _ => return None,
+            };
+            let list = match list {
+                // A single string may be given without wrapping it in a table.
+                AnyLuaValue::LuaString(s) => vec![s],
+                list => Vec::<String>::from_lua(list)?,
+            };
+            map.insert(name, list);
+        }
+        Some(map)
+    }
+}
+
+/// One field of a configuration struct `T`, holding a value of type `V`.
+pub struct Field<T, V> {
+    identifier: &'static str,
+    accessor: fn(&mut T) -> &mut V,
+    required: bool,
+    default: Option<fn(&T) -> V>,
+    // Each check, and a description of what it requires, for error messages.
+    checks: Vec<(String, Box<Fn(&V) -> bool>)>,
+}
+
+impl<T, V: ConfigValue> Field<T, V> {
+    /// Describe a field read from the global `identifier` into the part of the struct returned
+    /// by `accessor`. If the global is missing, the struct's existing value is kept.
+    pub fn new(identifier: &'static str, accessor: fn(&mut T) -> &mut V) -> Field<T, V> {
+        Field {
+            identifier: identifier,
+            accessor: accessor,
+            required: false,
+            default: None,
+            checks: Vec::new(),
+        }
+    }
+
+    /// Report the field as an error if the global is missing, rather than silently keeping the
+    /// existing value.
+    pub fn required(mut self) -> Field<T, V> {
+        self.required = true;
+        self
+    }
+
+    /// If the global is missing or invalid, compute the value from the fields read before this
+    /// one, rather than keeping the existing value.
+    pub fn default_from(mut self, default: fn(&T) -> V) -> Field<T, V> {
+        self.default = Some(default);
+        self
+    }
+
+    /// Only accept values for which `check` returns true. `description` says what is required,
+    /// e.g. "a multiple of 8", and is used in error messages.
+    pub fn check<F>(mut self, description: &str, check: F) -> Field<T, V>
+    where
+        F: Fn(&V) -> bool + 'static,
+    {
+        self.checks.push((String::from(description), Box::new(check)));
+        self
+    }
+
+    /// Describe the expected type, including any checks, for error messages.
+    fn expected(&self) -> String {
+        let mut expected = V::type_name();
+        for &(ref description, _) in self.checks.iter() {
+            expected.push_str(" ");
+            expected.push_str(description);
+        }
+        expected
+    }
+}
+
+impl<T, V: ConfigValue + PartialOrd + Display + 'static> Field<T, V> {
+    /// Only accept values from `minimum` to `maximum`, inclusive.
+    pub fn range(self, minimum: V, maximum: V) -> Field<T, V> {
+        let description = format!("from {} to {}", minimum, maximum);
+        self.check(&description, move |v| *v >= minimum && *v <= maximum)
+    }
+}
+
+/// A field of a configuration struct `T`, whatever the type of its value.
+trait FieldReader<T> {
+    /// Read the field into the config, getting values by their identifier with `lookup`,
+    /// leaving the config alone and returning an error if the value is invalid.
+    fn read(
+        &self,
+        lookup: &mut FnMut(&str) -> Option<AnyLuaValue>,
+        config: &mut T,
+    ) -> Result<(), DeucalionError>;
+}
+
+impl<T, V: ConfigValue> FieldReader<T> for Field<T, V> {
+    fn read(
+        &self,
+        lookup: &mut FnMut(&str) -> Option<AnyLuaValue>,
+        config: &mut T,
+    ) -> Result<(), DeucalionError> {
+        let error = || DeucalionError::LuaGetFailed(String::from(self.identifier), self.expected());
+        let result = match lookup(self.identifier) {
+            Some(AnyLuaValue::LuaNil) | None => {
+                if self.required {
+                    Err(error())
+                } else {
+                    Ok(())
+                }
+            }
+            Some(value) => match V::from_lua(value) {
+                Some(ref value) if !self.checks.iter().all(|&(_, ref check)| check(value)) => {
+                    Err(error())
+                }
+                Some(value) => {
+                    *(self.accessor)(config) = value;
+                    return Ok(());
+                }
+                None => Err(error()),
+            },
+        };
+        // Without a valid value, fall back to the computed default, if there is one.
+        if let Some(default) = self.default {
+            let value = default(config);
+            *(self.accessor)(config) = value;
+        }
+        result
+    }
+}
+
+/// A description of every field of a configuration struct `T`.
+pub struct Schema<T> {
+    fields: Vec<Box<FieldReader<T>>>,
+}
+
+impl<T: 'static> Schema<T> {
+    /// Create a schema with no fields.
+    pub fn new() -> Schema<T> {
+        Schema { fields: Vec::new() }
+    }
+
+    /// Add a field. Fields are read in the order they are added.
+    pub fn field<V: ConfigValue + 'static>(mut self, field: Field<T, V>) -> Schema<T> {
+        self.fields.push(Box::new(field));
+        self
+    }
+
+    /// Read every field from the environment into `config`, which should hold the defaults.
+    /// Fields that are invalid keep their default (or get their computed one, see
+    /// `Field::default_from`), and every problem is returned as a
+    /// `DeucalionError::LuaGetFailed`, in the order the fields were added.
+    pub fn read(&self, environment: &mut Lua, config: T) -> (T, Vec<DeucalionError>) {
+        self.read_from(|identifier| environment.get::<AnyLuaValue, _>(identifier), config)
+    }
+
+    /// Read every field into `config` like `read`, but getting values by their identifier with
+    /// `lookup` rather than from the globals of an environment, such as from the properties of
+    /// a map object.
+    pub fn read_from<F>(&self, mut lookup: F, mut config: T) -> (T, Vec<DeucalionError>)
+    where
+        F: FnMut(&str) -> Option<AnyLuaValue>,
+    {
+        let mut errors = Vec::new();
+        for field in self.fields.iter() {
+            if let Err(e) = field.read(&mut lookup, &mut config) {
+                errors.push(e);
+            }
+        }
+        (config, errors)
+    }
+}
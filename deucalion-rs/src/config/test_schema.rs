@@ -0,0 +1,125 @@
+use config::schema::*;
+use error::DeucalionError;
+use hlua::AnyLuaValue;
+use scripting::get_scripting_environment;
+
+#[derive(PartialEq, Debug)]
+struct TestConfig {
+    name: String,
+    count: u32,
+    doubled: u32,
+    tags: Vec<String>,
+}
+
+fn default_config() -> TestConfig {
+    TestConfig {
+        name: String::from("default"),
+        count: 5,
+        doubled: 10,
+        tags: vec![String::from("a")],
+    }
+}
+
+fn schema() -> Schema<TestConfig> {
+    Schema::new()
+        .field(Field::new("NAME", |c: &mut TestConfig| &mut c.name).required())
+        .field(Field::new("COUNT", |c: &mut TestConfig| &mut c.count).range(1, 10))
+        .field(
+            Field::new("DOUBLED", |c: &mut TestConfig| &mut c.doubled)
+                .default_from(|c| c.count * 2),
+        )
+        .field(Field::new("TAGS", |c: &mut TestConfig| &mut c.tags))
+}
+
+#[test]
+// Valid values should all be read.
+fn test_schema_reads_values() {
+    let mut environment = get_scripting_environment();
+    environment
+        .execute::<()>("NAME = 'test'\nCOUNT = 3\nTAGS = {'x', 'y', 'z'}")
+        .unwrap();
+    let (config, errors) = schema().read(&mut environment, default_config());
+    assert!(errors.is_empty(), "Unexpected errors {:?}", errors);
+    assert_eq!(
+        config,
+        TestConfig {
+            name: String::from("test"),
+            count: 3,
+            // Computed from COUNT, since DOUBLED is missing
+            doubled: 6,
+            tags: vec![String::from("x"), String::from("y"), String::from("z")],
+        }
+    );
+}
+
+#[test]
+// Every invalid field should be reported, and only those should keep their defaults.
+fn test_schema_reports_every_error() {
+    let mut environment = get_scripting_environment();
+    environment
+        .execute::<()>("COUNT = 11\nDOUBLED = 'four'\nTAGS = {'ok'}")
+        .unwrap();
+    let (config, errors) = schema().read(&mut environment, default_config());
+    assert_eq!(config.name, "default");
+    assert_eq!(config.count, 5);
+    assert_eq!(config.doubled, 10);
+    assert_eq!(config.tags, vec![String::from("ok")]);
+
+    let identifiers: Vec<&str> = errors
+        .iter()
+        .map(|e| match *e {
+            DeucalionError::LuaGetFailed(ref identifier, _) => identifier.as_str(),
+            _ => panic!("Expected LuaGetFailed, got {:?}", e),
+        })
+        .collect();
+    assert_eq!(identifiers, vec!["NAME", "COUNT", "DOUBLED"]);
+    // The range should be part of the expected type
+    match errors[1] {
+        DeucalionError::LuaGetFailed(_, ref expected) => {
+            assert_eq!(expected, "non-negative integer from 1 to 10")
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+// A computed default should replace an invalid value as well as a missing one, and the invalid
+// value should still be reported.
+fn test_schema_invalid_value_uses_computed_default() {
+    let mut environment = get_scripting_environment();
+    environment
+        .execute::<()>("NAME = 'test'\nCOUNT = 4\nDOUBLED = 'four'")
+        .unwrap();
+    let (config, errors) = schema().read(&mut environment, default_config());
+    assert_eq!(config.doubled, 8);
+    assert_eq!(errors.len(), 1);
+    match errors[0] {
+        DeucalionError::LuaGetFailed(ref identifier, _) => assert_eq!(identifier, "DOUBLED"),
+        ref e => panic!("Expected LuaGetFailed, got {:?}", e),
+    }
+}
+
+#[test]
+// Fractional and negative numbers aren't valid unsigned integers.
+fn test_u32_conversion() {
+    use hlua::AnyLuaValue;
+    assert_eq!(u32::from_lua(AnyLuaValue::LuaNumber(4.0)), Some(4));
+    assert_eq!(u32::from_lua(AnyLuaValue::LuaNumber(4.5)), None);
+    assert_eq!(u32::from_lua(AnyLuaValue::LuaNumber(-1.0)), None);
+}
+
+#[test]
+// Values can come from somewhere other than an environment's globals.
+fn test_schema_reads_from_lookup() {
+    let (config, errors) = schema().read_from(
+        |identifier| match identifier {
+            "NAME" => Some(AnyLuaValue::LuaString(String::from("looked up"))),
+            "COUNT" => Some(AnyLuaValue::LuaNumber(4.0)),
+            _ => None,
+        },
+        default_config(),
+    );
+    assert!(errors.is_empty(), "Unexpected errors {:?}", errors);
+    assert_eq!(config.name, "looked up");
+    assert_eq!(config.doubled, 8);
+}
@@ -0,0 +1,41 @@
+//! The order things on a map are drawn in, so that characters can walk behind trees and houses.
+//!
+//! A map is drawn in three passes: tiles under the characters, then everything that stands on
+//! the map (actors and tile objects) sorted by where their feet are, so that whatever is lower
+//! down the screen is in front, and then tiles over the characters. A tile layer is drawn over
+//! the characters if it has the custom property `above_characters` set to true; a tile is drawn
+//! over them wherever it's placed if its tileset gives it that property, like the tops of trees.
+
+use std::cmp::Ordering;
+
+/// The property that puts a tile layer, or a tile, over the characters.
+pub const ABOVE_CHARACTERS_PROPERTY: &'static str = "above_characters";
+
+/// When a tile is drawn, relative to the characters.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum TilePass {
+    /// Before the characters, so they stand in front of it.
+    Under,
+    /// After the characters, so they walk behind it.
+    Over,
+}
+
+/// Work out which pass a tile is drawn in, from whether its layer and the tile itself are
+/// above the characters.
+pub fn get_tile_pass(layer_above: bool, tile_above: bool) -> TilePass {
+    if layer_above || tile_above {
+        TilePass::Over
+    } else {
+        TilePass::Under
+    }
+}
+
+/// Sort things standing on the map into the order they're drawn in, by the Y of their feet,
+/// back to front. Things with their feet level keep the order they were given in.
+pub fn sort_by_depth<T, F>(items: &mut [T], foot: F)
+where
+    F: Fn(&T) -> f32,
+{
+    // sort_by is stable, so ties keep their order.
+    items.sort_by(|a, b| foot(a).partial_cmp(&foot(b)).unwrap_or(Ordering::Equal));
+}
@@ -0,0 +1,89 @@
+//! An overlay, drawn over everything else in developer mode, showing the script errors that have
+//! happened recently, so the developer doesn't have to go digging through the log for them.
+
+use std::time::{Duration, Instant};
+
+use sfml::graphics::{Color, Font, RectangleShape, RenderTarget, Shape, Text, Transformable};
+use sfml::system::Vector2f;
+
+use error::LuaScriptError;
+use geom::ScreenSize;
+
+/// How long an error stays on screen.
+pub const ERROR_DISPLAY_TIME: Duration = Duration::from_secs(10);
+
+/// How many errors are shown at once; older ones are dropped.
+pub const MAXIMUM_ERRORS: usize = 3;
+
+/// How many lines of each traceback are shown.
+pub const TRACEBACK_LINES: usize = 6;
+
+/// The recent script errors, and when they happened.
+pub struct DeveloperOverlay {
+    errors: Vec<(Instant, LuaScriptError)>,
+}
+
+impl DeveloperOverlay {
+    /// Create an overlay with no errors to show.
+    pub fn new() -> DeveloperOverlay {
+        DeveloperOverlay { errors: Vec::new() }
+    }
+
+    /// Show an error.
+    pub fn push(&mut self, error: LuaScriptError) {
+        self.errors.push((Instant::now(), error));
+        if self.errors.len() > MAXIMUM_ERRORS {
+            self.errors.remove(0);
+        }
+    }
+
+    /// The errors currently being shown, oldest first.
+    pub fn errors(&self) -> Vec<&LuaScriptError> {
+        self.errors.iter().map(|&(_, ref error)| error).collect()
+    }
+
+    /// Get the lines of text describing the errors currently being shown.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        for &(_, ref error) in self.errors.iter() {
+            match error.line {
+                Some(line) => lines.push(format!("{}:{}: {}", error.file, line, error.message)),
+                None => lines.push(format!("{}: {}", error.file, error.message)),
+            }
+            for frame in error.traceback.lines().take(TRACEBACK_LINES) {
+                lines.push(format!("    {}", frame.trim()));
+            }
+        }
+        lines
+    }
+
+    /// Forget errors that have been shown for long enough.
+    pub fn expire(&mut self) {
+        self.errors
+            .retain(|&(shown_at, _)| shown_at.elapsed() < ERROR_DISPLAY_TIME);
+    }
+
+    /// Draw the errors over the top of everything else.
+    pub fn draw<T: RenderTarget>(&mut self, target: &mut T, font: &Font, size: ScreenSize) {
+        self.expire();
+        if self.errors.is_empty() {
+            return;
+        }
+        let lines = self.lines();
+        let line_height = 14.0;
+
+        let mut background = RectangleShape::with_size(Vector2f::new(
+            size.width as f32,
+            line_height * lines.len() as f32 + 8.0,
+        ));
+        background.set_fill_color(&Color::rgba(64, 0, 0, 224));
+        target.draw(&background);
+
+        for (i, line) in lines.iter().enumerate() {
+            let mut text = Text::new(line, font, 12);
+            text.set_position((4.0, 4.0 + line_height * i as f32));
+            text.set_fill_color(&Color::WHITE);
+            target.draw(&text);
+        }
+    }
+}
@@ -0,0 +1,154 @@
+//! The game's window, and the off-screen canvas the game is rendered to before being scaled up
+//! to fill it.
+
+use sfml::graphics::{Color, RenderTarget, RenderTexture, RenderWindow, Sprite, Transformable};
+use sfml::window::{ContextSettings, Style as WindowStyle, VideoMode};
+
+use config::engine_config::{EngineConfig, ScalingMode, WindowMode};
+use error::DeucalionError;
+use geom::ScreenSize;
+
+pub mod depth;
+pub mod developer_overlay;
+pub mod scaling;
+#[cfg(test)]
+mod test_depth;
+#[cfg(test)]
+mod test_scaling;
+
+/// The window, plus the canvas everything is drawn on. The canvas is always the configured
+/// render resolution; `present` scales it into the window according to the scaling mode.
+pub struct Display {
+    pub window: RenderWindow,
+    pub canvas: RenderTexture,
+    scaling_mode: ScalingMode,
+    title: String,
+    // The configuration the window and canvas were last created from.
+    config: EngineConfig,
+}
+
+impl Display {
+    /// Open the window and create the canvas as described by the engine configuration.
+    pub fn new(config: &EngineConfig, title: &str) -> Result<Display, DeucalionError> {
+        let window = create_window(config, title);
+        let canvas = create_canvas(config)?;
+        Ok(Display {
+            window: window,
+            canvas: canvas,
+            scaling_mode: config.scaling_mode,
+            title: String::from(title),
+            config: config.clone(),
+        })
+    }
+
+    /// Apply a changed configuration while the game is running. The window is only reopened if
+    /// its size, mode or vsync setting changed, and the canvas only recreated if the render
+    /// resolution changed.
+    pub fn apply_config(&mut self, config: &EngineConfig) -> Result<(), DeucalionError> {
+        if config.render_width != self.config.render_width
+            || config.render_height != self.config.render_height
+        {
+            self.canvas = create_canvas(config)?;
+        } else if config.scaling_mode != self.config.scaling_mode {
+            self.canvas.set_smooth(config.scaling_mode != ScalingMode::Integer);
+        }
+
+        if config.screen_width != self.config.screen_width
+            || config.screen_height != self.config.screen_height
+            || config.window_mode != self.config.window_mode
+            || config.vsync != self.config.vsync
+        {
+            // SFML can't change a window's style in place, so the window has to be reopened.
+            self.window.close();
+            self.window = create_window(config, &self.title);
+        } else if config.maximum_framerate != self.config.maximum_framerate && !config.vsync {
+            self.window.set_framerate_limit(config.maximum_framerate);
+        }
+
+        self.scaling_mode = config.scaling_mode;
+        self.config = config.clone();
+        Ok(())
+    }
+
+    /// The size everything should be laid out for, regardless of the window's actual size.
+    pub fn render_size(&self) -> ScreenSize {
+        let size = self.canvas.size();
+        ScreenSize::new(size.x, size.y)
+    }
+
+    /// Draw the finished canvas into the window, scaled and letterboxed, and show it.
+    pub fn present(&mut self) {
+        self.canvas.display();
+        let window_size = self.window.size();
+        let viewport = scaling::compute_viewport(
+            ScreenSize::new(window_size.x, window_size.y),
+            self.render_size(),
+            self.scaling_mode,
+        );
+        let mut frame = Sprite::with_texture(self.canvas.texture());
+        frame.set_position((viewport.offset_x, viewport.offset_y));
+        frame.set_scale((viewport.scale_x, viewport.scale_y));
+
+        // Letterboxing is always black, whatever the game's background is.
+        self.window.clear(&Color::BLACK);
+        self.window.draw(&frame);
+        self.window.display();
+    }
+}
+
+/// Open a window according to the window mode, size and vsync settings of the configuration.
+fn create_window(config: &EngineConfig, title: &str) -> RenderWindow {
+    let desktop = VideoMode::desktop_mode();
+    let requested = VideoMode::new(
+        config.screen_width,
+        config.screen_height,
+        desktop.bits_per_pixel,
+    );
+    let (mode, style) = match config.window_mode {
+        WindowMode::Windowed => (requested, WindowStyle::CLOSE),
+        WindowMode::Fullscreen => {
+            if requested.is_valid() {
+                (requested, WindowStyle::FULLSCREEN)
+            } else {
+                error!(
+                    "{}x{} is not a valid fullscreen resolution; using the desktop's instead",
+                    config.screen_width, config.screen_height
+                );
+                (desktop, WindowStyle::FULLSCREEN)
+            }
+        }
+        WindowMode::Borderless => (desktop, WindowStyle::NONE),
+    };
+    debug!(
+        "Opening a {}x{} {} window",
+        mode.width,
+        mode.height,
+        config.window_mode.name()
+    );
+
+    let mut window = RenderWindow::new(mode, title, style, &ContextSettings::default());
+    window.set_vertical_sync_enabled(config.vsync);
+    // Vsync already limits the framerate, and combining it with a framerate limit causes
+    // stuttering, so only one of them is used.
+    if !config.vsync {
+        window.set_framerate_limit(config.maximum_framerate);
+    }
+    window
+}
+
+/// Create the off-screen canvas at the configured render resolution.
+fn create_canvas(config: &EngineConfig) -> Result<RenderTexture, DeucalionError> {
+    let mut canvas = match RenderTexture::new(config.render_width, config.render_height, false) {
+        Some(c) => c,
+        None => {
+            return Err(DeucalionError::from(format!(
+                "could not create a {}x{} render canvas",
+                config.render_width, config.render_height
+            )))
+        }
+    };
+    // Only whole-number scaling is pixel-perfect; smoothing makes the other modes look less
+    // blocky at odd scale factors.
+    canvas.set_smooth(config.scaling_mode != ScalingMode::Integer);
+    Ok(canvas)
+}
@@ -0,0 +1,54 @@
+//! Computation of where the internal render resolution ends up in the window.
+
+use config::engine_config::ScalingMode;
+use geom::ScreenSize;
+
+/// Where and how large the rendered frame is drawn in the window.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct ScaledViewport {
+    /// The horizontal distance from the window's left edge to the frame, in window pixels.
+    pub offset_x: f32,
+    /// The vertical distance from the window's top edge to the frame, in window pixels.
+    pub offset_y: f32,
+    /// The horizontal scale factor applied to the frame.
+    pub scale_x: f32,
+    /// The vertical scale factor applied to the frame.
+    pub scale_y: f32,
+}
+
+/// Compute how a frame rendered at `render_size` should be placed in a window of `window_size`.
+/// Whatever the frame doesn't cover is left as letterboxing.
+pub fn compute_viewport(
+    window_size: ScreenSize,
+    render_size: ScreenSize,
+    mode: ScalingMode,
+) -> ScaledViewport {
+    let horizontal = window_size.width as f32 / render_size.width as f32;
+    let vertical = window_size.height as f32 / render_size.height as f32;
+    let (scale_x, scale_y) = match mode {
+        ScalingMode::Stretch => (horizontal, vertical),
+        ScalingMode::Letterbox => {
+            let scale = horizontal.min(vertical);
+            (scale, scale)
+        }
+        ScalingMode::Integer => {
+            let scale = horizontal.min(vertical).floor();
+            // A window smaller than the render resolution can't fit even one whole multiple, so
+            // shrink the frame rather than cropping it.
+            let scale = if scale >= 1.0 {
+                scale
+            } else {
+                horizontal.min(vertical)
+            };
+            (scale, scale)
+        }
+    };
+    // Centre the frame. Offsets are rounded to whole pixels so integer scaling stays crisp.
+    ScaledViewport {
+        offset_x: ((window_size.width as f32 - render_size.width as f32 * scale_x) / 2.0).floor(),
+        offset_y: ((window_size.height as f32 - render_size.height as f32 * scale_y) / 2.0)
+            .floor(),
+        scale_x: scale_x,
+        scale_y: scale_y,
+    }
+}
@@ -0,0 +1,19 @@
+use display::depth::*;
+
+#[test]
+// A tile goes over the characters if either its layer or the tile itself says so.
+fn test_tile_pass() {
+    assert_eq!(get_tile_pass(false, false), TilePass::Under);
+    assert_eq!(get_tile_pass(true, false), TilePass::Over);
+    assert_eq!(get_tile_pass(false, true), TilePass::Over);
+    assert_eq!(get_tile_pass(true, true), TilePass::Over);
+}
+
+#[test]
+// Whatever's lower down is drawn later, in front; level things keep their order.
+fn test_sort_by_depth() {
+    let mut items = vec![("tree", 48.0), ("player", 32.0), ("npc", 64.0), ("follower", 32.0)];
+    sort_by_depth(&mut items, |&(_, foot)| foot);
+    let names: Vec<&str> = items.iter().map(|&(name, _)| name).collect();
+    assert_eq!(names, vec!["player", "follower", "tree", "npc"]);
+}
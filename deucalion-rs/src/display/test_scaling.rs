@@ -0,0 +1,49 @@
+use display::scaling::*;
+use config::engine_config::ScalingMode;
+use geom::ScreenSize;
+
+#[test]
+// Integer scaling should use the largest whole multiple and centre the result.
+fn test_integer_scaling() {
+    let viewport = compute_viewport(
+        ScreenSize::new(1280, 800),
+        ScreenSize::new(320, 240),
+        ScalingMode::Integer,
+    );
+    assert_eq!(viewport.scale_x, 3.0);
+    assert_eq!(viewport.scale_y, 3.0);
+    assert_eq!(viewport.offset_x, 160.0);
+    assert_eq!(viewport.offset_y, 40.0);
+}
+
+#[test]
+// A window smaller than the render resolution should shrink the frame rather than crop it.
+fn test_integer_scaling_small_window() {
+    let viewport = compute_viewport(
+        ScreenSize::new(160, 120),
+        ScreenSize::new(320, 240),
+        ScalingMode::Integer,
+    );
+    assert_eq!(viewport.scale_x, 0.5);
+    assert_eq!(viewport.offset_x, 0.0);
+}
+
+#[test]
+// Letterboxing keeps the aspect ratio, stretching doesn't.
+fn test_letterbox_and_stretch() {
+    let letterbox = compute_viewport(
+        ScreenSize::new(1000, 600),
+        ScreenSize::new(400, 300),
+        ScalingMode::Letterbox,
+    );
+    assert_eq!((letterbox.scale_x, letterbox.scale_y), (2.0, 2.0));
+    assert_eq!((letterbox.offset_x, letterbox.offset_y), (100.0, 0.0));
+
+    let stretch = compute_viewport(
+        ScreenSize::new(1000, 600),
+        ScreenSize::new(400, 300),
+        ScalingMode::Stretch,
+    );
+    assert_eq!((stretch.scale_x, stretch.scale_y), (2.5, 2.0));
+    assert_eq!((stretch.offset_x, stretch.offset_y), (0.0, 0.0));
+}
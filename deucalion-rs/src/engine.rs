@@ -0,0 +1,153 @@
+//! The engine's shared state: everything a scene might need to reach, other than itself.
+
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use hlua::AnyLuaValue;
+
+use audio::{Mixer, MusicPlayer};
+use battle::database::Database;
+use config::engine_config::{self, EngineConfig};
+use config::game_config::GameConfig;
+use display::Display;
+use display::developer_overlay::DeveloperOverlay;
+use error::DeucalionError;
+use game_state::{SharedGameState, LEVEL_UP_HOOK};
+use input::KeyBindings;
+use resource::font::LoadedFont;
+use resource::loading;
+use resource::ResourceKind;
+use scripting::sandbox::{ScriptEnvironments, ScriptOwner};
+use ui::skin::Windowskin;
+
+/// The state shared by every scene.
+pub struct Engine {
+    pub display: Display,
+    pub mixer: Mixer,
+    /// The music playing, at the mixer's music volume.
+    pub music: MusicPlayer,
+    pub key_bindings: KeyBindings,
+    pub engine_config: EngineConfig,
+    pub game_config: GameConfig,
+    /// Where the player's engine configuration is saved, if the platform has somewhere for it.
+    pub user_config_path: Option<PathBuf>,
+    /// The font used for all engine UI.
+    pub font: LoadedFont,
+    /// The windowskin the UI's windows are drawn with, if the game has one.
+    pub windowskin: Option<Windowskin>,
+    /// The sandboxed environments game scripts run in.
+    pub scripts: ScriptEnvironments,
+    /// Shows script errors on screen, in developer mode.
+    pub developer_overlay: DeveloperOverlay,
+    /// The game's skills and status effects, shared with every battle.
+    pub database: Rc<Database>,
+    /// The game in progress: the party members, their items, and everything else a save keeps.
+    /// Scripts share it, so it mustn't be left borrowed while they run.
+    pub game_state: SharedGameState,
+}
+
+impl Engine {
+    /// Replace the engine configuration, applying it to the window, audio and input as it
+    /// changes. Nothing requires a restart.
+    pub fn apply_engine_config(&mut self, config: EngineConfig) -> Result<(), DeucalionError> {
+        if config == self.engine_config {
+            return Ok(());
+        }
+        self.display.apply_config(&config)?;
+        self.mixer.apply_config(&config);
+        self.music.apply_volume(&self.mixer);
+        if config.key_bindings != self.engine_config.key_bindings {
+            self.key_bindings = KeyBindings::from_config(&config.key_bindings);
+        }
+        self.engine_config = config;
+        Ok(())
+    }
+
+    /// Play a piece of music, by name, on a loop, or stop the music if the name is empty. Music
+    /// that can't be played is reported, and the game carries on in silence.
+    pub fn play_music(&mut self, name: &str) {
+        if let Err(e) = self.music.play(name, &self.mixer) {
+            self.report_error(&format!("Failed to play the music '{}'", name), e);
+        }
+    }
+
+    /// Run the game's entry point, data/scripts/init.lua, in the game's own script environment.
+    /// A game doesn't have to have one.
+    pub fn run_init_script(&mut self) {
+        let path = match loading::get_resource_relative_path_by_name(ResourceKind::Script, "init") {
+            Ok(path) => path,
+            Err(e) => return self.report_error("Failed to find the init script", e),
+        };
+        if !loading::data_file_exists(&path) {
+            info!("There is no init script at {}; skipping it", path.display());
+            return;
+        }
+        match self.scripts.execute(&ScriptOwner::Game, &path) {
+            Ok(_) => info!("Ran the init script at {}", path.display()),
+            Err(e) => self.report_error("Failed to run the init script", e),
+        }
+    }
+
+    /// Forget every loaded script module and run the init script again, picking up any changes
+    /// to them. Meant for developer mode.
+    pub fn reload_scripts(&mut self) {
+        info!("Reloading scripts");
+        self.scripts.clear_module_caches();
+        self.run_init_script();
+    }
+
+    /// Report an error that the game can carry on from, such as a failing event script. It is
+    /// always logged; in developer mode, script errors are also shown on screen.
+    pub fn report_error(&mut self, context: &str, error: DeucalionError) {
+        error!("{}: {}", context, error);
+        if let DeucalionError::LuaScriptError(details) = error {
+            if self.game_config.developer_mode {
+                self.developer_overlay.push(details);
+            }
+        }
+    }
+
+    /// Draw the developer overlay over the canvas. Call this after every scene has been drawn.
+    pub fn draw_developer_overlay(&mut self) {
+        let size = self.display.render_size();
+        self.developer_overlay
+            .draw(&mut self.display.canvas, &self.font.font, size);
+    }
+
+    /// Give a party member EXP, calling the game's `on_level_up(character, level)`, if
+    /// data/scripts/init.lua defines one, for each level they reach. Returns the levels reached.
+    pub fn give_exp(&mut self, character: &str, amount: u32) -> Result<Vec<u32>, DeucalionError> {
+        let levels = match self.game_state.borrow_mut().member_mut(character) {
+            Some(member) => member.gain_exp(amount),
+            None => {
+                return Err(DeucalionError::from(format!(
+                    "{} has never joined the party",
+                    character
+                )))
+            }
+        };
+        for level in levels.iter() {
+            info!("{} has reached level {}", character, level);
+            let arguments = vec![
+                AnyLuaValue::LuaString(String::from(character)),
+                AnyLuaValue::LuaNumber(f64::from(*level)),
+            ];
+            if let Err(e) = self.scripts
+                .call_function(&ScriptOwner::Game, LEVEL_UP_HOOK, arguments)
+            {
+                self.report_error(&format!("Failed to run {}", LEVEL_UP_HOOK), e);
+            }
+        }
+        Ok(levels)
+    }
+
+    /// Save the current engine configuration as the player's own, so it is used next time.
+    pub fn save_engine_config(&self) -> Result<(), DeucalionError> {
+        match self.user_config_path {
+            Some(ref path) => engine_config::save_user_engine_config(&self.engine_config, path),
+            None => Err(DeucalionError::from(
+                "there is no per-user configuration directory on this platform",
+            )),
+        }
+    }
+}
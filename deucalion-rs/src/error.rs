@@ -7,16 +7,51 @@ use std::io;
 use hlua;
 use tiled;
 
+/// The details of an error raised by a script: where it happened, what it was, and the Lua
+/// traceback leading to it.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct LuaScriptError {
+    /// The script's path, relative to the data directory.
+    pub file: String,
+    /// The line the error was raised on, if Lua reported one.
+    pub line: Option<u32>,
+    /// The error message, without the file and line.
+    pub message: String,
+    /// The Lua stack traceback, one frame per line.
+    pub traceback: String,
+}
+
+impl fmt::Display for LuaScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.file, line, self.message)?,
+            None => write!(f, "{}: {}", self.file, self.message)?,
+        }
+        if !self.traceback.is_empty() {
+            write!(f, "\n{}", self.traceback)?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug)]
 pub enum DeucalionError {
     /// An error in I/O caused this error
     IoError(io::Error),
     /// An error in a script caused this error
     LuaError(hlua::LuaError),
+    /// An error raised by a script, with its location and traceback
+    LuaScriptError(LuaScriptError),
     /// An problem getting a value from a Lua context. (Identifier, Typename)
     LuaGetFailed(String, String),
+    /// A script ran past its execution budget and was aborted. (Script path, Budget)
+    ScriptBudgetExceeded(String, String),
     /// A problem with tilemaps caused this error
     TiledError(tiled::TiledError),
+    /// A packed data archive is malformed or corrupt. (Archive path, Description)
+    ArchiveError(String, String),
+    /// An entry of the game's database is invalid. (Entry, e.g. "skills.fire", Description)
+    DatabaseError(String, String),
     /// Some functionality that is not yet implemented was called, causing this error.
     /// Note that there is not ::from that creates this error; it must be created explicitly.
     NotImplementedError(String),
@@ -60,8 +95,12 @@ impl Error for DeucalionError {
         match *self {
             DeucalionError::IoError(_) => "there was a problem with an I/O operation",
             DeucalionError::LuaError(_) => "there was an error in Lua code",
+            DeucalionError::LuaScriptError(_) => "a script raised an error",
             DeucalionError::LuaGetFailed(_, _) => "could not retrieve a value from a Lua context",
+            DeucalionError::ScriptBudgetExceeded(_, _) => "a script ran for too long",
             DeucalionError::TiledError(_) => "there was a problem with a Tiled map",
+            DeucalionError::ArchiveError(_, _) => "a data archive is malformed or corrupt",
+            DeucalionError::DatabaseError(_, _) => "an entry of the database is invalid",
             DeucalionError::NotImplementedError(_) => "an unimplemented operation was executed",
             DeucalionError::OtherError(_) => "an error of an unknown type occurred",
         }
@@ -72,9 +111,13 @@ impl Error for DeucalionError {
             // LuaError doesn't currently implement Error. If it ever does,
             //  this can be changed to be more useful.
             DeucalionError::LuaError(_) => None,
+            DeucalionError::LuaScriptError(_) => None,
             DeucalionError::LuaGetFailed(_, _) => None,
+            DeucalionError::ScriptBudgetExceeded(_, _) => None,
             // TiledError currently doesn't implement Error.
             DeucalionError::TiledError(ref err) => Some(err as &Error),
+            DeucalionError::ArchiveError(_, _) => None,
+            DeucalionError::DatabaseError(_, _) => None,
             DeucalionError::NotImplementedError(_) => None,
             DeucalionError::OtherError(_) => None,
         }
@@ -88,15 +131,28 @@ impl fmt::Display for DeucalionError {
             // Currently, LuaError doesn't implement Display. If it ever does,
             //  this can be changed in order to be more useful
             DeucalionError::LuaError(ref err) => write!(f, "error in Lua code: {:?}", err),
+            DeucalionError::LuaScriptError(ref err) => write!(f, "error in script {}", err),
             DeucalionError::LuaGetFailed(ref identifier, ref typename) => write!(
                 f,
                 "failed to acquire value '{}' of type '{}' from the Lua context",
                 identifier,
                 typename
             ),
+            DeucalionError::ScriptBudgetExceeded(ref path, ref budget) => write!(
+                f,
+                "the script at '{}' was aborted for running past its budget of {}",
+                path,
+                budget
+            ),
             DeucalionError::TiledError(ref err) => {
                 write!(f, "error in Tiled data or parsing: {}", err)
             }
+            DeucalionError::ArchiveError(ref archive, ref description) => {
+                write!(f, "error in data archive '{}': {}", archive, description)
+            }
+            DeucalionError::DatabaseError(ref entry, ref description) => {
+                write!(f, "invalid database entry '{}': {}", entry, description)
+            }
             DeucalionError::NotImplementedError(ref string) => {
                 write!(f, "not implemented: {}", string)
             }
@@ -0,0 +1,247 @@
+//! How party members grow as they level up: how much EXP each level takes, and the stats they
+//! have at each level.
+//!
+//! Both are curves, set in a character's info.lua. A curve is either a list of values, one for
+//! each level starting from level 1, or a Lua formula of `level`, run like the database's (see
+//! `battle::formula::FormulaEnvironment`):
+//!
+//! ```lua
+//! MAX_LEVEL = 50
+//! EXP_CURVE = "12 * (level - 1) ^ 2"
+//! GROWTH = {
+//!     max_hp = "30 + (level - 1) * 8",
+//!     attack = { 10, 12, 13, 15, 16, 18 },
+//! }
+//! ```
+//!
+//! `EXP_CURVE` gives the total EXP needed to reach each level; level 1 needs none. A character
+//! can't go past the end of a list of EXP, but a stat's list is carried on at its last value.
+//! Stats left out of `GROWTH` stay at the character's `STATS` at every level.
+
+use hlua::AnyLuaValue;
+
+use battle::combatant::Stats;
+use battle::formula::{round_amount, FormulaEnvironment};
+use config::schema::ConfigValue;
+use error::DeucalionError;
+use scripting::convert::get_table_field;
+
+/// The EXP curve of characters that don't have one.
+pub const DEFAULT_EXP_CURVE: &'static str = "10 * (level - 1) ^ 2";
+
+/// The highest level of characters that don't say otherwise.
+pub const DEFAULT_MAX_LEVEL: u32 = 99;
+
+/// A value that changes with a character's level.
+#[derive(PartialEq, Debug, Clone)]
+pub enum Curve {
+    /// The value at each level, starting from level 1.
+    Table(Vec<f64>),
+    /// A Lua expression of `level`.
+    Formula(String),
+}
+
+impl Curve {
+    /// Work out the curve's value at every level from 1 to `levels`, or fewer, for a table that
+    /// ends sooner.
+    pub fn values(
+        &self,
+        levels: u32,
+        formulas: &mut FormulaEnvironment,
+    ) -> Result<Vec<f64>, DeucalionError> {
+        match *self {
+            Curve::Table(ref values) => Ok(values.iter().take(levels as usize).cloned().collect()),
+            Curve::Formula(ref formula) => (1..levels + 1)
+                .map(|level| {
+                    let globals = vec![("level", AnyLuaValue::LuaNumber(f64::from(level)))];
+                    formulas.evaluate_with(formula, globals)
+                })
+                .collect(),
+        }
+    }
+}
+
+impl ConfigValue for Curve {
+    fn type_name() -> String {
+        String::from("curve (a list of numbers, one for each level, or a formula of level)")
+    }
+
+    fn from_lua(value: AnyLuaValue) -> Option<Curve> {
+        match value {
+            AnyLuaValue::LuaString(formula) => Some(Curve::Formula(formula)),
+            value => Vec::<f64>::from_lua(value)
+                .filter(|values| !values.is_empty())
+                .map(Curve::Table),
+        }
+    }
+}
+
+/// The curves that some of a character's stats grow along, as `GROWTH` in their info.lua, a
+/// table with any of the fields of `battle::combatant::Stats`.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct StatGrowth {
+    pub max_hp: Option<Curve>,
+    pub max_mp: Option<Curve>,
+    pub attack: Option<Curve>,
+    pub defense: Option<Curve>,
+    pub magic: Option<Curve>,
+    pub speed: Option<Curve>,
+}
+
+impl ConfigValue for StatGrowth {
+    fn type_name() -> String {
+        format!(
+            "stat growth (a table with any of max_hp, max_mp, attack, defense, magic and speed, \
+             each a {})",
+            Curve::type_name()
+        )
+    }
+
+    fn from_lua(value: AnyLuaValue) -> Option<StatGrowth> {
+        match value {
+            AnyLuaValue::LuaArray(_) => {}
+            _ => return None,
+        }
+        // Like stats, missing fields are left alone, but a field of the wrong type spoils the
+        //  table.
+        let curve = |name: &str| match get_table_field(&value, name) {
+            AnyLuaValue::LuaNil => Some(None),
+            field => Curve::from_lua(field).map(Some),
+        };
+        Some(StatGrowth {
+            max_hp: curve("max_hp")?,
+            max_mp: curve("max_mp")?,
+            attack: curve("attack")?,
+            defense: curve("defense")?,
+            magic: curve("magic")?,
+            speed: curve("speed")?,
+        })
+    }
+}
+
+/// A character's curves, worked out for every level they can reach.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Growth {
+    // The total EXP needed for each level, starting from level 1.
+    exp: Vec<u32>,
+    // The stats at each level, starting from level 1.
+    stats: Vec<Stats>,
+}
+
+impl Growth {
+    /// Work out a character's growth from their curves. `base` is their stats wherever
+    /// `growth` has no curve. Fails if a curve can't be worked out, or if it gives negative
+    /// stats, a max HP of 0 or EXP that goes down from one level to the next.
+    pub fn new(
+        base: Stats,
+        max_level: u32,
+        exp_curve: &Curve,
+        growth: &StatGrowth,
+        formulas: &mut FormulaEnvironment,
+    ) -> Result<Growth, DeucalionError> {
+        // Every character starts at level 1, whatever the curve says it takes.
+        let mut exp = vec![0];
+        for (i, value) in exp_curve.values(max_level, formulas)?.iter().enumerate().skip(1) {
+            let value = round_amount(*value);
+            if value < i64::from(exp[i - 1]) {
+                return Err(DeucalionError::from(format!(
+                    "the EXP curve goes down, to {} at level {}",
+                    value,
+                    i + 1
+                )));
+            }
+            exp.push(value as u32);
+        }
+
+        let levels = exp.len() as u32;
+        let max_hp = grow_stat("max_hp", &growth.max_hp, levels, 1, formulas)?;
+        let max_mp = grow_stat("max_mp", &growth.max_mp, levels, 0, formulas)?;
+        let attack = grow_stat("attack", &growth.attack, levels, 0, formulas)?;
+        let defense = grow_stat("defense", &growth.defense, levels, 0, formulas)?;
+        let magic = grow_stat("magic", &growth.magic, levels, 0, formulas)?;
+        let speed = grow_stat("speed", &growth.speed, levels, 0, formulas)?;
+        let stats = (0..exp.len())
+            .map(|i| {
+                let stat = |values: &Option<Vec<u32>>, base: u32| {
+                    values.as_ref().map_or(base, |values| values[i])
+                };
+                Stats {
+                    max_hp: stat(&max_hp, base.max_hp),
+                    max_mp: stat(&max_mp, base.max_mp),
+                    attack: stat(&attack, base.attack),
+                    defense: stat(&defense, base.defense),
+                    magic: stat(&magic, base.magic),
+                    speed: stat(&speed, base.speed),
+                }
+            })
+            .collect();
+        Ok(Growth {
+            exp: exp,
+            stats: stats,
+        })
+    }
+
+    /// Growth for a character who stays at level 1 with the same stats.
+    pub fn flat(stats: Stats) -> Growth {
+        Growth {
+            exp: vec![0],
+            stats: vec![stats],
+        }
+    }
+
+    /// The highest level the character can reach.
+    pub fn max_level(&self) -> u32 {
+        self.exp.len() as u32
+    }
+
+    /// The total EXP needed to reach a level, or None if it can't be reached.
+    pub fn exp_for_level(&self, level: u32) -> Option<u32> {
+        match level {
+            0 => None,
+            level => self.exp.get(level as usize - 1).cloned(),
+        }
+    }
+
+    /// The level a character with some total EXP has reached.
+    pub fn level_for_exp(&self, exp: u32) -> u32 {
+        self.exp.iter().filter(|needed| **needed <= exp).count().max(1) as u32
+    }
+
+    /// The character's stats at a level, before any equipment or status effects. Levels past
+    /// the highest have the highest's stats.
+    pub fn stats_at(&self, level: u32) -> Stats {
+        let index = (level.max(1) as usize - 1).min(self.stats.len() - 1);
+        self.stats[index]
+    }
+}
+
+// Work out a stat's value at every level from 1 to `levels`, if it has a curve, checking that it
+//  never goes below `minimum`.
+fn grow_stat(
+    name: &str,
+    curve: &Option<Curve>,
+    levels: u32,
+    minimum: i64,
+    formulas: &mut FormulaEnvironment,
+) -> Result<Option<Vec<u32>>, DeucalionError> {
+    let values = match *curve {
+        Some(ref curve) => curve.values(levels, formulas)?,
+        None => return Ok(None),
+    };
+    let mut stats = Vec::with_capacity(levels as usize);
+    for level in 1..levels + 1 {
+        // A table that ends early carries on at its last value.
+        let value = values
+            .get(level as usize - 1)
+            .or(values.last())
+            .map_or(minimum, |value| round_amount(*value));
+        if value < minimum {
+            return Err(DeucalionError::from(format!(
+                "the {} curve comes to {} at level {}, below {}",
+                name, value, level, minimum
+            )));
+        }
+        stats.push(value as u32);
+    }
+    Ok(Some(stats))
+}
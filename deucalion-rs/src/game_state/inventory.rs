@@ -0,0 +1,58 @@
+//! The party's inventory: how many of each item they're carrying, between them.
+
+use std::collections::BTreeMap;
+
+/// The items the party is carrying, by their names in the database.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct Inventory {
+    // How many of each item there are; items run out are removed.
+    items: BTreeMap<String, u32>,
+}
+
+impl Inventory {
+    /// Create an empty inventory.
+    pub fn new() -> Inventory {
+        Inventory::default()
+    }
+
+    /// How many of an item the party is carrying.
+    pub fn count(&self, item: &str) -> u32 {
+        self.items.get(item).cloned().unwrap_or(0)
+    }
+
+    /// Whether the party is carrying at least `count` of an item.
+    pub fn has(&self, item: &str, count: u32) -> bool {
+        self.count(item) >= count
+    }
+
+    /// Add as many of an item as will fit, up to `stack_limit` in all. Returns how many were
+    /// added.
+    pub fn add(&mut self, item: &str, count: u32, stack_limit: u32) -> u32 {
+        let held = self.count(item);
+        let added = count.min(stack_limit.saturating_sub(held));
+        if added > 0 {
+            self.items.insert(String::from(item), held + added);
+        }
+        added
+    }
+
+    /// Take away `count` of an item. If the party has fewer, nothing is taken away and false is
+    /// returned.
+    pub fn remove(&mut self, item: &str, count: u32) -> bool {
+        let held = self.count(item);
+        if held < count {
+            return false;
+        }
+        if held == count {
+            self.items.remove(item);
+        } else {
+            self.items.insert(String::from(item), held - count);
+        }
+        true
+    }
+
+    /// Every item the party is carrying and how many, in order of name.
+    pub fn items(&self) -> &BTreeMap<String, u32> {
+        &self.items
+    }
+}
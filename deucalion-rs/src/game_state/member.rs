@@ -0,0 +1,162 @@
+//! Party members as they are between battles: their level, EXP, HP, MP and equipment. Battles
+//! start from them and write back to them when they're over, and saves keep them.
+
+use std::collections::BTreeMap;
+
+use battle::combatant::{Combatant, Side, StatBonus, Stats};
+use battle::database::{Database, EquipSlot};
+use battle::formula::FormulaEnvironment;
+use error::DeucalionError;
+use game_state::growth::Growth;
+use world::character::CharacterInfo;
+
+/// A member of the player's party.
+#[derive(PartialEq, Debug, Clone)]
+pub struct PartyMember {
+    /// The character the member is, whose info is data/characters/<character>/info.lua.
+    pub character: String,
+    /// The member's name, as shown to the player.
+    pub name: String,
+    pub level: u32,
+    /// The total EXP the member has earned.
+    pub exp: u32,
+    pub hp: u32,
+    pub mp: u32,
+    /// The skills the member can use in battle, by their names in the database.
+    pub skills: Vec<String>,
+    /// The items the member is wearing, by their names in the database.
+    pub equipment: BTreeMap<EquipSlot, String>,
+    growth: Growth,
+}
+
+impl PartyMember {
+    /// Create a member from a character's info, at the level it starts them at, with full HP
+    /// and MP.
+    pub fn new(character: &str, info: &CharacterInfo, growth: Growth) -> PartyMember {
+        let level = info.level.max(1).min(growth.max_level());
+        let stats = growth.stats_at(level);
+        PartyMember {
+            character: String::from(character),
+            name: info.name.clone(),
+            level: level,
+            exp: growth.exp_for_level(level).unwrap_or(0),
+            hp: stats.max_hp,
+            mp: stats.max_mp,
+            skills: info.skills.clone(),
+            equipment: BTreeMap::new(),
+            growth: growth,
+        }
+    }
+
+    /// Create a member from a character's info.lua, working out their growth curves. If the
+    /// curves can't be worked out, it's reported, and the member stays at their level with the
+    /// same stats.
+    pub fn by_name(character: &str) -> Result<PartyMember, DeucalionError> {
+        let info = CharacterInfo::by_name(character)?;
+        let growth = Growth::new(
+            info.stats,
+            info.max_level,
+            &info.exp_curve,
+            &info.growth,
+            &mut FormulaEnvironment::new(),
+        ).unwrap_or_else(|e| {
+            error!("Invalid growth for character {}, so they won't grow: {}", character, e);
+            Growth::flat(info.stats)
+        });
+        Ok(PartyMember::new(character, &info, growth))
+    }
+
+    /// How the member grows from level to level.
+    pub fn growth(&self) -> &Growth {
+        &self.growth
+    }
+
+    /// The member's stats at their level, before any equipment or status effects.
+    pub fn base_stats(&self) -> Stats {
+        self.growth.stats_at(self.level)
+    }
+
+    /// The member's stats with bonuses, such as from their equipment, added. Status effects
+    /// change them further in battle; see `battle::state::Battle::stats`.
+    pub fn stats(&self, bonuses: &[StatBonus]) -> Stats {
+        bonuses
+            .iter()
+            .fold(self.base_stats(), |stats, bonus| stats.with_bonus(bonus))
+    }
+
+    /// What each item the member is wearing adds to their stats. Items missing from the database
+    /// add nothing.
+    pub fn equipment_bonuses(&self, database: &Database) -> Vec<StatBonus> {
+        get_bonuses(&self.equipment, database)
+    }
+
+    /// The member's stats if they wore an item in a slot, in place of whatever they're wearing
+    /// there, with the rest of their equipment.
+    pub fn stats_wearing(&self, slot: EquipSlot, item: &str, database: &Database) -> Stats {
+        let mut equipment = self.equipment.clone();
+        equipment.insert(slot, String::from(item));
+        self.stats(&get_bonuses(&equipment, database))
+    }
+
+    /// Take away any HP and MP over the member's maximums, such as after taking off equipment
+    /// that raised them.
+    pub fn limit_to(&mut self, stats: Stats) {
+        self.hp = self.hp.min(stats.max_hp);
+        self.mp = self.mp.min(stats.max_mp);
+    }
+
+    /// How much more EXP the member needs for their next level, or None at the highest level.
+    pub fn exp_to_next_level(&self) -> Option<u32> {
+        self.growth
+            .exp_for_level(self.level + 1)
+            .map(|needed| needed.saturating_sub(self.exp))
+    }
+
+    /// Give the member EXP. Returns each level they reach, in order. Each level's rise in max
+    /// HP and MP is added to their HP and MP, unless they've been knocked out.
+    pub fn gain_exp(&mut self, amount: u32) -> Vec<u32> {
+        self.exp = self.exp.saturating_add(amount);
+        let old_stats = self.base_stats();
+        let new_level = self.growth.level_for_exp(self.exp).max(self.level);
+        let levels: Vec<u32> = (self.level + 1..new_level + 1).collect();
+        self.level = new_level;
+        let new_stats = self.base_stats();
+        if self.hp > 0 {
+            self.hp += new_stats.max_hp.saturating_sub(old_stats.max_hp);
+        }
+        self.mp += new_stats.max_mp.saturating_sub(old_stats.max_mp);
+        levels
+    }
+
+    /// Restore all of the member's HP and MP, even if they've been knocked out.
+    pub fn recover(&mut self, bonuses: &[StatBonus]) {
+        let stats = self.stats(bonuses);
+        self.hp = stats.max_hp;
+        self.mp = stats.max_mp;
+    }
+
+    /// The member as a combatant, ready for battle, with bonuses such as from their equipment.
+    pub fn combatant(&self, bonuses: &[StatBonus]) -> Combatant {
+        let stats = self.stats(bonuses);
+        let mut combatant =
+            Combatant::new(&self.name, Side::Party, stats).with_skills(self.skills.clone());
+        combatant.hp = self.hp.min(stats.max_hp);
+        combatant.mp = self.mp.min(stats.max_mp);
+        combatant
+    }
+
+    /// Keep the HP and MP the member had at the end of a battle.
+    pub fn update_from(&mut self, combatant: &Combatant) {
+        self.hp = combatant.hp;
+        self.mp = combatant.mp;
+    }
+}
+
+// What each item of some equipment adds to stats, leaving out items missing from the database.
+fn get_bonuses(equipment: &BTreeMap<EquipSlot, String>, database: &Database) -> Vec<StatBonus> {
+    equipment
+        .values()
+        .filter_map(|item| database.item(item))
+        .map(|item| item.bonus)
+        .collect()
+}
@@ -0,0 +1,227 @@
+//! The state of a game in progress: everything a save keeps, such as the party members' levels
+//! and EXP, and the items and gold they carry. The engine holds the one game state, so that
+//! battles, menus and scripts all see the same party.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use battle::database::{Database, EquipSlot, Item};
+use error::DeucalionError;
+use game_state::inventory::Inventory;
+use game_state::member::PartyMember;
+
+pub mod growth;
+pub mod inventory;
+pub mod member;
+pub mod save;
+pub mod shop;
+#[cfg(test)]
+mod test_growth;
+#[cfg(test)]
+mod test_inventory;
+#[cfg(test)]
+mod test_member;
+#[cfg(test)]
+mod test_save;
+#[cfg(test)]
+mod test_shop;
+
+/// The game's function that's told when a party member reaches a new level.
+pub const LEVEL_UP_HOOK: &'static str = "on_level_up";
+
+/// The most gold the party can carry.
+pub const MAX_GOLD: u32 = 9_999_999;
+
+/// The game state, shared between the engine and the scripts that read and change it. It must
+/// never be left borrowed while a script runs.
+pub type SharedGameState = Rc<RefCell<GameState>>;
+
+/// The state of a game in progress.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct GameState {
+    /// Everyone who has joined the party, by character, whether or not they're with the player
+    /// at the moment. Members who leave and join again carry on from where they left off.
+    pub members: BTreeMap<String, PartyMember>,
+    /// The items the party is carrying, apart from what they're wearing.
+    pub inventory: Inventory,
+    /// The party's money, up to `MAX_GOLD`.
+    pub gold: u32,
+    /// Where the player was when the main menu was last opened from the map, or where a save
+    /// left them.
+    pub location: Option<Location>,
+}
+
+/// Where the player is in the world, kept up to date by the map so that a save can put them
+/// back there.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct Location {
+    /// The map the player is on, by name.
+    pub map: String,
+    /// Where the player stands, in pixels from the map's top left corner.
+    pub x: f64,
+    pub y: f64,
+    /// The characters following the player, in order.
+    pub followers: Vec<String>,
+}
+
+impl GameState {
+    /// Create a game state with nobody in the party.
+    pub fn new() -> GameState {
+        GameState::default()
+    }
+
+    /// A party member, by character.
+    pub fn member(&self, character: &str) -> Option<&PartyMember> {
+        self.members.get(character)
+    }
+
+    /// A party member, by character, to change.
+    pub fn member_mut(&mut self, character: &str) -> Option<&mut PartyMember> {
+        self.members.get_mut(character)
+    }
+
+    /// Have a character join the party, reading their info if they haven't joined before.
+    pub fn join(&mut self, character: &str) -> Result<&mut PartyMember, DeucalionError> {
+        if !self.members.contains_key(character) {
+            let member = PartyMember::by_name(character)?;
+            self.members.insert(String::from(character), member);
+        }
+        Ok(self.members
+            .get_mut(character)
+            .expect("the member has just joined"))
+    }
+
+    /// Give the party gold, as much as they can carry. Returns how much was given.
+    pub fn give_gold(&mut self, amount: u32) -> u32 {
+        let given = amount.min(MAX_GOLD.saturating_sub(self.gold));
+        self.gold += given;
+        given
+    }
+
+    /// Take gold from the party. If they have less, none is taken and false is returned.
+    pub fn spend_gold(&mut self, amount: u32) -> bool {
+        if self.gold < amount {
+            return false;
+        }
+        self.gold -= amount;
+        true
+    }
+
+    /// Give the party an item from the database, as many as will fit. Returns how many were
+    /// given.
+    pub fn give_item(
+        &mut self,
+        item: &str,
+        count: u32,
+        database: &Database,
+    ) -> Result<u32, DeucalionError> {
+        let stack_limit = find_item(database, item)?.stack_limit;
+        Ok(self.inventory.add(item, count, stack_limit))
+    }
+
+    /// Use one of a consumable the party is carrying on a member outside battle, restoring its
+    /// `hp` and `mp`, up to their maximums. Returns false, using nothing, if the party doesn't
+    /// have it, the member has been knocked out, or it would restore nothing.
+    pub fn use_item(
+        &mut self,
+        item: &str,
+        character: &str,
+        database: &Database,
+    ) -> Result<bool, DeucalionError> {
+        let definition = find_item(database, item)?;
+        if !definition.is_usable_outside_battle() {
+            return Err(DeucalionError::from(format!(
+                "{} can't be used outside battle",
+                item
+            )));
+        }
+        let member = self.members
+            .get_mut(character)
+            .ok_or_else(|| not_in_party(character))?;
+        let stats = member.stats(&member.equipment_bonuses(database));
+        let hp = member.hp.saturating_add(definition.hp).min(stats.max_hp);
+        let mp = member.mp.saturating_add(definition.mp).min(stats.max_mp);
+        if member.hp == 0 || (hp == member.hp && mp == member.mp) {
+            return Ok(false);
+        }
+        if !self.inventory.remove(item, 1) {
+            return Ok(false);
+        }
+        member.hp = hp;
+        member.mp = mp;
+        Ok(true)
+    }
+
+    /// Put an item the party is carrying on a member, in its slot, taking off whatever they were
+    /// wearing there. Returns false, changing nothing, if the party doesn't have the item.
+    pub fn equip(
+        &mut self,
+        character: &str,
+        item: &str,
+        database: &Database,
+    ) -> Result<bool, DeucalionError> {
+        let slot = find_item(database, item)?.equip_slot().ok_or_else(|| {
+            DeucalionError::from(format!("{} can't be equipped", item))
+        })?;
+        if !self.members.contains_key(character) {
+            return Err(not_in_party(character));
+        }
+        if !self.inventory.has(item, 1) {
+            return Ok(false);
+        }
+        self.unequip(character, slot, database)?;
+        self.inventory.remove(item, 1);
+        let member = self.members.get_mut(character).expect("the member was found above");
+        member.equipment.insert(slot, String::from(item));
+        let stats = member.stats(&member.equipment_bonuses(database));
+        member.limit_to(stats);
+        Ok(true)
+    }
+
+    /// Take off whatever a member is wearing in a slot, putting it back with the party's items.
+    /// Returns false if they aren't wearing anything there. Fails if the party has no room for
+    /// it.
+    pub fn unequip(
+        &mut self,
+        character: &str,
+        slot: EquipSlot,
+        database: &Database,
+    ) -> Result<bool, DeucalionError> {
+        let item = match self.members.get(character) {
+            Some(member) => match member.equipment.get(&slot) {
+                Some(item) => item.clone(),
+                None => return Ok(false),
+            },
+            None => return Err(not_in_party(character)),
+        };
+        // Items that have left the database since they were put on can still be taken off.
+        let stack_limit = database
+            .item(&item)
+            .map(|item| item.stack_limit)
+            .unwrap_or(u32::max_value());
+        if self.inventory.add(&item, 1, stack_limit) == 0 {
+            return Err(DeucalionError::from(format!(
+                "there's no room for another {}",
+                item
+            )));
+        }
+        let member = self.members.get_mut(character).expect("the member was found above");
+        member.equipment.remove(&slot);
+        let stats = member.stats(&member.equipment_bonuses(database));
+        member.limit_to(stats);
+        Ok(true)
+    }
+}
+
+// Find an item in the database, failing if there's no such item.
+fn find_item<'a>(database: &'a Database, item: &str) -> Result<&'a Item, DeucalionError> {
+    database
+        .item(item)
+        .ok_or_else(|| DeucalionError::from(format!("there is no item named '{}'", item)))
+}
+
+// The error for a character who isn't in the party.
+fn not_in_party(character: &str) -> DeucalionError {
+    DeucalionError::from(format!("{} has never joined the party", character))
+}
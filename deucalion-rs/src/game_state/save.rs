@@ -0,0 +1,332 @@
+//! Saves: the game state written to a file, and read back.
+//!
+//! Like the player's engine configuration, a save is a Lua script that sets globals, so that it
+//! is read with the same machinery as the game's own files. It's run in a sandbox of its own,
+//! since the player may have changed it. `PARTY` is a list of the party members, each a table of
+//! `character`, `name`, `level`, `exp`, `hp`, `mp`, `skills` and `equipment`; what a save doesn't
+//! say is taken from the character's info, as when they first join the party. `INVENTORY` is a
+//! table of the items the party is carrying, by name, to how many, and `GOLD` how much money
+//! they have. `LOCATION` is where the player is: a table of the `map`, the player's `x` and `y`
+//! in pixels, and the `followers` behind them. Items that are no longer in the database are
+//! reported and left out.
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use dirs;
+use hlua::{AnyLuaValue, Lua};
+
+use battle::database::Database;
+use config::engine_config::get_game_directory_name;
+use config::game_config::GameConfig;
+use config::schema::{ConfigValue, Field, Schema};
+use error::DeucalionError;
+use game_state::member::PartyMember;
+use game_state::{GameState, Location, MAX_GOLD};
+use scripting::budget::ScriptBudget;
+use scripting::convert::{get_table_field, make_table};
+use scripting::execute_script_from_reader;
+use scripting::modules::get_module_roots;
+use scripting::sandbox::{get_sandboxed_environment, LuaLibrary};
+use scripting::serialize::to_lua_source;
+
+/// The global a save lists the party members in.
+pub const PARTY_GLOBAL: &'static str = "PARTY";
+
+/// The global a save lists the party's items in.
+pub const INVENTORY_GLOBAL: &'static str = "INVENTORY";
+
+/// The global a save says where the player is in.
+pub const LOCATION_GLOBAL: &'static str = "LOCATION";
+
+/// Get the directory a game's saves are kept in, in the per-user data directory (e.g.
+/// ~/.local/share/deucalion/<title>/saves on Linux). Returns None if the platform has no such
+/// directory.
+pub fn get_save_directory(game_config: &GameConfig) -> Option<PathBuf> {
+    dirs::data_dir().map(|directory| {
+        directory
+            .join("deucalion")
+            .join(get_game_directory_name(game_config))
+            .join("saves")
+    })
+}
+
+/// Get the path of the save in a numbered slot.
+pub fn get_save_path(directory: &Path, slot: u32) -> PathBuf {
+    directory.join(format!("save{}.lua", slot))
+}
+
+/// Find the slot that was saved in last, if any has been.
+pub fn get_latest_save(directory: &Path, slots: u32) -> Option<u32> {
+    (1..slots + 1)
+        .filter_map(|slot| {
+            fs::metadata(get_save_path(directory, slot))
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .map(|modified| (modified, slot))
+        })
+        .max()
+        .map(|(_, slot)| slot)
+}
+
+/// Write the game state to a save, creating its directory if needed.
+pub fn write_save(state: &GameState, path: &Path) -> Result<(), DeucalionError> {
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+    let mut file = File::create(path)?;
+    file.write_all(game_state_to_lua(state).as_bytes())?;
+    info!("Saved the game to {}", path.display());
+    Ok(())
+}
+
+/// Read a save, checking its items against the database. Fails if it can't be run at all;
+/// otherwise, each invalid value is returned as an error, and left out or replaced by its
+/// default.
+pub fn read_save(
+    path: &Path,
+    database: &Database,
+) -> Result<(GameState, Vec<DeucalionError>), DeucalionError> {
+    let mut environment = get_sandboxed_environment(&[LuaLibrary::Base], &get_module_roots(&[]));
+    let reader = File::open(path)?;
+    execute_script_from_reader(&mut environment, reader, path, &ScriptBudget::for_config())?;
+    Ok(read_save_from_environment(&mut environment, database))
+}
+
+/// Read the game state from the globals a save has set, checking its items against the
+/// database.
+pub fn read_save_from_environment(
+    environment: &mut Lua,
+    database: &Database,
+) -> (GameState, Vec<DeucalionError>) {
+    let (mut state, mut errors) = get_save_schema().read(environment, GameState::new());
+    let party = match environment.get::<AnyLuaValue, _>(PARTY_GLOBAL) {
+        Some(AnyLuaValue::LuaNil) | None => Vec::new(),
+        Some(party) => Vec::<AnyLuaValue>::from_lua(party).unwrap_or_else(|| {
+            errors.push(DeucalionError::LuaGetFailed(
+                String::from(PARTY_GLOBAL),
+                String::from("list of party members"),
+            ));
+            Vec::new()
+        }),
+    };
+    for saved in party.iter() {
+        match read_member(saved, database) {
+            Ok((member, mut member_errors)) => {
+                errors.append(&mut member_errors);
+                state.members.insert(member.character.clone(), member);
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+    let inventory = match environment.get::<AnyLuaValue, _>(INVENTORY_GLOBAL) {
+        Some(AnyLuaValue::LuaArray(inventory)) => inventory,
+        Some(AnyLuaValue::LuaNil) | None => Vec::new(),
+        Some(_) => {
+            errors.push(DeucalionError::LuaGetFailed(
+                String::from(INVENTORY_GLOBAL),
+                String::from("table of items to how many"),
+            ));
+            Vec::new()
+        }
+    };
+    for (item, count) in inventory {
+        let (item, count) = match (String::from_lua(item), u32::from_lua(count)) {
+            (Some(item), Some(count)) => (item, count),
+            (item, _) => {
+                errors.push(DeucalionError::LuaGetFailed(
+                    format!("{}.{}", INVENTORY_GLOBAL, item.unwrap_or_default()),
+                    String::from("whole number of items, by name"),
+                ));
+                continue;
+            }
+        };
+        if state.give_item(&item, count, database).is_err() {
+            errors.push(DeucalionError::LuaGetFailed(
+                format!("{}.{}", INVENTORY_GLOBAL, item),
+                String::from("name of an item in the database"),
+            ));
+        }
+    }
+    state.location = match environment.get::<AnyLuaValue, _>(LOCATION_GLOBAL) {
+        Some(AnyLuaValue::LuaNil) | None => None,
+        Some(saved) => {
+            let (location, location_errors) = get_saved_location_schema().read_from(
+                |identifier| Some(get_table_field(&saved, identifier)),
+                Location::default(),
+            );
+            errors.extend(location_errors.into_iter().map(|e| match e {
+                DeucalionError::LuaGetFailed(field, expected) => DeucalionError::LuaGetFailed(
+                    format!("{}.{}", LOCATION_GLOBAL, field),
+                    expected,
+                ),
+                e => e,
+            }));
+            if location.map.is_empty() {
+                None
+            } else {
+                Some(location)
+            }
+        }
+    };
+    (state, errors)
+}
+
+/// Write the game state as a Lua script that sets the globals a save does.
+pub fn game_state_to_lua(state: &GameState) -> String {
+    let party = AnyLuaValue::LuaArray(
+        state
+            .members
+            .values()
+            .enumerate()
+            .map(|(i, member)| (AnyLuaValue::LuaNumber((i + 1) as f64), member_to_lua(member)))
+            .collect(),
+    );
+    let inventory = make_table(
+        state
+            .inventory
+            .items()
+            .iter()
+            .map(|(item, &count)| (item.as_str(), AnyLuaValue::LuaNumber(f64::from(count))))
+            .collect(),
+    );
+    let mut source = String::from(
+        "-- Saved Game\n\
+         -- This file was written by the game, and is read back when the save is loaded\n",
+    );
+    source.push_str(&format!("{} = {}\n", PARTY_GLOBAL, to_lua_source(&party, 0)));
+    source.push_str(&format!("{} = {}\n", INVENTORY_GLOBAL, to_lua_source(&inventory, 0)));
+    source.push_str(&format!("GOLD = {}\n", state.gold));
+    if let Some(ref location) = state.location {
+        let location = location_to_lua(location);
+        source.push_str(&format!("{} = {}\n", LOCATION_GLOBAL, to_lua_source(&location, 0)));
+    }
+    source
+}
+
+/// Describe how the globals of a save that aren't tables of their own are read.
+pub fn get_save_schema() -> Schema<GameState> {
+    Schema::new().field(Field::new("GOLD", |s: &mut GameState| &mut s.gold).range(0, MAX_GOLD))
+}
+
+/// Describe how each field of a party member is read from their table in a save.
+pub fn get_saved_member_schema() -> Schema<PartyMember> {
+    Schema::new()
+        .field(Field::new("name", |m: &mut PartyMember| &mut m.name))
+        .field(Field::new("level", |m: &mut PartyMember| &mut m.level).range(1, 9999))
+        .field(Field::new("exp", |m: &mut PartyMember| &mut m.exp))
+        .field(Field::new("hp", |m: &mut PartyMember| &mut m.hp))
+        .field(Field::new("mp", |m: &mut PartyMember| &mut m.mp))
+        .field(Field::new("skills", |m: &mut PartyMember| &mut m.skills))
+        .field(Field::new("equipment", |m: &mut PartyMember| &mut m.equipment))
+}
+
+/// Describe how each field of the player's location is read from its table in a save.
+pub fn get_saved_location_schema() -> Schema<Location> {
+    Schema::new()
+        .field(Field::new("map", |l: &mut Location| &mut l.map).required())
+        .field(Field::new("x", |l: &mut Location| &mut l.x))
+        .field(Field::new("y", |l: &mut Location| &mut l.y))
+        .field(Field::new("followers", |l: &mut Location| &mut l.followers))
+}
+
+// Describe the player's location for a save.
+fn location_to_lua(location: &Location) -> AnyLuaValue {
+    let followers = location
+        .followers
+        .iter()
+        .enumerate()
+        .map(|(i, character)| {
+            (
+                AnyLuaValue::LuaNumber((i + 1) as f64),
+                AnyLuaValue::LuaString(character.clone()),
+            )
+        })
+        .collect();
+    make_table(vec![
+        ("map", AnyLuaValue::LuaString(location.map.clone())),
+        ("x", AnyLuaValue::LuaNumber(location.x)),
+        ("y", AnyLuaValue::LuaNumber(location.y)),
+        ("followers", AnyLuaValue::LuaArray(followers)),
+    ])
+}
+
+// Describe a party member for a save.
+fn member_to_lua(member: &PartyMember) -> AnyLuaValue {
+    let string = |s: &str| AnyLuaValue::LuaString(String::from(s));
+    let number = |n: u32| AnyLuaValue::LuaNumber(f64::from(n));
+    let skills = member
+        .skills
+        .iter()
+        .enumerate()
+        .map(|(i, skill)| (AnyLuaValue::LuaNumber((i + 1) as f64), string(skill)))
+        .collect();
+    let equipment = make_table(
+        member
+            .equipment
+            .iter()
+            .map(|(slot, item)| (slot.name(), string(item)))
+            .collect(),
+    );
+    make_table(vec![
+        ("character", string(&member.character)),
+        ("name", string(&member.name)),
+        ("level", number(member.level)),
+        ("exp", number(member.exp)),
+        ("hp", number(member.hp)),
+        ("mp", number(member.mp)),
+        ("skills", AnyLuaValue::LuaArray(skills)),
+        ("equipment", equipment),
+    ])
+}
+
+// Read a party member from their table in a save, starting from the character's info. Fails if
+//  the table doesn't say who the member is, or their info can't be read.
+fn read_member(
+    saved: &AnyLuaValue,
+    database: &Database,
+) -> Result<(PartyMember, Vec<DeucalionError>), DeucalionError> {
+    let character = String::from_lua(get_table_field(saved, "character")).ok_or_else(|| {
+        DeucalionError::LuaGetFailed(
+            format!("{}[].character", PARTY_GLOBAL),
+            String::from("string"),
+        )
+    })?;
+    let (mut member, errors) = get_saved_member_schema().read_from(
+        |identifier| Some(get_table_field(saved, identifier)),
+        PartyMember::by_name(&character)?,
+    );
+    // The character's curves may have changed since the game was saved.
+    member.level = member.level.min(member.growth().max_level());
+    let mut errors = errors;
+    let worn: Vec<_> = member
+        .equipment
+        .iter()
+        .map(|(&slot, item)| (slot, item.clone()))
+        .collect();
+    for (slot, item) in worn {
+        let fits = database
+            .item(&item)
+            .and_then(|item| item.equip_slot())
+            .map_or(false, |item_slot| item_slot == slot);
+        if !fits {
+            errors.push(DeucalionError::LuaGetFailed(
+                format!("equipment.{}", slot.name()),
+                format!("item in the database worn in the {} slot", slot.name()),
+            ));
+            member.equipment.remove(&slot);
+        }
+    }
+    let errors = errors
+        .into_iter()
+        .map(|e| match e {
+            DeucalionError::LuaGetFailed(field, expected) => DeucalionError::LuaGetFailed(
+                format!("{}[{}].{}", PARTY_GLOBAL, character, field),
+                expected,
+            ),
+            e => e,
+        })
+        .collect();
+    Ok((member, errors))
+}
@@ -0,0 +1,151 @@
+//! Shops: what a shop sells and for how much, and trading gold for items with it.
+//!
+//! Scripts open a shop with `open_shop(items, [prices])`, which `scene::shop` shows. The party
+//! buys an item for its `price` in the database, unless the shop sets a price of its own, and
+//! can sell anything they carry for half its price in the database, whatever shop they're in.
+//! Key items, and items without a price, can't be sold.
+
+use std::collections::BTreeMap;
+
+use battle::combatant::StatBonus;
+use battle::database::{Database, ItemKind};
+use error::DeucalionError;
+use game_state::GameState;
+
+/// What an item's price is divided by to give what shops pay for it.
+pub const SELL_PRICE_DIVISOR: u32 = 2;
+
+/// Something a shop sells.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ShopItem {
+    /// The item's name in the database.
+    pub item: String,
+    /// What one costs.
+    pub price: u32,
+}
+
+/// A shop's goods.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Shop {
+    /// What the shop sells, in the order it's shown.
+    pub goods: Vec<ShopItem>,
+}
+
+impl Shop {
+    /// Set up a shop selling items from the database, by name, at their own prices unless
+    /// `prices` gives others. Fails if any of the items isn't in the database.
+    pub fn new(
+        items: &[String],
+        prices: &BTreeMap<String, u32>,
+        database: &Database,
+    ) -> Result<Shop, DeucalionError> {
+        let mut goods = Vec::with_capacity(items.len());
+        for item in items.iter() {
+            let price = match (prices.get(item), database.item(item)) {
+                (_, None) => {
+                    return Err(DeucalionError::from(format!(
+                        "a shop can't sell '{}', which isn't in the database",
+                        item
+                    )))
+                }
+                (Some(&price), Some(_)) => price,
+                (None, Some(definition)) => definition.price,
+            };
+            goods.push(ShopItem {
+                item: item.clone(),
+                price: price,
+            });
+        }
+        Ok(Shop { goods: goods })
+    }
+
+    /// How many of the shop's goods, by index, the party can afford and has room for.
+    pub fn most_buyable(&self, index: usize, state: &GameState, database: &Database) -> u32 {
+        let goods = match self.goods.get(index) {
+            Some(goods) => goods,
+            None => return 0,
+        };
+        let held = state.inventory.count(&goods.item);
+        let room = database
+            .item(&goods.item)
+            .map_or(0, |item| item.stack_limit.saturating_sub(held));
+        match state.gold.checked_div(goods.price) {
+            Some(affordable) => room.min(affordable),
+            None => room,
+        }
+    }
+
+    /// Buy `count` of the shop's goods, by index. Fails, changing nothing, if the party can't
+    /// afford them or has no room for them.
+    pub fn buy(
+        &self,
+        index: usize,
+        count: u32,
+        state: &mut GameState,
+        database: &Database,
+    ) -> Result<(), DeucalionError> {
+        if count > self.most_buyable(index, state, database) {
+            return Err(DeucalionError::from(format!(
+                "the party can't buy {} of the shop's goods",
+                count
+            )));
+        }
+        let goods = &self.goods[index];
+        state.spend_gold(goods.price * count);
+        state.give_item(&goods.item, count, database)?;
+        Ok(())
+    }
+}
+
+/// What a shop pays for one of an item, or None if it can't be sold.
+pub fn get_sell_price(item: &str, database: &Database) -> Option<u32> {
+    match database.item(item) {
+        Some(definition) if definition.kind != ItemKind::Key && definition.price > 0 => {
+            Some(definition.price / SELL_PRICE_DIVISOR)
+        }
+        _ => None,
+    }
+}
+
+/// Sell `count` of an item the party is carrying. Fails, changing nothing, if it can't be sold
+/// or they don't have that many.
+pub fn sell(
+    item: &str,
+    count: u32,
+    state: &mut GameState,
+    database: &Database,
+) -> Result<(), DeucalionError> {
+    let price = get_sell_price(item, database)
+        .ok_or_else(|| DeucalionError::from(format!("{} can't be sold", item)))?;
+    if !state.inventory.remove(item, count) {
+        return Err(DeucalionError::from(format!(
+            "the party doesn't have {} {}",
+            count, item
+        )));
+    }
+    state.give_gold(price.saturating_mul(count));
+    Ok(())
+}
+
+/// How wearing an item would change each of the given party members' stats, by character, in
+/// place of what they're wearing in its slot. Empty if the item isn't equipment.
+pub fn compare_equipment(
+    item: &str,
+    members: &[String],
+    state: &GameState,
+    database: &Database,
+) -> Vec<(String, StatBonus)> {
+    let slot = match database.item(item).and_then(|item| item.equip_slot()) {
+        Some(slot) => slot,
+        None => return Vec::new(),
+    };
+    members
+        .iter()
+        .filter_map(|character| state.member(character).map(|member| (character, member)))
+        .map(|(character, member)| {
+            let current = member.stats(&member.equipment_bonuses(database));
+            let wearing = member.stats_wearing(slot, item, database);
+            (character.clone(), current.difference(wearing))
+        })
+        .collect()
+}
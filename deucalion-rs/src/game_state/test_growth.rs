@@ -0,0 +1,108 @@
+use hlua::AnyLuaValue;
+
+use battle::combatant::Stats;
+use battle::formula::FormulaEnvironment;
+use config::schema::ConfigValue;
+use game_state::growth::*;
+use scripting::convert::make_table;
+
+fn formula(formula: &str) -> Curve {
+    Curve::Formula(String::from(formula))
+}
+
+fn grow(max_level: u32, exp_curve: &Curve, growth: &StatGrowth) -> Result<Growth, String> {
+    Growth::new(
+        Stats::default(),
+        max_level,
+        exp_curve,
+        growth,
+        &mut FormulaEnvironment::new(),
+    ).map_err(|e| e.to_string())
+}
+
+#[test]
+// Curves are lists of numbers or formulas, and growth tables may leave stats out.
+fn test_curves_from_lua() {
+    let list = AnyLuaValue::LuaArray(vec![
+        (AnyLuaValue::LuaNumber(2.0), AnyLuaValue::LuaNumber(12.0)),
+        (AnyLuaValue::LuaNumber(1.0), AnyLuaValue::LuaNumber(10.0)),
+    ]);
+    assert_eq!(Curve::from_lua(list), Some(Curve::Table(vec![10.0, 12.0])));
+    assert_eq!(
+        Curve::from_lua(AnyLuaValue::LuaString(String::from("level * 2"))),
+        Some(formula("level * 2"))
+    );
+    assert_eq!(Curve::from_lua(AnyLuaValue::LuaArray(vec![])), None);
+    assert_eq!(Curve::from_lua(AnyLuaValue::LuaNumber(3.0)), None);
+
+    let growth = StatGrowth::from_lua(make_table(vec![(
+        "attack",
+        AnyLuaValue::LuaString(String::from("level + 9")),
+    )])).unwrap();
+    assert_eq!(growth.attack, Some(formula("level + 9")));
+    assert_eq!(growth.max_hp, None);
+    let wrong = make_table(vec![("speed", AnyLuaValue::LuaBoolean(true))]);
+    assert_eq!(StatGrowth::from_lua(wrong), None);
+}
+
+#[test]
+// EXP should come from the curve, with level 1 needing none, and decide the level reached.
+fn test_exp_curve() {
+    let growth = grow(5, &formula("10 * (level - 1) ^ 2"), &StatGrowth::default()).unwrap();
+    assert_eq!(growth.max_level(), 5);
+    assert_eq!(growth.exp_for_level(1), Some(0));
+    assert_eq!(growth.exp_for_level(3), Some(40));
+    assert_eq!(growth.exp_for_level(6), None);
+    assert_eq!(growth.level_for_exp(0), 1);
+    assert_eq!(growth.level_for_exp(39), 2);
+    assert_eq!(growth.level_for_exp(40), 3);
+    assert_eq!(growth.level_for_exp(100_000), 5);
+
+    // A list of EXP ends at its last level, and its first is ignored.
+    let growth = grow(10, &Curve::Table(vec![5.0, 20.0, 50.0]), &StatGrowth::default()).unwrap();
+    assert_eq!(growth.max_level(), 3);
+    assert_eq!(growth.exp_for_level(1), Some(0));
+    assert_eq!(growth.level_for_exp(20), 2);
+}
+
+#[test]
+// Stats should follow their curves, keep the base stats without one, and carry on at the end
+// of a list.
+fn test_stat_growth() {
+    let growth = StatGrowth {
+        max_hp: Some(formula("30 + (level - 1) * 8")),
+        attack: Some(Curve::Table(vec![10.0, 12.5, 15.0])),
+        ..StatGrowth::default()
+    };
+    let growth = grow(5, &formula("level * 10"), &growth).unwrap();
+    assert_eq!(growth.stats_at(1).max_hp, 30);
+    assert_eq!(growth.stats_at(4).max_hp, 54);
+    assert_eq!(growth.stats_at(2).attack, 13);
+    assert_eq!(growth.stats_at(5).attack, 15);
+    assert_eq!(growth.stats_at(5).speed, Stats::default().speed);
+    // Past the highest level, stats stay at the highest level's.
+    assert_eq!(growth.stats_at(50), growth.stats_at(5));
+}
+
+#[test]
+// Curves that can't be worked out, or that give impossible values, should be refused.
+fn test_invalid_curves() {
+    let none = StatGrowth::default();
+    assert!(grow(5, &formula("level +"), &none).is_err());
+    assert!(grow(5, &formula("'lots'"), &none).is_err());
+    assert!(
+        grow(5, &Curve::Table(vec![0.0, 20.0, 10.0]), &none)
+            .unwrap_err()
+            .contains("goes down")
+    );
+    let dying = StatGrowth {
+        max_hp: Some(formula("10 - level * 5")),
+        ..StatGrowth::default()
+    };
+    assert!(grow(5, &formula("level"), &dying).is_err());
+    let weak = StatGrowth {
+        attack: Some(Curve::Table(vec![-1.0])),
+        ..StatGrowth::default()
+    };
+    assert!(grow(5, &formula("level"), &weak).is_err());
+}
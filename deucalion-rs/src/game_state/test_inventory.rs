@@ -0,0 +1,108 @@
+use battle::database::EquipSlot;
+use battle::test_support::item_database;
+use game_state::inventory::*;
+use game_state::GameState;
+
+#[test]
+// Items should be counted, added up to their stack limit, and only removed if there are enough.
+fn test_inventory() {
+    let mut inventory = Inventory::new();
+    assert_eq!(inventory.add("potion", 3, 5), 3);
+    assert_eq!(inventory.add("potion", 3, 5), 2);
+    assert_eq!(inventory.add("potion", 3, 5), 0);
+    assert_eq!(inventory.count("potion"), 5);
+    assert!(inventory.has("potion", 5));
+    assert!(!inventory.has("potion", 6));
+
+    assert!(!inventory.remove("potion", 6));
+    assert_eq!(inventory.count("potion"), 5);
+    assert!(inventory.remove("potion", 5));
+    assert_eq!(inventory.count("potion"), 0);
+    assert!(inventory.items().is_empty());
+}
+
+#[test]
+// Giving items should go by the database's stack limits, and fail for items it doesn't have.
+fn test_give_item() {
+    let database = item_database();
+    let mut state = GameState::new();
+    assert_eq!(state.give_item("potion", 150, &database).unwrap(), 99);
+    assert_eq!(state.give_item("helmet", 2, &database).unwrap(), 1);
+    assert!(state.give_item("excalibur", 1, &database).is_err());
+    assert_eq!(state.inventory.count("excalibur"), 0);
+}
+
+#[test]
+// Equipping should swap items between the inventory and the member's slot, changing their
+// stats, and fail for what can't be worn.
+fn test_equip() {
+    let database = item_database();
+    let mut state = GameState::new();
+    state.join("player").unwrap();
+    let base = state.member("player").unwrap().base_stats();
+    let stats = |state: &GameState| {
+        let player = state.member("player").unwrap();
+        player.stats(&player.equipment_bonuses(&database))
+    };
+
+    assert!(!state.equip("player", "sword", &database).unwrap());
+    state.give_item("sword", 1, &database).unwrap();
+    assert!(state.equip("player", "sword", &database).unwrap());
+    assert_eq!(state.inventory.count("sword"), 0);
+    assert_eq!(stats(&state).attack, base.attack + 5);
+    let combatant = {
+        let player = state.member("player").unwrap();
+        player.combatant(&player.equipment_bonuses(&database))
+    };
+    assert_eq!(combatant.stats.attack, base.attack + 5);
+
+    // Putting on a new helmet takes off the old one.
+    state.give_item("crown", 1, &database).unwrap();
+    state.give_item("helmet", 1, &database).unwrap();
+    assert!(state.equip("player", "crown", &database).unwrap());
+    state.member_mut("player").unwrap().hp = base.max_hp + 20;
+    assert!(state.equip("player", "helmet", &database).unwrap());
+    assert_eq!(state.inventory.count("crown"), 1);
+    assert_eq!(
+        state.member("player").unwrap().equipment.get(&EquipSlot::Head),
+        Some(&String::from("helmet"))
+    );
+    // HP beyond what the crown gave is lost with it.
+    assert_eq!(stats(&state).max_hp, base.max_hp);
+    assert_eq!(state.member("player").unwrap().hp, base.max_hp);
+
+    // The party can only carry one helmet.
+    state.give_item("helmet", 1, &database).unwrap();
+    assert!(state.unequip("player", EquipSlot::Head, &database).is_err());
+    state.inventory.remove("helmet", 1);
+    assert!(state.unequip("player", EquipSlot::Head, &database).unwrap());
+    assert!(!state.unequip("player", EquipSlot::Head, &database).unwrap());
+    assert_eq!(state.inventory.count("helmet"), 1);
+
+    state.give_item("potion", 1, &database).unwrap();
+    assert!(state.equip("player", "potion", &database).is_err());
+    assert!(state.equip("nobody_at_all", "crown", &database).is_err());
+}
+
+#[test]
+// Using an item outside battle should restore HP up to the maximum, and use nothing if it
+// wouldn't help.
+fn test_use_item() {
+    let database = item_database();
+    let mut state = GameState::new();
+    state.join("player").unwrap();
+    let max_hp = state.member("player").unwrap().base_stats().max_hp;
+    state.give_item("potion", 2, &database).unwrap();
+
+    assert!(!state.use_item("potion", "player", &database).unwrap());
+    state.member_mut("player").unwrap().hp = max_hp - 5;
+    assert!(state.use_item("potion", "player", &database).unwrap());
+    assert_eq!(state.member("player").unwrap().hp, max_hp);
+    assert_eq!(state.inventory.count("potion"), 1);
+
+    state.member_mut("player").unwrap().hp = 0;
+    assert!(!state.use_item("potion", "player", &database).unwrap());
+    assert_eq!(state.inventory.count("potion"), 1);
+    assert!(state.use_item("sword", "player", &database).is_err());
+    assert!(state.use_item("potion", "nobody_at_all", &database).is_err());
+}
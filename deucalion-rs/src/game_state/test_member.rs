@@ -0,0 +1,97 @@
+use battle::combatant::{StatBonus, Stats};
+use battle::formula::FormulaEnvironment;
+use game_state::growth::{Curve, Growth, StatGrowth};
+use game_state::member::*;
+use game_state::GameState;
+use world::character::CharacterInfo;
+
+// A member who needs 10 EXP for level 2, 20 for level 3 and so on, up to level 5, gaining 10
+// max HP and 2 max MP a level.
+fn member(level: u32) -> PartyMember {
+    let mut info = CharacterInfo::default_for("hero");
+    info.level = level;
+    let growth = StatGrowth {
+        max_hp: Some(Curve::Formula(String::from("20 + level * 10"))),
+        max_mp: Some(Curve::Formula(String::from("level * 2"))),
+        ..StatGrowth::default()
+    };
+    let growth = Growth::new(
+        Stats::default(),
+        5,
+        &Curve::Formula(String::from("(level - 1) * 10")),
+        &growth,
+        &mut FormulaEnvironment::new(),
+    ).unwrap();
+    PartyMember::new("hero", &info, growth)
+}
+
+#[test]
+// New members start at their level, with the EXP it takes and full HP and MP.
+fn test_new_member() {
+    let hero = member(3);
+    assert_eq!(hero.level, 3);
+    assert_eq!(hero.exp, 20);
+    assert_eq!((hero.hp, hero.mp), (50, 6));
+    assert_eq!(hero.exp_to_next_level(), Some(10));
+    // A starting level past the highest is brought down to it.
+    assert_eq!(member(12).level, 5);
+    assert_eq!(member(12).exp_to_next_level(), None);
+}
+
+#[test]
+// EXP should raise the level as far as it reaches, raising HP and MP with it.
+fn test_gaining_levels() {
+    let mut hero = member(1);
+    hero.hp = 5;
+    assert_eq!(hero.gain_exp(9), Vec::<u32>::new());
+    assert_eq!(hero.gain_exp(15), vec![2, 3]);
+    assert_eq!(hero.level, 3);
+    assert_eq!(hero.exp, 24);
+    assert_eq!(hero.base_stats().max_hp, 50);
+    assert_eq!((hero.hp, hero.mp), (25, 6));
+    assert_eq!(hero.gain_exp(1000), vec![4, 5]);
+    assert_eq!(hero.gain_exp(1000), Vec::<u32>::new());
+
+    // Knocked out members stay down.
+    let mut fallen = member(1);
+    fallen.hp = 0;
+    fallen.gain_exp(10);
+    assert_eq!(fallen.hp, 0);
+}
+
+#[test]
+// Battles should start from the member's HP and MP, with bonuses added to their stats, and
+// leave them as the battle did.
+fn test_member_in_battle() {
+    let mut hero = member(2);
+    hero.hp = 12;
+    let bonus = StatBonus {
+        attack: 5,
+        max_hp: -100,
+        ..StatBonus::default()
+    };
+    let combatant = hero.combatant(&[bonus, bonus]);
+    assert_eq!(combatant.stats.attack, Stats::default().attack + 10);
+    assert_eq!(combatant.stats.max_hp, 1);
+    assert_eq!(combatant.hp, 1);
+
+    let mut combatant = hero.combatant(&[]);
+    assert_eq!(combatant.hp, 12);
+    combatant.take_damage(5);
+    hero.update_from(&combatant);
+    assert_eq!(hero.hp, 7);
+    hero.recover(&[]);
+    assert_eq!((hero.hp, hero.mp), (40, 4));
+}
+
+#[test]
+// Joining should read the character's info, and joining again should change nothing.
+fn test_join() {
+    let mut state = GameState::new();
+    state.join("player").unwrap().gain_exp(10);
+    let player = state.member("player").unwrap().clone();
+    assert_eq!(player.name, "Hero");
+    assert_eq!(player.level, 2);
+    assert_eq!(state.join("player").unwrap().clone(), player);
+    assert!(state.member("nobody_at_all").is_none());
+}
@@ -0,0 +1,141 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+use battle::test_support::item_database;
+use error::DeucalionError;
+use game_state::save::*;
+use game_state::{GameState, Location};
+use scripting::modules::get_module_roots;
+use scripting::sandbox::{get_default_script_libraries, get_sandboxed_environment};
+
+// Read a game state from Lua source that sets the globals a save does.
+fn read(source: &str) -> (GameState, Vec<DeucalionError>) {
+    let mut environment =
+        get_sandboxed_environment(&get_default_script_libraries(), &get_module_roots(&[]));
+    environment.execute::<()>(source).unwrap();
+    read_save_from_environment(&mut environment, &item_database())
+}
+
+#[test]
+// Reading back a written game state should give the same state.
+fn test_save_round_trip() {
+    let mut state = GameState::new();
+    {
+        let player = state.join("player").unwrap();
+        player.gain_exp(95);
+        player.hp = 3;
+        player.name = String::from("Deucalion \"the Flooded\"");
+        player.skills.push(String::from("sleep"));
+    }
+    state.give_item("potion", 3, &item_database()).unwrap();
+    state.give_item("sword", 1, &item_database()).unwrap();
+    state.equip("player", "sword", &item_database()).unwrap();
+    state.give_gold(1234);
+    state.location = Some(Location {
+        map: String::from("map001"),
+        x: 96.0,
+        y: 40.5,
+        followers: vec![String::from("old_man")],
+    });
+    let (read_back, errors) = read(&game_state_to_lua(&state));
+    assert!(errors.is_empty(), "Unexpected errors {:?}", errors);
+    assert_eq!(read_back, state);
+}
+
+#[test]
+// Invalid values should be reported and left as the character's own, and members who can't be
+// told apart left out.
+fn test_invalid_save() {
+    let (state, errors) = read(
+        r#"
+        PARTY = {
+            { character = "player", level = "high", hp = 10 },
+            { name = "Nobody" },
+        }
+        "#,
+    );
+    assert_eq!(errors.len(), 2);
+    let player = state.member("player").unwrap();
+    assert_eq!(player.level, 1);
+    assert_eq!(player.hp, 10);
+    assert_eq!(player.name, "Hero");
+    assert_eq!(state.members.len(), 1);
+
+    let (state, errors) = read("PARTY = 'everyone'");
+    assert_eq!(errors.len(), 1);
+    assert!(state.members.is_empty());
+
+    let (state, errors) = read("GOLD = -5");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(state.gold, 0);
+}
+
+#[test]
+// Items that aren't in the database, or equipment worn in the wrong slot, should be reported and
+// left out.
+fn test_invalid_items() {
+    let (state, errors) = read(
+        r#"
+        PARTY = {
+            { character = "player", equipment = { weapon = "potion", head = "sword" } },
+        }
+        INVENTORY = { potion = 2, excalibur = 1, sword = "sharp" }
+        "#,
+    );
+    let mut identifiers: Vec<String> = errors
+        .into_iter()
+        .map(|e| match e {
+            DeucalionError::LuaGetFailed(identifier, _) => identifier,
+            e => panic!("Expected a value to be wrong, not {:?}", e),
+        })
+        .collect();
+    identifiers.sort();
+    assert_eq!(
+        identifiers,
+        vec![
+            "INVENTORY.excalibur",
+            "INVENTORY.sword",
+            "PARTY[player].equipment.head",
+            "PARTY[player].equipment.weapon",
+        ]
+    );
+    assert_eq!(state.inventory.count("potion"), 2);
+    assert_eq!(state.inventory.items().len(), 1);
+    assert!(state.member("player").unwrap().equipment.is_empty());
+}
+
+#[test]
+// A location without a map can't be gone back to, so it should be reported and left out.
+fn test_location_without_map() {
+    let (state, errors) = read("LOCATION = { x = 10, y = 20 }");
+    assert_eq!(state.location, None);
+    assert_eq!(errors.len(), 1);
+    let (state, errors) = read("LOCATION = { map = \"map001\", x = \"left\" }");
+    assert!(state.location.is_some());
+    assert_eq!(errors.len(), 1);
+}
+
+#[test]
+// Saves are numbered within their directory.
+fn test_save_path() {
+    assert_eq!(
+        get_save_path(Path::new("saves"), 2),
+        Path::new("saves").join("save2.lua")
+    );
+}
+
+#[test]
+// The latest save should be found among the slots, and there should be none without any saves.
+fn test_latest_save() {
+    // Each run has a directory of its own, so runs at the same time don't see each other's saves.
+    let directory = env::temp_dir().join(format!("deucalion_test_latest_save_{}", process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    assert_eq!(get_latest_save(&directory, 3), None);
+    write_save(&GameState::new(), &get_save_path(&directory, 2)).unwrap();
+    assert_eq!(get_latest_save(&directory, 3), Some(2));
+    // Slots past the game's last aren't looked at.
+    assert_eq!(get_latest_save(&directory, 1), None);
+    fs::remove_dir_all(&directory).unwrap();
+}
@@ -0,0 +1,130 @@
+use std::collections::BTreeMap;
+
+use battle::combatant::StatBonus;
+use battle::database::Database;
+use battle::test_support::item_database;
+use game_state::shop::*;
+use game_state::{GameState, MAX_GOLD};
+
+// The shared items, with potions only carried 10 at a time.
+fn database() -> Database {
+    let mut database = item_database();
+    database.items.get_mut("potion").unwrap().stack_limit = 10;
+    database
+}
+
+// A shop selling potions and swords, at their own prices.
+fn shop(database: &Database) -> Shop {
+    let items = vec![String::from("potion"), String::from("sword")];
+    Shop::new(&items, &BTreeMap::new(), database).unwrap()
+}
+
+#[test]
+// Shops should charge the database's prices unless they set their own, and only sell items in
+// the database.
+fn test_shop_prices() {
+    let database = database();
+    let items = vec![String::from("potion"), String::from("sword")];
+    let mut prices = BTreeMap::new();
+    prices.insert(String::from("sword"), 80);
+    let shop = Shop::new(&items, &prices, &database).unwrap();
+    assert_eq!(
+        shop.goods,
+        vec![
+            ShopItem {
+                item: String::from("potion"),
+                price: 50,
+            },
+            ShopItem {
+                item: String::from("sword"),
+                price: 80,
+            },
+        ]
+    );
+    let unknown = vec![String::from("excalibur")];
+    assert!(Shop::new(&unknown, &BTreeMap::new(), &database).is_err());
+}
+
+#[test]
+// The party should only be able to buy as many as they can afford and carry.
+fn test_buying() {
+    let database = database();
+    let shop = shop(&database);
+    let mut state = GameState::new();
+    assert_eq!(shop.most_buyable(0, &state, &database), 0);
+    assert!(shop.buy(0, 1, &mut state, &database).is_err());
+
+    state.give_gold(420);
+    assert_eq!(shop.most_buyable(0, &state, &database), 8);
+    shop.buy(0, 3, &mut state, &database).unwrap();
+    assert_eq!(state.gold, 270);
+    assert_eq!(state.inventory.count("potion"), 3);
+
+    state.give_gold(1000);
+    assert_eq!(shop.most_buyable(0, &state, &database), 7);
+    assert!(shop.buy(0, 8, &mut state, &database).is_err());
+    assert_eq!(state.gold, 1270);
+    assert_eq!(state.inventory.count("potion"), 3);
+    assert_eq!(shop.most_buyable(2, &state, &database), 0);
+}
+
+#[test]
+// Items should sell for half their price, unless they're key items or have no price.
+fn test_selling() {
+    let database = database();
+    assert_eq!(get_sell_price("potion", &database), Some(25));
+    assert_eq!(get_sell_price("key", &database), None);
+    assert_eq!(get_sell_price("rock", &database), None);
+    assert_eq!(get_sell_price("excalibur", &database), None);
+
+    let mut state = GameState::new();
+    state.give_item("potion", 4, &database).unwrap();
+    state.give_item("key", 1, &database).unwrap();
+    sell("potion", 3, &mut state, &database).unwrap();
+    assert_eq!(state.gold, 75);
+    assert_eq!(state.inventory.count("potion"), 1);
+    assert!(sell("potion", 2, &mut state, &database).is_err());
+    assert!(sell("key", 1, &mut state, &database).is_err());
+    assert_eq!(state.gold, 75);
+    assert_eq!(state.inventory.count("potion"), 1);
+    assert_eq!(state.inventory.count("key"), 1);
+}
+
+#[test]
+// The party can't carry more than the most gold, however much they're given.
+fn test_gold_limits() {
+    let mut state = GameState::new();
+    assert_eq!(state.give_gold(MAX_GOLD - 10), MAX_GOLD - 10);
+    assert_eq!(state.give_gold(100), 10);
+    assert_eq!(state.gold, MAX_GOLD);
+    assert!(!state.spend_gold(MAX_GOLD + 1));
+    assert!(state.spend_gold(MAX_GOLD));
+    assert_eq!(state.gold, 0);
+}
+
+#[test]
+// Equipment should be compared with what each member is wearing in its slot, and other items
+// not compared at all.
+fn test_compare_equipment() {
+    let database = database();
+    let mut state = GameState::new();
+    state.join("player").unwrap();
+    let members = vec![String::from("player"), String::from("nobody")];
+    let attack = |comparison: Vec<(String, StatBonus)>| {
+        comparison
+            .into_iter()
+            .map(|(character, changes)| (character, changes.attack))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        attack(compare_equipment("sword", &members, &state, &database)),
+        vec![(String::from("player"), 5)]
+    );
+    state.give_item("spear", 1, &database).unwrap();
+    state.equip("player", "spear", &database).unwrap();
+    assert_eq!(
+        attack(compare_equipment("sword", &members, &state, &database)),
+        vec![(String::from("player"), -3)]
+    );
+    assert!(compare_equipment("potion", &members, &state, &database).is_empty());
+}
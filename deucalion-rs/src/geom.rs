@@ -13,3 +13,9 @@ pub struct WorldSpace;
 pub type WorldPoint = euclid::TypedPoint2D<f32, WorldSpace>;
 /// Indicates the size of a section of the world space.
 pub type WorldSize = euclid::TypedSize2D<f32, WorldSpace>;
+/// A displacement in the game's world space, such as a movement.
+pub type WorldVector = euclid::TypedVector2D<f32, WorldSpace>;
+/// A rectangular area of the game's world space, such as the bounds of an actor.
+pub type WorldRect = euclid::TypedRect<f32, WorldSpace>;
+/// A rectangular area of the screen, such as a window of the UI.
+pub type ScreenRect = euclid::TypedRect<u32, ScreenSpace>;
@@ -0,0 +1,251 @@
+//! Translation of raw keyboard input into the named actions the rest of the engine works with.
+//! Which keys trigger which action is configured by the player in engine_config.lua.
+//!
+//! Movement can also come from the left analog stick of the first joystick, which gives finer
+//! control than the direction keys when the map uses free movement.
+
+use std::collections::BTreeMap;
+
+use sfml::window::{joystick, Key};
+
+use geom::WorldVector;
+
+/// How far the analog stick must be pushed, as a fraction of its full range, before it counts as
+/// movement. Sticks rarely rest exactly at their centre.
+pub const ANALOG_DEAD_ZONE: f32 = 0.25;
+
+// The joystick whose stick is read for movement.
+const MOVEMENT_JOYSTICK: u32 = 0;
+
+#[cfg(test)]
+mod test_input;
+
+/// An input action. Game logic should only ever look at actions, never at raw keys.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Hash, PartialOrd, Ord)]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    Confirm,
+    Cancel,
+    Menu,
+}
+
+impl Action {
+    /// Every action, in the order they are presented to the player.
+    pub fn all() -> &'static [Action] {
+        static ALL: [Action; 7] = [
+            Action::Up,
+            Action::Down,
+            Action::Left,
+            Action::Right,
+            Action::Confirm,
+            Action::Cancel,
+            Action::Menu,
+        ];
+        &ALL
+    }
+
+    /// Parse an action from the name used for it in engine_config.lua.
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::all().iter().cloned().find(|a| a.name() == name)
+    }
+
+    /// The name used for this action in engine_config.lua.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::Left => "left",
+            Action::Right => "right",
+            Action::Confirm => "confirm",
+            Action::Cancel => "cancel",
+            Action::Menu => "menu",
+        }
+    }
+}
+
+/// Look up a key by the name used for it in engine_config.lua, which is the name of the SFML
+/// key, e.g. "A", "Num1", "Return" or "LShift".
+pub fn key_from_name(name: &str) -> Option<Key> {
+    let key = match name {
+        "A" => Key::A,
+        "B" => Key::B,
+        "C" => Key::C,
+        "D" => Key::D,
+        "E" => Key::E,
+        "F" => Key::F,
+        "G" => Key::G,
+        "H" => Key::H,
+        "I" => Key::I,
+        "J" => Key::J,
+        "K" => Key::K,
+        "L" => Key::L,
+        "M" => Key::M,
+        "N" => Key::N,
+        "O" => Key::O,
+        "P" => Key::P,
+        "Q" => Key::Q,
+        "R" => Key::R,
+        "S" => Key::S,
+        "T" => Key::T,
+        "U" => Key::U,
+        "V" => Key::V,
+        "W" => Key::W,
+        "X" => Key::X,
+        "Y" => Key::Y,
+        "Z" => Key::Z,
+        "Num0" => Key::Num0,
+        "Num1" => Key::Num1,
+        "Num2" => Key::Num2,
+        "Num3" => Key::Num3,
+        "Num4" => Key::Num4,
+        "Num5" => Key::Num5,
+        "Num6" => Key::Num6,
+        "Num7" => Key::Num7,
+        "Num8" => Key::Num8,
+        "Num9" => Key::Num9,
+        "Escape" => Key::Escape,
+        "LControl" => Key::LControl,
+        "LShift" => Key::LShift,
+        "LAlt" => Key::LAlt,
+        "RControl" => Key::RControl,
+        "RShift" => Key::RShift,
+        "RAlt" => Key::RAlt,
+        "Space" => Key::Space,
+        "Return" => Key::Return,
+        "BackSpace" => Key::BackSpace,
+        "Tab" => Key::Tab,
+        "Left" => Key::Left,
+        "Right" => Key::Right,
+        "Up" => Key::Up,
+        "Down" => Key::Down,
+        "Numpad0" => Key::Numpad0,
+        "Numpad1" => Key::Numpad1,
+        "Numpad2" => Key::Numpad2,
+        "Numpad3" => Key::Numpad3,
+        "Numpad4" => Key::Numpad4,
+        "Numpad5" => Key::Numpad5,
+        "Numpad6" => Key::Numpad6,
+        "Numpad7" => Key::Numpad7,
+        "Numpad8" => Key::Numpad8,
+        "Numpad9" => Key::Numpad9,
+        "F1" => Key::F1,
+        "F2" => Key::F2,
+        "F3" => Key::F3,
+        "F4" => Key::F4,
+        "F5" => Key::F5,
+        "F6" => Key::F6,
+        "F7" => Key::F7,
+        "F8" => Key::F8,
+        "F9" => Key::F9,
+        "F10" => Key::F10,
+        "F11" => Key::F11,
+        "F12" => Key::F12,
+        _ => return None,
+    };
+    Some(key)
+}
+
+/// The mapping from keys to actions.
+#[derive(Debug, Clone)]
+pub struct KeyBindings {
+    bindings: Vec<(Key, Action)>,
+}
+
+impl KeyBindings {
+    /// Build key bindings from the configured action and key names. Unknown names are reported
+    /// to the developer and skipped.
+    pub fn from_config(config: &BTreeMap<String, Vec<String>>) -> KeyBindings {
+        let mut bindings = Vec::new();
+        for (action_name, key_names) in config.iter() {
+            let action = match Action::from_name(action_name) {
+                Some(a) => a,
+                None => {
+                    warn!("Ignoring key bindings for unknown action '{}'", action_name);
+                    continue;
+                }
+            };
+            for key_name in key_names.iter() {
+                match key_from_name(key_name) {
+                    Some(key) => bindings.push((key, action)),
+                    None => warn!(
+                        "Ignoring unknown key '{}' bound to action '{}'",
+                        key_name, action_name
+                    ),
+                }
+            }
+        }
+        KeyBindings { bindings: bindings }
+    }
+
+    /// Get the action bound to a key, if there is one.
+    pub fn action_for_key(&self, key: Key) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|&&(k, _)| k == key)
+            .map(|&(_, action)| action)
+    }
+
+    /// Whether any key bound to an action is currently held down.
+    pub fn is_action_held(&self, action: Action) -> bool {
+        self.bindings
+            .iter()
+            .any(|&(key, a)| a == action && key.is_pressed())
+    }
+
+    /// Get every key bound to an action.
+    pub fn keys_for_action(&self, action: Action) -> Vec<Key> {
+        self.bindings
+            .iter()
+            .filter(|&&(_, a)| a == action)
+            .map(|&(key, _)| key)
+            .collect()
+    }
+}
+
+/// Turn analog stick axes, each from -1 to 1, into movement input of length at most one. Input
+/// inside the dead zone is ignored, and the rest of the range is stretched to cover it, so that
+/// movement starts smoothly from zero rather than jumping to the dead zone's edge.
+pub fn movement_from_axes(x: f32, y: f32) -> WorldVector {
+    let input = WorldVector::new(x, y);
+    let length = input.length();
+    if length <= ANALOG_DEAD_ZONE {
+        return WorldVector::new(0.0, 0.0);
+    }
+    let strength = ((length - ANALOG_DEAD_ZONE) / (1.0 - ANALOG_DEAD_ZONE)).min(1.0);
+    input * (strength / length)
+}
+
+/// Turn held direction actions into movement input. Opposite directions cancel out.
+pub fn movement_from_actions(up: bool, down: bool, left: bool, right: bool) -> WorldVector {
+    let axis = |negative: bool, positive: bool| match (negative, positive) {
+        (true, false) => -1.0,
+        (false, true) => 1.0,
+        _ => 0.0,
+    };
+    WorldVector::new(axis(left, right), axis(up, down))
+}
+
+/// Read the player's movement input: the analog stick, if it's being pushed, and otherwise the
+/// keys bound to the direction actions. Diagonal key input is left at full length on each axis;
+/// `world::movement::MovementSettings::restrict_input` limits it.
+pub fn read_movement(bindings: &KeyBindings) -> WorldVector {
+    if joystick::is_connected(MOVEMENT_JOYSTICK) {
+        // SFML reports axes from -100 to 100.
+        let stick = movement_from_axes(
+            joystick::axis_position(MOVEMENT_JOYSTICK, joystick::Axis::X) / 100.0,
+            joystick::axis_position(MOVEMENT_JOYSTICK, joystick::Axis::Y) / 100.0,
+        );
+        if stick.length() > 0.0 {
+            return stick;
+        }
+    }
+    movement_from_actions(
+        bindings.is_action_held(Action::Up),
+        bindings.is_action_held(Action::Down),
+        bindings.is_action_held(Action::Left),
+        bindings.is_action_held(Action::Right),
+    )
+}
@@ -0,0 +1,26 @@
+use input::*;
+use geom::WorldVector;
+
+#[test]
+// A stick resting near its centre shouldn't move anything.
+fn test_dead_zone() {
+    assert_eq!(movement_from_axes(0.1, -0.1), WorldVector::new(0.0, 0.0));
+}
+
+#[test]
+// Past the dead zone, strength rises from zero to one at full tilt.
+fn test_analog_strength() {
+    let full = movement_from_axes(1.0, 0.0);
+    assert!((full.x - 1.0).abs() < 1e-6);
+    let half = movement_from_axes(0.0, ANALOG_DEAD_ZONE + (1.0 - ANALOG_DEAD_ZONE) / 2.0);
+    assert!((half.y - 0.5).abs() < 1e-6);
+    // Corners of square stick gates aren't faster.
+    assert!(movement_from_axes(1.0, 1.0).length() <= 1.0 + 1e-6);
+}
+
+#[test]
+fn test_movement_from_actions() {
+    assert_eq!(movement_from_actions(true, false, false, true), WorldVector::new(1.0, -1.0));
+    // Opposite directions cancel out.
+    assert_eq!(movement_from_actions(true, true, false, false), WorldVector::new(0.0, 0.0));
+}
@@ -7,11 +7,14 @@ extern crate env_logger;
 extern crate hlua;
 extern crate tiled;
 extern crate euclid;
+extern crate flate2;
+extern crate dirs;
+extern crate lua52_sys;
 
-use sfml::window::{ContextSettings, VideoMode, Style as WindowStyle};
-use sfml::window::Event;
-use sfml::graphics::{RenderTarget, RenderWindow};
-use sfml::graphics::Color;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use sfml::window::{Event, Key};
 
 mod error;
 
@@ -20,6 +23,15 @@ mod utility;
 mod scripting;
 mod resource;
 mod geom;
+mod display;
+mod audio;
+mod input;
+mod engine;
+mod scene;
+mod world;
+mod battle;
+mod game_state;
+mod ui;
 
 fn fake_main<'engine>() -> i32 {
     // Init'ing the log system is the first thing to try. Without it, nothing else
@@ -27,57 +39,122 @@ fn fake_main<'engine>() -> i32 {
     env_logger::init().unwrap();
     info!("env_logger has been initialized successfully.");
 
-    // Init the scripting subsystem
-    let mut engine_scripting_environment = scripting::get_scripting_environment();
-
-    // Acquire the engine configuration.
-    let engine_config = config::engine_config::get_engine_config(&mut engine_scripting_environment);
+    // Init the scripting subsystem. The configuration scripts get an environment of their own,
+    //  which is thrown away once they've been read; game scripts never see it.
+    let mut engine_scripting_environment = scripting::sandbox::get_sandboxed_environment(
+        &scripting::sandbox::get_default_script_libraries(),
+        &scripting::modules::get_module_roots(&[]),
+    );
 
-    // Acquire the game's configuration.
+    // Acquire the game's configuration first, since the player's engine configuration is kept
+    //  in a directory named after the game.
     let game_config = config::game_config::get_game_config(&mut engine_scripting_environment);
 
-    // Initialize the game window.
-    let mut window = RenderWindow::new(
-        VideoMode::new(engine_config.screen_width, engine_config.screen_height, 32),
-        &game_config.title,
-        WindowStyle::CLOSE,
-        &ContextSettings::default());
-
-    // Set the game's maximum framerate.
-    window.set_framerate_limit(engine_config.maximum_framerate);
-
-    // Load the initial map into memory. This should go somewhere else later, but that design work
-    // has not yet been done.
-    let current_map = resource::map::Tilemap::by_name(&game_config.starting_map)
+    // Acquire the engine configuration, letting the player's saved options override the shipped
+    //  ones.
+    let user_config_path = config::engine_config::get_user_engine_config_path(&game_config);
+    let engine_config = config::engine_config::get_engine_config(
+        &mut engine_scripting_environment,
+        user_config_path.as_ref().map(|p| p.as_path()),
+    );
+    drop(engine_scripting_environment);
+
+    // Initialize the game window, and the canvas the game is drawn on.
+    let display = display::Display::new(&engine_config, &game_config.title)
         .unwrap_or_else(|e| {
             error!("{}", e);
             std::process::exit(1);
         });
 
-    // TEMP: TODO: Remove this, it's just for testing the map loading
-    let spr = sfml::graphics::Sprite::with_texture(&current_map.tilesets[0].texture);
+    // The font all engine UI, such as the options menu, is drawn with.
+    let font = resource::font::LoadedFont::by_name("default").unwrap_or_else(|e| {
+        error!("{}", e);
+        std::process::exit(1);
+    });
+
+    // The windowskin the UI is drawn with. Without one, or if it can't be loaded, windows are
+    //  drawn plainly.
+    let windowskin = if game_config.windowskin.is_empty() {
+        None
+    } else {
+        match ui::skin::Windowskin::by_name(&game_config.windowskin) {
+            Ok(windowskin) => Some(windowskin),
+            Err(e) => {
+                error!("Failed to load the windowskin, drawing windows plainly: {}", e);
+                None
+            }
+        }
+    };
 
-    while window.is_open() {
+    // The skills, status effects and items the game uses. Invalid entries are left out, so the
+    //  game can still be played while they're fixed.
+    let (database, errors) = battle::database::Database::load();
+    for e in errors.iter() {
+        error!("Invalid database entry, leaving it out: {}", e);
+    }
+    let database = Rc::new(database);
+    let game_state = Rc::new(RefCell::new(game_state::GameState::new()));
+
+    // Game scripts run sandboxed, with the libraries the developer allows, and can read and
+    //  change the game state.
+    let scripts = scripting::sandbox::ScriptEnvironments::new(
+        &game_config.script_libraries,
+        &scripting::modules::get_module_roots(&game_config.mod_roots),
+        game_config.script_budget(),
+    ).with_game_state(&game_state, &database);
+
+    let mut engine = engine::Engine {
+        display: display,
+        // Apply the player's volume settings.
+        mixer: audio::Mixer::new(&engine_config),
+        music: audio::MusicPlayer::new(),
+        // Translate keys into actions according to the player's bindings.
+        key_bindings: input::KeyBindings::from_config(&engine_config.key_bindings),
+        engine_config: engine_config,
+        game_config: game_config,
+        user_config_path: user_config_path,
+        font: font,
+        windowskin: windowskin,
+        scripts: scripts,
+        developer_overlay: display::developer_overlay::DeveloperOverlay::new(),
+        database: database,
+        game_state: game_state,
+    };
+
+    // Run the game's entry point, data/scripts/init.lua.
+    engine.run_init_script();
+
+    // The game starts on the title screen, which starts a new game or loads a save from there.
+    let title_scene = scene::title::TitleScene::new(&mut engine);
+    let mut scenes = scene::SceneStack::new(Box::new(title_scene));
+
+    while engine.display.window.is_open() && !scenes.is_empty() {
         // poll_event() returns Some(e) if there's an event to look at
-
-        while let Some(current_event) = window.poll_event() {
-            if current_event == Event::Closed {
-                window.close();
+        while let Some(current_event) = engine.display.window.poll_event() {
+            match current_event {
+                Event::Closed => engine.display.window.close(),
+                // In developer mode, F5 reloads every script module, so changes to them can be
+                //  tried out without restarting.
+                Event::KeyPressed { code: Key::F5, .. } if engine.game_config.developer_mode => {
+                    engine.reload_scripts()
+                }
+                Event::KeyPressed { code, .. } => {
+                    if let Some(action) = engine.key_bindings.action_for_key(code) {
+                        debug!("Key {:?} pressed, triggering action {:?}", code, action);
+                        scenes.on_action(&mut engine, action);
+                    }
+                }
+                _ => {}
             }
         }
 
-        // Clear the window to ready it for rendering
-        // TODO: Configurable background color
-        window.clear(&Color::BLACK);
-
-        // Draw the loaded map's tilesheet so we can see it
-        window.draw(&spr);
-
-
-        // TODO: Change the world's state here.
+        // Change the world's state
+        scenes.update(&mut engine);
 
-        // Present the new frame to the user
-        window.display();
+        // Draw the scenes onto the canvas, then present the new frame to the user
+        scenes.draw(&mut engine);
+        engine.draw_developer_overlay();
+        engine.display.present();
     }
     return 0;
 }
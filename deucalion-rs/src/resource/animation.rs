@@ -0,0 +1,264 @@
+//! Frame animations: animated tiles, character emotes, effects and the like.
+//!
+//! An animation is a list of frames, each shown for a number of seconds, that either loops or
+//! stops on its last frame. A frame may name an event, which happens when the frame is shown.
+//!
+//! Animations come from two places. Tiled tile animations are read along with their map. Any
+//! other animation is described by data/animations/<name>/info.lua, e.g.:
+//!
+//! ```lua
+//! IMAGE = "sheet.png"  -- beside info.lua
+//! FRAME_WIDTH = 16
+//! FRAME_HEIGHT = 16
+//! LOOP = false
+//! FRAMES = {
+//!     { frame = 0, duration = 0.1 },
+//!     { frame = 1, duration = 0.1, event = "pop" },
+//! }
+//! ```
+//!
+//! Frames are numbered across the image's rows, from the top left. When a frame with an event
+//! is shown, data/animations/<name>/on_<event>.lua is run, if there is one.
+
+use std::rc::Rc;
+
+use hlua::AnyLuaValue;
+use sfml::graphics::{IntRect, Texture};
+use tiled;
+
+use config::schema::{ConfigValue, Field, Schema};
+use error::DeucalionError;
+use geom::ScreenSize;
+use resource::loading;
+use resource::ResourceKind;
+use scripting::budget::ScriptBudget;
+use scripting::convert::get_table_field;
+use scripting::execute_script_from_reader;
+use scripting::modules::get_module_roots;
+use scripting::sandbox::{get_default_script_libraries, get_sandboxed_environment};
+
+/// One frame of an animation.
+#[derive(PartialEq, Debug, Clone)]
+pub struct AnimationFrame {
+    /// Which frame of the image to show: for a tile animation, the ID of a tile in the same
+    /// tileset.
+    pub frame: u32,
+    /// How long to show it, in seconds.
+    pub duration: f32,
+    /// What happens when the frame is shown, if anything.
+    pub event: Option<String>,
+}
+
+impl ConfigValue for AnimationFrame {
+    fn type_name() -> String {
+        String::from("frame (a table with frame, duration and, optionally, event)")
+    }
+
+    fn from_lua(value: AnyLuaValue) -> Option<AnimationFrame> {
+        let frame = u32::from_lua(get_table_field(&value, "frame"))?;
+        let duration = f64::from_lua(get_table_field(&value, "duration")).filter(|d| *d >= 0.0)?;
+        let event = match get_table_field(&value, "event") {
+            AnyLuaValue::LuaNil => None,
+            event => Some(String::from_lua(event)?),
+        };
+        Some(AnimationFrame {
+            frame: frame,
+            duration: duration as f32,
+            event: event,
+        })
+    }
+}
+
+/// Frames shown one after another.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Animation {
+    pub frames: Vec<AnimationFrame>,
+    /// Whether to start again after the last frame, rather than stopping on it.
+    pub looping: bool,
+}
+
+impl Animation {
+    /// Read a Tiled tile animation. These always loop, and have no events.
+    pub fn from_tiled(frames: &[tiled::Frame]) -> Animation {
+        Animation {
+            frames: frames
+                .iter()
+                .map(|frame| AnimationFrame {
+                    frame: frame.tile_id,
+                    duration: frame.duration as f32 / 1000.0,
+                    event: None,
+                })
+                .collect(),
+            looping: true,
+        }
+    }
+
+    /// How long it takes to show every frame once, in seconds.
+    pub fn duration(&self) -> f32 {
+        self.frames.iter().map(|frame| frame.duration).sum()
+    }
+
+    /// The frame shown `time` seconds after the animation started, or None if it has no
+    /// frames. Used for animations that are all kept in step, like animated tiles.
+    pub fn frame_at(&self, time: f32) -> Option<&AnimationFrame> {
+        let duration = self.duration();
+        let mut time = if self.looping && duration > 0.0 {
+            time % duration
+        } else {
+            time
+        };
+        for frame in self.frames.iter() {
+            if time < frame.duration {
+                return Some(frame);
+            }
+            time -= frame.duration;
+        }
+        self.frames.last()
+    }
+}
+
+/// An animation's info.lua.
+#[derive(PartialEq, Debug, Clone)]
+pub struct AnimationInfo {
+    /// The image holding the frames, relative to the animation's directory.
+    pub image: String,
+    /// The size of each frame in the image, in pixels.
+    pub frame_width: u32,
+    pub frame_height: u32,
+    /// How far the animation is drawn from the middle of what it's played on, in pixels, such
+    /// as to show an emote above a character's head.
+    pub offset_x: i32,
+    pub offset_y: i32,
+    pub frames: Vec<AnimationFrame>,
+    pub looping: bool,
+}
+
+impl Default for AnimationInfo {
+    fn default() -> AnimationInfo {
+        AnimationInfo {
+            image: String::from("sheet.png"),
+            frame_width: 16,
+            frame_height: 16,
+            offset_x: 0,
+            offset_y: 0,
+            frames: Vec::new(),
+            looping: false,
+        }
+    }
+}
+
+impl AnimationInfo {
+    /// Read an animation's info from data/animations/<name>/info.lua. Invalid values are
+    /// reported and replaced by their defaults, except for the frames, which are required.
+    pub fn by_name(name: &str) -> Result<AnimationInfo, DeucalionError> {
+        let path = loading::get_resource_relative_path_by_name(ResourceKind::Animation, name)?;
+        // Like a character's info, it gets an environment of its own, which is thrown away once
+        //  it's been read.
+        let mut environment =
+            get_sandboxed_environment(&get_default_script_libraries(), &get_module_roots(&[]));
+        let reader = loading::open_data_file(&path)?;
+        execute_script_from_reader(&mut environment, reader, &path, &ScriptBudget::for_config())?;
+        let (info, mut errors) =
+            get_animation_info_schema().read(&mut environment, AnimationInfo::default());
+        if info.frames.is_empty() {
+            return Err(errors.pop().unwrap_or_else(|| {
+                DeucalionError::from(format!("animation {} has no frames", name))
+            }));
+        }
+        for e in errors.iter() {
+            warn!("Invalid value in the info of animation {}, using its default: {}", name, e);
+        }
+        Ok(info)
+    }
+
+    /// The animation described by this info.
+    pub fn animation(&self) -> Animation {
+        Animation {
+            frames: self.frames.clone(),
+            looping: self.looping,
+        }
+    }
+}
+
+/// Describe how each field of an AnimationInfo is read from info.lua.
+pub fn get_animation_info_schema() -> Schema<AnimationInfo> {
+    Schema::new()
+        .field(Field::new("IMAGE", |a: &mut AnimationInfo| &mut a.image))
+        .field(
+            Field::new("FRAME_WIDTH", |a: &mut AnimationInfo| &mut a.frame_width)
+                .check("above zero", |w| *w > 0),
+        )
+        .field(
+            Field::new("FRAME_HEIGHT", |a: &mut AnimationInfo| &mut a.frame_height)
+                .check("above zero", |h| *h > 0),
+        )
+        .field(Field::new("OFFSET_X", |a: &mut AnimationInfo| &mut a.offset_x))
+        .field(Field::new("OFFSET_Y", |a: &mut AnimationInfo| &mut a.offset_y))
+        .field(
+            Field::new("FRAMES", |a: &mut AnimationInfo| &mut a.frames)
+                .required()
+                .check("with at least one frame", |f| !f.is_empty()),
+        )
+        .field(Field::new("LOOP", |a: &mut AnimationInfo| &mut a.looping))
+}
+
+/// An animation with its image loaded, ready to be played.
+pub struct LoadedAnimation {
+    pub info: AnimationInfo,
+    /// The animation, shared with everything playing it.
+    pub animation: Rc<Animation>,
+    pub texture: Texture,
+}
+
+impl LoadedAnimation {
+    /// Load an animation and its image.
+    pub fn by_name(name: &str) -> Result<LoadedAnimation, DeucalionError> {
+        let info = AnimationInfo::by_name(name)?;
+        let mut image_path =
+            loading::get_resource_relative_path_by_name(ResourceKind::Animation, name)?;
+        image_path.set_file_name(&info.image);
+        let image_data = loading::read_data_file(&image_path)?;
+        let texture = match Texture::from_memory(&image_data, &IntRect::new(0, 0, 0, 0)) {
+            Some(texture) => texture,
+            None => {
+                return Err(DeucalionError::from(format!(
+                    "could not decode the image '{}' for animation '{}'",
+                    info.image, name
+                )))
+            }
+        };
+        info!("Successfully loaded the animation '{}'", name);
+        Ok(LoadedAnimation {
+            animation: Rc::new(info.animation()),
+            info: info,
+            texture: texture,
+        })
+    }
+
+    /// The part of the image showing a frame.
+    pub fn frame_rect(&self, frame: u32) -> IntRect {
+        let size = ScreenSize::new(self.info.frame_width, self.info.frame_height);
+        let columns = (self.texture.size().x / size.width).max(1);
+        get_sheet_rect(frame, columns, size, 0, 0)
+    }
+}
+
+/// Find the part of an image holding one of a grid of frames, numbered across the rows from the
+/// top left, given how many columns there are, the size of each frame, the space between
+/// frames and the margin around the edge of the image, all in pixels.
+pub fn get_sheet_rect(
+    frame: u32,
+    columns: u32,
+    size: ScreenSize,
+    spacing: u32,
+    margin: u32,
+) -> IntRect {
+    let column = frame % columns;
+    let row = frame / columns;
+    IntRect::new(
+        (margin + column * (size.width + spacing)) as i32,
+        (margin + row * (size.height + spacing)) as i32,
+        size.width as i32,
+        size.height as i32,
+    )
+}
@@ -0,0 +1,430 @@
+//! Packed data archives, for shipping a game's data directory as a single file.
+//!
+//! An archive has the following layout. All integers are little-endian.
+//!
+//! ```text
+//! magic        8 bytes, "DCLNPAK\0"
+//! version      u32
+//! entry count  u32
+//! index        one entry per file, see below
+//! file data    the stored contents of every file, back to back
+//! ```
+//!
+//! Each index entry is laid out as:
+//!
+//! ```text
+//! path length      u16
+//! path             UTF-8, '/'-separated, relative to the data directory
+//! compression      u8, see `Compression`
+//! offset           u64, from the start of the archive
+//! stored length    u64, the number of bytes in the archive
+//! original length  u64, the number of bytes after decompression
+//! content hash     u32, the CRC-32 of the original (uncompressed) contents
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::io::SeekFrom;
+use std::path::{Component, Path, PathBuf};
+
+use flate2;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+
+use error::DeucalionError;
+
+/// The bytes every archive starts with.
+pub const ARCHIVE_MAGIC: &'static [u8; 8] = b"DCLNPAK\0";
+/// The version of the archive format written by this module.
+pub const ARCHIVE_VERSION: u32 = 1;
+/// The conventional extension for archive files.
+pub const ARCHIVE_EXTENSION: &'static str = "pak";
+
+/// How the contents of a file are stored in an archive.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum Compression {
+    /// The contents are stored as-is.
+    Stored,
+    /// The contents are compressed with raw DEFLATE.
+    Deflate,
+}
+
+impl Compression {
+    fn to_byte(self) -> u8 {
+        match self {
+            Compression::Stored => 0,
+            Compression::Deflate => 1,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<Compression> {
+        match byte {
+            0 => Some(Compression::Stored),
+            1 => Some(Compression::Deflate),
+            _ => None,
+        }
+    }
+}
+
+/// The index information about a single file in an archive.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ArchiveEntry {
+    /// How the file's contents are stored.
+    pub compression: Compression,
+    /// The offset of the stored contents from the start of the archive.
+    pub offset: u64,
+    /// The number of bytes the contents take up in the archive.
+    pub stored_length: u64,
+    /// The number of bytes the contents take up once decompressed.
+    pub original_length: u64,
+    /// The CRC-32 of the decompressed contents.
+    pub content_hash: u32,
+}
+
+/// An opened archive. Only the index is kept in memory; file contents are read from disk on
+/// request.
+#[derive(Debug)]
+pub struct Archive {
+    path: PathBuf,
+    entries: BTreeMap<String, ArchiveEntry>,
+}
+
+impl Archive {
+    /// Open the archive at the given path and read its index.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Archive, DeucalionError> {
+        let path = path.as_ref().to_path_buf();
+        let mut reader = File::open(&path)?;
+        let entries = read_index(&mut reader, &path)?;
+        info!(
+            "Opened data archive at {} with {} entries",
+            path.display(),
+            entries.len()
+        );
+        Ok(Archive {
+            path: path,
+            entries: entries,
+        })
+    }
+
+    /// The path this archive was opened from.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Check whether the archive contains a file at the given data-relative path.
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.contains_key(&normalize_archive_name(name))
+    }
+
+    /// Get the index entry for a file, if it exists.
+    pub fn entry(&self, name: &str) -> Option<&ArchiveEntry> {
+        self.entries.get(&normalize_archive_name(name))
+    }
+
+    /// Iterate over the names of every file in the archive, in sorted order.
+    pub fn names<'a>(&'a self) -> Box<Iterator<Item = &'a str> + 'a> {
+        Box::new(self.entries.keys().map(|k| k.as_str()))
+    }
+
+    /// Read and decompress a file from the archive, verifying its content hash.
+    pub fn read(&self, name: &str) -> Result<Vec<u8>, DeucalionError> {
+        let name = normalize_archive_name(name);
+        let entry = match self.entries.get(&name) {
+            Some(e) => e,
+            None => {
+                return Err(DeucalionError::IoError(::std::io::Error::new(
+                    ::std::io::ErrorKind::NotFound,
+                    format!("'{}' is not in archive {}", name, self.path.display()),
+                )))
+            }
+        };
+        trace!("Reading '{}' from archive {}", name, self.path.display());
+
+        let mut file = File::open(&self.path)?;
+        // Check the entry against the archive's real size before allocating anything for it, so
+        //  that a corrupt index can't make us try to allocate an absurd amount of memory.
+        let archive_length = file.metadata()?.len();
+        if entry.offset > archive_length || entry.stored_length > archive_length - entry.offset {
+            return Err(self.error(format!(
+                "'{}' is stored past the end of the archive ({} bytes at offset {}, in {} bytes)",
+                name, entry.stored_length, entry.offset, archive_length
+            )));
+        }
+        file.seek(SeekFrom::Start(entry.offset))?;
+        let mut stored = vec![0u8; entry.stored_length as usize];
+        file.read_exact(&mut stored)?;
+
+        let contents = match entry.compression {
+            Compression::Stored => stored,
+            Compression::Deflate => {
+                // One byte more than expected is enough to tell that the length is wrong.
+                let mut decoded = Vec::new();
+                DeflateDecoder::new(&stored[..])
+                    .take(entry.original_length.saturating_add(1))
+                    .read_to_end(&mut decoded)?;
+                decoded
+            }
+        };
+
+        if contents.len() as u64 != entry.original_length {
+            return Err(self.error(format!(
+                "'{}' should be {} bytes long, but is {}",
+                name,
+                entry.original_length,
+                contents.len()
+            )));
+        }
+        if content_hash(&contents) != entry.content_hash {
+            return Err(self.error(format!("'{}' failed its content hash check", name)));
+        }
+        Ok(contents)
+    }
+
+    fn error(&self, description: String) -> DeucalionError {
+        DeucalionError::ArchiveError(self.path.to_string_lossy().into_owned(), description)
+    }
+}
+
+/// Builds an archive from a set of files.
+pub struct ArchiveBuilder {
+    files: BTreeMap<String, Vec<u8>>,
+}
+
+impl ArchiveBuilder {
+    /// Create an empty builder.
+    pub fn new() -> ArchiveBuilder {
+        ArchiveBuilder {
+            files: BTreeMap::new(),
+        }
+    }
+
+    /// Create a builder containing every file under the given directory, named relative to it.
+    pub fn from_directory<P: AsRef<Path>>(root: P) -> Result<ArchiveBuilder, DeucalionError> {
+        let mut builder = ArchiveBuilder::new();
+        add_directory(&mut builder, root.as_ref(), root.as_ref())?;
+        Ok(builder)
+    }
+
+    /// Add a file to the archive under the given data-relative name, replacing any file already
+    /// added under that name.
+    pub fn add_file(&mut self, name: &str, contents: Vec<u8>) {
+        self.files.insert(normalize_archive_name(name), contents);
+    }
+
+    /// The number of files that will be written.
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Iterate over the names and contents of every file that will be written, in sorted order.
+    pub fn files<'a>(&'a self) -> Box<Iterator<Item = (&'a str, &'a [u8])> + 'a> {
+        Box::new(self.files.iter().map(|(k, v)| (k.as_str(), &v[..])))
+    }
+
+    /// Write the archive out. Each file is compressed only if doing so makes it smaller.
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), DeucalionError> {
+        // Compress everything first, since the index needs to know the stored sizes.
+        let mut stored_files: Vec<(&String, Compression, Vec<u8>, u64, u32)> =
+            Vec::with_capacity(self.files.len());
+        for (name, contents) in self.files.iter() {
+            let mut encoder = DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(contents)?;
+            let compressed = encoder.finish()?;
+            let hash = content_hash(contents);
+            if compressed.len() < contents.len() {
+                stored_files.push((
+                    name,
+                    Compression::Deflate,
+                    compressed,
+                    contents.len() as u64,
+                    hash,
+                ));
+            } else {
+                stored_files.push((
+                    name,
+                    Compression::Stored,
+                    contents.clone(),
+                    contents.len() as u64,
+                    hash,
+                ));
+            }
+        }
+
+        // The data starts right after the header and index.
+        let index_length: u64 = stored_files
+            .iter()
+            .map(|&(name, _, _, _, _)| 2 + name.len() as u64 + 1 + 8 + 8 + 8 + 4)
+            .sum();
+        let mut offset = ARCHIVE_MAGIC.len() as u64 + 4 + 4 + index_length;
+
+        writer.write_all(ARCHIVE_MAGIC)?;
+        write_u32(writer, ARCHIVE_VERSION)?;
+        write_u32(writer, stored_files.len() as u32)?;
+        for &(name, compression, ref stored, original_length, hash) in stored_files.iter() {
+            if name.len() > u16::max_value() as usize {
+                return Err(DeucalionError::from(format!(
+                    "the path '{}' is too long to store in an archive",
+                    name
+                )));
+            }
+            write_u16(writer, name.len() as u16)?;
+            writer.write_all(name.as_bytes())?;
+            writer.write_all(&[compression.to_byte()])?;
+            write_u64(writer, offset)?;
+            write_u64(writer, stored.len() as u64)?;
+            write_u64(writer, original_length)?;
+            write_u32(writer, hash)?;
+            offset += stored.len() as u64;
+        }
+        for &(_, _, ref stored, _, _) in stored_files.iter() {
+            writer.write_all(stored)?;
+        }
+        Ok(())
+    }
+}
+
+/// Recursively add the files under `directory` to the builder, naming them relative to `root`.
+fn add_directory(
+    builder: &mut ArchiveBuilder,
+    root: &Path,
+    directory: &Path,
+) -> Result<(), DeucalionError> {
+    for dir_entry in fs::read_dir(directory)? {
+        let path = dir_entry?.path();
+        if path.is_dir() {
+            add_directory(builder, root, &path)?;
+        } else {
+            let relative = match path.strip_prefix(root) {
+                Ok(r) => r.to_path_buf(),
+                Err(_) => {
+                    return Err(DeucalionError::from(format!(
+                        "{} is not inside {}",
+                        path.display(),
+                        root.display()
+                    )))
+                }
+            };
+            let mut contents = Vec::new();
+            File::open(&path)?.read_to_end(&mut contents)?;
+            debug!("Adding {} to archive", relative.display());
+            builder.add_file(&relative.to_string_lossy(), contents);
+        }
+    }
+    Ok(())
+}
+
+/// Turn a data-relative path into the name it has in an archive: '/'-separated, with `.` and
+/// `..` components resolved. `..` components that would escape the data directory are dropped.
+pub fn normalize_archive_name<P: AsRef<Path>>(path: P) -> String {
+    let mut components: Vec<String> = Vec::new();
+    for component in path.as_ref().components() {
+        match component {
+            Component::Normal(c) => components.push(c.to_string_lossy().into_owned()),
+            Component::ParentDir => {
+                components.pop();
+            }
+            Component::CurDir | Component::RootDir | Component::Prefix(_) => {}
+        }
+    }
+    // Paths written on Windows may still contain backslashes.
+    components.join("/").replace('\\', "/")
+}
+
+/// Compute the content hash stored in archive indices.
+pub fn content_hash(contents: &[u8]) -> u32 {
+    let mut crc = flate2::Crc::new();
+    crc.update(contents);
+    crc.sum()
+}
+
+fn read_index<R: Read>(
+    reader: &mut R,
+    path: &Path,
+) -> Result<BTreeMap<String, ArchiveEntry>, DeucalionError> {
+    let archive_error = |description: String| {
+        DeucalionError::ArchiveError(path.to_string_lossy().into_owned(), description)
+    };
+
+    let mut magic = [0u8; 8];
+    reader.read_exact(&mut magic)?;
+    if &magic != ARCHIVE_MAGIC {
+        return Err(archive_error(String::from("not a Deucalion data archive")));
+    }
+    let version = read_u32(reader)?;
+    if version != ARCHIVE_VERSION {
+        return Err(archive_error(format!(
+            "unsupported archive version {} (expected {})",
+            version, ARCHIVE_VERSION
+        )));
+    }
+
+    let count = read_u32(reader)?;
+    let mut entries = BTreeMap::new();
+    for _ in 0..count {
+        let name_length = read_u16(reader)?;
+        let mut name = vec![0u8; name_length as usize];
+        reader.read_exact(&mut name)?;
+        let name = match String::from_utf8(name) {
+            Ok(n) => n,
+            Err(_) => return Err(archive_error(String::from("an entry's path is not UTF-8"))),
+        };
+        let mut compression = [0u8; 1];
+        reader.read_exact(&mut compression)?;
+        let compression = match Compression::from_byte(compression[0]) {
+            Some(c) => c,
+            None => {
+                return Err(archive_error(format!(
+                    "'{}' uses unknown compression {}",
+                    name, compression[0]
+                )))
+            }
+        };
+        let entry = ArchiveEntry {
+            compression: compression,
+            offset: read_u64(reader)?,
+            stored_length: read_u64(reader)?,
+            original_length: read_u64(reader)?,
+            content_hash: read_u32(reader)?,
+        };
+        entries.insert(name, entry);
+    }
+    Ok(entries)
+}
+
+fn write_u16<W: Write>(writer: &mut W, value: u16) -> Result<(), DeucalionError> {
+    writer.write_all(&[value as u8, (value >> 8) as u8])?;
+    Ok(())
+}
+
+fn write_u32<W: Write>(writer: &mut W, value: u32) -> Result<(), DeucalionError> {
+    for i in 0..4 {
+        writer.write_all(&[(value >> (8 * i)) as u8])?;
+    }
+    Ok(())
+}
+
+fn write_u64<W: Write>(writer: &mut W, value: u64) -> Result<(), DeucalionError> {
+    for i in 0..8 {
+        writer.write_all(&[(value >> (8 * i)) as u8])?;
+    }
+    Ok(())
+}
+
+fn read_u16<R: Read>(reader: &mut R) -> Result<u16, DeucalionError> {
+    let mut buf = [0u8; 2];
+    reader.read_exact(&mut buf)?;
+    Ok(buf[0] as u16 | (buf[1] as u16) << 8)
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32, DeucalionError> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(buf.iter().rev().fold(0u32, |acc, &b| (acc << 8) | b as u32))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> Result<u64, DeucalionError> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(buf.iter().rev().fold(0u64, |acc, &b| (acc << 8) | b as u64))
+}
@@ -0,0 +1,37 @@
+//! Provides facilities for loading fonts
+
+use sfml::graphics::Font;
+
+use error::DeucalionError;
+use resource::loading;
+use resource::ResourceKind;
+
+/// A font, loaded from the data directory or archive.
+pub struct LoadedFont {
+    // SFML reads glyphs out of the font's file data lazily, so the data has to outlive the font.
+    // Fields are dropped in declaration order, so `font` goes first.
+    pub font: Font,
+    data: Vec<u8>,
+}
+
+impl LoadedFont {
+    /// Given a name, return the font corresponding to it (or not, if it doesn't exist)
+    pub fn by_name(name: &str) -> Result<LoadedFont, DeucalionError> {
+        let path = loading::get_resource_relative_path_by_name(ResourceKind::Font, name)?;
+        let data = loading::read_data_file(&path)?;
+        match Font::from_memory(&data) {
+            Some(font) => {
+                info!("Successfully loaded font '{}'", name);
+                Ok(LoadedFont {
+                    font: font,
+                    data: data,
+                })
+            }
+            None => Err(DeucalionError::from(format!(
+                "could not decode the font '{}' at {}",
+                name,
+                path.display()
+            ))),
+        }
+    }
+}
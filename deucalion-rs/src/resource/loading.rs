@@ -1,30 +1,142 @@
 //! Utilities for loading resources.
+//!
+//! Resources are read either from the loose `data/` directory or, if it doesn't exist, from the
+//! packed `data.pak` archive. Both are looked for in the working directory. See
+//! `resource::archive` for the archive format. Callers should go through the functions here rather than opening files directly, so
+//! that both sources work transparently.
 
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{Cursor, Read};
 use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use resource::archive::{normalize_archive_name, Archive, ARCHIVE_EXTENSION};
 use resource::ResourceKind;
+use scripting::modules;
 use error::DeucalionError;
 
-use std::fs::File;
+/// The name of the loose data directory.
+pub const DATA_DIRECTORY_NAME: &'static str = "data";
+
+/// A reader for a resource, from whichever data source it was found in.
+pub type ResourceReader = Box<Read>;
+
+/// Where game data is being read from.
+#[derive(Clone)]
+pub enum DataSource {
+    /// A loose directory of files.
+    Directory(PathBuf),
+    /// A packed archive, opened once and shared.
+    Archive(Rc<Archive>),
+}
+
+thread_local! {
+    // The data archive is opened at most once, since opening it reads its whole index.
+    static OPENED_ARCHIVE: RefCell<Option<Rc<Archive>>> = RefCell::new(None);
+}
+
+/// Determine where game data should be read from. The data directory takes priority, so that
+/// developers can keep working on loose files while an old archive is lying around.
+pub fn get_data_source() -> Result<DataSource, DeucalionError> {
+    let directory = Path::new(".").join(DATA_DIRECTORY_NAME);
+    if directory.is_dir() {
+        return Ok(DataSource::Directory(directory));
+    }
+
+    let mut archive_path = Path::new(".").join(DATA_DIRECTORY_NAME);
+    archive_path.set_extension(ARCHIVE_EXTENSION);
+    OPENED_ARCHIVE.with(|cell| {
+        if let Some(ref archive) = *cell.borrow() {
+            return Ok(DataSource::Archive(archive.clone()));
+        }
+        if !archive_path.is_file() {
+            return Err(DeucalionError::from(format!(
+                "neither a data directory at {} nor a data archive at {} exists",
+                directory.display(),
+                archive_path.display()
+            )));
+        }
+        let archive = Rc::new(Archive::open(&archive_path)?);
+        *cell.borrow_mut() = Some(archive.clone());
+        Ok(DataSource::Archive(archive))
+    })
+}
+
+/// Open a file by its path relative to the data directory, from whichever data source is in use.
+pub fn open_data_file<P: AsRef<Path>>(relative_path: P) -> Result<ResourceReader, DeucalionError> {
+    match get_data_source()? {
+        DataSource::Directory(directory) => {
+            let path = directory.join(relative_path.as_ref());
+            Ok(Box::new(File::open(&path)?))
+        }
+        DataSource::Archive(archive) => Ok(Box::new(Cursor::new(
+            archive.read(&normalize_archive_name(relative_path))?,
+        ))),
+    }
+}
 
-/// Get a reader that can read in the requested resource from disk, and the path it's reading from.
+/// Read the whole of a file by its path relative to the data directory.
+pub fn read_data_file<P: AsRef<Path>>(relative_path: P) -> Result<Vec<u8>, DeucalionError> {
+    let mut contents = Vec::new();
+    open_data_file(relative_path)?.read_to_end(&mut contents)?;
+    Ok(contents)
+}
+
+/// Whether a file exists, by its path relative to the data directory, in whichever data source is
+/// in use.
+pub fn data_file_exists<P: AsRef<Path>>(relative_path: P) -> bool {
+    match get_data_source() {
+        Ok(DataSource::Directory(directory)) => directory.join(relative_path.as_ref()).is_file(),
+        Ok(DataSource::Archive(archive)) => {
+            archive.contains(&normalize_archive_name(relative_path))
+        }
+        Err(_) => false,
+    }
+}
+
+/// Find a file that may have any of several extensions, by its path relative to the data
+/// directory without one, trying each extension in turn. Fails if there's no file with any of
+/// them.
+pub fn find_data_file_with_extension<P: AsRef<Path>>(
+    relative_path: P,
+    extensions: &[&str],
+) -> Result<PathBuf, DeucalionError> {
+    let mut path = relative_path.as_ref().to_path_buf();
+    for extension in extensions {
+        path.set_extension(extension);
+        if data_file_exists(&path) {
+            return Ok(path);
+        }
+    }
+    Err(DeucalionError::from(format!(
+        "there is no {}.<{}>",
+        relative_path.as_ref().display(),
+        extensions.join("|")
+    )))
+}
+
+/// Get a reader that can read in the requested resource, and the path it's reading from.
 /// If requesting a resource whose name doesn't change, the `name` argument is ignored.
 pub fn get_resource_reader_and_path_by_name(
     kind: ResourceKind,
     name: &str,
-) -> Result<(File, PathBuf), DeucalionError> {
+) -> Result<(ResourceReader, PathBuf), DeucalionError> {
     // Acquire the path to the resource
     let resource_path = try!(get_resource_path_by_name(kind, name));
-    let reader = try!(File::open(&resource_path));
+    let reader = try!(get_resource_reader_by_name(kind, name));
     return Ok((reader, resource_path));
 }
 
-/// Get a reader that can read in the requested resource from disk.
+/// Get a reader that can read in the requested resource.
 /// If requesting a resource whose name doesn't change, the `name` argument is ignored.
-pub fn get_resource_reader_by_name(kind: ResourceKind, name: &str) -> Result<File, DeucalionError> {
+pub fn get_resource_reader_by_name(
+    kind: ResourceKind,
+    name: &str,
+) -> Result<ResourceReader, DeucalionError> {
     // Acquire the path to the resource
-    let resource_path = try!(get_resource_path_by_name(kind, name));
-    let reader = try!(File::open(&resource_path));
-    return Ok(reader);
+    let relative_path = try!(get_resource_relative_path_by_name(kind, name));
+    return open_data_file(relative_path);
 }
 
 /// Get the path to a resource in the data directory scheme. See BLUEPRINT.md for more info.
@@ -34,7 +146,33 @@ pub fn get_resource_path_by_name(
     name: &str,
 ) -> Result<PathBuf, DeucalionError> {
     // Everything is in the data directory.
-    let mut path = Path::new(".").join("data");
+    let path = Path::new(".").join(DATA_DIRECTORY_NAME);
+    Ok(path.join(get_resource_relative_path_by_name(kind, name)?))
+}
+
+// Make sure a name can be used as a directory name without leaving the directory it belongs in.
+fn check_directory_name(kind: &str, name: &str) -> Result<(), DeucalionError> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if valid {
+        Ok(())
+    } else {
+        Err(DeucalionError::from(format!(
+            "'{}' is not a valid {} name",
+            name, kind
+        )))
+    }
+}
+
+/// Get the path to a resource, relative to the root of the data directory or archive.
+/// If requesting a resource whose name doesn't change, the `name` argument is ignored.
+pub fn get_resource_relative_path_by_name(
+    kind: ResourceKind,
+    name: &str,
+) -> Result<PathBuf, DeucalionError> {
+    let mut path = PathBuf::new();
     match kind {
         // maps are stored at data/maps/<name>/
         ResourceKind::Map => {
@@ -48,6 +186,78 @@ pub fn get_resource_path_by_name(
             // Done!
             Ok(path)
         }
+        // fonts are stored at data/fonts/<name>.ttf
+        ResourceKind::Font => {
+            path.push("fonts");
+            path.push(name);
+            path.set_extension("ttf");
+            Ok(path)
+        }
+        // scripts are stored at data/scripts/<name>.lua, with the dots in module names separating
+        //  directories
+        ResourceKind::Script => {
+            path.push("scripts");
+            path.push(modules::get_module_relative_path(name)?);
+            Ok(path)
+        }
+        // characters' info is stored at data/characters/<name>/info.lua; the rest of a
+        //  character's files are beside it
+        ResourceKind::Character => {
+            check_directory_name("character", name)?;
+            path.push("characters");
+            path.push(name);
+            path.push("info.lua");
+            Ok(path)
+        }
+        // animations are described by data/animations/<name>/info.lua, with their images and
+        //  event scripts beside it
+        ResourceKind::Animation => {
+            check_directory_name("animation", name)?;
+            path.push("animations");
+            path.push(name);
+            path.push("info.lua");
+            Ok(path)
+        }
+        // windowskins are described by data/windowskins/<name>/info.lua, with their images
+        //  beside it
+        ResourceKind::Windowskin => {
+            check_directory_name("windowskin", name)?;
+            path.push("windowskins");
+            path.push(name);
+            path.push("info.lua");
+            Ok(path)
+        }
+        // music is stored at data/music/<name>.<ext>; see `find_data_file_with_extension` for
+        //  which extensions are looked for
+        ResourceKind::Music => {
+            check_directory_name("music", name)?;
+            path.push("music");
+            path.push(name);
+            Ok(path)
+        }
+        // pictures are stored at data/pictures/<name>.<ext>, like music
+        ResourceKind::Picture => {
+            check_directory_name("picture", name)?;
+            path.push("pictures");
+            path.push(name);
+            Ok(path)
+        }
+        // troops are stored at data/troops/<name>.lua
+        ResourceKind::Troop => {
+            check_directory_name("troop", name)?;
+            path.push("troops");
+            path.push(name);
+            path.set_extension("lua");
+            Ok(path)
+        }
+        // the tables of the database are stored at data/database/<name>.lua
+        ResourceKind::Database => {
+            check_directory_name("database table", name)?;
+            path.push("database");
+            path.push(name);
+            path.set_extension("lua");
+            Ok(path)
+        }
         // Game and engine configurations are always in the same place
         ResourceKind::EngineConfig => Ok(path.join("engine_config.lua")),
         ResourceKind::GameConfig => Ok(path.join("game_config.lua")),
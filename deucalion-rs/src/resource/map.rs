@@ -1,20 +1,43 @@
 //! Provides facilities for working with tilemaps
 
+use std::collections::{HashMap, HashSet};
+
+use display::depth::{get_tile_pass, TilePass, ABOVE_CHARACTERS_PROPERTY};
 use error::DeucalionError;
+use resource::animation::{get_sheet_rect, Animation};
 use resource::loading;
 use resource::ResourceKind;
 use tiled;
-use sfml::graphics::{Color, Texture};
+use sfml::graphics::{Color, IntRect, RenderTarget, Sprite, Texture, Transformable};
+
+use geom::{ScreenSize, WorldPoint, WorldSize};
 
-use geom::{ScreenSize, WorldSize};
+// The top bits of a tile's global ID say how it's flipped; the rest are the ID itself.
+const TILE_ID_MASK: u32 = 0x1FFF_FFFF;
 
 /// A TileMap is roughly equivalent to a `tiled::Map`, with pre-loaded images for the tilesets.
 pub struct Tilemap {
+    /// The name the map was loaded by.
+    pub name: String,
     pub map: tiled::Map,
     pub dimensions: WorldSize,
     pub tile_dimensions: ScreenSize,
     pub tilesets: Vec<Tileset>,
     pub background_color: Color,
+    /// The animations of animated tiles, by global tile ID. Their frames are tile IDs within
+    /// the same tileset.
+    pub tile_animations: HashMap<u32, Animation>,
+    /// The global IDs of tiles drawn over the characters wherever they're placed.
+    pub above_character_tiles: HashSet<u32>,
+}
+
+/// A tile placed on a map as an object, such as a tree, which is drawn in among the characters.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TileObject {
+    /// The tile's global ID, including any flipping flags.
+    pub gid: u32,
+    /// Where the bottom left of the tile is, which is what Tiled positions tile objects by.
+    pub position: WorldPoint,
 }
 
 // A Tileset simply associates a `tiled::Tileset` with its pre-loaded image.
@@ -26,28 +49,34 @@ pub struct Tileset {
 impl Tilemap {
     /// Given a name, return a Tilemap corresponding to it (or not, if it doesn't exist)
     pub fn by_name(name: &str) -> Result<Tilemap, DeucalionError> {
-        // Get the reader and path for the map's file
-        let mut map_path = loading::get_resource_path_by_name(ResourceKind::Map, name)?;
+        // Get the reader and path for the map's file. The path is only used for resolving
+        // external tilesets and for logging; the reader may be reading from an archive. External
+        // tilesets are always read from the data directory, so they don't work from an archive,
+        // and deucalion-pack refuses to pack maps that use them.
+        let (reader, map_path) =
+            loading::get_resource_reader_and_path_by_name(ResourceKind::Map, name)?;
         debug!("Loading map {} at {}.", name, map_path.display());
-        let map = tiled::parse_file(&map_path)?;
+        let map = tiled::parse_with_path(reader, &map_path)?;
         info!(
             "Successfully loaded a map '{}' from its TMX file at '{}'",
             name,
             map_path.to_string_lossy()
         );
         
-        // Load the tilesets used by this map. First, figure out the directory they're in...
-        map_path.pop();
+        // Load the tilesets used by this map. Their images are referenced relative to the map's
+        // directory, so figure out where that is within the data directory...
+        let mut image_path = loading::get_resource_relative_path_by_name(ResourceKind::Map, name)?;
+        image_path.pop();
         // Then load them all.
         debug!("Loading tilesets for map {} ", name);
         let mut tilesets: Vec<Tileset> = Vec::with_capacity(map.tilesets.len());
         for ts in map.tilesets.iter() {
             // Determine the file path and load the file
-            map_path.push(&ts.images[0].source);
-            debug!("Loading texture from {}", map_path.display());
-            let image = Texture::from_file(&map_path.to_string_lossy());
-            info!("Successfully loaded texture from {}", map_path.display());
-            map_path.pop();
+            image_path.push(&ts.images[0].source);
+            debug!("Loading texture from {}", image_path.display());
+            let image_data = loading::read_data_file(&image_path)?;
+            let image = Texture::from_memory(&image_data, &IntRect::new(0, 0, 0, 0));
+            image_path.pop();
 
             // Deal with the loaded file
             if let Some(image) = image {
@@ -62,11 +91,15 @@ impl Tilemap {
                 });
             } else {
                 info!(
-                    "Failed to load a tileset image '{}' for map '{}', returning last OS error.",
+                    "Failed to decode a tileset image '{}' for map '{}'.",
                     &ts.images[0].source,
                     name
                 );
-                return Err(DeucalionError::IoError(::std::io::Error::last_os_error()));
+                return Err(DeucalionError::from(format!(
+                    "could not decode the tileset image '{}' for map '{}'",
+                    &ts.images[0].source,
+                    name
+                )));
             }
         }
 
@@ -84,12 +117,141 @@ impl Tilemap {
             Color::BLACK
         };
 
+        let tile_animations = get_tile_animations(&map);
+        let above_character_tiles = get_above_character_tiles(&map);
+
         return Ok(Tilemap {
+            name: String::from(name),
             map: map,
             dimensions: dimensions,
             tile_dimensions: tile_dimensions,
             tilesets: tilesets,
             background_color: bgcolor,
+            tile_animations: tile_animations,
+            above_character_tiles: above_character_tiles,
         });
     }
+
+    /// Find the tileset a global tile ID belongs to, and the part of its image showing that
+    /// tile `time` seconds after the map was shown, taking animated tiles into account.
+    pub fn get_tile_image(&self, gid: u32, time: f32) -> Option<(&Tileset, IntRect)> {
+        let gid = gid & TILE_ID_MASK;
+        if gid == 0 {
+            return None;
+        }
+        // Tilesets are in order of their first IDs, so the last one starting at or before the
+        //  ID is the one it's in.
+        let tileset = self.tilesets
+            .iter()
+            .rev()
+            .find(|tileset| tileset.metadata.first_gid <= gid)?;
+        let id = match self.tile_animations.get(&gid) {
+            Some(animation) => animation.frame_at(time)?.frame,
+            None => gid - tileset.metadata.first_gid,
+        };
+        Some((tileset, get_tile_rect(&tileset.metadata, id)))
+    }
+
+    /// Draw the tiles of every visible tile layer that belong in a pass, as they look `time`
+    /// seconds after the map was shown. See `display::depth`.
+    pub fn draw_layers<T: RenderTarget>(&self, target: &mut T, time: f32, pass: TilePass) {
+        for layer in self.map.layers.iter().filter(|layer| layer.visible) {
+            let layer_above = is_property_set(&layer.properties, ABOVE_CHARACTERS_PROPERTY);
+            for (y, row) in layer.tiles.iter().enumerate() {
+                for (x, &gid) in row.iter().enumerate() {
+                    if get_tile_pass(layer_above, self.is_above_characters(gid)) != pass {
+                        continue;
+                    }
+                    // Tiles taller than the map's grid stick up out of the top of their cell.
+                    let bottom_left = WorldPoint::new(
+                        (x as u32 * self.map.tile_width) as f32,
+                        ((y as u32 + 1) * self.map.tile_height) as f32,
+                    );
+                    self.draw_tile(target, gid, bottom_left, time);
+                }
+            }
+        }
+    }
+
+    /// Draw a tile with its bottom left at a point, as it looks `time` seconds after the map was
+    /// shown.
+    pub fn draw_tile<T: RenderTarget>(
+        &self,
+        target: &mut T,
+        gid: u32,
+        bottom_left: WorldPoint,
+        time: f32,
+    ) {
+        if let Some((tileset, rect)) = self.get_tile_image(gid, time) {
+            let mut sprite = Sprite::with_texture_and_rect(&tileset.texture, &rect);
+            sprite.set_position((bottom_left.x, bottom_left.y - rect.height as f32));
+            target.draw(&sprite);
+        }
+    }
+
+    /// Whether a tile is drawn over the characters wherever it's placed.
+    pub fn is_above_characters(&self, gid: u32) -> bool {
+        self.above_character_tiles.contains(&(gid & TILE_ID_MASK))
+    }
+
+    /// Find the visible tiles placed as objects, in any object group.
+    pub fn get_tile_objects(&self) -> Vec<TileObject> {
+        self.map
+            .object_groups
+            .iter()
+            .filter(|group| group.visible)
+            .flat_map(|group| group.objects.iter())
+            .filter(|object| object.visible && object.gid != 0)
+            .map(|object| TileObject {
+                gid: object.gid,
+                position: WorldPoint::new(object.x, object.y),
+            })
+            .collect()
+    }
+}
+
+// Whether a boolean property is present and true.
+fn is_property_set(properties: &tiled::Properties, name: &str) -> bool {
+    match properties.get(name) {
+        Some(&tiled::PropertyValue::BoolValue(value)) => value,
+        _ => false,
+    }
+}
+
+/// Find the global IDs of the tiles whose tileset puts them over the characters.
+pub fn get_above_character_tiles(map: &tiled::Map) -> HashSet<u32> {
+    map.tilesets
+        .iter()
+        .flat_map(|tileset| {
+            tileset
+                .tiles
+                .iter()
+                .filter(|tile| is_property_set(&tile.properties, ABOVE_CHARACTERS_PROPERTY))
+                .map(move |tile| tileset.first_gid + tile.id)
+        })
+        .collect()
+}
+
+/// Find the part of a tileset's image showing a tile, by its ID within the tileset.
+pub fn get_tile_rect(tileset: &tiled::Tileset, id: u32) -> IntRect {
+    let size = ScreenSize::new(tileset.tile_width, tileset.tile_height);
+    let image_width = tileset.images.get(0).map(|image| image.width as u32).unwrap_or(0);
+    let columns = (image_width.saturating_sub(2 * tileset.margin) + tileset.spacing)
+        / (tileset.tile_width + tileset.spacing);
+    get_sheet_rect(id, columns.max(1), size, tileset.spacing, tileset.margin)
+}
+
+/// Read the animations of a map's animated tiles, by global tile ID.
+pub fn get_tile_animations(map: &tiled::Map) -> HashMap<u32, Animation> {
+    let mut animations = HashMap::new();
+    for tileset in map.tilesets.iter() {
+        for tile in tileset.tiles.iter() {
+            if let Some(ref frames) = tile.animation {
+                if !frames.is_empty() {
+                    animations.insert(tileset.first_gid + tile.id, Animation::from_tiled(frames));
+                }
+            }
+        }
+    }
+    animations
 }
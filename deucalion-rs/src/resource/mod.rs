@@ -1,12 +1,19 @@
 //! Utilities for loading and managing resources, including images, maps, and sounds.
 pub mod map;
+pub mod font;
+pub mod animation;
+pub mod music;
+pub mod picture;
 
 // Only ResourceKind is used from here; no need for the extra indirection.
 mod resource_kind;
 pub use resource::resource_kind::ResourceKind;
 
 pub mod loading;
+pub mod archive;
 
 // Imports to run unit tests
 #[cfg(test)] mod test_map;
 #[cfg(test)] mod test_loading;
+#[cfg(test)] mod test_archive;
+#[cfg(test)] mod test_animation;
@@ -0,0 +1,43 @@
+//! Provides facilities for loading music
+
+use sfml::audio::Music;
+
+use error::DeucalionError;
+use resource::loading;
+use resource::ResourceKind;
+
+/// The extensions music is looked for with, in order.
+pub const MUSIC_EXTENSIONS: [&'static str; 3] = ["ogg", "flac", "wav"];
+
+/// A piece of music, loaded from the data directory or archive, ready to be played.
+pub struct LoadedMusic {
+    // SFML streams music out of its file data as it plays, so the data has to outlive the music.
+    // Fields are dropped in declaration order, so `music` goes first.
+    pub music: Music,
+    data: Vec<u8>,
+}
+
+impl LoadedMusic {
+    /// Given a name, return the music corresponding to it (or not, if it doesn't exist)
+    pub fn by_name(name: &str) -> Result<LoadedMusic, DeucalionError> {
+        let path = loading::find_data_file_with_extension(
+            loading::get_resource_relative_path_by_name(ResourceKind::Music, name)?,
+            &MUSIC_EXTENSIONS,
+        )?;
+        let data = loading::read_data_file(&path)?;
+        match Music::from_memory(&data) {
+            Some(music) => {
+                info!("Successfully loaded music '{}'", name);
+                Ok(LoadedMusic {
+                    music: music,
+                    data: data,
+                })
+            }
+            None => Err(DeucalionError::from(format!(
+                "could not decode the music '{}' at {}",
+                name,
+                path.display()
+            ))),
+        }
+    }
+}
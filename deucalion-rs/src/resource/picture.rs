@@ -0,0 +1,30 @@
+//! Provides facilities for loading pictures
+
+use sfml::graphics::{IntRect, Texture};
+
+use error::DeucalionError;
+use resource::loading;
+use resource::ResourceKind;
+
+/// The extensions pictures are looked for with, in order.
+pub const PICTURE_EXTENSIONS: [&'static str; 3] = ["png", "jpg", "bmp"];
+
+/// Load a picture, by name, into a texture.
+pub fn load_picture(name: &str) -> Result<Texture, DeucalionError> {
+    let path = loading::find_data_file_with_extension(
+        loading::get_resource_relative_path_by_name(ResourceKind::Picture, name)?,
+        &PICTURE_EXTENSIONS,
+    )?;
+    let data = loading::read_data_file(&path)?;
+    match Texture::from_memory(&data, &IntRect::new(0, 0, 0, 0)) {
+        Some(texture) => {
+            info!("Successfully loaded picture '{}'", name);
+            Ok(texture)
+        }
+        None => Err(DeucalionError::from(format!(
+            "could not decode the picture '{}' at {}",
+            name,
+            path.display()
+        ))),
+    }
+}
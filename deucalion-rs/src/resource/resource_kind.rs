@@ -12,8 +12,22 @@ pub enum ResourceKind {
     SoundFX,
     /// A character - the basic actors of a JRPG.
     Character,
+    /// An animation - frames from an image, shown one after another.
+    Animation,
+    /// A troop - a group of enemies the party battles together.
+    Troop,
+    /// A table of the game's database, such as its skills.
+    Database,
     /// Engine configuration files
     EngineConfig,
     /// Game configuration
     GameConfig,
+    /// A font, for drawing text.
+    Font,
+    /// A windowskin - the image windows of the UI are drawn with.
+    Windowskin,
+    /// A picture - an image shown on its own, such as the title screen's background.
+    Picture,
+    /// A Lua script module, by its module name, e.g. "battle.formulas".
+    Script,
 }
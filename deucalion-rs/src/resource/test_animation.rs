@@ -0,0 +1,118 @@
+use resource::animation::*;
+use config::schema::ConfigValue;
+use geom::ScreenSize;
+use hlua::AnyLuaValue;
+use sfml::graphics::IntRect;
+use tiled;
+
+fn frame(frame: u32, duration: f32) -> AnimationFrame {
+    AnimationFrame {
+        frame: frame,
+        duration: duration,
+        event: None,
+    }
+}
+
+fn animation(looping: bool) -> Animation {
+    Animation {
+        frames: vec![frame(4, 0.5), frame(5, 0.25), frame(6, 0.25)],
+        looping: looping,
+    }
+}
+
+#[test]
+// Looping animations should start over after their last frame; others should stay on it.
+fn test_frame_at() {
+    let looping = animation(true);
+    assert_eq!(looping.duration(), 1.0);
+    assert_eq!(looping.frame_at(0.0).unwrap().frame, 4);
+    assert_eq!(looping.frame_at(0.6).unwrap().frame, 5);
+    assert_eq!(looping.frame_at(0.8).unwrap().frame, 6);
+    assert_eq!(looping.frame_at(1.1).unwrap().frame, 4);
+
+    let once = animation(false);
+    assert_eq!(once.frame_at(1.1).unwrap().frame, 6);
+    assert_eq!(once.frame_at(50.0).unwrap().frame, 6);
+
+    let empty = Animation {
+        frames: vec![],
+        looping: true,
+    };
+    assert_eq!(empty.frame_at(1.0), None);
+}
+
+#[test]
+// Tiled's frame durations are in milliseconds, and tile animations always loop.
+fn test_from_tiled() {
+    let frames = vec![
+        tiled::Frame {
+            tile_id: 7,
+            duration: 250,
+        },
+        tiled::Frame {
+            tile_id: 8,
+            duration: 500,
+        },
+    ];
+    let animation = Animation::from_tiled(&frames);
+    assert_eq!(animation.frames, vec![frame(7, 0.25), frame(8, 0.5)]);
+    assert!(animation.looping);
+}
+
+#[test]
+// Frames are read from tables, and their events are optional.
+fn test_frame_from_lua() {
+    let table = |fields: Vec<(&str, AnyLuaValue)>| {
+        AnyLuaValue::LuaArray(
+            fields
+                .into_iter()
+                .map(|(k, v)| (AnyLuaValue::LuaString(String::from(k)), v))
+                .collect(),
+        )
+    };
+    assert_eq!(
+        AnimationFrame::from_lua(table(vec![
+            ("frame", AnyLuaValue::LuaNumber(2.0)),
+            ("duration", AnyLuaValue::LuaNumber(0.5)),
+            ("event", AnyLuaValue::LuaString(String::from("pop"))),
+        ])),
+        Some(AnimationFrame {
+            frame: 2,
+            duration: 0.5,
+            event: Some(String::from("pop")),
+        })
+    );
+    assert_eq!(
+        AnimationFrame::from_lua(table(vec![
+            ("frame", AnyLuaValue::LuaNumber(2.0)),
+            ("duration", AnyLuaValue::LuaNumber(0.5)),
+        ])),
+        Some(frame(2, 0.5))
+    );
+    assert_eq!(
+        AnimationFrame::from_lua(table(vec![
+            ("frame", AnyLuaValue::LuaNumber(2.0)),
+            ("duration", AnyLuaValue::LuaNumber(-1.0)),
+        ])),
+        None
+    );
+    assert_eq!(
+        AnimationFrame::from_lua(table(vec![("duration", AnyLuaValue::LuaNumber(1.0))])),
+        None
+    );
+}
+
+#[test]
+// Frames are counted across the rows, skipping the margin and spacing.
+fn test_sheet_rect() {
+    let size = ScreenSize::new(16, 16);
+    assert_eq!(get_sheet_rect(0, 4, size, 0, 0), IntRect::new(0, 0, 16, 16));
+    assert_eq!(get_sheet_rect(5, 4, size, 0, 0), IntRect::new(16, 16, 16, 16));
+    assert_eq!(get_sheet_rect(5, 4, size, 2, 1), IntRect::new(19, 19, 16, 16));
+}
+
+#[test]
+// An animation without an info.lua can't be loaded.
+fn test_missing_animation() {
+    assert!(AnimationInfo::by_name("no_such_animation").is_err());
+}
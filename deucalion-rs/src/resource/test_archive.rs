@@ -0,0 +1,108 @@
+use resource::archive::*;
+
+use std::env;
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::PathBuf;
+
+// Write an archive containing the given files to a fresh temporary file, returning its path.
+fn write_test_archive(name: &str, files: &[(&str, &[u8])]) -> PathBuf {
+    let path = env::temp_dir().join(format!("deucalion_test_{}.pak", name));
+    let mut builder = ArchiveBuilder::new();
+    for &(file_name, contents) in files {
+        builder.add_file(file_name, contents.to_vec());
+    }
+    builder.write(&mut File::create(&path).unwrap()).unwrap();
+    path
+}
+
+#[test]
+// Files written into an archive should come back out unchanged, compressed or not.
+fn test_archive_round_trip() {
+    let compressible = vec![b'a'; 4096];
+    let path = write_test_archive(
+        "round_trip",
+        &[
+            ("maps/map001/map001.tmx", &compressible[..]),
+            ("engine_config.lua", &b"SCREEN_WIDTH = 640"[..]),
+        ],
+    );
+    let archive = Archive::open(&path).unwrap();
+    assert_eq!(
+        archive.entry("maps/map001/map001.tmx").unwrap().compression,
+        Compression::Deflate
+    );
+    assert_eq!(
+        archive.read("maps/map001/map001.tmx").unwrap(),
+        compressible
+    );
+    assert_eq!(
+        archive.read("engine_config.lua").unwrap(),
+        b"SCREEN_WIDTH = 640"
+    );
+    assert!(archive.read("game_config.lua").is_err());
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+// Tileset images are referenced relative to their map, so names must be normalized.
+fn test_archive_name_normalization() {
+    assert_eq!(
+        normalize_archive_name("./maps/map001/../tilesets/grass.png"),
+        "tilesets/grass.png"
+    );
+    let path = write_test_archive("normalization", &[("tilesets/grass.png", &b"png"[..])]);
+    let archive = Archive::open(&path).unwrap();
+    assert!(archive.contains("maps/map001/../tilesets/grass.png"));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+// Corrupting a file's stored contents should be caught by the content hash.
+fn test_archive_detects_corruption() {
+    let path = write_test_archive("corruption", &[("scripts/init.lua", &b"print('hi')"[..])]);
+    let offset = Archive::open(&path)
+        .unwrap()
+        .entry("scripts/init.lua")
+        .unwrap()
+        .offset;
+    let mut contents = Vec::new();
+    File::open(&path)
+        .unwrap()
+        .read_to_end(&mut contents)
+        .unwrap();
+    contents[offset as usize] ^= 0xff;
+    File::create(&path).unwrap().write_all(&contents).unwrap();
+
+    match Archive::open(&path).unwrap().read("scripts/init.lua") {
+        Err(::error::DeucalionError::ArchiveError(_, _)) => {}
+        other => panic!("Expected an archive error, got {:?}", other),
+    }
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+// An index entry claiming more data than the archive holds should be an error, not an allocation
+// of however many bytes it claims.
+fn test_archive_rejects_oversized_entry() {
+    let name = "scripts/init.lua";
+    let path = write_test_archive("oversized", &[(name, &b"print('hi')"[..])]);
+    let mut contents = Vec::new();
+    File::open(&path)
+        .unwrap()
+        .read_to_end(&mut contents)
+        .unwrap();
+    // The stored length follows the header, the path's length and the path, the compression and
+    //  the offset.
+    let stored_length_at = ARCHIVE_MAGIC.len() + 4 + 4 + 2 + name.len() + 1 + 8;
+    for byte in contents[stored_length_at..stored_length_at + 8].iter_mut() {
+        *byte = 0xff;
+    }
+    File::create(&path).unwrap().write_all(&contents).unwrap();
+
+    match Archive::open(&path).unwrap().read(name) {
+        Err(::error::DeucalionError::ArchiveError(_, _)) => {}
+        other => panic!("Expected an archive error, got {:?}", other),
+    }
+    fs::remove_file(path).unwrap();
+}
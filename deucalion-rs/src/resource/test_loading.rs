@@ -48,3 +48,61 @@ fn test_engine_config_reader() {
 
     assert_eq!(correct_content, test_content);
 }
+
+#[test]
+// Script module names should map to paths under the scripts directory
+fn test_script_path() {
+    let result = get_resource_relative_path_by_name(ResourceKind::Script, "battle.formulas");
+    assert_eq!(result.unwrap(), PathBuf::from("scripts/battle/formulas.lua"));
+    assert!(get_resource_relative_path_by_name(ResourceKind::Script, "../escape").is_err());
+}
+
+#[test]
+// Characters' info should be in their own directories, and names shouldn't escape them
+fn test_character_path() {
+    let result = get_resource_relative_path_by_name(ResourceKind::Character, "old_man");
+    assert_eq!(result.unwrap(), PathBuf::from("characters/old_man/info.lua"));
+    assert!(get_resource_relative_path_by_name(ResourceKind::Character, "../player").is_err());
+}
+
+#[test]
+// Animations are directories beside each other, like characters.
+fn test_animation_path() {
+    let result = get_resource_relative_path_by_name(ResourceKind::Animation, "exclamation");
+    assert_eq!(result.unwrap(), PathBuf::from("animations/exclamation/info.lua"));
+    assert!(get_resource_relative_path_by_name(ResourceKind::Animation, "").is_err());
+}
+
+#[test]
+// Windowskins are directories too, with their images beside their info.
+fn test_windowskin_path() {
+    let result = get_resource_relative_path_by_name(ResourceKind::Windowskin, "default");
+    assert_eq!(result.unwrap(), PathBuf::from("windowskins/default/info.lua"));
+    assert!(get_resource_relative_path_by_name(ResourceKind::Windowskin, "a/b").is_err());
+}
+
+#[test]
+// Music and pictures are found by their names alone, since they may have any of several
+// extensions.
+fn test_music_and_picture_paths() {
+    let result = get_resource_relative_path_by_name(ResourceKind::Music, "theme");
+    assert_eq!(result.unwrap(), PathBuf::from("music/theme"));
+    let result = get_resource_relative_path_by_name(ResourceKind::Picture, "title");
+    assert_eq!(result.unwrap(), PathBuf::from("pictures/title"));
+    assert!(get_resource_relative_path_by_name(ResourceKind::Music, "../theme").is_err());
+}
+
+#[test]
+// Troops are single scripts, and their names shouldn't escape data/troops either.
+fn test_troop_path() {
+    let result = get_resource_relative_path_by_name(ResourceKind::Troop, "slimes");
+    assert_eq!(result.unwrap(), PathBuf::from("troops/slimes.lua"));
+    assert!(get_resource_relative_path_by_name(ResourceKind::Troop, "../slimes").is_err());
+}
+
+#[test]
+// Each database table is a single script in data/database.
+fn test_database_path() {
+    let result = get_resource_relative_path_by_name(ResourceKind::Database, "skills");
+    assert_eq!(result.unwrap(), PathBuf::from("database/skills.lua"));
+}
@@ -0,0 +1,633 @@
+//! The battle scene, where the party fights a troop. The battle itself is a
+//! `battle::state::Battle`; this scene shows it, and lets the player choose each party member's
+//! command.
+//!
+//! When the battle is over, the party members' HP and MP, and the items they've used, are kept
+//! in the game state, and on a victory, every member still standing earns the defeated enemies'
+//! EXP. The scene then calls the game's `on_battle_end(troop, outcome)`, if data/scripts/init.lua
+//! defines one, with the troop's name and one of "victory", "defeat" or "escaped", and returns to
+//! the map. A defeat ends the game, showing the game over screen, unless the battle can be lost;
+//! then the fallen get back up with 1 HP, and the game carries on.
+
+use std::time::Instant;
+
+use hlua::AnyLuaValue;
+use sfml::graphics::{Color, Font, RenderTarget};
+
+use battle::combatant::{Combatant, Side};
+use battle::state::{Battle, BattleCommand, BattleEvent, BattleItem, BattleOutcome, BattlePhase,
+                    TurnMode};
+use engine::Engine;
+use game_state::member::PartyMember;
+use input::Action;
+use scene::game_over::GameOverScene;
+use scene::menu::{COMMAND_WINDOW_WIDTH, MARGIN};
+use scene::{get_frame_delta, Scene, Transition};
+use scripting::sandbox::ScriptOwner;
+use ui::gauge::Gauge;
+use ui::list::{self, List, ListItem};
+use ui::widget::widget_rect;
+use ui::window::Window;
+use ui::{DISABLED_COLOR, HIGHLIGHT_COLOR, LINE_HEIGHT, TEXT_COLOR};
+use utility::random::Random;
+
+/// The game's function that's told how battles end.
+pub const BATTLE_END_HOOK: &'static str = "on_battle_end";
+
+/// How many messages about what has happened are shown at once.
+pub const LOG_LENGTH: usize = 4;
+
+/// How many choices the battle menu shows at a time.
+pub const MENU_ROWS: usize = 6;
+
+// How long, in seconds, the battle stays on screen after it's over, so the last messages can be
+//  read. Confirm leaves straight away.
+const END_DELAY: f32 = 2.0;
+
+/// A command on the battle menu.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum BattleMenuEntry {
+    Attack,
+    Skill,
+    Item,
+    Guard,
+    Flee,
+}
+
+impl BattleMenuEntry {
+    /// Every entry, in the order they are shown.
+    pub fn all() -> &'static [BattleMenuEntry] {
+        static ALL: [BattleMenuEntry; 5] = [
+            BattleMenuEntry::Attack,
+            BattleMenuEntry::Skill,
+            BattleMenuEntry::Item,
+            BattleMenuEntry::Guard,
+            BattleMenuEntry::Flee,
+        ];
+        &ALL
+    }
+
+    /// The text shown for the entry.
+    pub fn label(self) -> &'static str {
+        match self {
+            BattleMenuEntry::Attack => "Attack",
+            BattleMenuEntry::Skill => "Skill",
+            BattleMenuEntry::Item => "Item",
+            BattleMenuEntry::Guard => "Guard",
+            BattleMenuEntry::Flee => "Flee",
+        }
+    }
+}
+
+/// What a target is being chosen for.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum TargetFor {
+    Attack,
+    Skill(String),
+    Item(String),
+}
+
+/// What the player is choosing.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum MenuStep {
+    /// Which command to give.
+    Command,
+    /// Which of the member's skills to use.
+    Skill,
+    /// Which of the party's items to use.
+    Item,
+    /// Who to use an attack, skill or item on.
+    Target(TargetFor),
+}
+
+/// The state of the battle menu, separate from drawing it: what's being chosen for the party
+/// member whose turn it is, and which choice is selected.
+#[derive(Debug, Clone)]
+pub struct BattleMenu {
+    step: MenuStep,
+    selected: usize,
+}
+
+impl BattleMenu {
+    /// Start choosing a command.
+    pub fn new() -> BattleMenu {
+        BattleMenu::default()
+    }
+
+    /// What's being chosen.
+    pub fn step(&self) -> &MenuStep {
+        &self.step
+    }
+
+    /// The index of the selected choice.
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// The text of each choice at the current step, for a party member.
+    pub fn choices(&self, battle: &Battle, member: usize) -> Vec<String> {
+        match self.step {
+            MenuStep::Command => BattleMenuEntry::all()
+                .iter()
+                .map(|entry| String::from(entry.label()))
+                .collect(),
+            MenuStep::Skill => battle.combatants[member]
+                .skills
+                .iter()
+                .map(|name| match battle.database().skill(name) {
+                    Some(skill) => format!("{} ({} MP)", skill.name, skill.mp_cost),
+                    None => name.clone(),
+                })
+                .collect(),
+            MenuStep::Item => usable_items(battle)
+                .iter()
+                .map(|&(ref id, count)| format!("{} x{}", battle.database().item_name(id), count))
+                .collect(),
+            MenuStep::Target(ref target_for) => get_targets(battle, member, target_for)
+                .iter()
+                .map(|target| battle.combatants[*target].name.clone())
+                .collect(),
+        }
+    }
+
+    /// The choices at the current step as a list to draw, with the selected one selected.
+    pub fn list(&self, battle: &Battle, member: usize) -> List {
+        let items = self.choices(battle, member)
+            .into_iter()
+            .map(ListItem::new)
+            .collect();
+        let mut list = List::new(items, MENU_ROWS);
+        list.select(self.selected);
+        list
+    }
+
+    /// Move the selection up (negative) or down (positive), wrapping around.
+    pub fn move_selection(&mut self, battle: &Battle, member: usize, delta: i32) {
+        let count = self.choices(battle, member).len();
+        self.selected = list::wrap(self.selected, delta, count);
+    }
+
+    /// Choose the selected choice. Returns the member's command once it's complete; until then,
+    /// the menu moves on to the next thing to choose.
+    pub fn confirm(&mut self, battle: &Battle, member: usize) -> Option<BattleCommand> {
+        let next = match self.step {
+            MenuStep::Command => match BattleMenuEntry::all()[self.selected] {
+                BattleMenuEntry::Attack => MenuStep::Target(TargetFor::Attack),
+                BattleMenuEntry::Skill if !battle.combatants[member].skills.is_empty() => {
+                    MenuStep::Skill
+                }
+                BattleMenuEntry::Item if !usable_items(battle).is_empty() => MenuStep::Item,
+                BattleMenuEntry::Skill | BattleMenuEntry::Item => return None,
+                BattleMenuEntry::Guard => return Some(BattleCommand::Guard),
+                BattleMenuEntry::Flee => return Some(BattleCommand::Flee),
+            },
+            MenuStep::Skill => {
+                let name = battle.combatants[member].skills[self.selected].clone();
+                if battle.database().skill(&name).is_none() {
+                    return None;
+                }
+                MenuStep::Target(TargetFor::Skill(name))
+            }
+            MenuStep::Item => {
+                MenuStep::Target(TargetFor::Item(usable_items(battle)[self.selected].0.clone()))
+            }
+            MenuStep::Target(ref target_for) => {
+                let target = match get_targets(battle, member, target_for).get(self.selected) {
+                    Some(target) => *target,
+                    None => return None,
+                };
+                return Some(match *target_for {
+                    TargetFor::Attack => BattleCommand::Attack(target),
+                    TargetFor::Skill(ref skill) => BattleCommand::Skill {
+                        skill: skill.clone(),
+                        target: target,
+                    },
+                    TargetFor::Item(ref item) => BattleCommand::Item {
+                        item: item.clone(),
+                        target: target,
+                    },
+                });
+            }
+        };
+        self.step = next;
+        self.selected = 0;
+        None
+    }
+
+    /// Go back to the previous step.
+    pub fn cancel(&mut self) {
+        self.step = match self.step {
+            MenuStep::Command | MenuStep::Skill | MenuStep::Item => MenuStep::Command,
+            MenuStep::Target(TargetFor::Attack) => MenuStep::Command,
+            MenuStep::Target(TargetFor::Skill(_)) => MenuStep::Skill,
+            MenuStep::Target(TargetFor::Item(_)) => MenuStep::Item,
+        };
+        self.selected = 0;
+    }
+
+    /// Start again from choosing a command, such as for the next member.
+    pub fn reset(&mut self) {
+        *self = BattleMenu::new();
+    }
+}
+
+impl Default for BattleMenu {
+    fn default() -> BattleMenu {
+        BattleMenu {
+            step: MenuStep::Command,
+            selected: 0,
+        }
+    }
+}
+
+// The party's items that haven't run out, by name, with how many there are.
+fn usable_items(battle: &Battle) -> Vec<(String, u32)> {
+    battle
+        .items()
+        .iter()
+        .filter(|item| item.count > 0)
+        .map(|item| (item.name.clone(), item.count))
+        .collect()
+}
+
+// Who a member can target with an attack, skill or item.
+fn get_targets(battle: &Battle, member: usize, target_for: &TargetFor) -> Vec<usize> {
+    let side = battle.combatants[member].side;
+    let side = match *target_for {
+        TargetFor::Attack => Some(side.opponent()),
+        TargetFor::Skill(ref name) => battle
+            .database()
+            .skill(name)
+            .map(|skill| skill.effect.target_side(side)),
+        TargetFor::Item(ref name) => battle
+            .items()
+            .iter()
+            .find(|item| item.name == *name)
+            .map(|item| item.effect.target_side(side)),
+    };
+    match side {
+        Some(side) => battle.living(side),
+        None => Vec::new(),
+    }
+}
+
+/// Keep the HP and MP a party member was left with after a battle. If the battle was lost, but
+/// could be, a fallen member gets back up with 1 HP.
+pub fn keep_battle_result(
+    member: &mut PartyMember,
+    combatant: &Combatant,
+    outcome: BattleOutcome,
+    can_lose: bool,
+) {
+    member.update_from(combatant);
+    if outcome == BattleOutcome::Defeat && can_lose {
+        member.hp = member.hp.max(1);
+    }
+}
+
+/// Whether a battle's outcome ends the game: a defeat does, unless the battle can be lost.
+pub fn is_game_over(outcome: BattleOutcome, can_lose: bool) -> bool {
+    outcome == BattleOutcome::Defeat && !can_lose
+}
+
+/// Describe something that happened in a battle, for the player.
+pub fn describe_event(battle: &Battle, event: &BattleEvent) -> String {
+    let name = |i: usize| battle.combatants[i].name.clone();
+    let skill_name = |id: &str| match battle.database().skill(id) {
+        Some(skill) => skill.name.clone(),
+        None => String::from(id),
+    };
+    let state_name = |id: &str| match battle.database().status_effect(id) {
+        Some(status_effect) => status_effect.name.clone(),
+        None => String::from(id),
+    };
+    match *event {
+        BattleEvent::Attacked { user, target } => {
+            format!("{} attacks {}!", name(user), name(target))
+        }
+        BattleEvent::UsedSkill {
+            user,
+            ref skill,
+            target,
+        } => format!(
+            "{} uses {} on {}!",
+            name(user),
+            skill_name(skill),
+            name(target)
+        ),
+        BattleEvent::UsedItem {
+            user,
+            ref item,
+            target,
+        } => format!(
+            "{} uses {} on {}!",
+            name(user),
+            battle.database().item_name(item),
+            name(target)
+        ),
+        BattleEvent::Guarded(user) => format!("{} guards.", name(user)),
+        BattleEvent::Damaged { target, amount } => {
+            format!("{} takes {} damage.", name(target), amount)
+        }
+        BattleEvent::Healed { target, amount } => {
+            format!("{} recovers {} HP.", name(target), amount)
+        }
+        BattleEvent::Defeated(target) => format!("{} is defeated!", name(target)),
+        BattleEvent::StateAdded { target, ref state } => {
+            format!("{} suffers {}!", name(target), state_name(state))
+        }
+        BattleEvent::StateRemoved { target, ref state } => {
+            format!("{} is rid of {}.", name(target), state_name(state))
+        }
+        BattleEvent::TurnSkipped(user) => format!("{} can't move!", name(user)),
+        BattleEvent::FleeFailed => String::from("Couldn't get away!"),
+        BattleEvent::Ended(BattleOutcome::Victory) => String::from("Victory!"),
+        BattleEvent::Ended(BattleOutcome::Defeat) => String::from("The party has fallen..."),
+        BattleEvent::Ended(BattleOutcome::Escaped) => String::from("Got away safely!"),
+    }
+}
+
+/// Shows a battle, and lets the player fight it.
+pub struct BattleScene {
+    /// The name of the troop being fought, which `on_battle_end` is given.
+    pub troop: String,
+    pub battle: Battle,
+    /// The characters the party's combatants are, in order, whose HP, MP and EXP are kept in the
+    /// game state when the battle is over.
+    pub members: Vec<String>,
+    /// Whether the game carries on after a defeat, rather than ending.
+    pub can_lose: bool,
+    // Whether the battle's outcome has been kept in the game state.
+    settled: bool,
+    menu: BattleMenu,
+    // The latest messages, oldest first.
+    log: Vec<String>,
+    // How long the battle has been over for.
+    ended_for: f32,
+    last_update: Option<Instant>,
+}
+
+impl BattleScene {
+    /// Start a battle between the party, with the items they can use, and a troop, by name,
+    /// taking turns the way the game is configured to.
+    pub fn new(
+        engine: &Engine,
+        troop: &str,
+        party: Vec<Combatant>,
+        enemies: Vec<Combatant>,
+        items: Vec<BattleItem>,
+        can_flee: bool,
+    ) -> BattleScene {
+        let mut battle = Battle::new(
+            party,
+            enemies,
+            engine.game_config.battle_mode,
+            Random::from_time(),
+        ).with_database(engine.database.clone())
+            .with_items(items);
+        battle.can_flee = can_flee;
+        BattleScene {
+            troop: String::from(troop),
+            battle: battle,
+            members: Vec::new(),
+            can_lose: false,
+            settled: false,
+            menu: BattleMenu::new(),
+            log: Vec::new(),
+            ended_for: 0.0,
+            last_update: None,
+        }
+    }
+
+    /// Say which characters the party's combatants are, in order, so that how the battle goes
+    /// for them is kept in the game state.
+    pub fn with_members(mut self, members: Vec<String>) -> BattleScene {
+        self.members = members;
+        self
+    }
+
+    /// Let the game carry on after a defeat, such as for a battle the story goes on from, rather
+    /// than ending.
+    pub fn with_can_lose(mut self, can_lose: bool) -> BattleScene {
+        self.can_lose = can_lose;
+        self
+    }
+
+    // Add a message to the log, forgetting the oldest if it's full.
+    fn show(&mut self, message: String) {
+        self.log.push(message);
+        if self.log.len() > LOG_LENGTH {
+            self.log.remove(0);
+        }
+    }
+
+    // Keep the party's HP and MP, and the items they've used up, in the game state, and share
+    //  out the EXP for a victory, once.
+    fn settle(&mut self, engine: &mut Engine, outcome: BattleOutcome) {
+        if self.settled {
+            return;
+        }
+        self.settled = true;
+        {
+            // The inventory is left alone during the battle, so it still has what the party
+            //  started with.
+            let mut state = engine.game_state.borrow_mut();
+            for item in self.battle.items() {
+                let used = state.inventory.count(&item.name).saturating_sub(item.count);
+                state.inventory.remove(&item.name, used);
+            }
+        }
+        let exp = match outcome {
+            BattleOutcome::Victory => self.battle.exp_reward(),
+            BattleOutcome::Defeat | BattleOutcome::Escaped => 0,
+        };
+        for (i, character) in self.members.clone().iter().enumerate() {
+            let combatant = self.battle.combatants[i].clone();
+            {
+                let mut state = engine.game_state.borrow_mut();
+                let member = match state.member_mut(character) {
+                    Some(member) => member,
+                    None => continue,
+                };
+                keep_battle_result(member, &combatant, outcome, self.can_lose);
+            }
+            if exp == 0 || !combatant.is_alive() {
+                continue;
+            }
+            self.show(format!("{} gains {} EXP.", combatant.name, exp));
+            match engine.give_exp(character, exp) {
+                Ok(levels) => for level in levels {
+                    self.show(format!("{} is now level {}!", combatant.name, level));
+                },
+                Err(e) => engine.report_error("Failed to give EXP", e),
+            }
+        }
+    }
+
+    // Tell the game how the battle ended, and leave.
+    fn end(&mut self, engine: &mut Engine, outcome: BattleOutcome) -> Transition {
+        self.settle(engine, outcome);
+        let arguments = vec![
+            AnyLuaValue::LuaString(self.troop.clone()),
+            AnyLuaValue::LuaString(String::from(outcome.name())),
+        ];
+        if let Err(e) = engine
+            .scripts
+            .call_function(&ScriptOwner::Game, BATTLE_END_HOOK, arguments)
+        {
+            engine.report_error(&format!("Failed to run {}", BATTLE_END_HOOK), e);
+        }
+        if is_game_over(outcome, self.can_lose) {
+            return Transition::Reset(Box::new(GameOverScene::new(engine)));
+        }
+        Transition::Pop
+    }
+
+    // Describe a combatant's condition, besides their HP and MP.
+    fn status_line(&self, combatant: &Combatant) -> String {
+        let mut line = combatant.name.clone();
+        for state in combatant.states.iter() {
+            if let Some(status_effect) = self.battle.database().status_effect(&state.id) {
+                line.push_str(&format!("  {}", status_effect.name));
+            }
+        }
+        if self.battle.mode == TurnMode::ActiveTime && combatant.is_alive() {
+            line.push_str(&format!("  {}%", (combatant.gauge * 100.0) as u32));
+        }
+        line
+    }
+
+    // Draw a row for each of a side's combatants in a window: their name and condition, then
+    //  their HP, and a party member's MP, as gauges.
+    fn draw_side<T: RenderTarget>(
+        &self,
+        target: &mut T,
+        font: &Font,
+        window: &Window,
+        side: Side,
+        current: Option<usize>,
+    ) {
+        let inner = window.inner();
+        let column = inner.size.width / 3;
+        let gauge_width = column.saturating_sub(MARGIN);
+        let combatants = self.battle
+            .combatants
+            .iter()
+            .enumerate()
+            .filter(|&(_, combatant)| combatant.side == side);
+        for (row, (i, combatant)) in combatants.enumerate() {
+            let color = if !combatant.is_alive() {
+                DISABLED_COLOR
+            } else if Some(i) == current {
+                HIGHLIGHT_COLOR
+            } else {
+                TEXT_COLOR
+            };
+            let row = row as u32;
+            window.draw_text(target, font, &self.status_line(combatant), (0, row), &color);
+            let (x, y) = (inner.origin.x + column, inner.origin.y + row * LINE_HEIGHT);
+            let hp = Gauge::hp(combatant.hp, combatant.stats.max_hp);
+            hp.draw(target, font, widget_rect(x, y, gauge_width, LINE_HEIGHT));
+            if side == Side::Party {
+                let mp = Gauge::mp(combatant.mp, combatant.stats.max_mp);
+                let x = x + column;
+                mp.draw(target, font, widget_rect(x, y, gauge_width, LINE_HEIGHT));
+            }
+        }
+    }
+}
+
+impl Scene for BattleScene {
+    fn on_action(&mut self, engine: &mut Engine, action: Action) -> Transition {
+        match self.battle.phase() {
+            BattlePhase::WaitingForCommand(member) => match action {
+                Action::Up => self.menu.move_selection(&self.battle, member, -1),
+                Action::Down => self.menu.move_selection(&self.battle, member, 1),
+                Action::Confirm => {
+                    if let Some(command) = self.menu.confirm(&self.battle, member) {
+                        if let Err(message) = self.battle.submit(command) {
+                            self.show(message);
+                        }
+                        self.menu.reset();
+                    }
+                }
+                Action::Cancel => self.menu.cancel(),
+                _ => {}
+            },
+            BattlePhase::Finished(outcome) if action == Action::Confirm => {
+                return self.end(engine, outcome)
+            }
+            _ => {}
+        }
+        Transition::Stay
+    }
+
+    fn update(&mut self, engine: &mut Engine) -> Transition {
+        let delta = get_frame_delta(&mut self.last_update);
+        self.battle.advance(delta);
+        for event in self.battle.take_events() {
+            let message = describe_event(&self.battle, &event);
+            self.show(message);
+        }
+        if let BattlePhase::Finished(outcome) = self.battle.phase() {
+            self.settle(engine, outcome);
+            self.ended_for += delta;
+            if self.ended_for >= END_DELAY {
+                return self.end(engine, outcome);
+            }
+        }
+        Transition::Stay
+    }
+
+    fn draw(&mut self, engine: &mut Engine) {
+        let size = engine.display.render_size();
+        let width = size.width.saturating_sub(MARGIN * 2);
+        let count = |side: Side| {
+            self.battle
+                .combatants
+                .iter()
+                .filter(|combatant| combatant.side == side)
+                .count() as u32
+        };
+        // What has happened at the top, the enemies below it, and the party at the bottom.
+        let log = Window::with_rows(MARGIN, MARGIN, width, LOG_LENGTH as u32);
+        let troop = Window::with_rows(MARGIN, log.rect.max_y() + MARGIN, width, count(Side::Troop));
+        let mut party = Window::with_rows(MARGIN, 0, width, count(Side::Party));
+        party.rect.origin.y = size.height.saturating_sub(party.rect.size.height + MARGIN);
+        let current = match self.battle.phase() {
+            BattlePhase::WaitingForCommand(member) => Some(member),
+            _ => None,
+        };
+
+        let canvas = &mut engine.display.canvas;
+        let font = &engine.font.font;
+        let skin = engine.windowskin.as_ref();
+        canvas.clear(&Color::rgb(16, 16, 40));
+        log.draw(canvas, skin);
+        for (row, message) in self.log.iter().enumerate() {
+            log.draw_text(canvas, font, message, (0, row as u32), &TEXT_COLOR);
+        }
+        troop.draw(canvas, skin);
+        self.draw_side(canvas, font, &troop, Side::Troop, current);
+        party.draw(canvas, skin);
+        self.draw_side(canvas, font, &party, Side::Party, current);
+
+        // The choices for the member whose turn it is, just above the party, on the right.
+        if let Some(member) = current {
+            let list = self.menu.list(&self.battle, member);
+            let mut window = Window::with_rows(
+                size.width.saturating_sub(COMMAND_WINDOW_WIDTH + MARGIN),
+                0,
+                COMMAND_WINDOW_WIDTH,
+                list.visible().len() as u32,
+            );
+            window.rect.origin.y = party
+                .rect
+                .origin
+                .y
+                .saturating_sub(window.rect.size.height + MARGIN);
+            window.draw(canvas, skin);
+            list.draw(canvas, font, &window, 0, true);
+        }
+    }
+}
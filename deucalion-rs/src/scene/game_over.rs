@@ -0,0 +1,135 @@
+//! The game over screen, shown when the party is defeated in a battle they can't lose: the game
+//! config's `GAME_OVER_BACKGROUND` picture, with its `GAME_OVER_MUSIC` playing, and the choice of
+//! loading the latest save, if there is one, or going back to the title screen.
+
+use std::path::PathBuf;
+
+use sfml::graphics::Texture;
+
+use engine::Engine;
+use game_state::save::{get_latest_save, get_save_directory, get_save_path};
+use input::Action;
+use scene::menu::MARGIN;
+use scene::screen::{draw_background, draw_heading, load_background, SCREEN_WINDOW_WIDTH};
+use scene::title::{load_game, TitleScene};
+use scene::{Scene, Transition};
+use ui::list::{List, ListItem};
+use ui::window::Window;
+use ui::TEXT_COLOR;
+
+/// The choice on the game over screen that loads the latest save.
+pub const LOAD_LAST_SAVE: usize = 0;
+
+/// The choice on the game over screen that goes back to the title screen.
+pub const RETURN_TO_TITLE: usize = 1;
+
+/// List the game over screen's choices, only letting the latest save be loaded if there is one.
+pub fn get_game_over_items(has_save: bool) -> Vec<ListItem> {
+    vec![
+        ListItem::enabled_if("Load Last Save", has_save),
+        ListItem::new("Return to Title"),
+    ]
+}
+
+/// The game over screen.
+pub struct GameOverScene {
+    background: Option<Texture>,
+    list: List,
+    // The save that was written last, if any.
+    latest: Option<PathBuf>,
+    // What went wrong, if the save couldn't be loaded.
+    message: Option<String>,
+}
+
+impl GameOverScene {
+    /// Show the game over screen, and start its music.
+    pub fn new(engine: &mut Engine) -> GameOverScene {
+        let background_name = engine.game_config.game_over_background.clone();
+        let background = load_background(engine, &background_name);
+        let music = engine.game_config.game_over_music.clone();
+        engine.play_music(&music);
+
+        let slots = engine.game_config.save_slots;
+        let latest = get_save_directory(&engine.game_config).and_then(|directory| {
+            get_latest_save(&directory, slots).map(|slot| get_save_path(&directory, slot))
+        });
+        let mut list = List::new(get_game_over_items(latest.is_some()), 2);
+        if latest.is_none() {
+            list.select(RETURN_TO_TITLE);
+        }
+        GameOverScene {
+            background: background,
+            list: list,
+            latest: latest,
+            message: None,
+        }
+    }
+
+    // Carry on from the latest save, or say why it couldn't be loaded.
+    fn load_latest(&mut self, engine: &mut Engine) -> Transition {
+        let path = match self.latest {
+            Some(ref path) => path.clone(),
+            None => return Transition::Stay,
+        };
+        match load_game(engine, &path) {
+            Ok(scene) => {
+                engine.play_music("");
+                Transition::Replace(Box::new(scene))
+            }
+            Err(e) => {
+                self.message = Some(format!("Couldn't load the save: {}", e));
+                engine.report_error("Couldn't load the save", e);
+                Transition::Stay
+            }
+        }
+    }
+}
+
+impl Scene for GameOverScene {
+    fn on_action(&mut self, engine: &mut Engine, action: Action) -> Transition {
+        self.message = None;
+        match action {
+            Action::Up => self.list.move_selection(-1),
+            Action::Down => self.list.move_selection(1),
+            Action::Confirm if self.list.is_selected_enabled() => match self.list.selected() {
+                Some(LOAD_LAST_SAVE) => return self.load_latest(engine),
+                Some(RETURN_TO_TITLE) => {
+                    return Transition::Replace(Box::new(TitleScene::new(engine)))
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+        Transition::Stay
+    }
+
+    fn draw(&mut self, engine: &mut Engine) {
+        draw_background(engine, self.background.as_ref());
+        let size = engine.display.render_size();
+        let x = size.width.saturating_sub(SCREEN_WINDOW_WIDTH) / 2;
+        let choices = Window::with_rows(
+            x,
+            size.height / 2,
+            SCREEN_WINDOW_WIDTH,
+            self.list.items().len() as u32,
+        );
+        let message = Window::with_rows(
+            MARGIN,
+            choices.rect.max_y() + MARGIN,
+            size.width.saturating_sub(MARGIN * 2),
+            1,
+        );
+
+        let canvas = &mut engine.display.canvas;
+        let font = &engine.font.font;
+        let skin = engine.windowskin.as_ref();
+        let heading_y = size.height as f32 / 4.0;
+        draw_heading(canvas, font, "Game Over", size.width, heading_y);
+        choices.draw(canvas, skin);
+        self.list.draw(canvas, font, &choices, 0, true);
+        if let Some(ref line) = self.message {
+            message.draw(canvas, skin);
+            message.draw_text(canvas, font, line, (0, 0), &TEXT_COLOR);
+        }
+    }
+}
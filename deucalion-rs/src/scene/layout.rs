@@ -0,0 +1,74 @@
+//! Layouts opened by scripts: a screen of widgets over the world, which closes once the player
+//! has chosen something from it or backed out. See `ui::layout`.
+
+use hlua::AnyLuaValue;
+
+use engine::Engine;
+use input::Action;
+use scene::{Scene, Transition};
+use scripting::sandbox::ScriptOwner;
+use ui::widget::{UiEvent, Widget, WidgetTree};
+
+/// Shows a layout, and tells the game what the player chose from it.
+pub struct LayoutScene {
+    tree: WidgetTree,
+    // The function in init.lua's environment to call with what was chosen.
+    call: Option<String>,
+}
+
+impl LayoutScene {
+    /// Show a layout, calling a function, if one is named, once the player is done with it.
+    pub fn new(layout: Widget, call: Option<String>) -> LayoutScene {
+        LayoutScene {
+            tree: WidgetTree::new(layout),
+            call: call,
+        }
+    }
+
+    // Tell the game what was chosen.
+    fn finish(&self, engine: &mut Engine, arguments: Vec<AnyLuaValue>) {
+        if let Some(ref function) = self.call {
+            if let Err(e) = engine
+                .scripts
+                .call_function(&ScriptOwner::Game, function, arguments)
+            {
+                engine.report_error(&format!("Failed to run layout function {}", function), e);
+            }
+        }
+    }
+}
+
+impl Scene for LayoutScene {
+    fn on_action(&mut self, engine: &mut Engine, action: Action) -> Transition {
+        match self.tree.on_action(action) {
+            UiEvent::None => Transition::Stay,
+            UiEvent::Chosen { list, index } => {
+                let arguments = vec![
+                    AnyLuaValue::LuaString(list),
+                    AnyLuaValue::LuaNumber((index + 1) as f64),
+                ];
+                self.finish(engine, arguments);
+                Transition::Pop
+            }
+            UiEvent::Cancelled => {
+                self.finish(engine, Vec::new());
+                Transition::Pop
+            }
+        }
+    }
+
+    fn draw(&mut self, engine: &mut Engine) {
+        let state = engine.game_state.borrow();
+        self.tree.draw(
+            &mut engine.display.canvas,
+            &engine.font.font,
+            engine.windowskin.as_ref(),
+            &state,
+            &engine.database,
+        );
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,538 @@
+//! The map scene, where the player walks around the world.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::Instant;
+
+use hlua::AnyLuaValue;
+use sfml::graphics::{Color, RectangleShape, RenderTarget, Shape, Sprite, Transformable};
+use sfml::system::Vector2f;
+
+use battle::state::BattleItem;
+use battle::troop::Troop;
+use display::depth::{sort_by_depth, TilePass};
+use engine::Engine;
+use error::DeucalionError;
+use game_state::shop::Shop;
+use game_state::Location;
+use geom::{WorldPoint, WorldVector};
+use input::{self, Action};
+use resource::animation::{Animation, LoadedAnimation};
+use resource::loading;
+use resource::map::{TileObject, Tilemap};
+use resource::ResourceKind;
+use scene::battle::BattleScene;
+use scene::layout::LayoutScene;
+use scene::menu::MainMenuScene;
+use scene::shop::ShopScene;
+use scene::{get_frame_delta, Scene, Transition};
+use scripting::commands::ScriptCommand;
+use scripting::sandbox::ScriptOwner;
+use world::actor::{Actor, DEFAULT_SPEED_IN_TILES};
+use world::animation::AnimationTarget;
+use world::character::PLAYER_CHARACTER;
+use world::collision::CollisionGrid;
+use world::encounter::{get_map_encounter_regions, Encounters};
+use world::movement::MovementSettings;
+use world::npc::get_map_npcs;
+use world::World;
+
+/// The name of the map object marking where the player starts.
+pub const PLAYER_START_OBJECT: &'static str = "player_start";
+
+/// The game's function that's asked about each step the player takes in an encounter region.
+pub const ENCOUNTER_HOOK: &'static str = "on_encounter";
+
+/// Shows a map and the things on it.
+pub struct MapScene {
+    pub map: Tilemap,
+    pub world: World,
+    // The tiles placed on the map as objects.
+    tile_objects: Vec<TileObject>,
+    /// The map's encounter regions, and the player's steps through them.
+    pub encounters: Encounters,
+    /// The animations scripts have played, loaded the first time each is played.
+    pub animations: HashMap<String, LoadedAnimation>,
+    // How long the map has been shown for, which animated tiles are kept in step by.
+    time: f32,
+    last_update: Option<Instant>,
+}
+
+impl MapScene {
+    /// Create a scene showing the given map, with the player at its start point and the map's
+    /// NPCs in their places.
+    pub fn new(engine: &Engine, map: Tilemap) -> MapScene {
+        let start = get_player_start(&map);
+        MapScene {
+            world: create_world(engine, &map, start),
+            tile_objects: map.get_tile_objects(),
+            encounters: Encounters::new(
+                get_map_encounter_regions(&map.map),
+                engine.game_config.encounter_random(),
+            ),
+            map: map,
+            animations: HashMap::new(),
+            time: 0.0,
+            last_update: None,
+        }
+    }
+
+    /// Create a scene showing a map where a save left the player: standing where they were, with
+    /// their followers behind them.
+    pub fn at_location(
+        engine: &Engine,
+        map: Tilemap,
+        location: &Location,
+    ) -> Result<MapScene, DeucalionError> {
+        let mut scene = MapScene::new(engine, map);
+        scene
+            .world
+            .warp_player(WorldPoint::new(location.x as f32, location.y as f32));
+        for character in location.followers.iter() {
+            scene.world.add_follower(character)?;
+        }
+        Ok(scene)
+    }
+
+    /// Where the player is, and who's following them, for saves.
+    pub fn location(&self) -> Location {
+        let position = self.world.player.position;
+        Location {
+            map: self.map.name.clone(),
+            x: f64::from(position.x),
+            y: f64::from(position.y),
+            followers: self.world
+                .party
+                .followers()
+                .iter()
+                .map(|follower| follower.character.clone())
+                .collect(),
+        }
+    }
+
+    /// Take the player, and their followers, to another map, at the given position or the
+    /// map's start point.
+    pub fn change_map(&mut self, engine: &Engine, map: Tilemap, start: Option<WorldPoint>) {
+        let start = start.unwrap_or_else(|| get_player_start(&map));
+        let mut world = create_world(engine, &map, start);
+        world.player.facing = self.world.player.facing;
+        world.party = self.world.party.clone();
+        world.party.warp(&world.player);
+        self.world = world;
+        self.tile_objects = map.get_tile_objects();
+        // The steps to the next encounter carry on from map to map.
+        self.encounters.regions = get_map_encounter_regions(&map.map);
+        self.encounters.forget_position();
+        self.map = map;
+    }
+
+    // Draw the playing animations, each centred on what it's played on.
+    fn draw_animations(&self, engine: &mut Engine) {
+        for playing in self.world.animations.iter() {
+            let (loaded, frame) = match (
+                self.animations.get(&playing.name),
+                playing.player.current_frame(),
+            ) {
+                (Some(loaded), Some(frame)) => (loaded, frame),
+                _ => continue,
+            };
+            let position = match self.world.get_animation_position(&playing.target) {
+                Some(position) => position,
+                None => continue,
+            };
+            let mut sprite =
+                Sprite::with_texture_and_rect(&loaded.texture, &loaded.frame_rect(frame.frame));
+            sprite.set_position((
+                position.x - loaded.info.frame_width as f32 / 2.0 + loaded.info.offset_x as f32,
+                position.y - loaded.info.frame_height as f32 / 2.0 + loaded.info.offset_y as f32,
+            ));
+            engine.display.canvas.draw(&sprite);
+        }
+    }
+
+    // Get an animation, loading it if it hasn't been played yet.
+    fn load_animation(&mut self, name: &str) -> Result<Rc<Animation>, DeucalionError> {
+        if !self.animations.contains_key(name) {
+            let animation = LoadedAnimation::by_name(name)?;
+            self.animations.insert(String::from(name), animation);
+        }
+        Ok(self.animations[name].animation.clone())
+    }
+
+    // Run the scripts of the animation events that have happened since the last frame.
+    fn run_animation_events(&mut self, engine: &mut Engine) {
+        for event in self.world.take_animation_events() {
+            let path = loading::get_resource_relative_path_by_name(
+                ResourceKind::Animation,
+                &event.animation,
+            ).map(|path| path.with_file_name(format!("on_{}.lua", event.event)));
+            let result = path.and_then(|path| {
+                if loading::data_file_exists(&path) {
+                    engine.scripts.execute(&ScriptOwner::Game, &path).map(|_| ())
+                } else {
+                    Ok(())
+                }
+            });
+            if let Err(e) = result {
+                engine.report_error(
+                    &format!(
+                        "Failed to run the {} event of animation {}",
+                        event.event, event.animation
+                    ),
+                    e,
+                );
+            }
+        }
+    }
+
+    // The characters in the party: the player and then their followers, in order.
+    fn party_members(&self) -> Vec<String> {
+        let mut members = vec![String::from(PLAYER_CHARACTER)];
+        for follower in self.world.party.followers() {
+            members.push(follower.character.clone());
+        }
+        members
+    }
+
+    // Set up a battle against a troop, by name, fought by the party as they are in the game
+    //  state, with the party's items that can be used in battle.
+    fn start_battle(
+        &self,
+        engine: &mut Engine,
+        troop: &str,
+        can_flee: bool,
+        can_lose: bool,
+    ) -> Result<BattleScene, DeucalionError> {
+        let enemies = Troop::by_name(troop)?.combatants();
+        let members = self.party_members();
+        let mut party = Vec::with_capacity(members.len());
+        let items;
+        {
+            let mut state = engine.game_state.borrow_mut();
+            for character in members.iter() {
+                let member = state.join(character)?;
+                party.push(member.combatant(&member.equipment_bonuses(&engine.database)));
+            }
+            items = state
+                .inventory
+                .items()
+                .iter()
+                .filter_map(|(id, &count)| {
+                    engine
+                        .database
+                        .item(id)
+                        .and_then(|item| BattleItem::from_item(id, item, count))
+                })
+                .collect();
+        }
+        let scene = BattleScene::new(engine, troop, party, enemies, items, can_flee);
+        Ok(scene.with_members(members).with_can_lose(can_lose))
+    }
+
+    // Carry out what scripts have asked the world to do since the last frame. Returns the scene
+    //  to push if a script has started a battle, or opened a shop or a layout.
+    fn run_script_commands(&mut self, engine: &mut Engine) -> Transition {
+        let mut opened: Option<Box<Scene>> = None;
+        for command in engine.scripts.take_commands() {
+            let result = match command {
+                ScriptCommand::PauseRoutes(ref npc) => {
+                    self.world.pause_routes(npc.as_ref().map(String::as_str))
+                }
+                ScriptCommand::ResumeRoutes(ref npc) => {
+                    self.world.resume_routes(npc.as_ref().map(String::as_str))
+                }
+                ScriptCommand::MoveTo { ref actor, x, y } => self.world.move_to(actor, (x, y)),
+                ScriptCommand::AddFollower(ref character) => {
+                    let joined = engine.game_state.borrow_mut().join(character).map(|_| ());
+                    joined.and_then(|_| self.world.add_follower(character))
+                }
+                ScriptCommand::RemoveFollower(ref character) => {
+                    self.world.remove_follower(character)
+                }
+                ScriptCommand::SetPartyFollowing(following) => {
+                    self.world.party.set_following(following, &self.world.player);
+                    Ok(())
+                }
+                ScriptCommand::GatherParty => {
+                    self.world.party.gather();
+                    Ok(())
+                }
+                ScriptCommand::Warp { ref map, x, y } => match *map {
+                    Some(ref name) => Tilemap::by_name(name).map(|map| {
+                        let start = get_tile_position(&map, x, y);
+                        self.change_map(engine, map, Some(start));
+                    }),
+                    None => {
+                        let position = get_tile_position(&self.map, x, y);
+                        self.world.warp_player(position);
+                        self.encounters.forget_position();
+                        Ok(())
+                    }
+                },
+                ScriptCommand::PlayAnimation {
+                    ref animation,
+                    ref actor,
+                } => self.load_animation(animation).and_then(|loaded| {
+                    let target = AnimationTarget::Actor(actor.clone());
+                    self.world.play_animation(animation, loaded, target)
+                }),
+                ScriptCommand::PlayAnimationAt { ref animation, x, y } => {
+                    let tile = self.world.collision.tile_size();
+                    let centre = get_tile_position(&self.map, x, y)
+                        + WorldVector::new(tile.width / 2.0, tile.height / 2.0);
+                    self.load_animation(animation).and_then(|loaded| {
+                        let target = AnimationTarget::Position(centre);
+                        self.world.play_animation(animation, loaded, target)
+                    })
+                }
+                ScriptCommand::StopAnimation(ref animation) => {
+                    self.world.stop_animation(animation);
+                    Ok(())
+                }
+                ScriptCommand::GiveExp {
+                    ref character,
+                    amount,
+                } => engine.give_exp(character, amount).map(|_| ()),
+                ScriptCommand::RecoverParty => {
+                    for member in engine.game_state.borrow_mut().members.values_mut() {
+                        let bonuses = member.equipment_bonuses(&engine.database);
+                        member.recover(&bonuses);
+                    }
+                    Ok(())
+                }
+                ScriptCommand::StartBattle {
+                    ref troop,
+                    can_flee,
+                    can_lose,
+                } => if opened.is_some() {
+                    // Only one battle or shop can be open at a time.
+                    Err(DeucalionError::from(String::from(
+                        "a battle was started after another scene was opened in the same frame",
+                    )))
+                } else {
+                    self.start_battle(engine, troop, can_flee, can_lose)
+                        .map(|scene| opened = Some(Box::new(scene)))
+                },
+                ScriptCommand::OpenShop {
+                    ref items,
+                    ref prices,
+                } => if opened.is_some() {
+                    Err(DeucalionError::from(String::from(
+                        "a shop was opened after another scene was opened in the same frame",
+                    )))
+                } else {
+                    Shop::new(items, prices, &engine.database).map(|shop| {
+                        opened = Some(Box::new(ShopScene::new(shop, self.party_members())))
+                    })
+                },
+                ScriptCommand::OpenLayout {
+                    ref layout,
+                    ref call,
+                } => if opened.is_some() {
+                    Err(DeucalionError::from(String::from(
+                        "a layout was opened after another scene was opened in the same frame",
+                    )))
+                } else {
+                    let scene = LayoutScene::new(layout.clone(), call.clone());
+                    opened = Some(Box::new(scene));
+                    Ok(())
+                },
+            };
+            if let Err(e) = result {
+                engine.report_error(&format!("Failed to run script command {:?}", command), e);
+            }
+        }
+        match opened {
+            Some(scene) => Transition::Push(scene),
+            None => Transition::Stay,
+        }
+    }
+
+    // Count the player's steps through encounter regions, asking the game about each, and start
+    //  a battle when an encounter happens.
+    fn check_encounter(&mut self, engine: &mut Engine) -> Transition {
+        // Walking the player in a cutscene doesn't count.
+        if self.world.is_player_moving_to() {
+            self.encounters.forget_position();
+            return Transition::Stay;
+        }
+        let centre = self.world.player.bounds().center();
+        let tile = self.world.collision.tile_at(centre);
+        let step = match self.encounters.track(centre, tile) {
+            Some(step) => step,
+            None => return Transition::Stay,
+        };
+
+        let arguments = vec![
+            AnyLuaValue::LuaString(self.encounters.regions[step.region].name.clone()),
+            AnyLuaValue::LuaBoolean(step.due),
+        ];
+        let decision = match engine
+            .scripts
+            .call_function(&ScriptOwner::Game, ENCOUNTER_HOOK, arguments)
+        {
+            Ok(decision) => decision.unwrap_or(AnyLuaValue::LuaNil),
+            Err(e) => {
+                engine.report_error(&format!("Failed to run {}", ENCOUNTER_HOOK), e);
+                AnyLuaValue::LuaNil
+            }
+        };
+        let troop = match decision {
+            AnyLuaValue::LuaBoolean(false) => {
+                if step.due {
+                    self.encounters.reset();
+                }
+                return Transition::Stay;
+            }
+            AnyLuaValue::LuaString(troop) => Some(troop),
+            AnyLuaValue::LuaBoolean(true) => None,
+            _ if step.due => None,
+            _ => return Transition::Stay,
+        };
+
+        self.encounters.reset();
+        let troop = match troop.or_else(|| self.encounters.choose_troop(step.region)) {
+            Some(troop) => troop,
+            None => return Transition::Stay,
+        };
+        debug!("The player has encountered troop {}", troop);
+        match self.start_battle(engine, &troop, true, false) {
+            Ok(scene) => Transition::Push(Box::new(scene)),
+            Err(e) => {
+                engine.report_error(&format!("Failed to start an encounter with {}", troop), e);
+                Transition::Stay
+            }
+        }
+    }
+}
+
+// Set up the world on a map, with the player at `start` and the map's NPCs in their places.
+fn create_world(engine: &Engine, map: &Tilemap, start: WorldPoint) -> World {
+    let collision = CollisionGrid::from_map(&map.map);
+    let movement = MovementSettings::for_map(&engine.game_config, &map.map);
+    let tile_size = collision.tile_size();
+    let player = Actor::new(start, tile_size, DEFAULT_SPEED_IN_TILES * tile_size.width);
+    let npcs = get_map_npcs(&map.map, &movement);
+    World::new(collision, movement, player, npcs)
+}
+
+/// Find where the top left of a tile is on a map, given its tile coordinates.
+pub fn get_tile_position(map: &Tilemap, x: i32, y: i32) -> WorldPoint {
+    WorldPoint::new(
+        x as f32 * map.map.tile_width as f32,
+        y as f32 * map.map.tile_height as f32,
+    )
+}
+
+/// Find where the player starts on a map: the top left of the object named "player_start", in
+/// any object group, or the top left of the map if there isn't one.
+pub fn get_player_start(map: &Tilemap) -> WorldPoint {
+    map.map
+        .object_groups
+        .iter()
+        .flat_map(|group| group.objects.iter())
+        .find(|object| object.name == PLAYER_START_OBJECT)
+        .map(|object| WorldPoint::new(object.x, object.y))
+        .unwrap_or_else(WorldPoint::origin)
+}
+
+impl Scene for MapScene {
+    fn on_action(&mut self, engine: &mut Engine, action: Action) -> Transition {
+        match action {
+            Action::Menu => {
+                // The menu can save the game, so note where the player is first.
+                engine.game_state.borrow_mut().location = Some(self.location());
+                let members = self.party_members();
+                Transition::Push(Box::new(MainMenuScene::new(engine, members)))
+            }
+            _ => Transition::Stay,
+        }
+    }
+
+    fn update(&mut self, engine: &mut Engine) -> Transition {
+        let delta = get_frame_delta(&mut self.last_update);
+        self.time += delta;
+        let transition = self.run_script_commands(engine);
+        let movement = input::read_movement(&engine.key_bindings);
+        self.world.update(movement, delta);
+        self.run_animation_events(engine);
+        match transition {
+            Transition::Stay => self.check_encounter(engine),
+            transition => transition,
+        }
+    }
+
+    fn draw(&mut self, engine: &mut Engine) {
+        engine.display.canvas.clear(&self.map.background_color);
+        self.map
+            .draw_layers(&mut engine.display.canvas, self.time, TilePass::Under);
+
+        // Everything standing on the map, back to front. Tile objects that are over the
+        //  characters wherever they're placed are left for the last pass.
+        let mut standing = Vec::new();
+        for object in self.tile_objects.iter() {
+            if !self.map.is_above_characters(object.gid) {
+                standing.push(MapSprite::Tile(*object));
+            }
+        }
+        // TEMP: TODO: Draw the actors' spritesheets; for now, boxes show where they are
+        for follower in self.world.party.followers().iter().rev() {
+            standing.push(MapSprite::Actor(&follower.actor, Color::rgba(160, 200, 255, 160)));
+        }
+        standing.push(MapSprite::Actor(&self.world.player, Color::rgba(255, 255, 255, 160)));
+        for npc in self.world.npcs.iter() {
+            standing.push(MapSprite::Actor(&npc.actor, Color::rgba(255, 200, 0, 160)));
+        }
+        sort_by_depth(&mut standing, |sprite| sprite.foot());
+        for sprite in standing.iter() {
+            match *sprite {
+                MapSprite::Tile(object) => self.map.draw_tile(
+                    &mut engine.display.canvas,
+                    object.gid,
+                    object.position,
+                    self.time,
+                ),
+                MapSprite::Actor(actor, color) => draw_actor_box(engine, actor, color),
+            }
+        }
+
+        self.map
+            .draw_layers(&mut engine.display.canvas, self.time, TilePass::Over);
+        for object in self.tile_objects.iter() {
+            if self.map.is_above_characters(object.gid) {
+                self.map.draw_tile(
+                    &mut engine.display.canvas,
+                    object.gid,
+                    object.position,
+                    self.time,
+                );
+            }
+        }
+        self.draw_animations(engine);
+    }
+}
+
+// Something standing on the map, drawn in among the characters.
+enum MapSprite<'a> {
+    Tile(TileObject),
+    // TEMP: Actors are drawn as coloured boxes for now.
+    Actor(&'a Actor, Color),
+}
+
+impl<'a> MapSprite<'a> {
+    // The Y of the bottom of the sprite, where it stands.
+    fn foot(&self) -> f32 {
+        match *self {
+            MapSprite::Tile(object) => object.position.y,
+            MapSprite::Actor(actor, _) => actor.bounds().max_y(),
+        }
+    }
+}
+
+// TEMP: Draw a box where an actor is.
+fn draw_actor_box(engine: &mut Engine, actor: &Actor, color: Color) {
+    let mut shape = RectangleShape::with_size(Vector2f::new(actor.size.width, actor.size.height));
+    shape.set_position((actor.position.x, actor.position.y));
+    shape.set_fill_color(&color);
+    engine.display.canvas.draw(&shape);
+}
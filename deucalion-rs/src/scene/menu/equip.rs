@@ -0,0 +1,299 @@
+//! The equipment screen, where the player changes what party members are wearing, seeing how
+//! each item would change their stats before putting it on.
+
+use battle::combatant::StatBonus;
+use battle::database::{Database, EquipSlot};
+use engine::Engine;
+use game_state::member::PartyMember;
+use game_state::shop::compare_equipment;
+use game_state::GameState;
+use input::Action;
+use scene::menu::{describe_vitals, draw_backdrop, MARGIN};
+use scene::shop::describe_stat_changes;
+use scene::{Scene, Transition};
+use ui::list::{self, List, ListItem};
+use ui::window::Window;
+use ui::{HIGHLIGHT_COLOR, TEXT_COLOR};
+
+/// How many items that fit a slot are listed at a time.
+pub const CHOICE_ROWS: usize = 8;
+
+/// The text of the choice to take off what's in a slot.
+pub const REMOVE_CHOICE: &'static str = "(Remove)";
+
+/// The state of the equipment screen, separate from drawing it: whose equipment is shown, which
+/// slot is selected and, once one has been chosen, what to wear in it.
+#[derive(Debug, Clone)]
+pub struct EquipMenu {
+    /// The characters in the party, in order.
+    pub members: Vec<String>,
+    member: usize,
+    slots: List,
+    // While choosing what to wear, the items that fit the slot, by name, or None to take off
+    //  what's there, and the list of them.
+    choices: Option<(Vec<Option<String>>, List)>,
+    message: Option<String>,
+}
+
+impl EquipMenu {
+    /// Show the equipment of a party's first member.
+    pub fn new(members: Vec<String>, state: &GameState, database: &Database) -> EquipMenu {
+        let mut menu = EquipMenu {
+            members: members,
+            member: 0,
+            slots: List::new(Vec::new(), EquipSlot::ALL.len()),
+            choices: None,
+            message: None,
+        };
+        menu.refresh(state, database);
+        menu
+    }
+
+    /// The character whose equipment is shown.
+    pub fn character(&self) -> Option<&str> {
+        self.members.get(self.member).map(String::as_str)
+    }
+
+    /// The list of slots, with what's worn in each.
+    pub fn slots(&self) -> &List {
+        &self.slots
+    }
+
+    /// The list of what can be worn in the chosen slot, if one has been chosen.
+    pub fn choices(&self) -> Option<&List> {
+        self.choices.as_ref().map(|&(_, ref list)| list)
+    }
+
+    /// The selected slot.
+    pub fn selected_slot(&self) -> Option<EquipSlot> {
+        self.slots.selected().map(|index| EquipSlot::ALL[index])
+    }
+
+    /// The item selected to wear, by name, if what to wear is being chosen and it isn't to take
+    /// off what's there.
+    pub fn selected_choice(&self) -> Option<&str> {
+        match self.choices {
+            Some((ref items, ref list)) => list.selected()
+                .and_then(|index| items[index].as_ref())
+                .map(String::as_str),
+            None => None,
+        }
+    }
+
+    /// Why the last change couldn't be made, if it couldn't.
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_ref().map(String::as_str)
+    }
+
+    /// How wearing the selected choice would change the member's stats.
+    pub fn comparison(&self, state: &GameState, database: &Database) -> Option<StatBonus> {
+        let (item, character) = match (self.selected_choice(), self.character()) {
+            (Some(item), Some(character)) => (item, character),
+            _ => return None,
+        };
+        compare_equipment(item, &[String::from(character)], state, database)
+            .into_iter()
+            .next()
+            .map(|(_, changes)| changes)
+    }
+
+    /// List the member's equipment, and what could be worn, again, after it has changed.
+    pub fn refresh(&mut self, state: &GameState, database: &Database) {
+        let member = match self.character().and_then(|character| state.member(character)) {
+            Some(member) => member,
+            None => {
+                self.slots.set_items(Vec::new());
+                self.choices = None;
+                return;
+            }
+        };
+        let slots = EquipSlot::ALL
+            .iter()
+            .map(|slot| {
+                let worn = member
+                    .equipment
+                    .get(slot)
+                    .map_or(String::from("-"), |item| database.item_name(item));
+                ListItem::new(format!("{}: {}", slot.label(), worn))
+            })
+            .collect();
+        self.slots.set_items(slots);
+
+        if let Some(slot) = self.selected_slot() {
+            if let Some((ref mut choices, ref mut list)) = self.choices {
+                let (items, list_items) = get_choices(member, slot, state, database);
+                *choices = items;
+                list.set_items(list_items);
+            }
+        }
+    }
+
+    /// Move the selection in whichever list is being chosen from.
+    pub fn move_selection(&mut self, delta: i32) {
+        self.message = None;
+        match self.choices {
+            Some((_, ref mut list)) => list.move_selection(delta),
+            None => self.slots.move_selection(delta),
+        }
+    }
+
+    /// Show the equipment of the member `delta` places on from the one being shown. Only
+    /// while choosing a slot.
+    pub fn change_member(&mut self, delta: i32, state: &GameState, database: &Database) {
+        if self.choices.is_some() {
+            return;
+        }
+        self.message = None;
+        self.member = list::wrap(self.member, delta, self.members.len());
+        self.refresh(state, database);
+    }
+
+    /// Choose the selected slot, or wear the selected choice in it.
+    pub fn confirm(&mut self, state: &mut GameState, database: &Database) {
+        let (slot, character) = match (self.selected_slot(), self.character()) {
+            (Some(slot), Some(character)) => (slot, String::from(character)),
+            _ => return,
+        };
+        if self.choices.is_none() {
+            if let Some(member) = state.member(&character) {
+                let (items, list_items) = get_choices(member, slot, state, database);
+                self.choices = Some((items, List::new(list_items, CHOICE_ROWS)));
+            }
+            return;
+        }
+        let enabled = self.choices().map_or(false, List::is_selected_enabled);
+        let changed = match self.selected_choice().map(String::from) {
+            Some(item) => state.equip(&character, &item, database),
+            None if enabled => state.unequip(&character, slot, database),
+            None => return,
+        };
+        match changed {
+            Ok(_) => {
+                self.choices = None;
+                self.message = None;
+            }
+            Err(e) => self.message = Some(e.to_string()),
+        }
+        self.refresh(state, database);
+    }
+
+    /// Go back from choosing what to wear. Returns false if the player has backed out of the
+    /// screen.
+    pub fn cancel(&mut self) -> bool {
+        self.message = None;
+        self.choices.take().is_some()
+    }
+}
+
+// What can be worn in a member's slot: taking off what's there, as None, and then the party's
+//  items that fit it, by name, along with the list items for them.
+fn get_choices(
+    member: &PartyMember,
+    slot: EquipSlot,
+    state: &GameState,
+    database: &Database,
+) -> (Vec<Option<String>>, Vec<ListItem>) {
+    let mut items = vec![None];
+    let mut list_items = vec![ListItem::enabled_if(
+        REMOVE_CHOICE,
+        member.equipment.contains_key(&slot),
+    )];
+    for (item, count) in state.inventory.items().iter() {
+        if database.item(item).and_then(|item| item.equip_slot()) == Some(slot) {
+            items.push(Some(item.clone()));
+            list_items.push(ListItem::new(format!("{} x{}", database.item_name(item), count)));
+        }
+    }
+    (items, list_items)
+}
+
+/// Shows each party member's equipment, and lets the player change it.
+pub struct EquipScene {
+    menu: EquipMenu,
+}
+
+impl EquipScene {
+    /// Open the screen for a party, starting with its first member.
+    pub fn new(engine: &Engine, members: Vec<String>) -> EquipScene {
+        let state = engine.game_state.borrow();
+        EquipScene {
+            menu: EquipMenu::new(members, &state, &engine.database),
+        }
+    }
+}
+
+impl Scene for EquipScene {
+    fn on_action(&mut self, engine: &mut Engine, action: Action) -> Transition {
+        match action {
+            Action::Up => self.menu.move_selection(-1),
+            Action::Down => self.menu.move_selection(1),
+            Action::Left | Action::Right => {
+                let delta = if action == Action::Left { -1 } else { 1 };
+                let state = engine.game_state.borrow();
+                self.menu.change_member(delta, &state, &engine.database);
+            }
+            Action::Confirm => {
+                let mut state = engine.game_state.borrow_mut();
+                self.menu.confirm(&mut state, &engine.database);
+            }
+            Action::Cancel | Action::Menu => {
+                if !self.menu.cancel() {
+                    return Transition::Pop;
+                }
+            }
+        }
+        Transition::Stay
+    }
+
+    fn draw(&mut self, engine: &mut Engine) {
+        draw_backdrop(engine);
+        let size = engine.display.render_size();
+        let width = size.width.saturating_sub(MARGIN * 2);
+        let header = Window::with_rows(MARGIN, MARGIN, width, 2);
+        let slots = Window::with_rows(
+            MARGIN,
+            header.rect.max_y() + MARGIN,
+            width,
+            EquipSlot::ALL.len() as u32,
+        );
+        let choices = Window::with_rows(
+            MARGIN,
+            slots.rect.max_y() + MARGIN,
+            width,
+            CHOICE_ROWS as u32,
+        );
+
+        let (name, detail) = {
+            let state = engine.game_state.borrow();
+            let member = self.menu.character().and_then(|c| state.member(c));
+            let name = member.map_or(String::new(), |member| {
+                format!("{}  Lv {}", member.name, member.level)
+            });
+            // Show how the selected item would change the member's stats, or failing that,
+            //  how they are.
+            let comparison = self.menu.comparison(&state, &engine.database);
+            let detail = match (self.menu.message(), comparison) {
+                (Some(message), _) => String::from(message),
+                (None, Some(changes)) => describe_stat_changes(&changes),
+                (None, None) => member.map_or(String::new(), |member| {
+                    describe_vitals(member, &engine.database)
+                }),
+            };
+            (name, detail)
+        };
+
+        let canvas = &mut engine.display.canvas;
+        let font = &engine.font.font;
+        let skin = engine.windowskin.as_ref();
+        header.draw(canvas, skin);
+        header.draw_text(canvas, font, &name, (0, 0), &HIGHLIGHT_COLOR);
+        header.draw_text(canvas, font, &detail, (0, 1), &TEXT_COLOR);
+        slots.draw(canvas, skin);
+        let menu = &self.menu;
+        menu.slots().draw(canvas, font, &slots, 0, menu.choices().is_none());
+        if let Some(list) = menu.choices() {
+            choices.draw(canvas, skin);
+            list.draw(canvas, font, &choices, 0, true);
+        }
+    }
+}
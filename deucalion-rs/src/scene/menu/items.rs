@@ -0,0 +1,224 @@
+//! The items screen, where the player looks over what the party is carrying and uses items that
+//! restore HP and MP on party members.
+
+use battle::database::Database;
+use engine::Engine;
+use game_state::GameState;
+use input::Action;
+use scene::menu::{describe_vitals, draw_backdrop, MARGIN};
+use scene::{Scene, Transition};
+use ui::list::{List, ListItem};
+use ui::window::Window;
+use ui::{HIGHLIGHT_COLOR, TEXT_COLOR};
+
+/// How many items are listed at a time.
+pub const ITEM_ROWS: usize = 12;
+
+/// The state of the items screen, separate from drawing it: the party's items, and, once one
+/// has been chosen, who to use it on.
+#[derive(Debug, Clone)]
+pub struct ItemsMenu {
+    /// The characters in the party, in order.
+    pub members: Vec<String>,
+    // The party's items, by name, in the order they're listed.
+    item_names: Vec<String>,
+    items: List,
+    // The member list, while choosing who to use the chosen item on.
+    targets: Option<List>,
+    message: Option<String>,
+}
+
+impl ItemsMenu {
+    /// List the party's items.
+    pub fn new(members: Vec<String>, state: &GameState, database: &Database) -> ItemsMenu {
+        let mut menu = ItemsMenu {
+            members: members,
+            item_names: Vec::new(),
+            items: List::new(Vec::new(), ITEM_ROWS),
+            targets: None,
+            message: None,
+        };
+        menu.refresh(state, database);
+        menu
+    }
+
+    /// The list of items.
+    pub fn items(&self) -> &List {
+        &self.items
+    }
+
+    /// The list of party members to use the chosen item on, if one has been chosen.
+    pub fn targets(&self) -> Option<&List> {
+        self.targets.as_ref()
+    }
+
+    /// The selected item, by name.
+    pub fn selected_item(&self) -> Option<&str> {
+        self.items
+            .selected()
+            .map(|index| self.item_names[index].as_str())
+    }
+
+    /// What happened when an item was last used, such as "Used a Potion on Hero.".
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_ref().map(String::as_str)
+    }
+
+    /// List the party's items and members again, after they've changed.
+    pub fn refresh(&mut self, state: &GameState, database: &Database) {
+        self.item_names = state.inventory.items().keys().cloned().collect();
+        let items = state
+            .inventory
+            .items()
+            .iter()
+            .map(|(item, count)| match database.item(item) {
+                Some(definition) => ListItem::enabled_if(
+                    format!("{} x{}", definition.name, count),
+                    definition.is_usable_outside_battle(),
+                ),
+                None => ListItem::enabled_if(format!("{} x{}", item, count), false),
+            })
+            .collect();
+        self.items.set_items(items);
+        if let Some(ref mut targets) = self.targets {
+            targets.set_items(get_target_items(&self.members, state, database));
+        }
+    }
+
+    /// Move the selection in whichever list is being chosen from.
+    pub fn move_selection(&mut self, delta: i32) {
+        self.message = None;
+        match self.targets {
+            Some(ref mut targets) => targets.move_selection(delta),
+            None => self.items.move_selection(delta),
+        }
+    }
+
+    /// Choose the selected item, or use it on the selected member.
+    pub fn confirm(&mut self, state: &mut GameState, database: &Database) {
+        let item = match self.selected_item() {
+            Some(item) => String::from(item),
+            None => return,
+        };
+        if self.targets.is_none() {
+            if self.items.is_selected_enabled() {
+                let items = get_target_items(&self.members, state, database);
+                self.targets = Some(List::new(items, self.members.len()));
+                self.message = None;
+            }
+            return;
+        }
+        let target = self.targets.as_ref().and_then(List::selected);
+        let character = match target.and_then(|index| self.members.get(index)) {
+            Some(character) => character.clone(),
+            None => return,
+        };
+        self.message = Some(match state.use_item(&item, &character, database) {
+            Ok(true) => format!(
+                "Used a {} on {}.",
+                database.item_name(&item),
+                state
+                    .member(&character)
+                    .map_or(character.clone(), |member| member.name.clone())
+            ),
+            Ok(false) => String::from("It would have no effect."),
+            Err(e) => e.to_string(),
+        });
+        // Once it's all gone, there's nothing left to use.
+        if !state.inventory.has(&item, 1) {
+            self.targets = None;
+        }
+        self.refresh(state, database);
+    }
+
+    /// Go back from choosing who to use an item on. Returns false if the player has backed out
+    /// of the screen.
+    pub fn cancel(&mut self) -> bool {
+        self.targets.take().is_some()
+    }
+}
+
+// The party members to choose from, with their HP and MP.
+fn get_target_items(members: &[String], state: &GameState, database: &Database) -> Vec<ListItem> {
+    members
+        .iter()
+        .filter_map(|character| state.member(character))
+        .map(|member| {
+            ListItem::new(format!(
+                "{}  {}",
+                member.name,
+                describe_vitals(member, database)
+            ))
+        })
+        .collect()
+}
+
+/// Shows the party's items, and lets the player use them.
+pub struct ItemsScene {
+    menu: ItemsMenu,
+}
+
+impl ItemsScene {
+    /// Open the screen for a party.
+    pub fn new(engine: &Engine, members: Vec<String>) -> ItemsScene {
+        let state = engine.game_state.borrow();
+        ItemsScene {
+            menu: ItemsMenu::new(members, &state, &engine.database),
+        }
+    }
+}
+
+impl Scene for ItemsScene {
+    fn on_action(&mut self, engine: &mut Engine, action: Action) -> Transition {
+        match action {
+            Action::Up => self.menu.move_selection(-1),
+            Action::Down => self.menu.move_selection(1),
+            Action::Confirm => {
+                let mut state = engine.game_state.borrow_mut();
+                self.menu.confirm(&mut state, &engine.database);
+            }
+            Action::Cancel | Action::Menu => {
+                if !self.menu.cancel() {
+                    return Transition::Pop;
+                }
+            }
+            Action::Left | Action::Right => {}
+        }
+        Transition::Stay
+    }
+
+    fn draw(&mut self, engine: &mut Engine) {
+        draw_backdrop(engine);
+        let size = engine.display.render_size();
+        let width = size.width.saturating_sub(MARGIN * 2);
+        let info = Window::with_rows(MARGIN, MARGIN, width, 2);
+        let items = Window::with_rows(MARGIN, info.rect.max_y() + MARGIN, width, ITEM_ROWS as u32);
+
+        let menu = &self.menu;
+        let description = match menu.message() {
+            Some(message) => String::from(message),
+            None => menu.selected_item()
+                .and_then(|item| engine.database.item(item))
+                .map_or(String::new(), |item| item.description.clone()),
+        };
+        let canvas = &mut engine.display.canvas;
+        let font = &engine.font.font;
+        let skin = engine.windowskin.as_ref();
+        info.draw(canvas, skin);
+        info.draw_text(canvas, font, "Items", (0, 0), &HIGHLIGHT_COLOR);
+        info.draw_text(canvas, font, &description, (0, 1), &TEXT_COLOR);
+        items.draw(canvas, skin);
+        menu.items().draw(canvas, font, &items, 0, menu.targets().is_none());
+
+        if let Some(targets) = menu.targets() {
+            let window = Window::with_rows(
+                size.width / 3,
+                items.rect.origin.y + MARGIN * 2,
+                size.width / 2,
+                targets.items().len() as u32,
+            );
+            window.draw(canvas, skin);
+            targets.draw(canvas, font, &window, 0, true);
+        }
+    }
+}
@@ -0,0 +1,362 @@
+//! The main menu, opened from the map, where the player looks after the party between battles:
+//! using items, looking over skills and status, changing equipment and saving. Each command
+//! opens a screen of its own, in the submodules.
+//!
+//! The game can change the commands each time the menu opens by defining
+//! `on_main_menu(commands)` in data/scripts/init.lua. It's given the default commands, a list of
+//! names (`"items"`, `"skills"`, `"equip"`, `"status"`, `"save"`, `"options"` and `"quit"`),
+//! and returns the list to show: the same names in any order, leaving out any it likes, along
+//! with commands of its own, each a table of a `label` and the name of a global function to
+//! `call`, in init.lua's environment, once the menu has closed.
+
+use hlua::AnyLuaValue;
+use sfml::graphics::{Color, RectangleShape, RenderTarget, Shape};
+use sfml::system::Vector2f;
+
+use battle::database::Database;
+use config::schema::ConfigValue;
+use engine::Engine;
+use error::DeucalionError;
+use game_state::member::PartyMember;
+use game_state::save::get_save_directory;
+use input::Action;
+use scene::menu::equip::EquipScene;
+use scene::menu::items::ItemsScene;
+use scene::menu::save::SaveScene;
+use scene::menu::skills::SkillsScene;
+use scene::menu::status::StatusScene;
+use scene::options::OptionsScene;
+use scene::{Scene, Transition};
+use scripting::convert::get_table_field;
+use scripting::sandbox::ScriptOwner;
+use ui::gauge::Gauge;
+use ui::list::{List, ListItem};
+use ui::widget::widget_rect;
+use ui::window::Window;
+use ui::{HIGHLIGHT_COLOR, LINE_HEIGHT};
+
+pub mod equip;
+pub mod items;
+pub mod save;
+pub mod skills;
+pub mod status;
+#[cfg(test)]
+mod test_equip;
+#[cfg(test)]
+mod test_items;
+#[cfg(test)]
+mod test_menu;
+#[cfg(test)]
+mod test_status;
+
+/// The game's function that chooses the main menu's commands.
+pub const MAIN_MENU_HOOK: &'static str = "on_main_menu";
+
+/// How wide the main menu's command window is.
+pub const COMMAND_WINDOW_WIDTH: u32 = 160;
+
+/// How far windows are kept from the edges of the screen and each other.
+pub const MARGIN: u32 = 8;
+
+/// A command on the main menu.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum MenuCommand {
+    Items,
+    Skills,
+    Equip,
+    Status,
+    Save,
+    Options,
+    Quit,
+    /// One of the game's own commands, which calls a function in init.lua's environment.
+    Script { label: String, function: String },
+}
+
+impl MenuCommand {
+    /// The commands shown unless the game says otherwise, in order.
+    pub fn defaults() -> Vec<MenuCommand> {
+        vec![
+            MenuCommand::Items,
+            MenuCommand::Skills,
+            MenuCommand::Equip,
+            MenuCommand::Status,
+            MenuCommand::Save,
+            MenuCommand::Options,
+            MenuCommand::Quit,
+        ]
+    }
+
+    /// Look up one of the engine's commands by the name scripts use for it.
+    pub fn from_name(name: &str) -> Option<MenuCommand> {
+        match name {
+            "items" => Some(MenuCommand::Items),
+            "skills" => Some(MenuCommand::Skills),
+            "equip" => Some(MenuCommand::Equip),
+            "status" => Some(MenuCommand::Status),
+            "save" => Some(MenuCommand::Save),
+            "options" => Some(MenuCommand::Options),
+            "quit" => Some(MenuCommand::Quit),
+            _ => None,
+        }
+    }
+
+    /// The name scripts use for one of the engine's commands, or None for the game's own.
+    pub fn name(&self) -> Option<&'static str> {
+        match *self {
+            MenuCommand::Items => Some("items"),
+            MenuCommand::Skills => Some("skills"),
+            MenuCommand::Equip => Some("equip"),
+            MenuCommand::Status => Some("status"),
+            MenuCommand::Save => Some("save"),
+            MenuCommand::Options => Some("options"),
+            MenuCommand::Quit => Some("quit"),
+            MenuCommand::Script { .. } => None,
+        }
+    }
+
+    /// The text shown for the command.
+    pub fn label(&self) -> &str {
+        match *self {
+            MenuCommand::Items => "Items",
+            MenuCommand::Skills => "Skills",
+            MenuCommand::Equip => "Equip",
+            MenuCommand::Status => "Status",
+            MenuCommand::Save => "Save",
+            MenuCommand::Options => "Options",
+            MenuCommand::Quit => "Quit",
+            MenuCommand::Script { ref label, .. } => label,
+        }
+    }
+}
+
+/// Read the commands returned by `on_main_menu`: a list of engine commands, by name, and tables
+/// of a `label` and a function to `call`.
+pub fn parse_menu_commands(value: AnyLuaValue) -> Result<Vec<MenuCommand>, DeucalionError> {
+    let entries = Vec::<AnyLuaValue>::from_lua(value)
+        .ok_or_else(|| DeucalionError::from(format!("{} must return a list", MAIN_MENU_HOOK)))?;
+    let mut commands = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let command = match entry {
+            AnyLuaValue::LuaString(name) => MenuCommand::from_name(&name).ok_or_else(|| {
+                DeucalionError::from(format!("there is no menu command named '{}'", name))
+            })?,
+            table @ AnyLuaValue::LuaArray(_) => {
+                match (
+                    get_table_field(&table, "label"),
+                    get_table_field(&table, "call"),
+                ) {
+                    (AnyLuaValue::LuaString(label), AnyLuaValue::LuaString(function)) => {
+                        MenuCommand::Script {
+                            label: label,
+                            function: function,
+                        }
+                    }
+                    _ => {
+                        return Err(DeucalionError::from(String::from(
+                            "the game's own menu commands must have a label and a function to \
+                             call",
+                        )))
+                    }
+                }
+            }
+            _ => {
+                return Err(DeucalionError::from(String::from(
+                    "menu commands must be names or tables",
+                )))
+            }
+        };
+        commands.push(command);
+    }
+    Ok(commands)
+}
+
+/// Ask the game which commands to show on the main menu. If it doesn't say, or what it says
+/// can't be read, which is reported, the default commands are shown.
+pub fn get_menu_commands(engine: &mut Engine) -> Vec<MenuCommand> {
+    let defaults = MenuCommand::defaults();
+    let names = defaults
+        .iter()
+        .enumerate()
+        .filter_map(|(i, command)| {
+            command.name().map(|name| {
+                (
+                    AnyLuaValue::LuaNumber((i + 1) as f64),
+                    AnyLuaValue::LuaString(String::from(name)),
+                )
+            })
+        })
+        .collect();
+    let result = engine.scripts.call_function(
+        &ScriptOwner::Game,
+        MAIN_MENU_HOOK,
+        vec![AnyLuaValue::LuaArray(names)],
+    );
+    match result.and_then(|value| match value {
+        None | Some(AnyLuaValue::LuaNil) => Ok(defaults.clone()),
+        Some(value) => parse_menu_commands(value),
+    }) {
+        Ok(commands) => commands,
+        Err(e) => {
+            engine.report_error(&format!("Failed to run {}", MAIN_MENU_HOOK), e);
+            defaults
+        }
+    }
+}
+
+/// A party member's HP and MP, out of their maximums, for the menus.
+pub fn describe_vitals(member: &PartyMember, database: &Database) -> String {
+    let stats = member.stats(&member.equipment_bonuses(database));
+    format!(
+        "HP {}/{}  MP {}/{}",
+        member.hp, stats.max_hp, member.mp, stats.max_mp
+    )
+}
+
+/// Cover the whole screen, for the menu's screens, which hide whatever is underneath them.
+pub fn draw_backdrop(engine: &mut Engine) {
+    let size = engine.display.render_size();
+    let mut backdrop = RectangleShape::with_size(Vector2f::new(
+        size.width as f32,
+        size.height as f32,
+    ));
+    backdrop.set_fill_color(&Color::rgb(8, 8, 24));
+    engine.display.canvas.draw(&backdrop);
+}
+
+/// The main menu: the party's status, and the commands, over the map.
+pub struct MainMenuScene {
+    /// The characters in the party, in order.
+    pub members: Vec<String>,
+    commands: Vec<MenuCommand>,
+    list: List,
+}
+
+impl MainMenuScene {
+    /// Open the menu for a party, with the commands the game chooses.
+    pub fn new(engine: &mut Engine, members: Vec<String>) -> MainMenuScene {
+        let commands = get_menu_commands(engine);
+        // Saving needs somewhere to put the saves.
+        let can_save = get_save_directory(&engine.game_config).is_some();
+        let items = commands
+            .iter()
+            .map(|command| {
+                ListItem::enabled_if(command.label(), *command != MenuCommand::Save || can_save)
+            })
+            .collect();
+        let rows = commands.len();
+        MainMenuScene {
+            members: members,
+            commands: commands,
+            list: List::new(items, rows),
+        }
+    }
+
+    // Carry out a command.
+    fn choose(&self, engine: &mut Engine, command: MenuCommand) -> Transition {
+        let members = self.members.clone();
+        match command {
+            MenuCommand::Items => Transition::Push(Box::new(ItemsScene::new(engine, members))),
+            MenuCommand::Skills => Transition::Push(Box::new(SkillsScene::new(engine, members))),
+            MenuCommand::Equip => Transition::Push(Box::new(EquipScene::new(engine, members))),
+            MenuCommand::Status => Transition::Push(Box::new(StatusScene::new(members))),
+            MenuCommand::Save => Transition::Push(Box::new(SaveScene::new(engine))),
+            MenuCommand::Options => Transition::Push(Box::new(OptionsScene::new(engine))),
+            MenuCommand::Quit => Transition::Quit,
+            MenuCommand::Script { ref function, .. } => {
+                // The menu closes straight afterwards, so that whatever the function asks the
+                //  map to do happens on its next update.
+                if let Err(e) = engine
+                    .scripts
+                    .call_function(&ScriptOwner::Game, function, Vec::new())
+                {
+                    engine.report_error(&format!("Failed to run menu command {}", function), e);
+                }
+                Transition::Pop
+            }
+        }
+    }
+}
+
+impl Scene for MainMenuScene {
+    fn on_action(&mut self, engine: &mut Engine, action: Action) -> Transition {
+        match action {
+            Action::Up => self.list.move_selection(-1),
+            Action::Down => self.list.move_selection(1),
+            Action::Confirm => {
+                if !self.list.is_selected_enabled() {
+                    return Transition::Stay;
+                }
+                let command = match self.list.selected() {
+                    Some(index) => self.commands[index].clone(),
+                    None => return Transition::Stay,
+                };
+                return self.choose(engine, command);
+            }
+            Action::Cancel | Action::Menu => return Transition::Pop,
+            Action::Left | Action::Right => {}
+        }
+        Transition::Stay
+    }
+
+    fn draw(&mut self, engine: &mut Engine) {
+        let size = engine.display.render_size();
+        let command_x = size.width.saturating_sub(COMMAND_WINDOW_WIDTH + MARGIN);
+        let commands = Window::with_rows(
+            command_x,
+            MARGIN,
+            COMMAND_WINDOW_WIDTH,
+            self.list.items().len() as u32,
+        );
+        let gold = Window::with_rows(
+            command_x,
+            commands.rect.max_y() + MARGIN,
+            COMMAND_WINDOW_WIDTH,
+            1,
+        );
+        let party = Window::new(
+            MARGIN,
+            MARGIN,
+            command_x.saturating_sub(MARGIN * 2),
+            size.height.saturating_sub(MARGIN * 2),
+        );
+
+        // Work out what to show first, so the game state isn't borrowed while drawing.
+        let (gold_line, party_status) = {
+            let state = engine.game_state.borrow();
+            let mut status = Vec::new();
+            for member in self.members.iter().filter_map(|c| state.member(c)) {
+                let stats = member.stats(&member.equipment_bonuses(&engine.database));
+                status.push((
+                    format!("{}  Lv {}", member.name, member.level),
+                    Gauge::hp(member.hp, stats.max_hp),
+                    Gauge::mp(member.mp, stats.max_mp),
+                ));
+            }
+            (format!("{} G", state.gold), status)
+        };
+
+        let canvas = &mut engine.display.canvas;
+        let font = &engine.font.font;
+        let skin = engine.windowskin.as_ref();
+        party.draw(canvas, skin);
+        // Each member takes three rows: their name, their gauges side by side, and a gap.
+        let inner = party.inner();
+        let gauge_width = (inner.size.width / 2).saturating_sub(MARGIN);
+        for (i, &(ref name, ref hp, ref mp)) in party_status.iter().enumerate() {
+            let row = i as u32 * 3;
+            party.draw_text(canvas, font, name, (0, row), &HIGHLIGHT_COLOR);
+            let (x, y) = (inner.origin.x, inner.origin.y + (row + 1) * LINE_HEIGHT);
+            hp.draw(canvas, font, widget_rect(x, y, gauge_width, LINE_HEIGHT));
+            let x = x + gauge_width + MARGIN;
+            mp.draw(canvas, font, widget_rect(x, y, gauge_width, LINE_HEIGHT));
+        }
+        commands.draw(canvas, skin);
+        self.list.draw(canvas, font, &commands, 0, true);
+        gold.draw(canvas, skin);
+        gold.draw_text(canvas, font, &gold_line, (0, 0), &HIGHLIGHT_COLOR);
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,104 @@
+//! The save screen, where the player saves the game state in one of the numbered slots.
+
+use std::path::{Path, PathBuf};
+
+use engine::Engine;
+use game_state::save::{get_save_directory, get_save_path, write_save};
+use input::Action;
+use scene::menu::{draw_backdrop, MARGIN};
+use scene::{Scene, Transition};
+use ui::list::{List, ListItem};
+use ui::window::Window;
+use ui::TEXT_COLOR;
+
+/// List the save slots, saying which are empty, and only letting empty ones be chosen if
+/// `empty_enabled` is true.
+pub fn get_slot_items(directory: &Path, slots: u32, empty_enabled: bool) -> Vec<ListItem> {
+    (1..slots + 1)
+        .map(|slot| {
+            if get_save_path(directory, slot).is_file() {
+                ListItem::new(format!("Slot {}", slot))
+            } else {
+                ListItem::enabled_if(format!("Slot {} (empty)", slot), empty_enabled)
+            }
+        })
+        .collect()
+}
+
+/// Lists the save slots, and saves the game in whichever the player chooses.
+pub struct SaveScene {
+    directory: Option<PathBuf>,
+    list: List,
+    message: String,
+}
+
+impl SaveScene {
+    /// Open the screen, listing as many slots as the game has.
+    pub fn new(engine: &Engine) -> SaveScene {
+        let directory = get_save_directory(&engine.game_config);
+        let slots = engine.game_config.save_slots;
+        let items = match directory {
+            Some(ref directory) => get_slot_items(directory, slots, true),
+            None => Vec::new(),
+        };
+        SaveScene {
+            directory: directory,
+            list: List::new(items, slots as usize),
+            message: String::from("Save in which slot?"),
+        }
+    }
+
+    // Save the game in the selected slot, and say how it went.
+    fn save(&mut self, engine: &mut Engine) {
+        let (directory, slot) = match (self.directory.as_ref(), self.list.selected()) {
+            (Some(directory), Some(index)) => (directory.clone(), index as u32 + 1),
+            _ => return,
+        };
+        let path = get_save_path(&directory, slot);
+        let result = write_save(&engine.game_state.borrow(), &path);
+        self.message = match result {
+            Ok(()) => format!("Saved in slot {}.", slot),
+            Err(e) => {
+                let message = format!("Couldn't save: {}", e);
+                engine.report_error(&format!("Failed to save to {}", path.display()), e);
+                message
+            }
+        };
+        let items = get_slot_items(&directory, engine.game_config.save_slots, true);
+        self.list.set_items(items);
+    }
+}
+
+impl Scene for SaveScene {
+    fn on_action(&mut self, engine: &mut Engine, action: Action) -> Transition {
+        match action {
+            Action::Up => self.list.move_selection(-1),
+            Action::Down => self.list.move_selection(1),
+            Action::Confirm => self.save(engine),
+            Action::Cancel | Action::Menu => return Transition::Pop,
+            Action::Left | Action::Right => {}
+        }
+        Transition::Stay
+    }
+
+    fn draw(&mut self, engine: &mut Engine) {
+        draw_backdrop(engine);
+        let size = engine.display.render_size();
+        let width = size.width.saturating_sub(MARGIN * 2);
+        let header = Window::with_rows(MARGIN, MARGIN, width, 1);
+        let slots = Window::with_rows(
+            MARGIN,
+            header.rect.max_y() + MARGIN,
+            width,
+            self.list.items().len() as u32,
+        );
+
+        let canvas = &mut engine.display.canvas;
+        let font = &engine.font.font;
+        let skin = engine.windowskin.as_ref();
+        header.draw(canvas, skin);
+        header.draw_text(canvas, font, &self.message, (0, 0), &TEXT_COLOR);
+        slots.draw(canvas, skin);
+        self.list.draw(canvas, font, &slots, 0, true);
+    }
+}
@@ -0,0 +1,116 @@
+//! The skills screen, where the player looks over each party member's skills. Skills are only
+//! used in battle.
+
+use battle::database::Database;
+use engine::Engine;
+use game_state::member::PartyMember;
+use input::Action;
+use scene::menu::{describe_vitals, draw_backdrop, MARGIN};
+use scene::{Scene, Transition};
+use ui::list::{self, List, ListItem};
+use ui::window::Window;
+use ui::{HIGHLIGHT_COLOR, TEXT_COLOR};
+
+/// How many skills are listed at a time.
+pub const SKILL_ROWS: usize = 12;
+
+/// List a member's skills, with what each costs. Those they haven't the MP for are greyed out.
+pub fn get_skill_items(member: &PartyMember, database: &Database) -> Vec<ListItem> {
+    member
+        .skills
+        .iter()
+        .map(|skill| match database.skill(skill) {
+            Some(definition) => ListItem::enabled_if(
+                format!("{}  {} MP", definition.name, definition.mp_cost),
+                member.mp >= definition.mp_cost,
+            ),
+            None => ListItem::enabled_if(skill.clone(), false),
+        })
+        .collect()
+}
+
+/// Shows each party member's skills in turn; Left and Right switch between members.
+pub struct SkillsScene {
+    members: Vec<String>,
+    member: usize,
+    list: List,
+}
+
+impl SkillsScene {
+    /// Open the screen for a party, starting with its first member.
+    pub fn new(engine: &Engine, members: Vec<String>) -> SkillsScene {
+        let mut scene = SkillsScene {
+            members: members,
+            member: 0,
+            list: List::new(Vec::new(), SKILL_ROWS),
+        };
+        scene.show_member(engine, 0);
+        scene
+    }
+
+    // Show the skills of the member `delta` places on from the one being shown.
+    fn show_member(&mut self, engine: &Engine, delta: i32) {
+        self.member = list::wrap(self.member, delta, self.members.len());
+        let state = engine.game_state.borrow();
+        let items = match self.members.get(self.member).and_then(|c| state.member(c)) {
+            Some(member) => get_skill_items(member, &engine.database),
+            None => Vec::new(),
+        };
+        self.list = List::new(items, SKILL_ROWS);
+    }
+}
+
+impl Scene for SkillsScene {
+    fn on_action(&mut self, engine: &mut Engine, action: Action) -> Transition {
+        match action {
+            Action::Up => self.list.move_selection(-1),
+            Action::Down => self.list.move_selection(1),
+            Action::Left => self.show_member(engine, -1),
+            Action::Right => self.show_member(engine, 1),
+            Action::Cancel | Action::Menu => return Transition::Pop,
+            Action::Confirm => {}
+        }
+        Transition::Stay
+    }
+
+    fn draw(&mut self, engine: &mut Engine) {
+        draw_backdrop(engine);
+        let size = engine.display.render_size();
+        let width = size.width.saturating_sub(MARGIN * 2);
+        let header = Window::with_rows(MARGIN, MARGIN, width, 2);
+        let skills = Window::with_rows(
+            MARGIN,
+            header.rect.max_y() + MARGIN,
+            width,
+            SKILL_ROWS as u32,
+        );
+        let description = Window::with_rows(MARGIN, skills.rect.max_y() + MARGIN, width, 1);
+
+        let (name, vitals, skill_description) = {
+            let state = engine.game_state.borrow();
+            match self.members.get(self.member).and_then(|c| state.member(c)) {
+                Some(member) => (
+                    format!("{}  Lv {}", member.name, member.level),
+                    describe_vitals(member, &engine.database),
+                    self.list
+                        .selected()
+                        .and_then(|index| member.skills.get(index))
+                        .and_then(|skill| engine.database.skill(skill))
+                        .map_or(String::new(), |skill| skill.description.clone()),
+                ),
+                None => (String::new(), String::new(), String::new()),
+            }
+        };
+
+        let canvas = &mut engine.display.canvas;
+        let font = &engine.font.font;
+        let skin = engine.windowskin.as_ref();
+        header.draw(canvas, skin);
+        header.draw_text(canvas, font, &name, (0, 0), &HIGHLIGHT_COLOR);
+        header.draw_text(canvas, font, &vitals, (0, 1), &TEXT_COLOR);
+        skills.draw(canvas, skin);
+        self.list.draw(canvas, font, &skills, 0, true);
+        description.draw(canvas, skin);
+        description.draw_text(canvas, font, &skill_description, (0, 0), &TEXT_COLOR);
+    }
+}
@@ -0,0 +1,98 @@
+//! The status screen, where the player looks over a party member's level, stats and equipment.
+
+use battle::database::{Database, EquipSlot};
+use engine::Engine;
+use game_state::member::PartyMember;
+use input::Action;
+use scene::menu::{describe_vitals, draw_backdrop, MARGIN};
+use scene::{Scene, Transition};
+use ui::list;
+use ui::window::Window;
+use ui::{HIGHLIGHT_COLOR, TEXT_COLOR};
+
+/// The lines of a member's status: their level and EXP, HP and MP, stats with their equipment,
+/// and what they're wearing in each slot.
+pub fn get_status_lines(member: &PartyMember, database: &Database) -> Vec<String> {
+    let stats = member.stats(&member.equipment_bonuses(database));
+    let next_level = match member.exp_to_next_level() {
+        Some(exp) => exp.to_string(),
+        None => String::from("-"),
+    };
+    let mut lines = vec![
+        format!("{}  Lv {}", member.name, member.level),
+        format!("EXP {}  Next {}", member.exp, next_level),
+        describe_vitals(member, database),
+        String::new(),
+        format!("ATK {}  DEF {}", stats.attack, stats.defense),
+        format!("MAG {}  SPD {}", stats.magic, stats.speed),
+        String::new(),
+    ];
+    for slot in EquipSlot::ALL.iter() {
+        let item = match member.equipment.get(slot) {
+            Some(item) => database.item_name(item),
+            None => String::from("-"),
+        };
+        lines.push(format!("{}: {}", slot.label(), item));
+    }
+    lines
+}
+
+/// Shows each party member's status in turn; Left and Right switch between members.
+pub struct StatusScene {
+    members: Vec<String>,
+    member: usize,
+}
+
+impl StatusScene {
+    /// Open the screen for a party, starting with its first member.
+    pub fn new(members: Vec<String>) -> StatusScene {
+        StatusScene {
+            members: members,
+            member: 0,
+        }
+    }
+}
+
+impl Scene for StatusScene {
+    fn on_action(&mut self, _engine: &mut Engine, action: Action) -> Transition {
+        match action {
+            Action::Left | Action::Up => {
+                self.member = list::wrap(self.member, -1, self.members.len())
+            }
+            Action::Right | Action::Down => {
+                self.member = list::wrap(self.member, 1, self.members.len())
+            }
+            Action::Cancel | Action::Menu | Action::Confirm => return Transition::Pop,
+        }
+        Transition::Stay
+    }
+
+    fn draw(&mut self, engine: &mut Engine) {
+        draw_backdrop(engine);
+        let size = engine.display.render_size();
+        let lines = {
+            let state = engine.game_state.borrow();
+            let member = self.members.get(self.member).and_then(|c| state.member(c));
+            member.map_or(Vec::new(), |member| get_status_lines(member, &engine.database))
+        };
+        let window = Window::with_rows(
+            MARGIN,
+            MARGIN,
+            size.width.saturating_sub(MARGIN * 2),
+            lines.len() as u32,
+        );
+
+        let canvas = &mut engine.display.canvas;
+        let font = &engine.font.font;
+        let skin = engine.windowskin.as_ref();
+        window.draw(canvas, skin);
+        for (row, line) in lines.iter().enumerate() {
+            let color = if row == 0 {
+                &HIGHLIGHT_COLOR
+            } else {
+                &TEXT_COLOR
+            };
+            window.draw_text(canvas, font, line, (0, row as u32), color);
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use battle::combatant::StatBonus;
+use battle::database::{Database, EquipSlot};
+use battle::test_support::item_database;
+use game_state::GameState;
+use scene::menu::equip::*;
+
+// The shared items, and a party of one carrying a sword.
+fn setup() -> (Database, GameState) {
+    let database = item_database();
+    let mut state = GameState::new();
+    state.join("player").unwrap();
+    state.give_item("sword", 1, &database).unwrap();
+    (database, state)
+}
+
+#[test]
+// Equipping should go through choosing the slot and the item, comparing it before it's worn.
+fn test_equip_through_menu() {
+    let (database, mut state) = setup();
+    let mut menu = EquipMenu::new(vec![String::from("player")], &state, &database);
+    assert_eq!(menu.character(), Some("player"));
+    assert_eq!(menu.selected_slot(), Some(EquipSlot::Weapon));
+    assert_eq!(menu.slots().items()[0].text, "Weapon: -");
+
+    menu.confirm(&mut state, &database);
+    let choices: Vec<_> = menu.choices()
+        .unwrap()
+        .items()
+        .iter()
+        .map(|item| (item.text.clone(), item.enabled))
+        .collect();
+    assert_eq!(
+        choices,
+        vec![
+            (String::from(REMOVE_CHOICE), false),
+            (String::from("Sword x1"), true),
+        ]
+    );
+    // There's nothing to take off.
+    menu.confirm(&mut state, &database);
+    assert!(menu.choices().is_some());
+    assert_eq!(menu.comparison(&state, &database), None);
+
+    menu.move_selection(1);
+    assert_eq!(menu.selected_choice(), Some("sword"));
+    assert_eq!(
+        menu.comparison(&state, &database),
+        Some(StatBonus {
+            attack: 5,
+            ..StatBonus::default()
+        })
+    );
+    menu.confirm(&mut state, &database);
+    assert!(menu.choices().is_none());
+    assert_eq!(menu.slots().items()[0].text, "Weapon: Sword");
+    assert_eq!(state.inventory.count("sword"), 0);
+}
+
+#[test]
+// Removing should put the item back with the party's, and backing out should close the list.
+fn test_remove_through_menu() {
+    let (database, mut state) = setup();
+    state.equip("player", "sword", &database).unwrap();
+    let mut menu = EquipMenu::new(vec![String::from("player")], &state, &database);
+    menu.confirm(&mut state, &database);
+    assert!(menu.choices().unwrap().is_selected_enabled());
+    assert!(menu.cancel());
+    assert!(menu.choices().is_none());
+
+    menu.confirm(&mut state, &database);
+    menu.confirm(&mut state, &database);
+    assert_eq!(menu.slots().items()[0].text, "Weapon: -");
+    assert_eq!(state.inventory.count("sword"), 1);
+    assert!(!menu.cancel());
+}
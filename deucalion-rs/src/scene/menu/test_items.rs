@@ -0,0 +1,49 @@
+use battle::database::Database;
+use battle::test_support::item_database;
+use game_state::GameState;
+use scene::menu::items::*;
+
+// The shared items, and a party of one carrying a key and two potions.
+fn setup() -> (Database, GameState) {
+    let database = item_database();
+    let mut state = GameState::new();
+    state.join("player").unwrap();
+    state.give_item("key", 1, &database).unwrap();
+    state.give_item("potion", 2, &database).unwrap();
+    (database, state)
+}
+
+#[test]
+// Using a potion should go through choosing it and who to use it on, and only work if it helps.
+fn test_use_item_through_menu() {
+    let (database, mut state) = setup();
+    let mut menu = ItemsMenu::new(vec![String::from("player")], &state, &database);
+    assert_eq!(menu.items().items().len(), 2);
+    assert_eq!(menu.selected_item(), Some("key"));
+
+    // Keys can't be used.
+    menu.confirm(&mut state, &database);
+    assert!(menu.targets().is_none());
+
+    menu.move_selection(1);
+    assert_eq!(menu.selected_item(), Some("potion"));
+    menu.confirm(&mut state, &database);
+    assert!(menu.targets().is_some());
+    menu.confirm(&mut state, &database);
+    assert_eq!(menu.message(), Some("It would have no effect."));
+    assert_eq!(state.inventory.count("potion"), 2);
+
+    let max_hp = state.member("player").unwrap().base_stats().max_hp;
+    state.member_mut("player").unwrap().hp = max_hp - 30;
+    menu.confirm(&mut state, &database);
+    let name = state.member("player").unwrap().name.clone();
+    assert_eq!(menu.message(), Some(&*format!("Used a Potion on {}.", name)));
+    assert_eq!(state.member("player").unwrap().hp, max_hp - 10);
+    assert_eq!(menu.items().items()[1].text, "Potion x1");
+
+    // Using the last one should go back to the list of items, which no longer has it.
+    menu.confirm(&mut state, &database);
+    assert!(menu.targets().is_none());
+    assert_eq!(menu.items().items().len(), 1);
+    assert!(!menu.cancel());
+}
@@ -0,0 +1,52 @@
+use hlua::AnyLuaValue;
+
+use error::DeucalionError;
+use scene::menu::*;
+use scripting::budget::ScriptBudget;
+use scripting::sandbox::{get_default_script_libraries, ScriptEnvironments, ScriptOwner};
+
+// Run a script in the game's environment and read the commands it returns.
+fn parse(script: &str) -> Result<Vec<MenuCommand>, DeucalionError> {
+    let mut environments =
+        ScriptEnvironments::new(&get_default_script_libraries(), &[], ScriptBudget::unlimited());
+    let value: AnyLuaValue = environments
+        .get(&ScriptOwner::Game)
+        .execute(script)
+        .unwrap();
+    parse_menu_commands(value)
+}
+
+#[test]
+// Every default command should be known by its name.
+fn test_default_names() {
+    for command in MenuCommand::defaults() {
+        let name = command.name().unwrap();
+        assert_eq!(MenuCommand::from_name(name), Some(command));
+    }
+    assert_eq!(MenuCommand::from_name("bestiary"), None);
+}
+
+#[test]
+// Commands should be read in order, by name or as the game's own.
+fn test_parse_menu_commands() {
+    let bestiary = MenuCommand::Script {
+        label: String::from("Bestiary"),
+        function: String::from("open_bestiary"),
+    };
+    assert_eq!(
+        parse("return {'status', {label = 'Bestiary', call = 'open_bestiary'}, 'quit'}").unwrap(),
+        vec![MenuCommand::Status, bestiary.clone(), MenuCommand::Quit]
+    );
+    assert_eq!(bestiary.label(), "Bestiary");
+    assert_eq!(bestiary.name(), None);
+}
+
+#[test]
+// Unknown names, incomplete tables and anything else should be rejected.
+fn test_parse_bad_menu_commands() {
+    assert!(parse("return 'items'").is_err());
+    assert!(parse("return {'items', 'bestiary'}").is_err());
+    assert!(parse("return {{label = 'Bestiary'}}").is_err());
+    assert!(parse("return {{call = 'open_bestiary'}}").is_err());
+    assert!(parse("return {'items', 5}").is_err());
+}
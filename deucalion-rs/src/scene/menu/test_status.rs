@@ -0,0 +1,23 @@
+use battle::database::EquipSlot;
+use battle::test_support::item_database;
+use game_state::GameState;
+use scene::menu::status::*;
+
+#[test]
+// The status should show the member's level, vitals and stats, and name what they're wearing.
+fn test_status_lines() {
+    let database = item_database();
+    let mut state = GameState::new();
+    state.join("player").unwrap();
+    state.give_item("sword", 1, &database).unwrap();
+    state.equip("player", "sword", &database).unwrap();
+
+    let member = state.member("player").unwrap();
+    let lines = get_status_lines(member, &database);
+    assert_eq!(lines[0], format!("{}  Lv {}", member.name, member.level));
+    assert!(lines[2].starts_with(&format!("HP {}/", member.hp)));
+    assert_eq!(lines.len(), 7 + EquipSlot::ALL.len());
+    assert!(lines.contains(&String::from("Weapon: Sword")));
+    let empty = lines.iter().filter(|line| line.ends_with(": -")).count();
+    assert_eq!(empty, EquipSlot::ALL.len() - 1);
+}
@@ -0,0 +1,156 @@
+//! Scenes: the separate screens of the game, such as the map, menus and battles. Scenes are kept
+//! on a stack; only the top one receives input, and scenes can push new scenes on top of
+//! themselves or remove themselves when they are done.
+
+use std::time::Instant;
+
+use engine::Engine;
+use input::Action;
+
+pub mod battle;
+pub mod game_over;
+pub mod layout;
+pub mod map;
+pub mod menu;
+pub mod options;
+pub mod screen;
+pub mod shop;
+pub mod title;
+#[cfg(test)]
+mod test_battle;
+#[cfg(test)]
+mod test_options;
+#[cfg(test)]
+mod test_shop;
+#[cfg(test)]
+mod test_title;
+
+// The longest frame that's simulated in one go; after a longer pause, such as the window being
+//  dragged, scenes carry on as if less time had passed rather than jumping ahead.
+const MAXIMUM_FRAME_TIME: f32 = 0.1;
+
+/// Work out how many seconds have passed since a scene's last update, which is then set to now.
+/// The first update takes no time.
+pub fn get_frame_delta(last_update: &mut Option<Instant>) -> f32 {
+    let now = Instant::now();
+    let delta = match *last_update {
+        Some(last) => {
+            let elapsed = now.duration_since(last);
+            (elapsed.as_secs() as f32 + elapsed.subsec_nanos() as f32 * 1e-9)
+                .min(MAXIMUM_FRAME_TIME)
+        }
+        None => 0.0,
+    };
+    *last_update = Some(now);
+    delta
+}
+
+/// What should happen to the scene stack after a scene has handled something.
+pub enum Transition {
+    /// Nothing; the current scene stays on top.
+    Stay,
+    /// Put a new scene on top of the current one.
+    Push(Box<Scene>),
+    /// Remove the current scene, returning to the one below it.
+    Pop,
+    /// Replace the current scene with a new one.
+    Replace(Box<Scene>),
+    /// Remove every scene, leaving a new one on its own, such as when the game ends.
+    Reset(Box<Scene>),
+    /// Quit the game.
+    Quit,
+}
+
+/// A screen of the game.
+pub trait Scene {
+    /// Respond to an input action. Only the top scene receives actions.
+    fn on_action(&mut self, engine: &mut Engine, action: Action) -> Transition;
+
+    /// Advance the scene by one frame. Only the top scene is updated.
+    fn update(&mut self, _engine: &mut Engine) -> Transition {
+        Transition::Stay
+    }
+
+    /// Draw the scene onto the display's canvas.
+    fn draw(&mut self, engine: &mut Engine);
+
+    /// Whether the scene is drawn over the scene below it, rather than hiding it completely.
+    fn is_overlay(&self) -> bool {
+        false
+    }
+}
+
+/// The stack of active scenes.
+pub struct SceneStack {
+    scenes: Vec<Box<Scene>>,
+}
+
+impl SceneStack {
+    /// Create a stack with a single, initial scene.
+    pub fn new(initial: Box<Scene>) -> SceneStack {
+        SceneStack {
+            scenes: vec![initial],
+        }
+    }
+
+    /// Whether every scene has been removed, meaning the game is over.
+    pub fn is_empty(&self) -> bool {
+        self.scenes.is_empty()
+    }
+
+    /// The number of scenes on the stack.
+    pub fn len(&self) -> usize {
+        self.scenes.len()
+    }
+
+    /// Pass an action to the top scene.
+    pub fn on_action(&mut self, engine: &mut Engine, action: Action) {
+        let transition = match self.scenes.last_mut() {
+            Some(scene) => scene.on_action(engine, action),
+            None => return,
+        };
+        self.apply(transition);
+    }
+
+    /// Update the top scene.
+    pub fn update(&mut self, engine: &mut Engine) {
+        let transition = match self.scenes.last_mut() {
+            Some(scene) => scene.update(engine),
+            None => return,
+        };
+        self.apply(transition);
+    }
+
+    /// Draw the top scene, along with any scenes visible underneath it.
+    pub fn draw(&mut self, engine: &mut Engine) {
+        // Start from the topmost scene that covers everything below it.
+        let first_visible = self.scenes
+            .iter()
+            .rposition(|scene| !scene.is_overlay())
+            .unwrap_or(0);
+        for scene in self.scenes[first_visible..].iter_mut() {
+            scene.draw(engine);
+        }
+    }
+
+    /// Carry out a transition.
+    pub fn apply(&mut self, transition: Transition) {
+        match transition {
+            Transition::Stay => {}
+            Transition::Push(scene) => self.scenes.push(scene),
+            Transition::Pop => {
+                self.scenes.pop();
+            }
+            Transition::Replace(scene) => {
+                self.scenes.pop();
+                self.scenes.push(scene);
+            }
+            Transition::Reset(scene) => {
+                self.scenes.clear();
+                self.scenes.push(scene);
+            }
+            Transition::Quit => self.scenes.clear(),
+        }
+        trace!("The scene stack is now {} deep", self.scenes.len());
+    }
+}
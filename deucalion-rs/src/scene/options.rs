@@ -0,0 +1,266 @@
+//! The options screen, where the player edits the engine configuration.
+
+use sfml::graphics::{Color, RectangleShape, RenderTarget, Shape, Text, Transformable};
+use sfml::system::Vector2f;
+
+use config::engine_config::{EngineConfig, ScalingMode, WindowMode};
+use engine::Engine;
+use input::Action;
+use scene::{Scene, Transition};
+
+/// The window resolutions offered by the options screen.
+pub const RESOLUTIONS: [(u32, u32); 7] = [
+    (640, 480),
+    (800, 600),
+    (1024, 768),
+    (1280, 720),
+    (1280, 960),
+    (1600, 900),
+    (1920, 1080),
+];
+
+/// How much a volume changes with each press.
+pub const VOLUME_STEP: u32 = 10;
+
+/// An entry on the options screen.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum OptionsEntry {
+    WindowMode,
+    Resolution,
+    Vsync,
+    ScalingMode,
+    MasterVolume,
+    MusicVolume,
+    SfxVolume,
+    Save,
+    Back,
+}
+
+impl OptionsEntry {
+    /// Every entry, in the order they are shown.
+    pub fn all() -> &'static [OptionsEntry] {
+        static ALL: [OptionsEntry; 9] = [
+            OptionsEntry::WindowMode,
+            OptionsEntry::Resolution,
+            OptionsEntry::Vsync,
+            OptionsEntry::ScalingMode,
+            OptionsEntry::MasterVolume,
+            OptionsEntry::MusicVolume,
+            OptionsEntry::SfxVolume,
+            OptionsEntry::Save,
+            OptionsEntry::Back,
+        ];
+        &ALL
+    }
+
+    /// The text shown for the entry.
+    pub fn label(self) -> &'static str {
+        match self {
+            OptionsEntry::WindowMode => "Window",
+            OptionsEntry::Resolution => "Resolution",
+            OptionsEntry::Vsync => "VSync",
+            OptionsEntry::ScalingMode => "Scaling",
+            OptionsEntry::MasterVolume => "Master Volume",
+            OptionsEntry::MusicVolume => "Music Volume",
+            OptionsEntry::SfxVolume => "Sound Effects Volume",
+            OptionsEntry::Save => "Save",
+            OptionsEntry::Back => "Back",
+        }
+    }
+}
+
+/// The state of the options screen, separate from drawing it: a working copy of the engine
+/// configuration, and which entry is selected.
+#[derive(Debug, Clone)]
+pub struct OptionsMenu {
+    pub config: EngineConfig,
+    selected: usize,
+}
+
+impl OptionsMenu {
+    /// Start editing a copy of the given configuration.
+    pub fn new(config: &EngineConfig) -> OptionsMenu {
+        OptionsMenu {
+            config: config.clone(),
+            selected: 0,
+        }
+    }
+
+    /// The currently selected entry.
+    pub fn selected(&self) -> OptionsEntry {
+        OptionsEntry::all()[self.selected]
+    }
+
+    /// Move the selection up (negative) or down (positive), wrapping around.
+    pub fn move_selection(&mut self, delta: i32) {
+        let count = OptionsEntry::all().len() as i32;
+        self.selected = ((self.selected as i32 + delta) % count + count) as usize % count as usize;
+    }
+
+    /// Change the selected entry's value to the previous (negative) or next (positive) one.
+    /// Returns whether the configuration changed.
+    pub fn adjust(&mut self, delta: i32) -> bool {
+        let before = self.config.clone();
+        match self.selected() {
+            OptionsEntry::WindowMode => {
+                let modes = [
+                    WindowMode::Windowed,
+                    WindowMode::Fullscreen,
+                    WindowMode::Borderless,
+                ];
+                self.config.window_mode = cycle(&modes, self.config.window_mode, delta);
+            }
+            OptionsEntry::Resolution => {
+                let current = (self.config.screen_width, self.config.screen_height);
+                let (width, height) = cycle(&RESOLUTIONS, current, delta);
+                self.config.screen_width = width;
+                self.config.screen_height = height;
+            }
+            OptionsEntry::Vsync => self.config.vsync = !self.config.vsync,
+            OptionsEntry::ScalingMode => {
+                let modes = [
+                    ScalingMode::Integer,
+                    ScalingMode::Letterbox,
+                    ScalingMode::Stretch,
+                ];
+                self.config.scaling_mode = cycle(&modes, self.config.scaling_mode, delta);
+            }
+            OptionsEntry::MasterVolume => {
+                self.config.master_volume = step_volume(self.config.master_volume, delta)
+            }
+            OptionsEntry::MusicVolume => {
+                self.config.music_volume = step_volume(self.config.music_volume, delta)
+            }
+            OptionsEntry::SfxVolume => {
+                self.config.sfx_volume = step_volume(self.config.sfx_volume, delta)
+            }
+            OptionsEntry::Save | OptionsEntry::Back => {}
+        }
+        self.config != before
+    }
+
+    /// The text describing an entry's current value, if it has one.
+    pub fn value_text(&self, entry: OptionsEntry) -> Option<String> {
+        match entry {
+            OptionsEntry::WindowMode => Some(String::from(self.config.window_mode.name())),
+            OptionsEntry::Resolution => Some(format!(
+                "{}x{}",
+                self.config.screen_width, self.config.screen_height
+            )),
+            OptionsEntry::Vsync => Some(String::from(if self.config.vsync { "on" } else { "off" })),
+            OptionsEntry::ScalingMode => Some(String::from(self.config.scaling_mode.name())),
+            OptionsEntry::MasterVolume => Some(format!("{}", self.config.master_volume)),
+            OptionsEntry::MusicVolume => Some(format!("{}", self.config.music_volume)),
+            OptionsEntry::SfxVolume => Some(format!("{}", self.config.sfx_volume)),
+            OptionsEntry::Save | OptionsEntry::Back => None,
+        }
+    }
+}
+
+/// Get the value `delta` places away from `current` in `values`, wrapping around. A current value
+/// that isn't in the list (e.g. a hand-configured resolution) is treated as coming before the
+/// first one.
+fn cycle<T: PartialEq + Copy>(values: &[T], current: T, delta: i32) -> T {
+    let count = values.len() as i32;
+    let index = match values.iter().position(|v| *v == current) {
+        Some(i) => i as i32 + delta,
+        None if delta > 0 => delta - 1,
+        None => count + delta,
+    };
+    values[((index % count + count) % count) as usize]
+}
+
+/// Step a volume up or down, staying within 0 to 100.
+fn step_volume(volume: u32, delta: i32) -> u32 {
+    let stepped = volume as i32 + delta * VOLUME_STEP as i32;
+    if stepped < 0 {
+        0
+    } else if stepped > 100 {
+        100
+    } else {
+        stepped as u32
+    }
+}
+
+/// Shows the options menu over whatever scene opened it.
+pub struct OptionsScene {
+    menu: OptionsMenu,
+}
+
+impl OptionsScene {
+    /// Open the options menu on the engine's current configuration.
+    pub fn new(engine: &Engine) -> OptionsScene {
+        OptionsScene {
+            menu: OptionsMenu::new(&engine.engine_config),
+        }
+    }
+
+    /// Apply the working configuration to the running engine.
+    fn apply(&self, engine: &mut Engine) {
+        if let Err(e) = engine.apply_engine_config(self.menu.config.clone()) {
+            error!("Failed to apply the new options: {}", e);
+        }
+    }
+}
+
+impl Scene for OptionsScene {
+    fn on_action(&mut self, engine: &mut Engine, action: Action) -> Transition {
+        match action {
+            Action::Up => self.menu.move_selection(-1),
+            Action::Down => self.menu.move_selection(1),
+            Action::Left | Action::Right | Action::Confirm => match self.menu.selected() {
+                OptionsEntry::Save if action == Action::Confirm => {
+                    if let Err(e) = engine.save_engine_config() {
+                        error!("Failed to save the options: {}", e);
+                    }
+                    return Transition::Pop;
+                }
+                OptionsEntry::Back if action == Action::Confirm => return Transition::Pop,
+                _ => {
+                    let delta = if action == Action::Left { -1 } else { 1 };
+                    if self.menu.adjust(delta) {
+                        // Changes take effect immediately, but are only kept for next time if
+                        // the player saves them.
+                        self.apply(engine);
+                    }
+                }
+            },
+            Action::Cancel | Action::Menu => return Transition::Pop,
+        }
+        Transition::Stay
+    }
+
+    fn draw(&mut self, engine: &mut Engine) {
+        let size = engine.display.render_size();
+        let canvas = &mut engine.display.canvas;
+
+        // Dim whatever is underneath.
+        let mut shade = RectangleShape::with_size(Vector2f::new(
+            size.width as f32,
+            size.height as f32,
+        ));
+        shade.set_fill_color(&Color::rgba(0, 0, 0, 192));
+        canvas.draw(&shade);
+
+        let line_height = 24.0;
+        let top = (size.height as f32 - line_height * OptionsEntry::all().len() as f32) / 2.0;
+        for (i, entry) in OptionsEntry::all().iter().enumerate() {
+            let line = match self.menu.value_text(*entry) {
+                Some(value) => format!("{}: {}", entry.label(), value),
+                None => String::from(entry.label()),
+            };
+            let mut text = Text::new(&line, &engine.font.font, 18);
+            text.set_position((32.0, top + line_height * i as f32));
+            if *entry == self.menu.selected() {
+                text.set_fill_color(&Color::YELLOW);
+            } else {
+                text.set_fill_color(&Color::WHITE);
+            }
+            canvas.draw(&text);
+        }
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
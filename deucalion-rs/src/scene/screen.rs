@@ -0,0 +1,59 @@
+//! Drawing shared by the screens shown around the game itself, such as the title and game over
+//! screens: a background picture from the game config, and headings over it.
+
+use sfml::graphics::{Font, RenderTarget, Sprite, Text, Texture, Transformable};
+
+use engine::Engine;
+use resource::picture::load_picture;
+use scene::menu::draw_backdrop;
+use ui::HIGHLIGHT_COLOR;
+
+/// How wide the windows on these screens are.
+pub const SCREEN_WINDOW_WIDTH: u32 = 160;
+
+/// How big headings, such as the game's title, are drawn.
+pub const HEADING_FONT_SIZE: u32 = 32;
+
+/// Load the picture a screen such as the title is drawn over, by name, if it has one. If it
+/// can't be loaded, it's reported, and the screen has a plain background.
+pub fn load_background(engine: &mut Engine, name: &str) -> Option<Texture> {
+    if name.is_empty() {
+        return None;
+    }
+    match load_picture(name) {
+        Ok(texture) => Some(texture),
+        Err(e) => {
+            engine.report_error(&format!("Failed to load the background '{}'", name), e);
+            None
+        }
+    }
+}
+
+/// Cover the screen with a background picture, stretched to fit, or a plain backdrop.
+pub fn draw_background(engine: &mut Engine, background: Option<&Texture>) {
+    let texture = match background {
+        Some(texture) => texture,
+        None => {
+            draw_backdrop(engine);
+            return;
+        }
+    };
+    let size = engine.display.render_size();
+    let texture_size = texture.size();
+    let mut sprite = Sprite::with_texture(texture);
+    sprite.set_scale((
+        size.width as f32 / texture_size.x.max(1) as f32,
+        size.height as f32 / texture_size.y.max(1) as f32,
+    ));
+    engine.display.canvas.draw(&sprite);
+}
+
+/// Draw a heading, such as the game's title, centred across a screen `width` wide, `y` pixels
+/// from the top.
+pub fn draw_heading<T: RenderTarget>(target: &mut T, font: &Font, line: &str, width: u32, y: f32) {
+    let mut text = Text::new(line, font, HEADING_FONT_SIZE);
+    let bounds = text.local_bounds();
+    text.set_position(((width as f32 - bounds.width) / 2.0, y));
+    text.set_fill_color(&HIGHLIGHT_COLOR);
+    target.draw(&text);
+}
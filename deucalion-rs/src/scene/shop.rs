@@ -0,0 +1,509 @@
+//! The shop scene, where the party buys and sells items. What a shop sells, and the rules for
+//! trading with it, are in `game_state::shop`; this scene lets the player choose what to trade
+//! and how many, and shows how each piece of equipment would change each party member's stats.
+
+use sfml::graphics::{Color, RectangleShape, RenderTarget, Shape};
+use sfml::system::Vector2f;
+
+use battle::combatant::StatBonus;
+use battle::database::Database;
+use engine::Engine;
+use game_state::shop::{self, Shop};
+use game_state::GameState;
+use input::Action;
+use scene::menu::MARGIN;
+use scene::{Scene, Transition};
+use ui::list::{self, List, ListItem};
+use ui::window::Window;
+use ui::{HIGHLIGHT_COLOR, TEXT_COLOR};
+
+/// How much Up and Down change the number of items to trade, where Left and Right change it by
+/// one.
+pub const QUANTITY_STEP: i32 = 10;
+
+/// How many choices are listed at a time.
+pub const CHOICE_ROWS: usize = 8;
+
+/// A command on the shop's first menu.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum ShopCommand {
+    Buy,
+    Sell,
+    Leave,
+}
+
+impl ShopCommand {
+    /// Every command, in the order they are shown.
+    pub fn all() -> &'static [ShopCommand] {
+        static ALL: [ShopCommand; 3] = [ShopCommand::Buy, ShopCommand::Sell, ShopCommand::Leave];
+        &ALL
+    }
+
+    /// Where the command is in the menu.
+    pub fn index(self) -> usize {
+        ShopCommand::all()
+            .iter()
+            .position(|&command| command == self)
+            .unwrap_or(0)
+    }
+
+    /// The text shown for the command.
+    pub fn label(self) -> &'static str {
+        match self {
+            ShopCommand::Buy => "Buy",
+            ShopCommand::Sell => "Sell",
+            ShopCommand::Leave => "Leave",
+        }
+    }
+}
+
+/// A trade the player is choosing the size of.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Trade {
+    /// Buying the shop's goods, by index.
+    Buy(usize),
+    /// Selling one of the party's items, by name.
+    Sell(String),
+}
+
+/// What the player is choosing.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum ShopStep {
+    /// Whether to buy, sell or leave.
+    Command,
+    /// Which of the shop's goods to buy.
+    Buy,
+    /// Which of the party's items to sell.
+    Sell,
+    /// How many to trade.
+    Quantity(Trade),
+}
+
+/// The state of the shop menu, separate from drawing it: what's being chosen, which choice is
+/// selected, and how many to trade.
+#[derive(Debug, Clone)]
+pub struct ShopMenu {
+    step: ShopStep,
+    // The selected choice. While the quantity is chosen, it's still the item being traded.
+    selected: usize,
+    quantity: u32,
+    message: Option<String>,
+}
+
+impl ShopMenu {
+    /// Start choosing whether to buy or sell.
+    pub fn new() -> ShopMenu {
+        ShopMenu::default()
+    }
+
+    /// What's being chosen.
+    pub fn step(&self) -> &ShopStep {
+        &self.step
+    }
+
+    /// The index of the selected choice.
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// How many items to trade.
+    pub fn quantity(&self) -> u32 {
+        self.quantity
+    }
+
+    /// What happened when the player last tried to trade, such as "Bought 2 Potion.".
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_ref().map(String::as_str)
+    }
+
+    /// The text of each choice at the current step. There are none while choosing how many.
+    pub fn choices(&self, shop: &Shop, state: &GameState, database: &Database) -> Vec<String> {
+        match self.step {
+            ShopStep::Command => ShopCommand::all()
+                .iter()
+                .map(|command| String::from(command.label()))
+                .collect(),
+            ShopStep::Buy => shop.goods
+                .iter()
+                .map(|goods| format!("{}  {}G", database.item_name(&goods.item), goods.price))
+                .collect(),
+            ShopStep::Sell => get_sellable_items(state, database)
+                .iter()
+                .map(|&(ref item, count, price)| {
+                    format!("{} x{}  {}G", database.item_name(item), count, price)
+                })
+                .collect(),
+            ShopStep::Quantity(_) => Vec::new(),
+        }
+    }
+
+    /// The choices at the current step as a list to draw, with the selected one selected.
+    pub fn list(&self, shop: &Shop, state: &GameState, database: &Database) -> List {
+        let items = self.choices(shop, state, database)
+            .into_iter()
+            .map(ListItem::new)
+            .collect();
+        let mut list = List::new(items, CHOICE_ROWS);
+        list.select(self.selected);
+        list
+    }
+
+    /// Move the selection up (negative) or down (positive), wrapping around.
+    pub fn move_selection(
+        &mut self,
+        delta: i32,
+        shop: &Shop,
+        state: &GameState,
+        database: &Database,
+    ) {
+        let count = self.choices(shop, state, database).len();
+        self.selected = list::wrap(self.selected, delta, count);
+    }
+
+    /// Change how many items to trade by `delta`, keeping it from 1 to as many as can be traded.
+    pub fn adjust_quantity(
+        &mut self,
+        delta: i32,
+        shop: &Shop,
+        state: &GameState,
+        database: &Database,
+    ) {
+        let most = match self.step {
+            ShopStep::Quantity(ref trade) => get_most_tradable(trade, shop, state, database),
+            _ => return,
+        };
+        let quantity = i64::from(self.quantity) + i64::from(delta);
+        self.quantity = quantity.min(i64::from(most)).max(1) as u32;
+    }
+
+    /// The item that's selected to buy or sell, by name, if there is one.
+    pub fn selected_item(
+        &self,
+        shop: &Shop,
+        state: &GameState,
+        database: &Database,
+    ) -> Option<String> {
+        match self.step {
+            ShopStep::Command => None,
+            ShopStep::Buy => shop.goods.get(self.selected).map(|goods| goods.item.clone()),
+            ShopStep::Sell => get_sellable_items(state, database)
+                .into_iter()
+                .nth(self.selected)
+                .map(|(item, _, _)| item),
+            ShopStep::Quantity(Trade::Buy(index)) => {
+                shop.goods.get(index).map(|goods| goods.item.clone())
+            }
+            ShopStep::Quantity(Trade::Sell(ref item)) => Some(item.clone()),
+        }
+    }
+
+    /// Choose the selected choice, or make the trade once its size has been chosen. Returns
+    /// false if the player has chosen to leave.
+    pub fn confirm(&mut self, shop: &Shop, state: &mut GameState, database: &Database) -> bool {
+        let next = match self.step {
+            ShopStep::Command => {
+                let next = match ShopCommand::all()[self.selected] {
+                    ShopCommand::Buy => ShopStep::Buy,
+                    ShopCommand::Sell => ShopStep::Sell,
+                    ShopCommand::Leave => return false,
+                };
+                self.selected = 0;
+                next
+            }
+            ShopStep::Buy => {
+                let goods = match shop.goods.get(self.selected) {
+                    Some(goods) => goods,
+                    None => return true,
+                };
+                if shop.most_buyable(self.selected, state, database) == 0 {
+                    self.message = Some(String::from(if state.gold < goods.price {
+                        "Not enough gold."
+                    } else {
+                        "The party can't carry any more."
+                    }));
+                    return true;
+                }
+                self.quantity = 1;
+                ShopStep::Quantity(Trade::Buy(self.selected))
+            }
+            ShopStep::Sell => {
+                match get_sellable_items(state, database).into_iter().nth(self.selected) {
+                    Some((item, _, _)) => {
+                        self.quantity = 1;
+                        ShopStep::Quantity(Trade::Sell(item))
+                    }
+                    None => return true,
+                }
+            }
+            ShopStep::Quantity(ref trade) => {
+                let name = match self.selected_item(shop, state, database) {
+                    Some(item) => database.item_name(&item),
+                    None => String::new(),
+                };
+                let (result, done, next) = match *trade {
+                    Trade::Buy(index) => (
+                        shop.buy(index, self.quantity, state, database),
+                        "Bought",
+                        ShopStep::Buy,
+                    ),
+                    Trade::Sell(ref item) => (
+                        shop::sell(item, self.quantity, state, database),
+                        "Sold",
+                        ShopStep::Sell,
+                    ),
+                };
+                self.message = Some(match result {
+                    Ok(()) => format!("{} {} {}.", done, self.quantity, name),
+                    Err(e) => e.to_string(),
+                });
+                // Selling the last of an item takes it off the list.
+                let count = match next {
+                    ShopStep::Sell => get_sellable_items(state, database).len(),
+                    _ => shop.goods.len(),
+                };
+                self.selected = self.selected.min(count.saturating_sub(1));
+                next
+            }
+        };
+        self.step = next;
+        true
+    }
+
+    /// Go back to the previous step. Returns false if the player has backed out of the shop.
+    pub fn cancel(&mut self) -> bool {
+        let (step, selected) = match self.step {
+            ShopStep::Command => return false,
+            ShopStep::Buy => (ShopStep::Command, ShopCommand::Buy.index()),
+            ShopStep::Sell => (ShopStep::Command, ShopCommand::Sell.index()),
+            ShopStep::Quantity(Trade::Buy(_)) => (ShopStep::Buy, self.selected),
+            ShopStep::Quantity(Trade::Sell(_)) => (ShopStep::Sell, self.selected),
+        };
+        self.step = step;
+        self.selected = selected;
+        true
+    }
+}
+
+impl Default for ShopMenu {
+    fn default() -> ShopMenu {
+        ShopMenu {
+            step: ShopStep::Command,
+            selected: 0,
+            quantity: 1,
+            message: None,
+        }
+    }
+}
+
+/// The party's items a shop will buy, by name, with how many they have and what the shop pays
+/// for one, in order of name.
+pub fn get_sellable_items(state: &GameState, database: &Database) -> Vec<(String, u32, u32)> {
+    state
+        .inventory
+        .items()
+        .iter()
+        .filter_map(|(item, &count)| {
+            shop::get_sell_price(item, database).map(|price| (item.clone(), count, price))
+        })
+        .collect()
+}
+
+/// Describe how stats change, for the player, e.g. "ATK +5  SPD -1".
+pub fn describe_stat_changes(changes: &StatBonus) -> String {
+    let stats = [
+        ("HP", changes.max_hp),
+        ("MP", changes.max_mp),
+        ("ATK", changes.attack),
+        ("DEF", changes.defense),
+        ("MAG", changes.magic),
+        ("SPD", changes.speed),
+    ];
+    let described: Vec<String> = stats
+        .iter()
+        .filter(|&&(_, change)| change != 0)
+        .map(|&(name, change)| format!("{} {:+}", name, change))
+        .collect();
+    if described.is_empty() {
+        String::from("no change")
+    } else {
+        described.join("  ")
+    }
+}
+
+// How many of an item can be traded: as many as the party can afford and carry, or as many as
+//  they have to sell.
+fn get_most_tradable(trade: &Trade, shop: &Shop, state: &GameState, database: &Database) -> u32 {
+    match *trade {
+        Trade::Buy(index) => shop.most_buyable(index, state, database),
+        Trade::Sell(ref item) => state.inventory.count(item),
+    }
+}
+
+/// Shows a shop over the map, and lets the player trade with it.
+pub struct ShopScene {
+    pub shop: Shop,
+    /// The characters in the party, in order, whose stats equipment is compared for.
+    pub members: Vec<String>,
+    menu: ShopMenu,
+}
+
+impl ShopScene {
+    /// Open a shop for a party.
+    pub fn new(shop: Shop, members: Vec<String>) -> ShopScene {
+        ShopScene {
+            shop: shop,
+            members: members,
+            menu: ShopMenu::new(),
+        }
+    }
+}
+
+impl Scene for ShopScene {
+    fn on_action(&mut self, engine: &mut Engine, action: Action) -> Transition {
+        let mut state = engine.game_state.borrow_mut();
+        let database = &engine.database;
+        let choosing_quantity = match *self.menu.step() {
+            ShopStep::Quantity(_) => true,
+            _ => false,
+        };
+        match action {
+            Action::Up | Action::Down | Action::Left | Action::Right if choosing_quantity => {
+                let delta = match action {
+                    Action::Up => QUANTITY_STEP,
+                    Action::Down => -QUANTITY_STEP,
+                    Action::Left => -1,
+                    _ => 1,
+                };
+                self.menu.adjust_quantity(delta, &self.shop, &state, database);
+            }
+            Action::Up => self.menu.move_selection(-1, &self.shop, &state, database),
+            Action::Down => self.menu.move_selection(1, &self.shop, &state, database),
+            Action::Confirm => {
+                if !self.menu.confirm(&self.shop, &mut state, database) {
+                    return Transition::Pop;
+                }
+            }
+            Action::Cancel | Action::Menu => {
+                if !self.menu.cancel() {
+                    return Transition::Pop;
+                }
+            }
+            Action::Left | Action::Right => {}
+        }
+        Transition::Stay
+    }
+
+    fn draw(&mut self, engine: &mut Engine) {
+        let size = engine.display.render_size();
+        // Dim the map underneath.
+        let mut shade = RectangleShape::with_size(Vector2f::new(
+            size.width as f32,
+            size.height as f32,
+        ));
+        shade.set_fill_color(&Color::rgba(0, 0, 0, 192));
+        engine.display.canvas.draw(&shade);
+
+        // Work out everything to show first, so the game state isn't borrowed while drawing.
+        let (gold, list, item, comparison, owned) = {
+            let state = engine.game_state.borrow();
+            let database = &engine.database;
+            let item = self.menu.selected_item(&self.shop, &state, database);
+            let comparison: Vec<String> = match item {
+                Some(ref item) => {
+                    shop::compare_equipment(item, &self.members, &state, database)
+                        .iter()
+                        .map(|&(ref character, ref changes)| {
+                            let name = state
+                                .member(character)
+                                .map_or(character.clone(), |member| member.name.clone());
+                            format!("{}  {}", name, describe_stat_changes(changes))
+                        })
+                        .collect()
+                }
+                None => Vec::new(),
+            };
+            let owned = item.as_ref().map(|item| state.inventory.count(item));
+            let item = item.and_then(|item| database.item(&item).cloned());
+            (
+                state.gold,
+                self.menu.list(&self.shop, &state, database),
+                item,
+                comparison,
+                owned,
+            )
+        };
+
+        // The gold and what happened at the top, then the choices, or how many to trade, on the
+        //  left, and the selected item, and how equipment compares, on the right.
+        let width = size.width.saturating_sub(MARGIN * 2);
+        let header = Window::with_rows(MARGIN, MARGIN, width, 2);
+        let choices = Window::with_rows(
+            MARGIN,
+            header.rect.max_y() + MARGIN,
+            width.saturating_sub(MARGIN) / 2,
+            CHOICE_ROWS as u32,
+        );
+        let details = Window::new(
+            choices.rect.max_x() + MARGIN,
+            choices.rect.origin.y,
+            width.saturating_sub(choices.rect.size.width + MARGIN),
+            choices.rect.size.height,
+        );
+        let quantity_line = match *self.menu.step() {
+            ShopStep::Quantity(ref trade) => {
+                let price = match *trade {
+                    Trade::Buy(index) => self.shop.goods.get(index).map_or(0, |goods| goods.price),
+                    Trade::Sell(ref item) => {
+                        shop::get_sell_price(item, &engine.database).unwrap_or(0)
+                    }
+                };
+                let quantity = self.menu.quantity();
+                Some(format!(
+                    "How many?  < {} >  {}G",
+                    quantity,
+                    price.saturating_mul(quantity)
+                ))
+            }
+            _ => None,
+        };
+
+        let canvas = &mut engine.display.canvas;
+        let font = &engine.font.font;
+        let skin = engine.windowskin.as_ref();
+        header.draw(canvas, skin);
+        let gold_line = format!("{} G", gold);
+        header.draw_text(canvas, font, &gold_line, (0, 0), &HIGHLIGHT_COLOR);
+        if let Some(message) = self.menu.message() {
+            header.draw_text(canvas, font, message, (0, 1), &TEXT_COLOR);
+        }
+        choices.draw(canvas, skin);
+        match quantity_line {
+            Some(line) => choices.draw_text(canvas, font, &line, (0, 0), &TEXT_COLOR),
+            None => list.draw(canvas, font, &choices, 0, true),
+        }
+        details.draw(canvas, skin);
+        let mut row = 0;
+        if let Some(item) = item {
+            details.draw_text(canvas, font, &item.name, (0, row), &HIGHLIGHT_COLOR);
+            row += 1;
+            if !item.description.is_empty() {
+                details.draw_text(canvas, font, &item.description, (0, row), &TEXT_COLOR);
+                row += 1;
+            }
+            if let Some(owned) = owned {
+                let line = format!("Carrying {}", owned);
+                details.draw_text(canvas, font, &line, (0, row), &TEXT_COLOR);
+                row += 1;
+            }
+        }
+        row += 1;
+        for line in comparison.iter() {
+            details.draw_text(canvas, font, line, (0, row), &TEXT_COLOR);
+            row += 1;
+        }
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,148 @@
+use std::rc::Rc;
+
+use battle::combatant::{Combatant, Side, Stats};
+use battle::database::{Database, EffectKind, Skill, StatusEffect};
+use battle::state::{Battle, BattleCommand, BattleEvent, BattleItem, BattleOutcome, Effect,
+                    TurnMode};
+use game_state::growth::Growth;
+use game_state::member::PartyMember;
+use scene::battle::*;
+use utility::random::Random;
+use world::character::CharacterInfo;
+
+// A hero who knows fire, with a potion, against two slimes.
+fn battle() -> Battle {
+    let hero = Combatant::new("Hero", Side::Party, Stats::default())
+        .with_skills(vec![String::from("fire")]);
+    let slimes = vec![
+        Combatant::new("Slime A", Side::Troop, Stats::default()),
+        Combatant::new("Slime B", Side::Troop, Stats::default()),
+    ];
+    let mut database = Database::default();
+    database.skills.insert(
+        String::from("fire"),
+        Skill {
+            name: String::from("Fire"),
+            mp_cost: 4,
+            effect: EffectKind::Damage,
+            formula: String::from("a.magic * 2 + 10 - b.defense"),
+            ..Skill::default()
+        },
+    );
+    database.status_effects.insert(
+        String::from("burn"),
+        StatusEffect {
+            name: String::from("Burn"),
+            ..StatusEffect::default()
+        },
+    );
+    Battle::new(vec![hero], slimes, TurnMode::Turns, Random::with_seed(1))
+        .with_database(Rc::new(database))
+        .with_items(vec![BattleItem {
+            name: String::from("potion"),
+            count: 2,
+            effect: Effect::Heal(20),
+        }])
+}
+
+#[test]
+// Attacking should ask for a target among the enemies, then give the command.
+fn test_choose_attack() {
+    let battle = battle();
+    let mut menu = BattleMenu::new();
+    assert_eq!(menu.confirm(&battle, 0), None);
+    assert_eq!(*menu.step(), MenuStep::Target(TargetFor::Attack));
+    assert_eq!(menu.choices(&battle, 0), vec!["Slime A", "Slime B"]);
+    menu.move_selection(&battle, 0, 1);
+    assert_eq!(menu.list(&battle, 0).selected(), Some(1));
+    assert_eq!(menu.confirm(&battle, 0), Some(BattleCommand::Attack(2)));
+}
+
+#[test]
+// Skills and items should be listed, and target the side their effect is for.
+fn test_choose_skill_and_item() {
+    let battle = battle();
+    let mut menu = BattleMenu::new();
+    menu.move_selection(&battle, 0, 1);
+    menu.confirm(&battle, 0);
+    assert_eq!(menu.choices(&battle, 0), vec!["Fire (4 MP)"]);
+    menu.confirm(&battle, 0);
+    assert_eq!(
+        menu.confirm(&battle, 0),
+        Some(BattleCommand::Skill {
+            skill: String::from("fire"),
+            target: 1,
+        })
+    );
+
+    menu.reset();
+    menu.move_selection(&battle, 0, 2);
+    menu.confirm(&battle, 0);
+    assert_eq!(menu.choices(&battle, 0), vec!["potion x2"]);
+    menu.confirm(&battle, 0);
+    assert_eq!(menu.choices(&battle, 0), vec!["Hero"]);
+    // Going back should return to the list of items, then the commands.
+    menu.cancel();
+    assert_eq!(*menu.step(), MenuStep::Item);
+    menu.cancel();
+    assert_eq!(*menu.step(), MenuStep::Command);
+}
+
+#[test]
+// Guarding and fleeing need nothing more to be chosen.
+fn test_choose_guard_and_flee() {
+    let battle = battle();
+    let mut menu = BattleMenu::new();
+    menu.move_selection(&battle, 0, -2);
+    assert_eq!(menu.confirm(&battle, 0), Some(BattleCommand::Guard));
+    menu.move_selection(&battle, 0, 1);
+    assert_eq!(menu.confirm(&battle, 0), Some(BattleCommand::Flee));
+}
+
+#[test]
+fn test_describe_event() {
+    let battle = battle();
+    let event = BattleEvent::Damaged {
+        target: 1,
+        amount: 12,
+    };
+    assert_eq!(describe_event(&battle, &event), "Slime A takes 12 damage.");
+    // Skills and status effects go by the names shown to the player.
+    let event = BattleEvent::UsedSkill {
+        user: 0,
+        skill: String::from("fire"),
+        target: 2,
+    };
+    assert_eq!(describe_event(&battle, &event), "Hero uses Fire on Slime B!");
+    let event = BattleEvent::StateAdded {
+        target: 2,
+        state: String::from("burn"),
+    };
+    assert_eq!(describe_event(&battle, &event), "Slime B suffers Burn!");
+}
+
+#[test]
+// After a defeat that can be lost, the fallen should get back up with 1 HP; otherwise they should
+// be left as the battle left them.
+fn test_keep_battle_result() {
+    let info = CharacterInfo::default_for("hero");
+    let mut member = PartyMember::new("hero", &info, Growth::flat(Stats::default()));
+    let mut fallen = member.combatant(&[]);
+    fallen.hp = 0;
+    fallen.mp = 1;
+    keep_battle_result(&mut member, &fallen, BattleOutcome::Defeat, true);
+    assert_eq!((member.hp, member.mp), (1, 1));
+    keep_battle_result(&mut member, &fallen, BattleOutcome::Defeat, false);
+    assert_eq!(member.hp, 0);
+    keep_battle_result(&mut member, &fallen, BattleOutcome::Escaped, true);
+    assert_eq!(member.hp, 0);
+}
+
+#[test]
+// Only a defeat in a battle that can't be lost should end the game.
+fn test_is_game_over() {
+    assert!(is_game_over(BattleOutcome::Defeat, false));
+    assert!(!is_game_over(BattleOutcome::Defeat, true));
+    assert!(!is_game_over(BattleOutcome::Victory, false));
+    assert!(!is_game_over(BattleOutcome::Escaped, false));
+}
@@ -0,0 +1,66 @@
+use scene::options::*;
+use config::engine_config::{get_default_engine_config, WindowMode};
+
+#[test]
+// Moving the selection past either end should wrap around.
+fn test_selection_wraps() {
+    let mut menu = OptionsMenu::new(&get_default_engine_config());
+    assert_eq!(menu.selected(), OptionsEntry::WindowMode);
+    menu.move_selection(-1);
+    assert_eq!(menu.selected(), OptionsEntry::Back);
+    menu.move_selection(1);
+    assert_eq!(menu.selected(), OptionsEntry::WindowMode);
+}
+
+#[test]
+// Cycling the window mode should go through every mode and back.
+fn test_adjust_window_mode() {
+    let mut menu = OptionsMenu::new(&get_default_engine_config());
+    assert_eq!(menu.config.window_mode, WindowMode::Windowed);
+    assert!(menu.adjust(1));
+    assert_eq!(menu.config.window_mode, WindowMode::Fullscreen);
+    assert!(menu.adjust(-1));
+    assert!(menu.adjust(-1));
+    assert_eq!(menu.config.window_mode, WindowMode::Borderless);
+}
+
+#[test]
+// A resolution that isn't one of the offered ones should step onto the list.
+fn test_adjust_unlisted_resolution() {
+    let mut config = get_default_engine_config();
+    config.screen_width = 700;
+    config.screen_height = 500;
+    let mut menu = OptionsMenu::new(&config);
+    menu.move_selection(1);
+    assert_eq!(menu.selected(), OptionsEntry::Resolution);
+    assert!(menu.adjust(1));
+    assert_eq!(
+        (menu.config.screen_width, menu.config.screen_height),
+        RESOLUTIONS[0]
+    );
+}
+
+#[test]
+// Volumes should stay between 0 and 100, and not report a change when they hit a limit.
+fn test_adjust_volume_limits() {
+    let mut config = get_default_engine_config();
+    config.master_volume = 95;
+    let mut menu = OptionsMenu::new(&config);
+    while menu.selected() != OptionsEntry::MasterVolume {
+        menu.move_selection(1);
+    }
+    assert!(menu.adjust(1));
+    assert_eq!(menu.config.master_volume, 100);
+    assert!(!menu.adjust(1));
+    assert_eq!(menu.value_text(OptionsEntry::MasterVolume), Some(String::from("100")));
+}
+
+#[test]
+// Adjusting the working copy should leave the original configuration alone.
+fn test_menu_edits_a_copy() {
+    let config = get_default_engine_config();
+    let mut menu = OptionsMenu::new(&config);
+    menu.adjust(1);
+    assert_eq!(config, get_default_engine_config());
+    assert!(menu.config != config);
+}
@@ -0,0 +1,117 @@
+use std::collections::BTreeMap;
+
+use battle::combatant::StatBonus;
+use battle::database::Database;
+use battle::test_support::item_database;
+use game_state::shop::Shop;
+use game_state::GameState;
+use scene::shop::*;
+
+// The shared items, and a shop selling potions.
+fn setup() -> (Database, Shop) {
+    let database = item_database();
+    let shop = Shop::new(&[String::from("potion")], &BTreeMap::new(), &database).unwrap();
+    (database, shop)
+}
+
+#[test]
+// Buying should go through choosing the item and how many, and then back to the list.
+fn test_buy_through_menu() {
+    let (database, shop) = setup();
+    let mut state = GameState::new();
+    state.give_gold(180);
+    let mut menu = ShopMenu::new();
+    assert_eq!(
+        menu.choices(&shop, &state, &database),
+        vec!["Buy", "Sell", "Leave"]
+    );
+    assert!(menu.confirm(&shop, &mut state, &database));
+    assert_eq!(*menu.step(), ShopStep::Buy);
+    assert_eq!(menu.choices(&shop, &state, &database), vec!["Potion  50G"]);
+    assert_eq!(
+        menu.selected_item(&shop, &state, &database),
+        Some(String::from("potion"))
+    );
+
+    assert!(menu.confirm(&shop, &mut state, &database));
+    assert_eq!(*menu.step(), ShopStep::Quantity(Trade::Buy(0)));
+    menu.adjust_quantity(QUANTITY_STEP, &shop, &state, &database);
+    assert_eq!(menu.quantity(), 3);
+    menu.adjust_quantity(-1, &shop, &state, &database);
+    menu.adjust_quantity(-QUANTITY_STEP, &shop, &state, &database);
+    assert_eq!(menu.quantity(), 1);
+    menu.adjust_quantity(1, &shop, &state, &database);
+
+    assert!(menu.confirm(&shop, &mut state, &database));
+    assert_eq!(*menu.step(), ShopStep::Buy);
+    assert_eq!(menu.message(), Some("Bought 2 Potion."));
+    assert_eq!(state.gold, 80);
+    assert_eq!(state.inventory.count("potion"), 2);
+
+    // The party can only afford one more.
+    assert!(menu.confirm(&shop, &mut state, &database));
+    assert!(menu.confirm(&shop, &mut state, &database));
+    assert!(menu.confirm(&shop, &mut state, &database));
+    assert_eq!(menu.message(), Some("Not enough gold."));
+    assert_eq!(*menu.step(), ShopStep::Buy);
+}
+
+#[test]
+// Only items that can be sold should be listed, and selling the last of one should take it off.
+fn test_sell_through_menu() {
+    let (database, shop) = setup();
+    let mut state = GameState::new();
+    state.give_item("potion", 2, &database).unwrap();
+    state.give_item("key", 1, &database).unwrap();
+    let mut menu = ShopMenu::new();
+    menu.move_selection(1, &shop, &state, &database);
+    assert!(menu.confirm(&shop, &mut state, &database));
+    assert_eq!(*menu.step(), ShopStep::Sell);
+    assert_eq!(menu.choices(&shop, &state, &database), vec!["Potion x2  25G"]);
+
+    assert!(menu.confirm(&shop, &mut state, &database));
+    menu.adjust_quantity(QUANTITY_STEP, &shop, &state, &database);
+    assert_eq!(menu.quantity(), 2);
+    assert!(menu.confirm(&shop, &mut state, &database));
+    assert_eq!(state.gold, 50);
+    assert_eq!(state.inventory.count("potion"), 0);
+    assert!(menu.choices(&shop, &state, &database).is_empty());
+    assert_eq!(menu.selected_item(&shop, &state, &database), None);
+
+    // Backing out should leave Sell selected.
+    assert!(menu.cancel());
+    assert_eq!(*menu.step(), ShopStep::Command);
+    assert_eq!(menu.selected(), ShopCommand::Sell.index());
+}
+
+#[test]
+// Cancelling should step back until the player leaves, as should choosing to leave.
+fn test_leaving() {
+    let (database, shop) = setup();
+    let mut state = GameState::new();
+    state.give_gold(100);
+    let mut menu = ShopMenu::new();
+    assert!(menu.confirm(&shop, &mut state, &database));
+    assert!(menu.confirm(&shop, &mut state, &database));
+    assert!(menu.cancel());
+    assert_eq!(*menu.step(), ShopStep::Buy);
+    assert!(menu.cancel());
+    assert_eq!(*menu.step(), ShopStep::Command);
+    assert!(!menu.cancel());
+
+    menu.move_selection(-1, &shop, &state, &database);
+    assert!(!menu.confirm(&shop, &mut state, &database));
+    assert_eq!(state.gold, 100);
+}
+
+#[test]
+// Only stats that change should be described.
+fn test_describe_stat_changes() {
+    let changes = StatBonus {
+        attack: 5,
+        speed: -1,
+        ..StatBonus::default()
+    };
+    assert_eq!(describe_stat_changes(&changes), "ATK +5  SPD -1");
+    assert_eq!(describe_stat_changes(&StatBonus::default()), "no change");
+}
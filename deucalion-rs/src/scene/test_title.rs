@@ -0,0 +1,62 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use hlua::AnyLuaValue;
+
+use config::game_config::TitleCommand;
+use config::schema::ConfigValue;
+use game_state::GameState;
+use scene::title::*;
+use scripting::budget::ScriptBudget;
+use scripting::sandbox::{get_default_script_libraries, ScriptEnvironments, ScriptOwner};
+
+#[test]
+// Commands should be read from the names the game config uses for them.
+fn test_title_command_names() {
+    assert_eq!(
+        TitleCommand::from_name("new_game"),
+        Some(TitleCommand::NewGame)
+    );
+    assert_eq!(TitleCommand::from_name("quit"), Some(TitleCommand::Quit));
+    assert_eq!(TitleCommand::from_name("New Game"), None);
+    assert_eq!(
+        TitleCommand::from_lua(AnyLuaValue::LuaString(String::from("continue"))),
+        Some(TitleCommand::Continue)
+    );
+    assert_eq!(TitleCommand::from_lua(AnyLuaValue::LuaNumber(1.0)), None);
+}
+
+#[test]
+// Continue should only be choosable when there's a save, and the rest always should be.
+fn test_title_items() {
+    let commands = TitleCommand::defaults();
+    let items = get_title_items(&commands, false);
+    let labels: Vec<_> = items.iter().map(|item| item.text.as_str()).collect();
+    assert_eq!(labels, vec!["New Game", "Continue", "Options", "Quit"]);
+    let enabled: Vec<_> = items.iter().map(|item| item.enabled).collect();
+    assert_eq!(enabled, vec![true, false, true, true]);
+    assert!(get_title_items(&commands, true)
+        .iter()
+        .all(|item| item.enabled));
+}
+
+#[test]
+// Starting a game should replace the game state, and forget what scripts asked the last game's
+// map to do.
+fn test_begin_game() {
+    let mut scripts = ScriptEnvironments::new(
+        &get_default_script_libraries(),
+        &[],
+        ScriptBudget::unlimited(),
+    );
+    scripts
+        .get(&ScriptOwner::Game)
+        .execute::<()>("pause_routes()")
+        .unwrap();
+    let mut old = GameState::new();
+    old.give_gold(100);
+    let game_state = Rc::new(RefCell::new(old));
+    begin_game(&game_state, &mut scripts, GameState::new());
+    assert_eq!(game_state.borrow().gold, 0);
+    assert!(scripts.take_commands().is_empty());
+}
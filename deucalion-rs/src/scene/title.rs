@@ -0,0 +1,282 @@
+//! The title screen, shown when the game starts: the game's title over the game config's
+//! `TITLE_BACKGROUND` picture, with its `TITLE_MUSIC` playing, and the commands its
+//! `TITLE_COMMANDS` lists, by name. `"new_game"` starts a new game, `"continue"` carries on from
+//! one of the saves (it can only be chosen once there are some), `"options"` opens the options
+//! screen and `"quit"` quits the game.
+//!
+//! A new game starts with the player and the game config's `STARTING_PARTY` in the party, the
+//! party carrying `STARTING_GOLD`, at the start of `STARTING_MAP`. Then `on_new_game()` is called
+//! in data/scripts/init.lua's environment, if it's defined, to set up the rest of the game, e.g.
+//! giving the party their first items; whatever it asks the map to do happens as soon as the map
+//! is shown.
+
+use std::path::{Path, PathBuf};
+
+use sfml::graphics::Texture;
+
+use config::game_config::TitleCommand;
+use engine::Engine;
+use error::DeucalionError;
+use game_state::save::{get_latest_save, get_save_directory, get_save_path, read_save};
+use game_state::{GameState, SharedGameState};
+use input::Action;
+use resource::map::Tilemap;
+use scene::map::MapScene;
+use scene::menu::save::get_slot_items;
+use scene::menu::MARGIN;
+use scene::options::OptionsScene;
+use scene::screen::{draw_background, draw_heading, load_background, SCREEN_WINDOW_WIDTH};
+use scene::{Scene, Transition};
+use scripting::sandbox::{ScriptEnvironments, ScriptOwner};
+use ui::list::{List, ListItem};
+use ui::window::Window;
+use ui::TEXT_COLOR;
+use world::character::PLAYER_CHARACTER;
+
+/// The game's function that sets up a new game.
+pub const NEW_GAME_HOOK: &'static str = "on_new_game";
+
+/// List the title screen's commands, only letting Continue be chosen if there's a save to
+/// continue from.
+pub fn get_title_items(commands: &[TitleCommand], can_continue: bool) -> Vec<ListItem> {
+    commands
+        .iter()
+        .map(|command| {
+            ListItem::enabled_if(
+                command.label(),
+                *command != TitleCommand::Continue || can_continue,
+            )
+        })
+        .collect()
+}
+
+/// Replace the game state with a new or loaded game's. Whatever scripts asked the last game's
+/// map to do is forgotten, so that it doesn't happen on the new game's map.
+pub fn begin_game(
+    game_state: &SharedGameState,
+    scripts: &mut ScriptEnvironments,
+    state: GameState,
+) {
+    *game_state.borrow_mut() = state;
+    scripts.take_commands();
+}
+
+/// Start a new game: a fresh game state with the starting party and gold, on the starting map,
+/// set up by the game's `on_new_game`. Fails if the party or the map can't be loaded.
+pub fn new_game(engine: &mut Engine) -> Result<MapScene, DeucalionError> {
+    let config = engine.game_config.clone();
+    let mut state = GameState::new();
+    // The player is always in the party.
+    state.join(PLAYER_CHARACTER)?;
+    for character in config.starting_party.iter() {
+        state.join(character)?;
+    }
+    state.give_gold(config.starting_gold);
+    let map = Tilemap::by_name(&config.starting_map)?;
+    begin_game(&engine.game_state, &mut engine.scripts, state);
+    let mut scene = MapScene::new(engine, map);
+    for character in config.starting_party.iter() {
+        scene.world.add_follower(character)?;
+    }
+    info!("Starting a new game on {}", config.starting_map);
+    if let Err(e) = engine
+        .scripts
+        .call_function(&ScriptOwner::Game, NEW_GAME_HOOK, Vec::new())
+    {
+        engine.report_error(&format!("Failed to run {}", NEW_GAME_HOOK), e);
+    }
+    Ok(scene)
+}
+
+/// Load a save, and carry on from where it left the player. Invalid values in the save are
+/// reported and left out; fails if it can't be read at all, doesn't say where the player is, or
+/// their map can't be loaded.
+pub fn load_game(engine: &mut Engine, path: &Path) -> Result<MapScene, DeucalionError> {
+    let (state, errors) = read_save(path, &engine.database)?;
+    for e in errors {
+        engine.report_error(
+            &format!("Invalid value in the save at {}", path.display()),
+            e,
+        );
+    }
+    let location = match state.location {
+        Some(ref location) => location.clone(),
+        None => {
+            return Err(DeucalionError::from(String::from(
+                "the save doesn't say where the player is",
+            )))
+        }
+    };
+    let map = Tilemap::by_name(&location.map)?;
+    begin_game(&engine.game_state, &mut engine.scripts, state);
+    info!("Loaded the save at {}", path.display());
+    MapScene::at_location(engine, map, &location)
+}
+
+/// The title screen.
+pub struct TitleScene {
+    background: Option<Texture>,
+    commands: Vec<TitleCommand>,
+    list: List,
+    directory: Option<PathBuf>,
+    // The save slots, while the player is choosing one to continue from.
+    slots: Option<List>,
+    // What went wrong, if the game couldn't be started.
+    message: Option<String>,
+}
+
+impl TitleScene {
+    /// Show the title screen, and start its music.
+    pub fn new(engine: &mut Engine) -> TitleScene {
+        let background_name = engine.game_config.title_background.clone();
+        let background = load_background(engine, &background_name);
+        let music = engine.game_config.title_music.clone();
+        engine.play_music(&music);
+
+        let directory = get_save_directory(&engine.game_config);
+        let latest = directory
+            .as_ref()
+            .and_then(|directory| get_latest_save(directory, engine.game_config.save_slots));
+        let commands = engine.game_config.title_commands.clone();
+        let mut list = List::new(get_title_items(&commands, latest.is_some()), commands.len());
+        // Players coming back to a game most likely want to carry on with it.
+        if latest.is_some() {
+            if let Some(index) = commands.iter().position(|c| *c == TitleCommand::Continue) {
+                list.select(index);
+            }
+        }
+        TitleScene {
+            background: background,
+            commands: commands,
+            list: list,
+            directory: directory,
+            slots: None,
+            message: None,
+        }
+    }
+
+    // Carry out a command.
+    fn choose(&mut self, engine: &mut Engine, command: TitleCommand) -> Transition {
+        match command {
+            TitleCommand::NewGame => {
+                let started = new_game(engine);
+                self.start(engine, started, "Couldn't start a new game")
+            }
+            TitleCommand::Continue => {
+                let directory = match self.directory {
+                    Some(ref directory) => directory.clone(),
+                    None => return Transition::Stay,
+                };
+                let slots = engine.game_config.save_slots;
+                let mut list = List::new(get_slot_items(&directory, slots, false), slots as usize);
+                if let Some(latest) = get_latest_save(&directory, slots) {
+                    list.select(latest as usize - 1);
+                }
+                self.slots = Some(list);
+                Transition::Stay
+            }
+            TitleCommand::Options => Transition::Push(Box::new(OptionsScene::new(engine))),
+            TitleCommand::Quit => Transition::Quit,
+        }
+    }
+
+    // Continue from the selected save slot.
+    fn load_selected(&mut self, engine: &mut Engine) -> Transition {
+        let slot = match (self.slots.as_ref(), self.directory.as_ref()) {
+            (Some(slots), Some(directory)) if slots.is_selected_enabled() => slots
+                .selected()
+                .map(|index| get_save_path(directory, index as u32 + 1)),
+            _ => None,
+        };
+        match slot {
+            Some(path) => {
+                let loaded = load_game(engine, &path);
+                self.start(engine, loaded, "Couldn't load the save")
+            }
+            None => Transition::Stay,
+        }
+    }
+
+    // Go to the map if the game started, or say why it didn't.
+    fn start(
+        &mut self,
+        engine: &mut Engine,
+        started: Result<MapScene, DeucalionError>,
+        failure: &str,
+    ) -> Transition {
+        match started {
+            Ok(scene) => {
+                engine.play_music("");
+                Transition::Replace(Box::new(scene))
+            }
+            Err(e) => {
+                self.message = Some(format!("{}: {}", failure, e));
+                engine.report_error(failure, e);
+                Transition::Stay
+            }
+        }
+    }
+}
+
+impl Scene for TitleScene {
+    fn on_action(&mut self, engine: &mut Engine, action: Action) -> Transition {
+        self.message = None;
+        if self.slots.is_some() {
+            match action {
+                Action::Up | Action::Down => {
+                    let delta = if action == Action::Up { -1 } else { 1 };
+                    if let Some(ref mut slots) = self.slots {
+                        slots.move_selection(delta);
+                    }
+                }
+                Action::Confirm => return self.load_selected(engine),
+                Action::Cancel | Action::Menu => self.slots = None,
+                Action::Left | Action::Right => {}
+            }
+            return Transition::Stay;
+        }
+        match action {
+            Action::Up => self.list.move_selection(-1),
+            Action::Down => self.list.move_selection(1),
+            Action::Confirm => {
+                if !self.list.is_selected_enabled() {
+                    return Transition::Stay;
+                }
+                let command = match self.list.selected() {
+                    Some(index) => self.commands[index],
+                    None => return Transition::Stay,
+                };
+                return self.choose(engine, command);
+            }
+            Action::Cancel | Action::Menu | Action::Left | Action::Right => {}
+        }
+        Transition::Stay
+    }
+
+    fn draw(&mut self, engine: &mut Engine) {
+        draw_background(engine, self.background.as_ref());
+        let size = engine.display.render_size();
+        let x = size.width.saturating_sub(SCREEN_WINDOW_WIDTH) / 2;
+        let shown = self.slots.as_ref().unwrap_or(&self.list);
+        let rows = shown.items().len() as u32;
+        let commands = Window::with_rows(x, size.height / 2, SCREEN_WINDOW_WIDTH, rows);
+        let message = Window::with_rows(
+            MARGIN,
+            commands.rect.max_y() + MARGIN,
+            size.width.saturating_sub(MARGIN * 2),
+            1,
+        );
+
+        let canvas = &mut engine.display.canvas;
+        let font = &engine.font.font;
+        let skin = engine.windowskin.as_ref();
+        let title_y = size.height as f32 / 4.0;
+        draw_heading(canvas, font, &engine.game_config.title, size.width, title_y);
+        commands.draw(canvas, skin);
+        shown.draw(canvas, font, &commands, 0, true);
+        if let Some(ref line) = self.message {
+            message.draw(canvas, skin);
+            message.draw_text(canvas, font, line, (0, 0), &TEXT_COLOR);
+        }
+    }
+}
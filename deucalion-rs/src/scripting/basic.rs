@@ -1,6 +1,11 @@
 //! Basic operations on the Lua context, such as creation, destruction, etc
 use hlua::{Lua, AnyLuaValue, LuaRead};
-use error::DeucalionError;
+use error::{DeucalionError, LuaScriptError};
+use std::io::Read;
+use std::path::Path;
+use resource::loading::DATA_DIRECTORY_NAME;
+use scripting::budget::{run_with_budget, ScriptBudget};
+use scripting::convert::get_table_field;
 
 /// Do all required work to initialize a game's Lua context.
 /// The Lua object returned by this function is fully ready to be used by the engine.
@@ -16,33 +21,158 @@ pub fn get_scripting_environment<'environment>() -> Lua<'environment> {
 }
 
 /// Execute a script from a file, returning whatever that script returns. If no data is expected,
-/// that return value can be safely ignored.
+/// that return value can be safely ignored. The script is aborted if it exceeds `budget`.
 pub fn execute_script(
     environment: &mut Lua,
     filename: &str,
+    budget: &ScriptBudget,
 ) -> Result<AnyLuaValue, DeucalionError> {
     use std::fs::File;
-    use std::io::prelude::*;
     // Open the file
-    let mut f = try!(File::open(filename));
-    // With the file open, read its contents into a String
+    let f = try!(File::open(filename));
+    execute_script_from_reader(environment, f, Path::new(filename), budget)
+}
+
+// Runs the script in __deucalion_source, named __deucalion_chunk_name, capturing a traceback if
+//  it fails. The outcome is returned as a table rather than raised, since hlua only keeps the
+//  message of a raised error. Sandboxed environments have no debug library, so they keep
+//  debug.traceback as __deucalion_traceback, and they load chunks with __deucalion_load rather
+//  than the global `load`, which scripts can replace; see `scripting::sandbox`.
+const SCRIPT_RUNNER: &'static str = r#"
+local source, name = __deucalion_source, __deucalion_chunk_name
+__deucalion_source, __deucalion_chunk_name = nil, nil
+local traceback = __deucalion_traceback or (debug and debug.traceback)
+local load = __deucalion_load or load
+local chunk, message = load(source, "@" .. name, "t")
+if not chunk then
+    return { ok = false, message = message, traceback = "" }
+end
+local ok, result = xpcall(chunk, function(e)
+    return {
+        message = tostring(e),
+        traceback = traceback and traceback("", 2) or "",
+    }
+end)
+if ok then
+    return { ok = true, value = result }
+end
+return { ok = false, message = result.message, traceback = result.traceback }
+"#;
+
+/// Get the name a script is known by in Lua errors: its path relative to the data directory, if
+/// it's in there, or else its whole path.
+pub fn get_chunk_name(path: &Path) -> String {
+    let data_directory = Path::new(".").join(DATA_DIRECTORY_NAME);
+    let relative = path.strip_prefix(&data_directory)
+        .or_else(|_| path.strip_prefix(DATA_DIRECTORY_NAME))
+        .unwrap_or(path);
+    // Chunk names always use forward slashes, so errors look the same on every platform.
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Split a Lua error message like "maps/town.lua:12: attempt to call a nil value" into the
+/// line number and the rest of the message. Messages without a location are returned whole.
+pub fn split_error_location(chunk_name: &str, message: &str) -> (Option<u32>, String) {
+    let prefix = format!("{}:", chunk_name);
+    if message.starts_with(&prefix) {
+        let rest = &message[prefix.len()..];
+        if let Some(colon) = rest.find(':') {
+            if let Ok(line) = rest[..colon].parse::<u32>() {
+                return (Some(line), String::from(rest[colon + 1..].trim_start()));
+            }
+        }
+    }
+    (None, String::from(message))
+}
+
+/// Execute a script read from any reader, such as one returned by `resource::loading`, returning
+/// whatever that script returns. `path` is where the script came from; errors are reported with
+/// it relative to the data directory, along with the line and a traceback. The script is aborted
+/// if it exceeds `budget`.
+pub fn execute_script_from_reader<R: Read>(
+    environment: &mut Lua,
+    mut reader: R,
+    path: &Path,
+    budget: &ScriptBudget,
+) -> Result<AnyLuaValue, DeucalionError> {
+    // Read the script's contents into a String
     let mut contents = String::new();
-    try!(f.read_to_string(&mut contents));
+    try!(reader.read_to_string(&mut contents));
+    let chunk_name = get_chunk_name(path);
+    environment.set("__deucalion_source", contents);
+    environment.set("__deucalion_chunk_name", chunk_name.clone());
     // Try to execute the contents of the file in the given scripting environment
     // Implementation note: AnyLuaValue typearg here means that the return value will be
     //  AnyLuaValue as well, which is what we want.
-    match environment.execute::<AnyLuaValue>(&contents) {
-        Ok(v) => Ok(v),
-        Err(e) => Err(DeucalionError::from(e)),
+    let (result, exceeded) = run_with_budget(environment, budget, |environment| {
+        environment.execute::<AnyLuaValue>(SCRIPT_RUNNER)
+    });
+    if exceeded {
+        warn!(
+            "The script at {} was aborted after running past its budget of {}",
+            chunk_name,
+            budget.describe()
+        );
+        return Err(DeucalionError::ScriptBudgetExceeded(
+            chunk_name,
+            budget.describe(),
+        ));
     }
+    let outcome = match result? {
+        outcome @ AnyLuaValue::LuaArray(_) => outcome,
+        other => {
+            return Err(DeucalionError::from(format!(
+                "running {} gave the unexpected outcome {:?}",
+                chunk_name, other
+            )))
+        }
+    };
+    if get_table_field(&outcome, "ok") == AnyLuaValue::LuaBoolean(true) {
+        return Ok(get_table_field(&outcome, "value"));
+    }
+
+    let message = match get_table_field(&outcome, "message") {
+        AnyLuaValue::LuaString(message) => message,
+        _ => String::from("unknown error"),
+    };
+    let traceback = match get_table_field(&outcome, "traceback") {
+        AnyLuaValue::LuaString(traceback) => String::from(traceback.trim()),
+        _ => String::new(),
+    };
+    let (line, message) = split_error_location(&chunk_name, &message);
+    Err(DeucalionError::LuaScriptError(LuaScriptError {
+        file: chunk_name,
+        line: line,
+        message: message,
+        traceback: traceback,
+    }))
 }
 
 /// Attempts to read a value of type V from the given Lua environment, returning a `DeucalionError` on
 /// failure.
 pub fn get_value_by_identifier<'l, 'e, I, V>(environment: &'l mut Lua<'e>, identifier: I) -> Result<V, DeucalionError>
     where I: ::std::borrow::Borrow<str>, V: LuaRead<::hlua::PushGuard<&'l mut Lua<'e>>> {
+    let name = String::from(identifier.borrow());
+    match environment.get(identifier) {
+        Some(v) => Ok(v),
+        None => Err(DeucalionError::LuaGetFailed(name, String::from(::std::any::type_name::<V>())))
+    }
+}
+
+/// Attempts to read a value of type V from the given Lua environment, warning the developer and
+/// returning `default` if it is not defined or has the wrong type.
+pub fn get_value_or_default<'l, 'e, I, V>(environment: &'l mut Lua<'e>, identifier: I, default: V) -> V
+    where I: ::std::borrow::Borrow<str>, V: LuaRead<::hlua::PushGuard<&'l mut Lua<'e>>> + ::std::fmt::Debug {
+    let name = String::from(identifier.borrow());
     match environment.get(identifier) {
-        Some(v) => Ok(v), 
-        None => Err(DeucalionError::from("SCREEN_WIDTH is not defined or is the wrong type",))
+        Some(v) => v,
+        None => {
+            warn!("{} is not defined or is the wrong type; using the default, {:?}", name, default);
+            default
+        }
     }
 }
@@ -0,0 +1,150 @@
+//! Limits on how long a single script invocation may run, so that a runaway script (say, a
+//! `while true do end` in an event script) is aborted instead of freezing the whole engine.
+//!
+//! Budgets are enforced with a Lua count hook, which checks the budget every `HOOK_INTERVAL`
+//! instructions. Once a script has run out, the hook raises an error on every instruction, so a
+//! script can't carry on by catching the error with `pcall`. The hook is written in C
+//! (budget_hook.c), since raising a Lua error longjmps out of it, which mustn't happen across Rust
+//! frames; it only asks this module whether the budget has run out.
+//!
+//! Budgets nest: a script run while another is running (say, by a Rust function the outer script
+//! called) counts against both budgets, and the outer one is still enforced once it finishes.
+
+use std::cell::RefCell;
+use std::os::raw::c_int;
+use std::time::{Duration, Instant};
+
+use hlua::{AsLua, Lua};
+use lua52_sys as ffi;
+
+/// How many instructions run between checks of the budget.
+const HOOK_INTERVAL: c_int = 1000;
+
+/// How much a single script invocation may do before it is aborted. `None` means no limit.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct ScriptBudget {
+    /// The number of Lua instructions the script may execute.
+    pub instructions: Option<u64>,
+    /// How long the script may run for.
+    pub time: Option<Duration>,
+}
+
+impl ScriptBudget {
+    /// No limit at all.
+    pub fn unlimited() -> ScriptBudget {
+        ScriptBudget {
+            instructions: None,
+            time: None,
+        }
+    }
+
+    /// The budget for configuration scripts. They run before the game's own limits are known,
+    /// and may reasonably do more work than a single event, so this is generous.
+    pub fn for_config() -> ScriptBudget {
+        ScriptBudget {
+            instructions: Some(100_000_000),
+            time: Some(Duration::from_secs(10)),
+        }
+    }
+
+    /// Describe the budget, for error messages.
+    pub fn describe(&self) -> String {
+        match (self.instructions, self.time) {
+            (Some(i), Some(t)) => format!("{} instructions or {} ms", i, duration_millis(t)),
+            (Some(i), None) => format!("{} instructions", i),
+            (None, Some(t)) => format!("{} ms", duration_millis(t)),
+            (None, None) => String::from("no limit"),
+        }
+    }
+}
+
+fn duration_millis(duration: Duration) -> u64 {
+    duration.as_secs() * 1000 + u64::from(duration.subsec_nanos() / 1_000_000)
+}
+
+/// The budget of a script that is running, counted down as it runs.
+struct RunningBudget {
+    instructions_left: Option<u64>,
+    deadline: Option<Instant>,
+    exceeded: bool,
+}
+
+thread_local! {
+    // The budgets of every script running on this thread, innermost last.
+    static RUNNING_BUDGETS: RefCell<Vec<RunningBudget>> = RefCell::new(Vec::new());
+}
+
+extern "C" {
+    // Install budget_hook.c's hook on a Lua state, to run every `instructions` instructions.
+    fn deucalion_set_budget_hook(state: *mut ffi::lua_State, instructions: c_int);
+}
+
+/// Called by the hook in budget_hook.c every time it runs, with the number of instructions run
+/// since it last did. Counts them against every running budget, and returns nonzero if any of them
+/// has run out. This must never raise a Lua error or panic.
+#[no_mangle]
+pub extern "C" fn deucalion_budget_exceeded(instructions: c_int) -> c_int {
+    RUNNING_BUDGETS.with(|cell| {
+        let mut exceeded = false;
+        for budget in cell.borrow_mut().iter_mut() {
+            if let Some(ref mut left) = budget.instructions_left {
+                *left = left.saturating_sub(instructions.max(0) as u64);
+                budget.exceeded |= *left == 0;
+            }
+            if let Some(deadline) = budget.deadline {
+                budget.exceeded |= Instant::now() >= deadline;
+            }
+            exceeded |= budget.exceeded;
+        }
+        exceeded as c_int
+    })
+}
+
+/// Run `f`, which executes Lua code in `environment`, within a budget. Returns what `f` returns,
+/// and whether the budget was exceeded (in which case `f` will have seen a Lua error). Any budget
+/// already running still applies to `f`, and is enforced again once `f` returns.
+pub fn run_with_budget<'lua, F, R>(
+    environment: &mut Lua<'lua>,
+    budget: &ScriptBudget,
+    f: F,
+) -> (R, bool)
+where
+    F: FnOnce(&mut Lua<'lua>) -> R,
+{
+    if budget.instructions.is_none() && budget.time.is_none() {
+        return (f(environment), false);
+    }
+
+    let state = environment.as_lua().state_ptr();
+    // Whatever hook was set before, such as an outer budget's, is put back afterwards.
+    let previous_hook = unsafe {
+        (
+            ffi::lua_gethook(state),
+            ffi::lua_gethookmask(state),
+            ffi::lua_gethookcount(state),
+        )
+    };
+    RUNNING_BUDGETS.with(|cell| {
+        cell.borrow_mut().push(RunningBudget {
+            instructions_left: budget.instructions,
+            deadline: budget.time.map(|t| Instant::now() + t),
+            exceeded: false,
+        })
+    });
+    unsafe {
+        deucalion_set_budget_hook(state, HOOK_INTERVAL);
+    }
+
+    let result = f(environment);
+
+    unsafe {
+        ffi::lua_sethook(state, previous_hook.0, previous_hook.1, previous_hook.2);
+    }
+    let exceeded = RUNNING_BUDGETS.with(|cell| {
+        cell.borrow_mut()
+            .pop()
+            .map(|budget| budget.exceeded)
+            .unwrap_or(false)
+    });
+    (result, exceeded)
+}
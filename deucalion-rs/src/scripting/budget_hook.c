@@ -0,0 +1,35 @@
+/*
+ * The count hook that enforces script budgets. It lives in C because it has to raise a Lua error,
+ * which longjmps out of the hook; doing that from a Rust function would skip over Rust frames.
+ * The budget itself is kept, and checked, on the Rust side (see budget.rs).
+ */
+
+typedef struct lua_State lua_State;
+typedef struct lua_Debug lua_Debug;
+typedef void (*lua_Hook)(lua_State *L, lua_Debug *ar);
+
+/* From lua.h; lua52-sys doesn't install its headers anywhere we can include them from. */
+#define LUA_MASKCOUNT (1 << 3)
+int lua_sethook(lua_State *L, lua_Hook func, int mask, int count);
+int lua_gethookcount(lua_State *L);
+const char *lua_pushstring(lua_State *L, const char *s);
+int lua_error(lua_State *L);
+
+/* Defined in budget.rs. Counts `instructions` against the running budgets, returning nonzero once
+ * one has run out. It never raises Lua errors itself. */
+int deucalion_budget_exceeded(int instructions);
+
+void deucalion_budget_hook(lua_State *L, lua_Debug *ar) {
+    (void)ar;
+    if (deucalion_budget_exceeded(lua_gethookcount(L))) {
+        /* Raise the error on every instruction from now on, so that a script can't carry on by
+         * catching it with pcall. */
+        lua_sethook(L, deucalion_budget_hook, LUA_MASKCOUNT, 1);
+        lua_pushstring(L, "script exceeded its execution budget");
+        lua_error(L);
+    }
+}
+
+void deucalion_set_budget_hook(lua_State *L, int instructions) {
+    lua_sethook(L, deucalion_budget_hook, LUA_MASKCOUNT, instructions);
+}
@@ -0,0 +1,346 @@
+//! The functions game scripts use to act on the world, such as pausing NPCs for a cutscene.
+//!
+//! Scripts can't reach the world directly: the world is owned by whichever scene is running, and
+//! a script may run while it's in the middle of being updated. Instead, each function checks its
+//! arguments and queues a `ScriptCommand`, and the scene carries out the queued commands once the
+//! script has finished. Argument errors are raised in the script, at the line that called the
+//! function, like any other Lua error.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use hlua::{self, AnyLuaValue, Lua};
+
+use config::schema::ConfigValue;
+use scripting::convert::get_table_field;
+use ui::layout::parse_layout;
+use ui::widget::Widget;
+
+/// Something a script asked the world to do.
+#[derive(PartialEq, Debug, Clone)]
+pub enum ScriptCommand {
+    /// Stop an NPC following its behavior, or every NPC if no name is given, until it's resumed.
+    PauseRoutes(Option<String>),
+    /// Let an NPC carry on with its behavior from where it was paused, or every NPC.
+    ResumeRoutes(Option<String>),
+    /// Walk the player, if the actor is "player", or the named NPC to a tile, given by its tile
+    /// coordinates.
+    MoveTo { actor: String, x: i32, y: i32 },
+    /// Add a character to the end of the player's line of followers.
+    AddFollower(String),
+    /// Remove a character from the player's line of followers.
+    RemoveFollower(String),
+    /// Show the player's followers, or hide them.
+    SetPartyFollowing(bool),
+    /// Have the player's followers walk onto the player, such as for a cutscene.
+    GatherParty,
+    /// Put the player and their followers on a tile, on another map if one is named.
+    Warp { x: i32, y: i32, map: Option<String> },
+    /// Play an animation on the player, if the actor is "player", or the named NPC.
+    PlayAnimation { animation: String, actor: String },
+    /// Play an animation in the middle of a tile, given by its tile coordinates.
+    PlayAnimationAt { animation: String, x: i32, y: i32 },
+    /// Stop every playing animation with a name.
+    StopAnimation(String),
+    /// Fight a troop, by name, letting the party flee if `can_flee` is set, and carrying on
+    /// after a defeat, rather than ending the game, if `can_lose` is.
+    StartBattle {
+        troop: String,
+        can_flee: bool,
+        can_lose: bool,
+    },
+    /// Give a party member EXP, by their character's name.
+    GiveExp { character: String, amount: u32 },
+    /// Restore all of every party member's HP and MP, such as at an inn.
+    RecoverParty,
+    /// Open a shop selling items, by name, at the prices given for any of them, and their own
+    /// prices otherwise.
+    OpenShop {
+        items: Vec<String>,
+        prices: BTreeMap<String, u32>,
+    },
+    /// Show a screen of widgets over the world, calling a function in init.lua's environment,
+    /// if one is named, with what the player chose once they're done with it.
+    OpenLayout { layout: Widget, call: Option<String> },
+}
+
+impl ScriptCommand {
+    /// Read a command from the table the Lua side of a function builds, with the command's name
+    /// in its `command` field. Returns a message for the script if the arguments are wrong.
+    pub fn from_lua(table: &AnyLuaValue) -> Result<ScriptCommand, String> {
+        let name = match get_table_field(table, "command") {
+            AnyLuaValue::LuaString(name) => name,
+            _ => return Err(String::from("malformed command")),
+        };
+        match name.as_str() {
+            "pause_routes" => Ok(ScriptCommand::PauseRoutes(optional_string(table, "npc")?)),
+            "resume_routes" => Ok(ScriptCommand::ResumeRoutes(optional_string(table, "npc")?)),
+            "move_to" => Ok(ScriptCommand::MoveTo {
+                actor: required_string(table, "actor")?,
+                x: whole_number(table, "x")?,
+                y: whole_number(table, "y")?,
+            }),
+            "add_follower" => Ok(ScriptCommand::AddFollower(required_string(table, "character")?)),
+            "remove_follower" => Ok(ScriptCommand::RemoveFollower(required_string(
+                table,
+                "character",
+            )?)),
+            "set_party_following" => Ok(ScriptCommand::SetPartyFollowing(required_boolean(
+                table,
+                "following",
+            )?)),
+            "gather_party" => Ok(ScriptCommand::GatherParty),
+            "warp" => Ok(ScriptCommand::Warp {
+                x: whole_number(table, "x")?,
+                y: whole_number(table, "y")?,
+                map: optional_string(table, "map")?,
+            }),
+            "play_animation" => Ok(ScriptCommand::PlayAnimation {
+                animation: required_string(table, "animation")?,
+                actor: required_string(table, "actor")?,
+            }),
+            "play_animation_at" => Ok(ScriptCommand::PlayAnimationAt {
+                animation: required_string(table, "animation")?,
+                x: whole_number(table, "x")?,
+                y: whole_number(table, "y")?,
+            }),
+            "stop_animation" => Ok(ScriptCommand::StopAnimation(required_string(
+                table,
+                "animation",
+            )?)),
+            "start_battle" => Ok(ScriptCommand::StartBattle {
+                troop: required_string(table, "troop")?,
+                can_flee: optional_boolean(table, "can_flee", true)?,
+                can_lose: optional_boolean(table, "can_lose", false)?,
+            }),
+            "give_exp" => Ok(ScriptCommand::GiveExp {
+                character: required_string(table, "character")?,
+                amount: non_negative_number(table, "amount")?,
+            }),
+            "recover_party" => Ok(ScriptCommand::RecoverParty),
+            "open_shop" => Ok(ScriptCommand::OpenShop {
+                items: Vec::<String>::from_lua(get_table_field(table, "items"))
+                    .ok_or_else(|| String::from("items must be a list of item names"))?,
+                prices: optional_prices(table, "prices")?,
+            }),
+            "open_layout" => Ok(ScriptCommand::OpenLayout {
+                layout: parse_layout(&get_table_field(table, "layout"))
+                    .map_err(|e| format!("invalid layout: {}", e))?,
+                call: optional_string(table, "call")?,
+            }),
+            _ => Err(format!("unknown command '{}'", name)),
+        }
+    }
+}
+
+// Read an optional string argument.
+fn optional_string(table: &AnyLuaValue, name: &str) -> Result<Option<String>, String> {
+    match get_table_field(table, name) {
+        AnyLuaValue::LuaNil => Ok(None),
+        AnyLuaValue::LuaString(value) => Ok(Some(value)),
+        _ => Err(format!("{} must be a string", name)),
+    }
+}
+
+/// Read a string argument that must be given.
+pub fn required_string(table: &AnyLuaValue, name: &str) -> Result<String, String> {
+    match get_table_field(table, name) {
+        AnyLuaValue::LuaString(value) => Ok(value),
+        _ => Err(format!("{} must be a string", name)),
+    }
+}
+
+// Read a boolean argument that must be given.
+fn required_boolean(table: &AnyLuaValue, name: &str) -> Result<bool, String> {
+    match get_table_field(table, name) {
+        AnyLuaValue::LuaBoolean(value) => Ok(value),
+        _ => Err(format!("{} must be true or false", name)),
+    }
+}
+
+// Read a boolean argument that may be left out, in which case it's `default`.
+fn optional_boolean(table: &AnyLuaValue, name: &str, default: bool) -> Result<bool, String> {
+    match get_table_field(table, name) {
+        AnyLuaValue::LuaNil => Ok(default),
+        AnyLuaValue::LuaBoolean(value) => Ok(value),
+        _ => Err(format!("{} must be true or false", name)),
+    }
+}
+
+// Read an integer argument that must be given.
+fn whole_number(table: &AnyLuaValue, name: &str) -> Result<i32, String> {
+    match get_table_field(table, name) {
+        AnyLuaValue::LuaNumber(value)
+            if value.fract() == 0.0
+                && value >= f64::from(i32::min_value())
+                && value <= f64::from(i32::max_value()) =>
+        {
+            Ok(value as i32)
+        }
+        _ => Err(format!("{} must be a whole number", name)),
+    }
+}
+
+/// Read an integer argument that must be given, and can't be negative.
+pub fn non_negative_number(table: &AnyLuaValue, name: &str) -> Result<u32, String> {
+    match get_table_field(table, name) {
+        AnyLuaValue::LuaNumber(value)
+            if value.fract() == 0.0 && value >= 0.0 && value <= f64::from(u32::max_value()) =>
+        {
+            Ok(value as u32)
+        }
+        _ => Err(format!("{} must be a whole number, not below zero", name)),
+    }
+}
+
+// Read an optional table of item names to prices.
+fn optional_prices(table: &AnyLuaValue, name: &str) -> Result<BTreeMap<String, u32>, String> {
+    let entries = match get_table_field(table, name) {
+        AnyLuaValue::LuaNil => return Ok(BTreeMap::new()),
+        AnyLuaValue::LuaArray(entries) => entries,
+        _ => return Err(format!("{} must be a table of item names to prices", name)),
+    };
+    let mut prices = BTreeMap::new();
+    for (item, price) in entries {
+        match (String::from_lua(item), u32::from_lua(price)) {
+            (Some(item), Some(price)) => {
+                prices.insert(item, price);
+            }
+            _ => return Err(format!("{} must be a table of item names to prices", name)),
+        }
+    }
+    Ok(prices)
+}
+
+/// The commands scripts have queued, shared between every environment they can come from.
+#[derive(Clone, Default)]
+pub struct CommandQueue {
+    commands: Rc<RefCell<Vec<ScriptCommand>>>,
+}
+
+impl CommandQueue {
+    /// Create an empty queue.
+    pub fn new() -> CommandQueue {
+        CommandQueue::default()
+    }
+
+    /// Queue a command.
+    pub fn push(&self, command: ScriptCommand) {
+        self.commands.borrow_mut().push(command);
+    }
+
+    /// Remove and return every queued command, oldest first.
+    pub fn take(&self) -> Vec<ScriptCommand> {
+        self.commands.borrow_mut().drain(..).collect()
+    }
+}
+
+// Defines the functions scripts call, in terms of the Rust function given to it, which is then
+//  removed from the globals.
+const COMMAND_FUNCTIONS: &'static str = r#"
+local push = __deucalion_push_command
+__deucalion_push_command = nil
+local function command(arguments)
+    local message = push(arguments)
+    if message ~= nil then
+        error(message, 3)
+    end
+end
+function pause_routes(npc)
+    command({ command = "pause_routes", npc = npc })
+end
+function resume_routes(npc)
+    command({ command = "resume_routes", npc = npc })
+end
+function move_to(actor, x, y)
+    command({ command = "move_to", actor = actor, x = x, y = y })
+end
+function add_follower(character)
+    command({ command = "add_follower", character = character })
+end
+function remove_follower(character)
+    command({ command = "remove_follower", character = character })
+end
+function set_party_following(following)
+    command({ command = "set_party_following", following = following })
+end
+function gather_party()
+    command({ command = "gather_party" })
+end
+function warp(x, y, map)
+    command({ command = "warp", x = x, y = y, map = map })
+end
+function play_animation(animation, actor)
+    command({ command = "play_animation", animation = animation, actor = actor })
+end
+function play_animation_at(animation, x, y)
+    command({ command = "play_animation_at", animation = animation, x = x, y = y })
+end
+function stop_animation(animation)
+    command({ command = "stop_animation", animation = animation })
+end
+function start_battle(troop, can_flee, can_lose)
+    command({ command = "start_battle", troop = troop, can_flee = can_flee, can_lose = can_lose })
+end
+function give_exp(character, amount)
+    command({ command = "give_exp", character = character, amount = amount })
+end
+function recover_party()
+    command({ command = "recover_party" })
+end
+function open_shop(items, prices)
+    command({ command = "open_shop", items = items, prices = prices })
+end
+function open_layout(layout, call)
+    command({ command = "open_layout", layout = layout, call = call })
+end
+"#;
+
+/// Give an environment the command functions, queueing what scripts ask for onto `queue`:
+///
+/// * `pause_routes([npc])` and `resume_routes([npc])` pause and resume an NPC's behavior, or
+///   every NPC's, such as around a cutscene.
+/// * `move_to(actor, x, y)` walks the player (`"player"`) or an NPC to the tile at (x, y),
+///   finding a way around anything in the way.
+/// * `add_follower(character)` and `remove_follower(character)` change who trails behind the
+///   player, `set_party_following(following)` shows or hides them, and `gather_party()` has them
+///   walk onto the player, such as for a cutscene.
+/// * `warp(x, y, [map])` puts the player and their followers on the tile at (x, y), on another
+///   map if one is named.
+/// * `play_animation(animation, actor)` plays an animation on the player or an NPC,
+///   `play_animation_at(animation, x, y)` plays one on the tile at (x, y), and
+///   `stop_animation(animation)` stops every playing animation with that name.
+/// * `start_battle(troop, [can_flee], [can_lose])` fights a troop, which the party may flee from
+///   unless `can_flee` is false. Losing ends the game unless `can_lose` is true, as for a battle
+///   the story carries on after. When the battle is over, the game's
+///   `on_battle_end(troop, outcome)` is called, if it has one; see `scene::battle`.
+/// * `give_exp(character, amount)` gives a party member EXP. For each level they reach, the
+///   game's `on_level_up(character, level)` is called, if it has one. `recover_party()` restores
+///   all of every party member's HP and MP, such as at an inn.
+/// * `open_shop(items, [prices])` opens a shop selling a list of items, by name, at their prices
+///   in the database, or those in `prices`, a table of item names to prices; see
+///   `game_state::shop`.
+/// * `open_layout(layout, [call])` shows a screen of widgets described by a table (see
+///   `ui::layout`) until the player chooses from one of its lists or backs out. Then the global
+///   function named by `call` is called, if there is one, with the id of the list and the
+///   choice's index, counting from 1, or with nothing if the player backed out.
+pub fn install_command_functions(environment: &mut Lua, queue: &CommandQueue) {
+    let queue = queue.clone();
+    environment.set(
+        "__deucalion_push_command",
+        hlua::function1(move |arguments: AnyLuaValue| {
+            match ScriptCommand::from_lua(&arguments) {
+                Ok(command) => {
+                    queue.push(command);
+                    None
+                }
+                Err(message) => Some(message),
+            }
+        }),
+    );
+    // This is the engine's own code, so failing is a bug in the engine rather than the game.
+    environment
+        .execute::<()>(COMMAND_FUNCTIONS)
+        .expect("The command functions could not be installed");
+}
@@ -0,0 +1,266 @@
+//! Conversions between the engine's own types and Lua values, for passing positions, sizes and
+//! directions to and from scripts.
+//!
+//! Points and sizes are tables with `x`/`y` and `width`/`height` fields; directions are strings
+//! like "up", or "up_left" for the diagonals of an `EightDirection`. Directions implement hlua's
+//! `Push` and `LuaRead`, so they can be given to `Lua::set` and read with `Lua::get` like any other
+//! value. The geom types are euclid's, which can't implement hlua's traits here, so they're
+//! passed wrapped in `LuaGeom`. `get_global` reads any of them, reporting what was expected if the
+//! value is the wrong shape.
+
+use hlua::{self, AnyLuaValue, AsMutLua, Lua, LuaRead, Push, PushGuard, PushOne, Void};
+
+use error::DeucalionError;
+use geom::{ScreenPoint, ScreenSize, WorldPoint, WorldSize};
+use utility::direction::Direction;
+use utility::eight_direction::EightDirection;
+
+/// A geom value, wrapped so that it can be pushed to and read from Lua.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct LuaGeom<T>(pub T);
+
+/// A type that has a Lua form, described for error messages.
+pub trait LuaTypeName {
+    /// A description of the Lua value expected.
+    fn lua_type_name() -> &'static str;
+}
+
+// Implement `LuaTypeName`, `Push`, `PushOne` and `LuaRead` for a type, given its description and
+//  functions converting it to and from an `AnyLuaValue`.
+macro_rules! impl_lua_value {
+    ($t:ty, $name:expr, $to_lua:expr, $from_lua:expr) => {
+        impl LuaTypeName for $t {
+            fn lua_type_name() -> &'static str {
+                $name
+            }
+        }
+
+        impl<'lua, L: AsMutLua<'lua>> Push<L> for $t {
+            type Err = Void;
+
+            fn push_to_lua(self, lua: L) -> Result<PushGuard<L>, (Void, L)> {
+                let to_lua: fn($t) -> AnyLuaValue = $to_lua;
+                to_lua(self).push_to_lua(lua)
+            }
+        }
+
+        impl<'lua, L: AsMutLua<'lua>> PushOne<L> for $t {}
+
+        impl<'lua, L: AsMutLua<'lua>> LuaRead<L> for $t {
+            fn lua_read_at_position(lua: L, index: i32) -> Result<$t, L> {
+                let from_lua: fn(&AnyLuaValue) -> Option<$t> = $from_lua;
+                read_with(lua, index, from_lua)
+            }
+        }
+    };
+}
+
+// Read the value at `index` and convert it with `convert`, handing the Lua context back if it
+//  isn't the right shape, as `LuaRead` requires.
+fn read_with<'lua, L, T>(
+    mut lua: L,
+    index: i32,
+    convert: fn(&AnyLuaValue) -> Option<T>,
+) -> Result<T, L>
+where
+    L: AsMutLua<'lua>,
+{
+    let value = AnyLuaValue::lua_read_at_position(&mut lua, index).ok();
+    match value.as_ref().and_then(convert) {
+        Some(converted) => Ok(converted),
+        None => Err(lua),
+    }
+}
+
+/// Get a field of a Lua table by name, or nil if the value isn't a table or has no such field.
+pub fn get_table_field(table: &AnyLuaValue, name: &str) -> AnyLuaValue {
+    match *table {
+        AnyLuaValue::LuaArray(ref fields) => fields
+            .iter()
+            .find(|&&(ref key, _)| match *key {
+                AnyLuaValue::LuaString(ref key) => key == name,
+                _ => false,
+            })
+            .map(|&(_, ref value)| value.clone())
+            .unwrap_or(AnyLuaValue::LuaNil),
+        _ => AnyLuaValue::LuaNil,
+    }
+}
+
+/// Build a Lua table with the given named fields.
+pub fn make_table(fields: Vec<(&str, AnyLuaValue)>) -> AnyLuaValue {
+    AnyLuaValue::LuaArray(
+        fields
+            .into_iter()
+            .map(|(name, value)| (AnyLuaValue::LuaString(String::from(name)), value))
+            .collect(),
+    )
+}
+
+// Read a number field as an f32.
+fn get_float_field(table: &AnyLuaValue, name: &str) -> Option<f32> {
+    match get_table_field(table, name) {
+        AnyLuaValue::LuaNumber(n) => Some(n as f32),
+        _ => None,
+    }
+}
+
+// Read a number field as a u32, if it's a whole, non-negative number.
+fn get_unsigned_field(table: &AnyLuaValue, name: &str) -> Option<u32> {
+    match get_table_field(table, name) {
+        AnyLuaValue::LuaNumber(n)
+            if n.fract() == 0.0 && n >= 0.0 && n <= u32::max_value() as f64 =>
+        {
+            Some(n as u32)
+        }
+        _ => None,
+    }
+}
+
+impl_lua_value!(
+    LuaGeom<WorldPoint>,
+    "table with numeric x and y fields",
+    |LuaGeom(point)| {
+        make_table(vec![
+            ("x", AnyLuaValue::LuaNumber(f64::from(point.x))),
+            ("y", AnyLuaValue::LuaNumber(f64::from(point.y))),
+        ])
+    },
+    |value| {
+        Some(LuaGeom(WorldPoint::new(
+            get_float_field(value, "x")?,
+            get_float_field(value, "y")?,
+        )))
+    }
+);
+
+impl_lua_value!(
+    LuaGeom<WorldSize>,
+    "table with numeric width and height fields",
+    |LuaGeom(size)| {
+        make_table(vec![
+            ("width", AnyLuaValue::LuaNumber(f64::from(size.width))),
+            ("height", AnyLuaValue::LuaNumber(f64::from(size.height))),
+        ])
+    },
+    |value| {
+        Some(LuaGeom(WorldSize::new(
+            get_float_field(value, "width")?,
+            get_float_field(value, "height")?,
+        )))
+    }
+);
+
+impl_lua_value!(
+    LuaGeom<ScreenPoint>,
+    "table with non-negative integer x and y fields",
+    |LuaGeom(point)| {
+        make_table(vec![
+            ("x", AnyLuaValue::LuaNumber(f64::from(point.x))),
+            ("y", AnyLuaValue::LuaNumber(f64::from(point.y))),
+        ])
+    },
+    |value| {
+        Some(LuaGeom(ScreenPoint::new(
+            get_unsigned_field(value, "x")?,
+            get_unsigned_field(value, "y")?,
+        )))
+    }
+);
+
+impl_lua_value!(
+    LuaGeom<ScreenSize>,
+    "table with non-negative integer width and height fields",
+    |LuaGeom(size)| {
+        make_table(vec![
+            ("width", AnyLuaValue::LuaNumber(f64::from(size.width))),
+            ("height", AnyLuaValue::LuaNumber(f64::from(size.height))),
+        ])
+    },
+    |value| {
+        Some(LuaGeom(ScreenSize::new(
+            get_unsigned_field(value, "width")?,
+            get_unsigned_field(value, "height")?,
+        )))
+    }
+);
+
+impl_lua_value!(
+    Direction,
+    "direction (one of \"up\", \"down\", \"left\" or \"right\")",
+    |direction| AnyLuaValue::LuaString(String::from(direction.name())),
+    |value| match *value {
+        AnyLuaValue::LuaString(ref name) => Direction::from_name(name),
+        _ => None,
+    }
+);
+
+impl_lua_value!(
+    EightDirection,
+    "direction (one of \"up\", \"up_right\", \"right\", \"down_right\", \"down\", \
+     \"down_left\", \"left\" or \"up_left\")",
+    |direction| AnyLuaValue::LuaString(String::from(direction.name())),
+    |value| match *value {
+        AnyLuaValue::LuaString(ref name) => EightDirection::from_name(name),
+        _ => None,
+    }
+);
+
+/// Read a global from the environment, reporting a `DeucalionError::LuaGetFailed` with the
+/// identifier and the expected type if it's missing or the wrong shape.
+pub fn get_global<'lua, T>(
+    environment: &mut Lua<'lua>,
+    identifier: &str,
+) -> Result<T, DeucalionError>
+where
+    T: LuaTypeName + for<'l> LuaRead<PushGuard<&'l mut Lua<'lua>>>,
+{
+    environment.get(identifier).ok_or_else(|| {
+        DeucalionError::LuaGetFailed(String::from(identifier), String::from(T::lua_type_name()))
+    })
+}
+
+// Builds the `direction` table scripts use, from the Rust functions given to it, which are then
+//  removed from the globals.
+const DIRECTION_HELPERS: &'static str = r#"
+local reverse, to_angle = __deucalion_direction_reverse, __deucalion_direction_to_angle
+__deucalion_direction_reverse, __deucalion_direction_to_angle = nil, nil
+local function check(d, result)
+    if result == nil then
+        error("'" .. tostring(d) .. "' is not a direction", 3)
+    end
+    return result
+end
+direction = {
+    up = "up",
+    down = "down",
+    left = "left",
+    right = "right",
+    reverse = function(d)
+        local result = check(d, reverse(tostring(d)))
+        return result
+    end,
+    to_angle = function(d)
+        local result = check(d, to_angle(tostring(d)))
+        return result
+    end,
+}
+"#;
+
+/// Give an environment the `direction` table, with `direction.reverse(d)` and
+/// `direction.to_angle(d)` mirroring `Direction::reverse` and `Direction::to_angle`, and the
+/// names of the directions as `direction.up` and so on.
+pub fn install_direction_helpers(environment: &mut Lua) {
+    environment.set(
+        "__deucalion_direction_reverse",
+        hlua::function1(|name: String| Direction::from_name(&name).map(|d| d.reverse().name())),
+    );
+    environment.set(
+        "__deucalion_direction_to_angle",
+        hlua::function1(|name: String| Direction::from_name(&name).map(|d| d.to_angle() as u32)),
+    );
+    // This is the engine's own code, so failing is a bug in the engine rather than the game.
+    environment
+        .execute::<()>(DIRECTION_HELPERS)
+        .expect("The direction helpers could not be installed");
+}
@@ -0,0 +1,182 @@
+//! The functions game scripts use to read and change the party's items, equipment and gold.
+//!
+//! Unlike the functions in `scripting::commands`, these act on the game state straight away:
+//! the engine shares it with every environment, and it's never in the middle of being changed
+//! while a script runs. A script can check what the party has with `has_item` and see its own
+//! changes at once. Argument errors, and items that aren't in the database, are raised in the
+//! script, at the line that called the function, like any other Lua error.
+
+use std::rc::Rc;
+
+use hlua::{self, AnyLuaValue, Lua};
+
+use battle::database::{Database, EquipSlot};
+use error::DeucalionError;
+use game_state::SharedGameState;
+use scripting::commands::{non_negative_number, required_string};
+use scripting::convert::{get_table_field, make_table};
+
+/// Carry out an item function for a script, by the name in the `function` field of the table
+/// its Lua side builds. Returns the function's result, or a message for the script if it failed.
+pub fn call_item_function(
+    arguments: &AnyLuaValue,
+    state: &SharedGameState,
+    database: &Database,
+) -> Result<AnyLuaValue, String> {
+    let name = match get_table_field(arguments, "function") {
+        AnyLuaValue::LuaString(name) => name,
+        _ => return Err(String::from("malformed item function")),
+    };
+    let mut state = state.borrow_mut();
+    match name.as_str() {
+        "give_item" => {
+            let item = required_string(arguments, "item")?;
+            let given = state
+                .give_item(&item, count(arguments)?, database)
+                .map_err(message)?;
+            Ok(AnyLuaValue::LuaNumber(f64::from(given)))
+        }
+        "remove_item" => {
+            let item = known_item(arguments, database)?;
+            let removed = state.inventory.remove(&item, count(arguments)?);
+            Ok(AnyLuaValue::LuaBoolean(removed))
+        }
+        "has_item" => {
+            let item = known_item(arguments, database)?;
+            let has = state.inventory.has(&item, count(arguments)?);
+            Ok(AnyLuaValue::LuaBoolean(has))
+        }
+        "item_count" => {
+            let item = known_item(arguments, database)?;
+            Ok(AnyLuaValue::LuaNumber(f64::from(state.inventory.count(&item))))
+        }
+        "equip" => {
+            let character = required_string(arguments, "character")?;
+            let item = required_string(arguments, "item")?;
+            let equipped = state.equip(&character, &item, database).map_err(message)?;
+            Ok(AnyLuaValue::LuaBoolean(equipped))
+        }
+        "unequip" => {
+            let character = required_string(arguments, "character")?;
+            let slot = EquipSlot::from_name(&required_string(arguments, "slot")?)
+                .ok_or_else(|| {
+                    String::from("slot must be one of weapon, shield, head, body or accessory")
+                })?;
+            let unequipped = state.unequip(&character, slot, database).map_err(message)?;
+            Ok(AnyLuaValue::LuaBoolean(unequipped))
+        }
+        "give_gold" => {
+            let given = state.give_gold(non_negative_number(arguments, "amount")?);
+            Ok(AnyLuaValue::LuaNumber(f64::from(given)))
+        }
+        "take_gold" => {
+            let taken = state.spend_gold(non_negative_number(arguments, "amount")?);
+            Ok(AnyLuaValue::LuaBoolean(taken))
+        }
+        "get_gold" => Ok(AnyLuaValue::LuaNumber(f64::from(state.gold))),
+        _ => Err(format!("unknown item function '{}'", name)),
+    }
+}
+
+// Read the item argument, which must be in the database.
+fn known_item(arguments: &AnyLuaValue, database: &Database) -> Result<String, String> {
+    let item = required_string(arguments, "item")?;
+    match database.item(&item) {
+        Some(_) => Ok(item),
+        None => Err(format!("there is no item named '{}'", item)),
+    }
+}
+
+// Read the count argument, which is 1 if it's left out.
+fn count(arguments: &AnyLuaValue) -> Result<u32, String> {
+    match get_table_field(arguments, "count") {
+        AnyLuaValue::LuaNil => Ok(1),
+        _ => non_negative_number(arguments, "count"),
+    }
+}
+
+// Describe an error for the script that caused it.
+fn message(error: DeucalionError) -> String {
+    match error {
+        DeucalionError::OtherError(message) => message,
+        error => error.to_string(),
+    }
+}
+
+// Defines the functions scripts call, in terms of the Rust function given to it, which is then
+//  removed from the globals.
+const ITEM_FUNCTIONS: &'static str = r#"
+local call = __deucalion_call_item_function
+__deucalion_call_item_function = nil
+local function item_function(arguments)
+    local outcome = call(arguments)
+    if outcome.message ~= nil then
+        error(outcome.message, 3)
+    end
+    return outcome.result
+end
+function give_item(item, count)
+    return item_function({ ["function"] = "give_item", item = item, count = count })
+end
+function remove_item(item, count)
+    return item_function({ ["function"] = "remove_item", item = item, count = count })
+end
+function has_item(item, count)
+    return item_function({ ["function"] = "has_item", item = item, count = count })
+end
+function item_count(item)
+    return item_function({ ["function"] = "item_count", item = item })
+end
+function equip(character, item)
+    return item_function({ ["function"] = "equip", character = character, item = item })
+end
+function unequip(character, slot)
+    return item_function({ ["function"] = "unequip", character = character, slot = slot })
+end
+function give_gold(amount)
+    return item_function({ ["function"] = "give_gold", amount = amount })
+end
+function take_gold(amount)
+    return item_function({ ["function"] = "take_gold", amount = amount })
+end
+function get_gold()
+    return item_function({ ["function"] = "get_gold" })
+end
+"#;
+
+/// Give an environment the item functions, acting on `state` with the items in `database`:
+///
+/// * `give_item(item, [count])` gives the party one of an item, or `count`, as many as will fit
+///   within its stack limit, and returns how many were given.
+/// * `remove_item(item, [count])` takes away one of an item, or `count`, and returns true, or
+///   returns false, taking nothing, if the party has fewer.
+/// * `has_item(item, [count])` returns whether the party has at least one of an item, or
+///   `count`, and `item_count(item)` how many they have.
+/// * `equip(character, item)` puts an item the party has on a party member, taking off what they
+///   were wearing in its slot, and returns false if the party doesn't have it.
+///   `unequip(character, slot)` takes off what they're wearing in a slot, and returns false if
+///   there's nothing there.
+/// * `give_gold(amount)` gives the party gold, as much as they can carry, and returns how much
+///   was given. `take_gold(amount)` takes it away and returns true, or returns false, taking
+///   nothing, if they have less. `get_gold()` returns how much they have.
+pub fn install_item_functions(
+    environment: &mut Lua,
+    state: &SharedGameState,
+    database: &Rc<Database>,
+) {
+    let state = state.clone();
+    let database = database.clone();
+    environment.set(
+        "__deucalion_call_item_function",
+        hlua::function1(move |arguments: AnyLuaValue| {
+            match call_item_function(&arguments, &state, &database) {
+                Ok(result) => make_table(vec![("result", result)]),
+                Err(message) => make_table(vec![("message", AnyLuaValue::LuaString(message))]),
+            }
+        }),
+    );
+    // This is the engine's own code, so failing is a bug in the engine rather than the game.
+    environment
+        .execute::<()>(ITEM_FUNCTIONS)
+        .expect("The item functions could not be installed");
+}
@@ -1,4 +1,26 @@
 pub mod basic;
+pub mod budget;
+pub mod commands;
+pub mod convert;
+pub mod items;
+pub mod modules;
+pub mod sandbox;
+pub mod serialize;
 mod test_basic;
+#[cfg(test)]
+mod test_budget;
+#[cfg(test)]
+mod test_commands;
+#[cfg(test)]
+mod test_convert;
+#[cfg(test)]
+mod test_items;
+#[cfg(test)]
+mod test_modules;
+#[cfg(test)]
+mod test_sandbox;
+#[cfg(test)]
+mod test_serialize;
 pub use hlua::Lua;
-pub use scripting::basic::{execute_script, get_scripting_environment, get_value_by_identifier};
+pub use scripting::basic::{execute_script, execute_script_from_reader, get_scripting_environment,
+                           get_value_by_identifier, get_value_or_default};
@@ -0,0 +1,140 @@
+//! Finding the modules game scripts `require`. A module name like "battle.formulas" is looked
+//! up as battle/formulas.lua under each module root in turn: first the roots of any mods, so
+//! that mods can replace the game's own modules, then data/scripts. Everything is read through
+//! `resource::loading`, so modules work the same from the data directory or a data archive.
+//!
+//! Module sources are cached after they're first read, so every environment that requires a
+//! module doesn't read it again. In developer mode, `clear_module_cache` lets changed modules be
+//! picked up without restarting.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+use hlua::AnyLuaValue;
+
+use error::DeucalionError;
+use resource::loading;
+use resource::ResourceKind;
+
+thread_local! {
+    // The sources of modules that have been read, by their path relative to the data directory.
+    static MODULE_SOURCES: RefCell<HashMap<PathBuf, String>> = RefCell::new(HashMap::new());
+}
+
+/// Get the path of a module relative to a module root, e.g. battle/formulas.lua for
+/// "battle.formulas". Names that could escape the module root are rejected.
+pub fn get_module_relative_path(name: &str) -> Result<PathBuf, DeucalionError> {
+    let mut path = PathBuf::new();
+    for component in name.split('.') {
+        let valid = !component.is_empty()
+            && component
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if !valid {
+            return Err(DeucalionError::from(format!(
+                "'{}' is not a valid module name",
+                name
+            )));
+        }
+        path.push(component);
+    }
+    path.set_extension("lua");
+    Ok(path)
+}
+
+/// Whether a mod root can be used: it must be relative to the data directory, and mustn't leave it
+/// with `..`. Anything else would let `require` read files outside the game's data.
+pub fn is_valid_mod_root(root: &str) -> bool {
+    !root.is_empty()
+        && Path::new(root)
+            .components()
+            .all(|component| match component {
+                Component::Normal(_) | Component::CurDir => true,
+                Component::ParentDir | Component::RootDir | Component::Prefix(_) => false,
+            })
+}
+
+/// Get the module roots, relative to the data directory, in the order they're searched: the
+/// given mod roots, then the game's own scripts directory.
+pub fn get_module_roots(mod_roots: &[String]) -> Vec<PathBuf> {
+    let mut roots: Vec<PathBuf> = mod_roots.iter().map(PathBuf::from).collect();
+    // The scripts directory is wherever the Script resource kind says init.lua is.
+    let scripts = loading::get_resource_relative_path_by_name(ResourceKind::Script, "init")
+        .ok()
+        .and_then(|path| path.parent().map(Path::to_path_buf))
+        .unwrap_or_else(|| PathBuf::from("scripts"));
+    roots.push(scripts);
+    roots
+}
+
+/// Find a module under the first root that has it, using `read` to read files by their path
+/// relative to the data directory. Returns the module's path relative to the data directory, and
+/// its source.
+pub fn find_module<F>(
+    roots: &[PathBuf],
+    name: &str,
+    mut read: F,
+) -> Result<(PathBuf, String), DeucalionError>
+where
+    F: FnMut(&Path) -> Result<Vec<u8>, DeucalionError>,
+{
+    let relative_path = get_module_relative_path(name)?;
+    for root in roots.iter() {
+        let path = root.join(&relative_path);
+        if let Ok(data) = read(&path) {
+            let source = String::from_utf8(data).map_err(|_| {
+                DeucalionError::from(format!(
+                    "module '{}' at {} is not UTF-8",
+                    name,
+                    path.display()
+                ))
+            })?;
+            return Ok((path, source));
+        }
+    }
+    Err(DeucalionError::from(format!(
+        "module '{}' was not found in any of the module roots {:?}",
+        name, roots
+    )))
+}
+
+/// Read a file from the data source, or from the cache if it's been read before.
+fn read_cached(path: &Path) -> Result<Vec<u8>, DeucalionError> {
+    if let Some(source) = MODULE_SOURCES.with(|cache| cache.borrow().get(path).cloned()) {
+        return Ok(source.into_bytes());
+    }
+    let data = loading::read_data_file(path)?;
+    if let Ok(source) = String::from_utf8(data.clone()) {
+        MODULE_SOURCES.with(|cache| cache.borrow_mut().insert(path.to_path_buf(), source));
+    }
+    Ok(data)
+}
+
+/// Forget every cached module source, so that modules are read again next time they're
+/// required.
+pub fn clear_module_cache() {
+    MODULE_SOURCES.with(|cache| cache.borrow_mut().clear());
+}
+
+/// Find a module for the sandboxed `require`, returning a table with its `path` and `source`,
+/// or nil. Problems are reported to the developer here, since the script only sees that the
+/// module couldn't be loaded.
+pub fn read_module_for_lua(roots: &[PathBuf], name: String) -> AnyLuaValue {
+    match find_module(roots, &name, read_cached) {
+        Ok((path, source)) => AnyLuaValue::LuaArray(vec![
+            (
+                AnyLuaValue::LuaString(String::from("path")),
+                AnyLuaValue::LuaString(path.to_string_lossy().replace('\\', "/")),
+            ),
+            (
+                AnyLuaValue::LuaString(String::from("source")),
+                AnyLuaValue::LuaString(source),
+            ),
+        ]),
+        Err(e) => {
+            warn!("A script failed to require module '{}': {}", name, e);
+            AnyLuaValue::LuaNil
+        }
+    }
+}
@@ -0,0 +1,379 @@
+//! Sandboxed Lua environments for game scripts. Game scripts, unlike the engine's own
+//! configuration, may come from mods or downloaded content, so they only get the standard
+//! libraries the developer allows, never get `os.execute` or any way to load native code, and can
+//! only `require` modules from inside the game's data (see `scripting::modules`). Each map and
+//! character gets its own environment, so that scripts can't interfere with each other's globals.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use hlua::{self, AnyLuaValue, Lua};
+
+use battle::database::Database;
+use config::schema::ConfigValue;
+use error::DeucalionError;
+use game_state::SharedGameState;
+use resource::loading;
+use scripting::basic::execute_script_from_reader;
+use scripting::budget::ScriptBudget;
+use scripting::commands::{self, CommandQueue, ScriptCommand};
+use scripting::convert;
+use scripting::items;
+use scripting::modules;
+
+/// A Lua standard library that may be opened in a sandboxed environment.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Hash)]
+pub enum LuaLibrary {
+    /// The basic functions (`print`, `pairs`, `pcall`, ...). Always opened, since nothing works
+    /// without it; `dofile` and `loadfile` are removed, and `load` only accepts source text.
+    Base,
+    Coroutine,
+    Table,
+    String,
+    Math,
+    Bit32,
+    /// The operating system library. Even when allowed, only the clock and date functions are
+    /// kept; nothing that runs programs, touches files or reads the environment is.
+    Os,
+    /// File access. Only for trusted games; this gives scripts the player's whole filesystem.
+    Io,
+    /// The debug library. Only for trusted games; it can break out of any sandbox.
+    Debug,
+}
+
+impl LuaLibrary {
+    /// Parse a library from the name used for it in game_config.lua, which is its Lua name.
+    pub fn from_name(name: &str) -> Option<LuaLibrary> {
+        match name {
+            "base" => Some(LuaLibrary::Base),
+            "coroutine" => Some(LuaLibrary::Coroutine),
+            "table" => Some(LuaLibrary::Table),
+            "string" => Some(LuaLibrary::String),
+            "math" => Some(LuaLibrary::Math),
+            "bit32" => Some(LuaLibrary::Bit32),
+            "os" => Some(LuaLibrary::Os),
+            "io" => Some(LuaLibrary::Io),
+            "debug" => Some(LuaLibrary::Debug),
+            _ => None,
+        }
+    }
+
+    /// The name used for this library in game_config.lua.
+    pub fn name(self) -> &'static str {
+        match self {
+            LuaLibrary::Base => "base",
+            LuaLibrary::Coroutine => "coroutine",
+            LuaLibrary::Table => "table",
+            LuaLibrary::String => "string",
+            LuaLibrary::Math => "math",
+            LuaLibrary::Bit32 => "bit32",
+            LuaLibrary::Os => "os",
+            LuaLibrary::Io => "io",
+            LuaLibrary::Debug => "debug",
+        }
+    }
+
+    /// Open the library in an environment.
+    fn open(self, environment: &mut Lua) {
+        match self {
+            LuaLibrary::Base => environment.open_base(),
+            LuaLibrary::Coroutine => environment.open_coroutine(),
+            LuaLibrary::Table => environment.open_table(),
+            LuaLibrary::String => environment.open_string(),
+            LuaLibrary::Math => environment.open_math(),
+            LuaLibrary::Bit32 => environment.open_bit32(),
+            LuaLibrary::Os => environment.open_os(),
+            LuaLibrary::Io => environment.open_io(),
+            LuaLibrary::Debug => environment.open_debug(),
+        }
+    }
+}
+
+impl ConfigValue for LuaLibrary {
+    fn type_name() -> String {
+        String::from("name of a Lua standard library")
+    }
+
+    fn from_lua(value: AnyLuaValue) -> Option<LuaLibrary> {
+        String::from_lua(value).and_then(|name| LuaLibrary::from_name(&name))
+    }
+}
+
+/// The libraries game scripts get unless the developer says otherwise: everything that can't
+/// reach outside the game.
+pub fn get_default_script_libraries() -> Vec<LuaLibrary> {
+    vec![
+        LuaLibrary::Base,
+        LuaLibrary::Coroutine,
+        LuaLibrary::Table,
+        LuaLibrary::String,
+        LuaLibrary::Math,
+        LuaLibrary::Bit32,
+        LuaLibrary::Os,
+    ]
+}
+
+// Run in every sandboxed environment after its libraries are opened. It removes everything that
+//  could reach outside the game, and replaces `require` with one that only loads modules through
+//  __deucalion_read_module, which is removed again so scripts can't call it themselves.
+//  __deucalion_traceback is left for `scripting::basic`; it can only describe the stack. So is
+//  __deucalion_load, which loads text chunks without the `load` wrapper scripts can replace.
+//  __deucalion_modules is the table of loaded modules, left so it can be emptied on reload.
+const SANDBOX_SETUP: &'static str = r#"
+-- The debug library is always opened so that errors can have tracebacks, but scripts only keep it
+--  if the developer allows it.
+__deucalion_traceback = debug.traceback
+if not __deucalion_keep_debug then
+    debug = nil
+end
+__deucalion_keep_debug = nil
+dofile = nil
+loadfile = nil
+local raw_load = load
+-- load only sets a chunk's _ENV when it's given a fourth argument, even a nil one, so env is only
+--  passed on if the caller gave it.
+load = function(chunk, name, mode, ...)
+    if select("#", ...) > 0 then
+        return raw_load(chunk, name, "t", ...)
+    end
+    return raw_load(chunk, name, "t")
+end
+__deucalion_load = function(chunk, name)
+    return raw_load(chunk, name, "t")
+end
+if os then
+    os.execute = nil
+    os.exit = nil
+    os.remove = nil
+    os.rename = nil
+    os.tmpname = nil
+    os.getenv = nil
+    os.setlocale = nil
+end
+package = nil
+
+local read_module = __deucalion_read_module
+__deucalion_read_module = nil
+local loaded = {}
+__deucalion_modules = loaded
+function require(name)
+    name = tostring(name)
+    if loaded[name] ~= nil then
+        return loaded[name]
+    end
+    local module = read_module(name)
+    if module == nil then
+        error("module '" .. name .. "' could not be loaded from the game data", 2)
+    end
+    local chunk = assert(raw_load(module.source, "@" .. module.path, "t"))
+    local result = chunk(name)
+    if result == nil then
+        result = true
+    end
+    loaded[name] = result
+    return result
+end
+"#;
+
+// Empties the table of loaded modules, so every module is run again the next time it's required.
+const CLEAR_LOADED_MODULES: &'static str = r#"
+local loaded = __deucalion_modules
+if type(loaded) == "table" then
+    for name in pairs(loaded) do
+        loaded[name] = nil
+    end
+end
+"#;
+
+// Calls the global function named by __deucalion_hook_name, if there is one, with the arguments in
+//  __deucalion_hook_arguments, a list of __deucalion_hook_count values. The table library may not
+//  be open, so the arguments are unpacked by hand.
+const HOOK_CALLER: &'static str = r#"
+local name, arguments = __deucalion_hook_name, __deucalion_hook_arguments
+local count = __deucalion_hook_count
+__deucalion_hook_name, __deucalion_hook_arguments, __deucalion_hook_count = nil, nil, nil
+local hook = _ENV[name]
+if type(hook) ~= "function" then
+    return { defined = false }
+end
+local function unpack_from(i)
+    if i <= count then
+        return arguments[i], unpack_from(i + 1)
+    end
+end
+return { defined = true, result = hook(unpack_from(1)) }
+"#;
+
+/// Create a sandboxed environment with the given standard libraries, whose `require` looks for
+/// modules under `module_roots` (see `scripting::modules::get_module_roots`).
+pub fn get_sandboxed_environment<'environment>(
+    libraries: &[LuaLibrary],
+    module_roots: &[PathBuf],
+) -> Lua<'environment> {
+    let mut environment = Lua::new();
+    LuaLibrary::Base.open(&mut environment);
+    LuaLibrary::Debug.open(&mut environment);
+    for library in libraries
+        .iter()
+        .filter(|l| **l != LuaLibrary::Base && **l != LuaLibrary::Debug)
+    {
+        library.open(&mut environment);
+    }
+    let module_roots = module_roots.to_vec();
+    environment.set(
+        "__deucalion_read_module",
+        hlua::function1(move |name: String| modules::read_module_for_lua(&module_roots, name)),
+    );
+    environment.set(
+        "__deucalion_keep_debug",
+        libraries.contains(&LuaLibrary::Debug),
+    );
+    // This is the engine's own code, so failing is a bug in the engine rather than the game.
+    environment
+        .execute::<()>(SANDBOX_SETUP)
+        .expect("The script sandbox could not be set up");
+    convert::install_direction_helpers(&mut environment);
+    trace!(
+        "get_sandboxed_environment created a Lua environment with libraries {:?}",
+        libraries
+    );
+    environment
+}
+
+/// What a sandboxed environment belongs to. Scripts belonging to different owners never share
+/// globals.
+#[derive(PartialEq, Eq, Debug, Clone, Hash)]
+pub enum ScriptOwner {
+    /// The game as a whole, such as data/scripts/init.lua.
+    Game,
+    /// A map, by name.
+    Map(String),
+    /// A character, by name.
+    Character(String),
+}
+
+/// The sandboxed environments of every script owner, created as they're first needed.
+pub struct ScriptEnvironments {
+    libraries: Vec<LuaLibrary>,
+    module_roots: Vec<PathBuf>,
+    budget: ScriptBudget,
+    environments: HashMap<ScriptOwner, Lua<'static>>,
+    commands: CommandQueue,
+    game_state: SharedGameState,
+    database: Rc<Database>,
+}
+
+impl ScriptEnvironments {
+    /// Create environments that will open the given standard libraries, require modules from
+    /// `module_roots`, and in which each script invocation runs within `budget`.
+    pub fn new(
+        libraries: &[LuaLibrary],
+        module_roots: &[PathBuf],
+        budget: ScriptBudget,
+    ) -> ScriptEnvironments {
+        ScriptEnvironments {
+            libraries: libraries.to_vec(),
+            module_roots: module_roots.to_vec(),
+            budget: budget,
+            environments: HashMap::new(),
+            commands: CommandQueue::new(),
+            game_state: SharedGameState::default(),
+            database: Rc::new(Database::default()),
+        }
+    }
+
+    /// Have scripts read and change a game state, with the items in a database, rather than an
+    /// empty state of their own. See `scripting::items`.
+    pub fn with_game_state(
+        mut self,
+        game_state: &SharedGameState,
+        database: &Rc<Database>,
+    ) -> ScriptEnvironments {
+        self.game_state = game_state.clone();
+        self.database = database.clone();
+        self
+    }
+
+    /// Run a script, by its path relative to the data directory, in an owner's environment.
+    pub fn execute<P: AsRef<Path>>(
+        &mut self,
+        owner: &ScriptOwner,
+        relative_path: P,
+    ) -> Result<AnyLuaValue, DeucalionError> {
+        let reader = loading::open_data_file(relative_path.as_ref())?;
+        let budget = self.budget;
+        execute_script_from_reader(self.get(owner), reader, relative_path.as_ref(), &budget)
+    }
+
+    /// Call a global function in an owner's environment, such as a hook the game defines in
+    /// data/scripts/init.lua, returning its first result. Returns None, without an error, if the
+    /// function isn't defined, since games only define the hooks they need.
+    pub fn call_function(
+        &mut self,
+        owner: &ScriptOwner,
+        name: &str,
+        arguments: Vec<AnyLuaValue>,
+    ) -> Result<Option<AnyLuaValue>, DeucalionError> {
+        let budget = self.budget;
+        let environment = self.get(owner);
+        environment.set("__deucalion_hook_name", String::from(name));
+        environment.set("__deucalion_hook_count", arguments.len() as f64);
+        environment.set(
+            "__deucalion_hook_arguments",
+            AnyLuaValue::LuaArray(
+                arguments
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, value)| (AnyLuaValue::LuaNumber((i + 1) as f64), value))
+                    .collect(),
+            ),
+        );
+        // The hook's own errors are reported against its own script; this name only shows up if
+        //  the caller itself fails.
+        let path = PathBuf::from(format!("<{}>", name));
+        let outcome =
+            execute_script_from_reader(environment, HOOK_CALLER.as_bytes(), &path, &budget)?;
+        match convert::get_table_field(&outcome, "defined") {
+            AnyLuaValue::LuaBoolean(true) => {
+                Ok(Some(convert::get_table_field(&outcome, "result")))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Get the environment belonging to an owner, creating it if it doesn't exist yet.
+    pub fn get(&mut self, owner: &ScriptOwner) -> &mut Lua<'static> {
+        if !self.environments.contains_key(owner) {
+            debug!("Creating a script environment for {:?}", owner);
+            let mut environment = get_sandboxed_environment(&self.libraries, &self.module_roots);
+            commands::install_command_functions(&mut environment, &self.commands);
+            items::install_item_functions(&mut environment, &self.game_state, &self.database);
+            self.environments.insert(owner.clone(), environment);
+        }
+        self.environments.get_mut(owner).unwrap()
+    }
+
+    /// Remove and return the commands scripts have queued since this was last called, oldest
+    /// first. See `scripting::commands`.
+    pub fn take_commands(&mut self) -> Vec<ScriptCommand> {
+        self.commands.take()
+    }
+
+    /// Throw away an owner's environment, such as when leaving a map. Its scripts start over
+    /// with fresh globals next time.
+    pub fn remove(&mut self, owner: &ScriptOwner) {
+        self.environments.remove(owner);
+    }
+
+    /// Forget every loaded module, in every environment, so that changed modules are read and
+    /// run again the next time they're required. Used for reloading in developer mode.
+    pub fn clear_module_caches(&mut self) {
+        modules::clear_module_cache();
+        for (owner, environment) in self.environments.iter_mut() {
+            if let Err(e) = environment.execute::<()>(CLEAR_LOADED_MODULES) {
+                warn!("Failed to clear the loaded modules of {:?}: {:?}", owner, e);
+            }
+        }
+    }
+}
@@ -0,0 +1,86 @@
+//! Writing values back out as Lua source, so that files the engine writes (such as the player's
+//! configuration) can be read back with the same machinery as files the developer writes.
+
+use hlua::AnyLuaValue;
+
+/// Quote a string as a Lua string literal.
+pub fn quote_string(string: &str) -> String {
+    let mut quoted = String::with_capacity(string.len() + 2);
+    quoted.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            // Lua 5.2 has no unicode escapes, but decimal escapes cover the control characters.
+            c if (c as u32) < 0x20 || c as u32 == 0x7f => {
+                quoted.push_str(&format!("\\{:03}", c as u32))
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Write a value as a Lua expression. Tables are written with one entry per line, indented by
+/// `indent` levels. Values that can't be written as source (functions, userdata) become `nil`.
+pub fn to_lua_source(value: &AnyLuaValue, indent: usize) -> String {
+    match *value {
+        AnyLuaValue::LuaString(ref s) => quote_string(s),
+        AnyLuaValue::LuaAnyString(ref s) => quote_string(&String::from_utf8_lossy(&s.0)),
+        AnyLuaValue::LuaNumber(n) => {
+            // Whole numbers are written without a fractional part so they read back as integers.
+            if n.fract() == 0.0 && n.abs() < 1e15 {
+                format!("{}", n as i64)
+            } else {
+                format!("{:?}", n)
+            }
+        }
+        AnyLuaValue::LuaBoolean(b) => format!("{}", b),
+        AnyLuaValue::LuaArray(ref entries) => {
+            if entries.is_empty() {
+                return String::from("{}");
+            }
+            let padding = "\t".repeat(indent + 1);
+            let mut source = String::from("{\n");
+            for &(ref key, ref value) in entries.iter() {
+                source.push_str(&padding);
+                source.push_str(&table_key_to_lua_source(key, indent + 1));
+                source.push_str(" = ");
+                source.push_str(&to_lua_source(value, indent + 1));
+                source.push_str(",\n");
+            }
+            source.push_str(&"\t".repeat(indent));
+            source.push('}');
+            source
+        }
+        AnyLuaValue::LuaNil | AnyLuaValue::LuaOther => String::from("nil"),
+    }
+}
+
+/// Write a table key. Identifiers are written bare, anything else in brackets.
+fn table_key_to_lua_source(key: &AnyLuaValue, indent: usize) -> String {
+    if let AnyLuaValue::LuaString(ref s) = *key {
+        if is_identifier(s) {
+            return s.clone();
+        }
+    }
+    format!("[{}]", to_lua_source(key, indent))
+}
+
+/// Whether a string can be used as a bare Lua name.
+fn is_identifier(string: &str) -> bool {
+    const KEYWORDS: [&'static str; 22] = [
+        "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "goto", "if",
+        "in", "local", "nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+    ];
+    let mut chars = string.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_') && !KEYWORDS.contains(&string)
+}
@@ -23,3 +23,112 @@ fn test_scripting_execution_basic() {
         x
     );
 }
+
+#[test]
+// A runtime error should report the script's data-relative path, line, message and traceback.
+fn test_script_error_details() {
+    use scripting::basic::{execute_script_from_reader, get_scripting_environment};
+    use scripting::budget::ScriptBudget;
+    use error::DeucalionError;
+    use std::path::Path;
+    let mut environment = get_scripting_environment();
+    let result = execute_script_from_reader(
+        &mut environment,
+        "local x = 1\nlocal function f() error('it broke') end\nf()".as_bytes(),
+        Path::new("./data/maps/town/on_enter.lua"),
+        &ScriptBudget::unlimited(),
+    );
+    match result {
+        Err(DeucalionError::LuaScriptError(details)) => {
+            assert_eq!(details.file, "maps/town/on_enter.lua");
+            assert_eq!(details.line, Some(2));
+            assert_eq!(details.message, "it broke");
+            assert!(
+                details.traceback.contains("maps/town/on_enter.lua:3"),
+                "Traceback should include the calling line: {}",
+                details.traceback
+            );
+        }
+        other => panic!("Expected a script error, got {:?}", other),
+    }
+}
+
+#[test]
+// Syntax errors should be reported with their location too.
+fn test_script_syntax_error_details() {
+    use scripting::basic::{execute_script_from_reader, get_scripting_environment};
+    use scripting::budget::ScriptBudget;
+    use error::DeucalionError;
+    use std::path::Path;
+    let mut environment = get_scripting_environment();
+    let result = execute_script_from_reader(
+        &mut environment,
+        "x = 1\ny = = 2".as_bytes(),
+        Path::new("scripts/broken.lua"),
+        &ScriptBudget::unlimited(),
+    );
+    match result {
+        Err(DeucalionError::LuaScriptError(details)) => {
+            assert_eq!(details.file, "scripts/broken.lua");
+            assert_eq!(details.line, Some(2));
+        }
+        other => panic!("Expected a script error, got {:?}", other),
+    }
+}
+
+#[test]
+// Scripts that succeed should still return their value.
+fn test_script_return_value() {
+    use scripting::basic::{execute_script_from_reader, get_scripting_environment};
+    use scripting::budget::ScriptBudget;
+    use hlua::AnyLuaValue;
+    use std::path::Path;
+    let mut environment = get_scripting_environment();
+    let result = execute_script_from_reader(
+        &mut environment,
+        "return 'done'".as_bytes(),
+        Path::new("scripts/ok.lua"),
+        &ScriptBudget::unlimited(),
+    );
+    assert_eq!(result.unwrap(), AnyLuaValue::LuaString(String::from("done")));
+}
+
+#[test]
+// Scripts in a sandbox should see its globals, even after a script has replaced load.
+fn test_sandboxed_script_globals() {
+    use scripting::basic::execute_script_from_reader;
+    use scripting::budget::ScriptBudget;
+    use scripting::modules::get_module_roots;
+    use scripting::sandbox::{get_default_script_libraries, get_sandboxed_environment};
+    use hlua::AnyLuaValue;
+    use std::path::Path;
+    let mut environment =
+        get_sandboxed_environment(&get_default_script_libraries(), &get_module_roots(&[]));
+    let result = execute_script_from_reader(
+        &mut environment,
+        "TITLE = 'Quest' load = nil return TITLE .. math.max(1, 2)".as_bytes(),
+        Path::new("game_config.lua"),
+        &ScriptBudget::unlimited(),
+    );
+    assert_eq!(result.unwrap(), AnyLuaValue::LuaString(String::from("Quest2")));
+    let result = execute_script_from_reader(
+        &mut environment,
+        "return TITLE".as_bytes(),
+        Path::new("game_config.lua"),
+        &ScriptBudget::unlimited(),
+    );
+    assert_eq!(result.unwrap(), AnyLuaValue::LuaString(String::from("Quest")));
+}
+
+#[test]
+fn test_split_error_location() {
+    use scripting::basic::split_error_location;
+    assert_eq!(
+        split_error_location("a/b.lua", "a/b.lua:12: attempt to call a nil value"),
+        (Some(12), String::from("attempt to call a nil value"))
+    );
+    assert_eq!(
+        split_error_location("a/b.lua", "something else"),
+        (None, String::from("something else"))
+    );
+}
@@ -0,0 +1,125 @@
+use scripting::budget::*;
+use scripting::{execute_script_from_reader, get_scripting_environment};
+use error::DeucalionError;
+use std::path::Path;
+use std::time::Duration;
+
+fn small_budget() -> ScriptBudget {
+    ScriptBudget {
+        instructions: Some(100_000),
+        time: None,
+    }
+}
+
+#[test]
+// An endless loop should be aborted with an error naming the script.
+fn test_infinite_loop_is_aborted() {
+    let mut environment = get_scripting_environment();
+    let result = execute_script_from_reader(
+        &mut environment,
+        "while true do end".as_bytes(),
+        Path::new("scripts/loop.lua"),
+        &small_budget(),
+    );
+    match result {
+        Err(DeucalionError::ScriptBudgetExceeded(ref path, _)) => {
+            assert_eq!(path, "scripts/loop.lua")
+        }
+        other => panic!("Expected the script to be aborted, got {:?}", other),
+    }
+}
+
+#[test]
+// Catching the error with pcall shouldn't let a script keep running.
+fn test_pcall_cannot_escape_budget() {
+    let mut environment = get_scripting_environment();
+    let result = execute_script_from_reader(
+        &mut environment,
+        "while true do pcall(function() while true do end end) end".as_bytes(),
+        Path::new("scripts/sneaky.lua"),
+        &small_budget(),
+    );
+    match result {
+        Err(DeucalionError::ScriptBudgetExceeded(_, _)) => {}
+        other => panic!("Expected the script to be aborted, got {:?}", other),
+    }
+}
+
+#[test]
+// A wall-clock limit alone should also stop a script.
+fn test_time_budget() {
+    let mut environment = get_scripting_environment();
+    let budget = ScriptBudget {
+        instructions: None,
+        time: Some(Duration::from_millis(50)),
+    };
+    let result = execute_script_from_reader(
+        &mut environment,
+        "while true do end".as_bytes(),
+        Path::new("scripts/loop.lua"),
+        &budget,
+    );
+    match result {
+        Err(DeucalionError::ScriptBudgetExceeded(_, ref limit)) => assert_eq!(limit, "50 ms"),
+        other => panic!("Expected the script to be aborted, got {:?}", other),
+    }
+}
+
+#[test]
+// A budget that ends inside another shouldn't lift the outer one.
+fn test_outer_budget_survives_inner() {
+    let mut environment = get_scripting_environment();
+    let (result, exceeded) = run_with_budget(&mut environment, &small_budget(), |environment| {
+        let (inner, inner_exceeded) =
+            run_with_budget(environment, &small_budget(), |environment| {
+                environment.execute::<()>("x = 1")
+            });
+        assert!(inner.is_ok());
+        assert!(!inner_exceeded);
+        environment.execute::<()>("while true do end")
+    });
+    assert!(result.is_err());
+    assert!(exceeded);
+}
+
+#[test]
+// Code run within a larger (or no) budget should still be stopped by the outer one.
+fn test_outer_budget_applies_inside_inner() {
+    let mut environment = get_scripting_environment();
+    let (result, exceeded) = run_with_budget(&mut environment, &small_budget(), |environment| {
+        let (inner, _) = run_with_budget(environment, &ScriptBudget::unlimited(), |environment| {
+            environment.execute::<()>("while true do end")
+        });
+        assert!(inner.is_err());
+        environment.execute::<()>("x = 1")
+    });
+    assert!(result.is_err());
+    assert!(exceeded);
+}
+
+#[test]
+// Scripts within their budget should run normally, and the environment should be usable after a
+// script was aborted.
+fn test_scripts_within_budget_run() {
+    let mut environment = get_scripting_environment();
+    let _ = execute_script_from_reader(
+        &mut environment,
+        "while true do end".as_bytes(),
+        Path::new("scripts/loop.lua"),
+        &small_budget(),
+    );
+    execute_script_from_reader(
+        &mut environment,
+        "x = 0 for i = 1, 100 do x = x + i end".as_bytes(),
+        Path::new("scripts/sum.lua"),
+        &small_budget(),
+    ).unwrap();
+    let x: i32 = environment.get("x").unwrap();
+    assert_eq!(x, 5050);
+}
+
+#[test]
+fn test_describe_budget() {
+    assert_eq!(small_budget().describe(), "100000 instructions");
+    assert_eq!(ScriptBudget::unlimited().describe(), "no limit");
+}
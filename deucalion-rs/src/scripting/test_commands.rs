@@ -0,0 +1,223 @@
+use std::collections::BTreeMap;
+
+use scripting::commands::*;
+use scripting::budget::ScriptBudget;
+use scripting::sandbox::{get_default_script_libraries, ScriptEnvironments, ScriptOwner};
+
+fn environments() -> ScriptEnvironments {
+    ScriptEnvironments::new(&get_default_script_libraries(), &[], ScriptBudget::unlimited())
+}
+
+#[test]
+// Commands from every environment should be queued in order, and taken only once.
+fn test_commands_are_queued() {
+    let mut environments = environments();
+    environments
+        .get(&ScriptOwner::Game)
+        .execute::<()>("pause_routes()")
+        .unwrap();
+    environments
+        .get(&ScriptOwner::Map(String::from("map001")))
+        .execute::<()>("resume_routes('guard')")
+        .unwrap();
+    assert_eq!(
+        environments.take_commands(),
+        vec![
+            ScriptCommand::PauseRoutes(None),
+            ScriptCommand::ResumeRoutes(Some(String::from("guard"))),
+        ]
+    );
+    assert!(environments.take_commands().is_empty());
+}
+
+#[test]
+// Bad arguments should raise an error in the script, and queue nothing.
+fn test_bad_arguments_raise_errors() {
+    let mut environments = environments();
+    let message: String = environments
+        .get(&ScriptOwner::Game)
+        .execute("local ok, message = pcall(pause_routes, {}) return message")
+        .unwrap();
+    assert!(message.contains("npc must be a string"));
+    assert!(environments.take_commands().is_empty());
+}
+
+#[test]
+// move_to should need an actor and whole tile coordinates.
+fn test_move_to() {
+    let mut environments = environments();
+    environments
+        .get(&ScriptOwner::Game)
+        .execute::<()>("move_to('guard', 3, 4)")
+        .unwrap();
+    assert_eq!(
+        environments.take_commands(),
+        vec![ScriptCommand::MoveTo {
+            actor: String::from("guard"),
+            x: 3,
+            y: 4,
+        }]
+    );
+    let message: String = environments
+        .get(&ScriptOwner::Game)
+        .execute("local ok, message = pcall(move_to, 'guard', 1.5, 4) return message")
+        .unwrap();
+    assert!(message.contains("x must be a whole number"));
+    let message: String = environments
+        .get(&ScriptOwner::Game)
+        .execute("local ok, message = pcall(move_to, nil, 1, 4) return message")
+        .unwrap();
+    assert!(message.contains("actor must be a string"));
+    assert!(environments.take_commands().is_empty());
+}
+
+#[test]
+// The party functions should check their arguments, and warp's map should be optional.
+fn test_party_commands() {
+    let mut environments = environments();
+    environments
+        .get(&ScriptOwner::Game)
+        .execute::<()>(
+            "add_follower('anna') set_party_following(false) gather_party() warp(2, 3) \
+             warp(0, 0, 'town')",
+        )
+        .unwrap();
+    assert_eq!(
+        environments.take_commands(),
+        vec![
+            ScriptCommand::AddFollower(String::from("anna")),
+            ScriptCommand::SetPartyFollowing(false),
+            ScriptCommand::GatherParty,
+            ScriptCommand::Warp {
+                x: 2,
+                y: 3,
+                map: None,
+            },
+            ScriptCommand::Warp {
+                x: 0,
+                y: 0,
+                map: Some(String::from("town")),
+            },
+        ]
+    );
+    let message: String = environments
+        .get(&ScriptOwner::Game)
+        .execute("local ok, message = pcall(set_party_following, 'yes') return message")
+        .unwrap();
+    assert!(message.contains("following must be true or false"));
+}
+
+#[test]
+// start_battle should need a troop, and let the party flee, but not lose, unless told otherwise.
+fn test_start_battle() {
+    let mut environments = environments();
+    environments
+        .get(&ScriptOwner::Game)
+        .execute::<()>("start_battle('slimes') start_battle('dragon', false, true)")
+        .unwrap();
+    assert_eq!(
+        environments.take_commands(),
+        vec![
+            ScriptCommand::StartBattle {
+                troop: String::from("slimes"),
+                can_flee: true,
+                can_lose: false,
+            },
+            ScriptCommand::StartBattle {
+                troop: String::from("dragon"),
+                can_flee: false,
+                can_lose: true,
+            },
+        ]
+    );
+    let message: String = environments
+        .get(&ScriptOwner::Game)
+        .execute("local ok, message = pcall(start_battle, 'dragon', 0) return message")
+        .unwrap();
+    assert!(message.contains("can_flee must be true or false"));
+    let message: String = environments
+        .get(&ScriptOwner::Game)
+        .execute("local ok, message = pcall(start_battle, 'dragon', true, 'no') return message")
+        .unwrap();
+    assert!(message.contains("can_lose must be true or false"));
+}
+
+#[test]
+// give_exp should need a character and an amount that isn't negative.
+fn test_give_exp() {
+    let mut environments = environments();
+    environments
+        .get(&ScriptOwner::Game)
+        .execute::<()>("give_exp('player', 30)")
+        .unwrap();
+    assert_eq!(
+        environments.take_commands(),
+        vec![ScriptCommand::GiveExp {
+            character: String::from("player"),
+            amount: 30,
+        }]
+    );
+    let message: String = environments
+        .get(&ScriptOwner::Game)
+        .execute("local ok, message = pcall(give_exp, 'player', -5) return message")
+        .unwrap();
+    assert!(message.contains("amount must be a whole number, not below zero"));
+}
+
+#[test]
+// open_shop should need a list of items, and may give some of them prices of their own.
+fn test_open_shop() {
+    let mut environments = environments();
+    environments
+        .get(&ScriptOwner::Game)
+        .execute::<()>("open_shop({ 'potion', 'sword' }, { sword = 80 })")
+        .unwrap();
+    let mut prices = BTreeMap::new();
+    prices.insert(String::from("sword"), 80);
+    assert_eq!(
+        environments.take_commands(),
+        vec![ScriptCommand::OpenShop {
+            items: vec![String::from("potion"), String::from("sword")],
+            prices: prices,
+        }]
+    );
+    for (call, expected) in vec![
+        ("open_shop('potion')", "items must be a list of item names"),
+        ("open_shop({ 'potion' }, { potion = -1 })", "prices must be a table"),
+    ] {
+        let message: String = environments
+            .get(&ScriptOwner::Game)
+            .execute(&format!(
+                "local ok, message = pcall(function() {} end) return message",
+                call
+            ))
+            .unwrap();
+        assert!(message.contains(expected), "{} gave {}", call, message);
+    }
+    assert!(environments.take_commands().is_empty());
+}
+
+#[test]
+// open_layout should read its layout straight away, raising an error in the script if it's wrong.
+fn test_open_layout() {
+    let mut environments = environments();
+    environments
+        .get(&ScriptOwner::Game)
+        .execute::<()>(
+            "open_layout({ kind = 'list', id = 'choices', items = { 'Yes', 'No' } }, 'answer')",
+        )
+        .unwrap();
+    match environments.take_commands().pop() {
+        Some(ScriptCommand::OpenLayout { layout, call }) => {
+            assert_eq!(layout.id, Some(String::from("choices")));
+            assert_eq!(call, Some(String::from("answer")));
+        }
+        command => panic!("expected a layout to be opened, not {:?}", command),
+    }
+    let message: String = environments
+        .get(&ScriptOwner::Game)
+        .execute("local ok, message = pcall(open_layout, { kind = 'button' }) return message")
+        .unwrap();
+    assert!(message.contains("invalid layout"), "{}", message);
+    assert!(environments.take_commands().is_empty());
+}
@@ -0,0 +1,81 @@
+use scripting::convert::*;
+use scripting::get_scripting_environment;
+use scripting::sandbox::get_sandboxed_environment;
+use error::DeucalionError;
+use geom::{ScreenPoint, WorldPoint, WorldSize};
+use utility::direction::Direction;
+use utility::eight_direction::EightDirection;
+
+#[test]
+// Values should survive a trip through Lua unchanged.
+fn test_round_trip_through_lua() {
+    let mut environment = get_scripting_environment();
+    environment.set("p", LuaGeom(WorldPoint::new(1.5, -2.0)));
+    environment.set("s", LuaGeom(WorldSize::new(16.0, 32.0)));
+    environment.set("d", Direction::Left);
+    environment
+        .execute::<()>("p.x = p.x + 1\ns.height = s.height * 2")
+        .unwrap();
+    let LuaGeom(p): LuaGeom<WorldPoint> = get_global(&mut environment, "p").unwrap();
+    let LuaGeom(s): LuaGeom<WorldSize> = get_global(&mut environment, "s").unwrap();
+    let d: Direction = environment.get("d").unwrap();
+    assert_eq!(p, WorldPoint::new(2.5, -2.0));
+    assert_eq!(s, WorldSize::new(16.0, 64.0));
+    assert_eq!(d, Direction::Left);
+}
+
+#[test]
+// Scripts should be able to write these values as plain tables and strings.
+fn test_read_from_script() {
+    let mut environment = get_scripting_environment();
+    environment
+        .execute::<()>("pos = { x = 3, y = 4 }\nfacing = 'up'")
+        .unwrap();
+    let pos: LuaGeom<ScreenPoint> = get_global(&mut environment, "pos").unwrap();
+    assert_eq!(pos, LuaGeom(ScreenPoint::new(3, 4)));
+    let facing: Direction = get_global(&mut environment, "facing").unwrap();
+    assert_eq!(facing, Direction::Up);
+    // The same names work for eight directions, which add the diagonals.
+    let facing: EightDirection = get_global(&mut environment, "facing").unwrap();
+    assert_eq!(facing, EightDirection::Up);
+    environment.set("facing", EightDirection::DownLeft);
+    let name: String = environment.get("facing").unwrap();
+    assert_eq!(name, "down_left");
+}
+
+#[test]
+// Bad values should be reported with the identifier and the expected type.
+fn test_conversion_errors() {
+    let mut environment = get_scripting_environment();
+    environment
+        .execute::<()>("pos = { x = -3, y = 4 }\nfacing = 'sideways'")
+        .unwrap();
+    match get_global::<LuaGeom<ScreenPoint>>(&mut environment, "pos") {
+        Err(DeucalionError::LuaGetFailed(ref identifier, ref expected)) => {
+            assert_eq!(identifier, "pos");
+            assert_eq!(expected, "table with non-negative integer x and y fields");
+        }
+        other => panic!("Expected LuaGetFailed, got {:?}", other),
+    }
+    match get_global::<Direction>(&mut environment, "facing") {
+        Err(DeucalionError::LuaGetFailed(ref identifier, _)) => assert_eq!(identifier, "facing"),
+        other => panic!("Expected LuaGetFailed, got {:?}", other),
+    }
+    assert!(get_global::<LuaGeom<WorldPoint>>(&mut environment, "missing").is_err());
+}
+
+#[test]
+// The Lua direction helpers should agree with Direction's methods.
+fn test_direction_helpers() {
+    let mut environment = get_sandboxed_environment(&[], &[]);
+    let reversed: String = environment
+        .execute("return direction.reverse(direction.left)")
+        .unwrap();
+    assert_eq!(reversed, Direction::Left.reverse().name());
+    let angle: u32 = environment.execute("return direction.to_angle('down')").unwrap();
+    assert_eq!(angle as usize, Direction::Down.to_angle());
+    let failed: bool = environment
+        .execute("return not pcall(direction.reverse, 'sideways')")
+        .unwrap();
+    assert!(failed);
+}
@@ -0,0 +1,125 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use battle::combatant::StatBonus;
+use battle::database::{Database, EquipSlot, Item, ItemKind};
+use game_state::{GameState, SharedGameState};
+use scripting::budget::ScriptBudget;
+use scripting::sandbox::{get_default_script_libraries, ScriptEnvironments, ScriptOwner};
+
+// Environments sharing a game state with the player in the party, and a database with potions
+// and a sword.
+fn environments() -> (ScriptEnvironments, SharedGameState) {
+    let mut database = Database::default();
+    database.items.insert(
+        String::from("potion"),
+        Item {
+            stack_limit: 10,
+            ..Item::default()
+        },
+    );
+    database.items.insert(
+        String::from("sword"),
+        Item {
+            kind: ItemKind::Weapon,
+            bonus: StatBonus {
+                attack: 5,
+                ..StatBonus::default()
+            },
+            ..Item::default()
+        },
+    );
+    let state = Rc::new(RefCell::new(GameState::new()));
+    state.borrow_mut().join("player").unwrap();
+    let environments =
+        ScriptEnvironments::new(&get_default_script_libraries(), &[], ScriptBudget::unlimited())
+            .with_game_state(&state, &Rc::new(database));
+    (environments, state)
+}
+
+#[test]
+// Scripts should see their changes to the items at once, in every environment.
+fn test_item_functions() {
+    let (mut environments, state) = environments();
+    let given: f64 = environments
+        .get(&ScriptOwner::Game)
+        .execute("return give_item('potion', 12)")
+        .unwrap();
+    assert_eq!(given, 10.0);
+    assert_eq!(state.borrow().inventory.count("potion"), 10);
+
+    let map = ScriptOwner::Map(String::from("map001"));
+    let checks: bool = environments
+        .get(&map)
+        .execute(
+            "return has_item('potion') and has_item('potion', 10) and not has_item('potion', 11) \
+             and item_count('potion') == 10 and remove_item('potion', 4) \
+             and not remove_item('potion', 7) and item_count('potion') == 6 \
+             and not has_item('sword')",
+        )
+        .unwrap();
+    assert!(checks);
+    assert_eq!(state.borrow().inventory.count("potion"), 6);
+}
+
+#[test]
+// Scripts should be able to put equipment on party members and take it off.
+fn test_equip_functions() {
+    let (mut environments, state) = environments();
+    let environment = environments.get(&ScriptOwner::Game);
+    let equipped: bool = environment.execute("return equip('player', 'sword')").unwrap();
+    assert!(!equipped);
+    let equipped: bool = environment
+        .execute("give_item('sword') return equip('player', 'sword')")
+        .unwrap();
+    assert!(equipped);
+    assert_eq!(
+        state.borrow().member("player").unwrap().equipment.get(&EquipSlot::Weapon),
+        Some(&String::from("sword"))
+    );
+    let unequipped: bool = environment
+        .execute("return unequip('player', 'weapon') and not unequip('player', 'weapon')")
+        .unwrap();
+    assert!(unequipped);
+    assert_eq!(state.borrow().inventory.count("sword"), 1);
+}
+
+#[test]
+// Scripts should be able to give the party gold and take it away.
+fn test_gold_functions() {
+    let (mut environments, state) = environments();
+    let checks: bool = environments
+        .get(&ScriptOwner::Game)
+        .execute(
+            "return give_gold(100) == 100 and get_gold() == 100 and take_gold(30) \
+             and not take_gold(71) and get_gold() == 70",
+        )
+        .unwrap();
+    assert!(checks);
+    assert_eq!(state.borrow().gold, 70);
+}
+
+#[test]
+// Unknown items, characters and slots, and bad arguments, should raise errors in the script.
+fn test_item_errors() {
+    let (mut environments, state) = environments();
+    let environment = environments.get(&ScriptOwner::Game);
+    for (call, expected) in vec![
+        ("give_item('excalibur')", "there is no item named 'excalibur'"),
+        ("has_item('excalibur')", "there is no item named 'excalibur'"),
+        ("give_item('potion', -1)", "count must be a whole number"),
+        ("remove_item(7)", "item must be a string"),
+        ("equip('player', 'potion')", "potion can't be equipped"),
+        ("equip('nobody', 'sword')", "nobody has never joined the party"),
+        ("unequip('player', 'feet')", "slot must be one of"),
+    ] {
+        let message: String = environment
+            .execute(&format!(
+                "local ok, message = pcall(function() {} end) return message",
+                call
+            ))
+            .unwrap();
+        assert!(message.contains(expected), "{} gave {}", call, message);
+    }
+    assert!(state.borrow().inventory.items().is_empty());
+}
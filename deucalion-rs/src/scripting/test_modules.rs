@@ -0,0 +1,70 @@
+use scripting::modules::*;
+use error::DeucalionError;
+use std::path::{Path, PathBuf};
+
+#[test]
+// Module names are dotted paths inside a module root, and can't escape it.
+fn test_module_relative_path() {
+    assert_eq!(
+        get_module_relative_path("battle.formulas").unwrap(),
+        PathBuf::from("battle/formulas.lua")
+    );
+    assert!(get_module_relative_path("..secrets").is_err());
+    assert!(get_module_relative_path("/etc/passwd").is_err());
+    assert!(get_module_relative_path("").is_err());
+}
+
+#[test]
+// Mod roots come before the game's own scripts directory.
+fn test_module_roots_order() {
+    let roots = get_module_roots(&[String::from("mods/extra")]);
+    assert_eq!(
+        roots,
+        vec![PathBuf::from("mods/extra"), PathBuf::from("scripts")]
+    );
+}
+
+#[test]
+// Mod roots can't point outside the data directory.
+fn test_valid_mod_roots() {
+    assert!(is_valid_mod_root("mods/extra"));
+    assert!(is_valid_mod_root("./mods"));
+    assert!(!is_valid_mod_root(""));
+    assert!(!is_valid_mod_root("/home/player/mods"));
+    assert!(!is_valid_mod_root("mods/../../secrets"));
+    assert!(!is_valid_mod_root(".."));
+}
+
+// Pretends to be a data directory containing only the given files.
+fn fake_reader(
+    files: &'static [(&'static str, &'static str)],
+) -> impl FnMut(&Path) -> Result<Vec<u8>, DeucalionError> {
+    move |path: &Path| {
+        files
+            .iter()
+            .find(|&&(name, _)| Path::new(name) == path)
+            .map(|&(_, source)| source.as_bytes().to_vec())
+            .ok_or_else(|| DeucalionError::from("not found"))
+    }
+}
+
+#[test]
+// The first root that has a module should provide it.
+fn test_find_module_prefers_mods() {
+    static FILES: [(&str, &str); 3] = [
+        ("scripts/util.lua", "return 'game'"),
+        ("mods/extra/util.lua", "return 'mod'"),
+        ("scripts/battle/formulas.lua", "return 'formulas'"),
+    ];
+    let roots = get_module_roots(&[String::from("mods/extra")]);
+
+    let (path, source) = find_module(&roots, "util", fake_reader(&FILES)).unwrap();
+    assert_eq!(path, PathBuf::from("mods/extra/util.lua"));
+    assert_eq!(source, "return 'mod'");
+
+    // Modules the mod doesn't have still come from the game
+    let (path, _) = find_module(&roots, "battle.formulas", fake_reader(&FILES)).unwrap();
+    assert_eq!(path, PathBuf::from("scripts/battle/formulas.lua"));
+
+    assert!(find_module(&roots, "missing", fake_reader(&FILES)).is_err());
+}
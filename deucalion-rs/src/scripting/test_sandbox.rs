@@ -0,0 +1,116 @@
+use hlua::AnyLuaValue;
+
+use scripting::sandbox::*;
+use scripting::budget::ScriptBudget;
+use scripting::modules::get_module_roots;
+
+#[test]
+// Nothing that reaches outside the game should be available, even with os allowed.
+fn test_sandbox_removes_dangerous_functions() {
+    let mut environment =
+        get_sandboxed_environment(&get_default_script_libraries(), &get_module_roots(&[]));
+    let unavailable: bool = environment
+        .execute(
+            "return os.execute == nil and os.remove == nil and os.getenv == nil \
+             and io == nil and debug == nil and dofile == nil and loadfile == nil \
+             and package == nil and __deucalion_read_module == nil",
+        )
+        .unwrap();
+    assert!(unavailable);
+    // The harmless parts of allowed libraries are still there
+    let clock_available: bool = environment.execute("return os.clock ~= nil").unwrap();
+    assert!(clock_available);
+}
+
+#[test]
+// Libraries that aren't on the allowlist shouldn't be opened.
+fn test_sandbox_allowlist() {
+    let mut environment = get_sandboxed_environment(&[LuaLibrary::String], &[]);
+    let opened: bool = environment
+        .execute("return string ~= nil and math == nil and os == nil")
+        .unwrap();
+    assert!(opened);
+}
+
+#[test]
+// Binary chunks could be used to break out of the sandbox, so load should refuse them.
+fn test_sandbox_load_only_accepts_text() {
+    let mut environment =
+        get_sandboxed_environment(&get_default_script_libraries(), &get_module_roots(&[]));
+    let refused: bool = environment
+        .execute("return load(string.dump(function() end)) == nil")
+        .unwrap();
+    assert!(refused);
+}
+
+#[test]
+// Text chunks should still load, seeing the environment's globals unless given one of their own.
+fn test_sandbox_load_runs_text() {
+    let mut environment =
+        get_sandboxed_environment(&get_default_script_libraries(), &get_module_roots(&[]));
+    let result: u32 = environment
+        .execute("return load(\"return math.max(1, 2)\")()")
+        .unwrap();
+    assert_eq!(result, 2);
+    let result: u32 = environment
+        .execute("return load(\"return x\", \"chunk\", \"t\", { x = 3 })()")
+        .unwrap();
+    assert_eq!(result, 3);
+}
+
+#[test]
+// require should load modules from the data directory, and fail cleanly for missing ones.
+fn test_sandboxed_require() {
+    let mut environment =
+        get_sandboxed_environment(&get_default_script_libraries(), &get_module_roots(&[]));
+    let loaded: bool = environment
+        .execute("return require('init') == true")
+        .unwrap();
+    assert!(loaded);
+    let failed: bool = environment
+        .execute("return not pcall(require, 'does_not_exist')")
+        .unwrap();
+    assert!(failed);
+}
+
+#[test]
+// Each owner should get its own globals.
+fn test_script_environments_are_isolated() {
+    let mut environments =
+        ScriptEnvironments::new(&get_default_script_libraries(), &[], ScriptBudget::unlimited());
+    let map = ScriptOwner::Map(String::from("map001"));
+    let character = ScriptOwner::Character(String::from("player"));
+    environments.get(&map).execute::<()>("x = 1").unwrap();
+    let x: Option<i32> = environments.get(&character).get("x");
+    assert_eq!(x, None);
+    let x: Option<i32> = environments.get(&map).get("x");
+    assert_eq!(x, Some(1));
+}
+
+#[test]
+// Calling a function should pass its arguments and return its result, or None if it isn't there.
+fn test_call_function() {
+    let mut environments =
+        ScriptEnvironments::new(&get_default_script_libraries(), &[], ScriptBudget::unlimited());
+    environments
+        .get(&ScriptOwner::Game)
+        .execute::<()>("function describe(a, b, c) return a .. tostring(b) .. tostring(c) end")
+        .unwrap();
+    let arguments = vec![
+        AnyLuaValue::LuaString(String::from("slimes")),
+        AnyLuaValue::LuaNil,
+        AnyLuaValue::LuaBoolean(true),
+    ];
+    let result = environments
+        .call_function(&ScriptOwner::Game, "describe", arguments)
+        .unwrap();
+    assert_eq!(result, Some(AnyLuaValue::LuaString(String::from("slimesniltrue"))));
+    let missing = environments
+        .call_function(&ScriptOwner::Game, "on_nothing", Vec::new())
+        .unwrap();
+    assert_eq!(missing, None);
+    let failing = vec![AnyLuaValue::LuaString(String::from("oops"))];
+    assert!(environments
+        .call_function(&ScriptOwner::Game, "error", failing)
+        .is_err());
+}
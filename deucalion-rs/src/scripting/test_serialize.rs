@@ -0,0 +1,48 @@
+//! Tests for writing values as Lua source
+
+use scripting::serialize::*;
+use scripting::get_scripting_environment;
+use hlua::AnyLuaValue;
+
+#[test]
+fn test_quote_string_round_trip() {
+    let original = "A \"quoted\" line\nwith a \\ backslash and a \u{7} bell";
+    let mut environment = get_scripting_environment();
+    environment
+        .execute::<()>(&format!("s = {}", quote_string(original)))
+        .unwrap();
+    let s: String = environment.get("s").unwrap();
+    assert_eq!(s, original);
+}
+
+#[test]
+fn test_table_to_lua_source() {
+    let table = AnyLuaValue::LuaArray(vec![
+        (
+            AnyLuaValue::LuaString(String::from("name")),
+            AnyLuaValue::LuaString(String::from("Joe")),
+        ),
+        (
+            AnyLuaValue::LuaString(String::from("end")),
+            AnyLuaValue::LuaNumber(3.0),
+        ),
+        (
+            AnyLuaValue::LuaNumber(1.0),
+            AnyLuaValue::LuaBoolean(true),
+        ),
+    ]);
+    let source = to_lua_source(&table, 0);
+    assert_eq!(
+        source,
+        "{\n\tname = \"Joe\",\n\t[\"end\"] = 3,\n\t[1] = true,\n}"
+    );
+
+    // And it should be valid Lua that reads back the same values.
+    let mut environment = get_scripting_environment();
+    environment
+        .execute::<()>(&format!("t = {}\nname = t.name\nn = t[\"end\"]", source))
+        .unwrap();
+    let name: String = environment.get("name").unwrap();
+    let n: i32 = environment.get("n").unwrap();
+    assert_eq!((name.as_str(), n), ("Joe", 3));
+}
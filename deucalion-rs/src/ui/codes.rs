@@ -0,0 +1,178 @@
+//! Message control codes: the escapes that let text shown to the player change colour and name
+//! things from the game state, e.g. `"\C[1]\N[player]\C[0] has \G gold."`. The codes are:
+//!
+//! * `\C[n]` shows the text after it in colour `n` of the `PALETTE`: 0 is the usual text colour.
+//! * `\N[character]` is a party member's name, by their character, or the character's name if
+//!   they aren't in the party.
+//! * `\I[item]` is an item's name, by its name in the database.
+//! * `\G` is how much gold the party has.
+//! * `\\` is a backslash.
+//!
+//! Text is parsed once, when it's given, so that mistakes are found then rather than every time
+//! it's shown; it's then resolved against the game state each time it's drawn.
+
+use sfml::graphics::Color;
+
+use battle::database::Database;
+use error::DeucalionError;
+use game_state::GameState;
+use ui::{DISABLED_COLOR, HIGHLIGHT_COLOR, TEXT_COLOR};
+
+/// The colours `\C[n]` chooses between.
+pub const PALETTE: [Color; 6] = [
+    TEXT_COLOR,
+    HIGHLIGHT_COLOR,
+    DISABLED_COLOR,
+    Color::RED,
+    Color::GREEN,
+    Color::CYAN,
+];
+
+/// A piece of text, between control codes.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum MessagePart {
+    /// Text shown as it is.
+    Text(String),
+    /// Change the colour of the text after it, by its index in the palette.
+    Color(usize),
+    /// A party member's name, by their character.
+    Name(String),
+    /// An item's name, by its name in the database.
+    Item(String),
+    /// The party's gold.
+    Gold,
+}
+
+/// A run of text all in one colour, ready to draw.
+#[derive(PartialEq, Debug, Clone)]
+pub struct TextRun {
+    pub text: String,
+    pub color: Color,
+}
+
+/// Split text into its pieces, between the control codes in it. Fails if it has a code that
+/// doesn't exist, or one missing its argument.
+pub fn parse_control_codes(text: &str) -> Result<Vec<MessagePart>, DeucalionError> {
+    let mut parts = Vec::new();
+    let mut plain = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            plain.push(c);
+            continue;
+        }
+        let code = match chars.next() {
+            Some('\\') => {
+                plain.push('\\');
+                continue;
+            }
+            Some(code) => code.to_ascii_uppercase(),
+            None => {
+                return Err(DeucalionError::from(String::from(
+                    "text ends with a lone backslash; write \\\\ for a backslash",
+                )))
+            }
+        };
+        let part = match code {
+            'G' => MessagePart::Gold,
+            'C' | 'N' | 'I' => {
+                // The argument, in square brackets.
+                if chars.next() != Some('[') {
+                    return Err(DeucalionError::from(format!(
+                        "the control code \\{} must be followed by [ and its argument",
+                        code
+                    )));
+                }
+                let mut argument = String::new();
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        closed = true;
+                        break;
+                    }
+                    argument.push(c);
+                }
+                if !closed {
+                    return Err(DeucalionError::from(format!(
+                        "the control code \\{}[{} is missing its ]",
+                        code, argument
+                    )));
+                }
+                match code {
+                    'C' => match argument.trim().parse::<usize>() {
+                        Ok(color) if color < PALETTE.len() => MessagePart::Color(color),
+                        _ => {
+                            return Err(DeucalionError::from(format!(
+                                "\\C[{}] must be a colour from 0 to {}",
+                                argument,
+                                PALETTE.len() - 1
+                            )))
+                        }
+                    },
+                    'N' => MessagePart::Name(argument),
+                    _ => MessagePart::Item(argument),
+                }
+            }
+            _ => {
+                return Err(DeucalionError::from(format!(
+                    "there is no control code \\{}",
+                    code
+                )))
+            }
+        };
+        if !plain.is_empty() {
+            parts.push(MessagePart::Text(plain.split_off(0)));
+        }
+        parts.push(part);
+    }
+    if !plain.is_empty() {
+        parts.push(MessagePart::Text(plain));
+    }
+    Ok(parts)
+}
+
+/// Fill in the names and numbers in parsed text from the game state, and split it into runs of
+/// one colour, starting in `color`.
+pub fn resolve_message(
+    parts: &[MessagePart],
+    color: Color,
+    state: &GameState,
+    database: &Database,
+) -> Vec<TextRun> {
+    let mut runs: Vec<TextRun> = Vec::new();
+    let mut current = TextRun {
+        text: String::new(),
+        color: color,
+    };
+    for part in parts {
+        match *part {
+            MessagePart::Text(ref text) => current.text.push_str(text),
+            MessagePart::Color(index) => {
+                let color = PALETTE[index];
+                if color != current.color {
+                    let text = current.text.split_off(0);
+                    if !text.is_empty() {
+                        runs.push(TextRun {
+                            text: text,
+                            color: current.color,
+                        });
+                    }
+                    current.color = color;
+                }
+            }
+            MessagePart::Name(ref character) => match state.member(character) {
+                Some(member) => current.text.push_str(&member.name),
+                None => current.text.push_str(character),
+            },
+            MessagePart::Item(ref item) => match database.item(item) {
+                Some(definition) => current.text.push_str(&definition.name),
+                None => current.text.push_str(item),
+            },
+            MessagePart::Gold => current.text.push_str(&state.gold.to_string()),
+        }
+    }
+    if !current.text.is_empty() {
+        runs.push(current);
+    }
+    runs
+}
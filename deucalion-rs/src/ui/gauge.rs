@@ -0,0 +1,103 @@
+//! Gauges: bars showing how full something is, such as a party member's HP, along with the
+//! numbers.
+
+use sfml::graphics::{Color, Font, RectangleShape, RenderTarget, Shape, Transformable};
+use sfml::system::Vector2f;
+
+use geom::ScreenRect;
+use ui::{self, LINE_HEIGHT, TEXT_COLOR};
+
+/// How tall a gauge's bar is, under its numbers.
+pub const BAR_HEIGHT: u32 = 4;
+
+/// The colour of HP gauges.
+pub const HP_COLOR: Color = Color {
+    r: 224,
+    g: 96,
+    b: 64,
+    a: 255,
+};
+
+/// The colour of MP gauges.
+pub const MP_COLOR: Color = Color {
+    r: 64,
+    g: 128,
+    b: 224,
+    a: 255,
+};
+
+/// How full something is, out of its maximum, with a label such as "HP".
+#[derive(PartialEq, Debug, Clone)]
+pub struct Gauge {
+    pub label: String,
+    pub value: u32,
+    pub max: u32,
+    /// The colour of the bar.
+    pub color: Color,
+}
+
+impl Gauge {
+    /// Create a gauge with a label, in a colour.
+    pub fn new<S: Into<String>>(label: S, value: u32, max: u32, color: Color) -> Gauge {
+        Gauge {
+            label: label.into(),
+            value: value,
+            max: max,
+            color: color,
+        }
+    }
+
+    /// A gauge of HP.
+    pub fn hp(value: u32, max: u32) -> Gauge {
+        Gauge::new("HP", value, max, HP_COLOR)
+    }
+
+    /// A gauge of MP.
+    pub fn mp(value: u32, max: u32) -> Gauge {
+        Gauge::new("MP", value, max, MP_COLOR)
+    }
+
+    /// How full the gauge is, from 0 to 1. An empty maximum counts as empty.
+    pub fn fraction(&self) -> f32 {
+        if self.max == 0 {
+            0.0
+        } else {
+            (self.value as f32 / self.max as f32).min(1.0)
+        }
+    }
+
+    /// How much of a bar of a width is filled, in whole pixels. Anything above zero shows at
+    /// least a pixel, so that nearly empty doesn't look empty.
+    pub fn filled_width(&self, width: u32) -> u32 {
+        if self.value == 0 || self.max == 0 {
+            return 0;
+        }
+        ((self.fraction() * width as f32) as u32).max(1).min(width)
+    }
+
+    /// The numbers shown above the bar, e.g. "HP 30/40".
+    pub fn text(&self) -> String {
+        format!("{} {}/{}", self.label, self.value, self.max)
+    }
+
+    /// Draw the gauge's numbers with the bar underneath, across the width of an area.
+    pub fn draw<T: RenderTarget>(&self, target: &mut T, font: &Font, rect: ScreenRect) {
+        let (x, y) = (rect.origin.x as f32, rect.origin.y as f32);
+        ui::draw_text(target, font, &self.text(), (x, y), &TEXT_COLOR);
+        let bar_y = y + (LINE_HEIGHT - BAR_HEIGHT) as f32;
+        let mut bar = RectangleShape::with_size(Vector2f::new(
+            rect.size.width as f32,
+            BAR_HEIGHT as f32,
+        ));
+        bar.set_position((x, bar_y));
+        bar.set_fill_color(&Color::rgb(32, 32, 32));
+        target.draw(&bar);
+        let mut fill = RectangleShape::with_size(Vector2f::new(
+            self.filled_width(rect.size.width) as f32,
+            BAR_HEIGHT as f32,
+        ));
+        fill.set_position((x, bar_y));
+        fill.set_fill_color(&self.color);
+        target.draw(&fill);
+    }
+}
@@ -0,0 +1,74 @@
+//! Labels: text shown to the player, with the message control codes filled in as it's drawn.
+
+use sfml::graphics::{Color, Font, RenderTarget, Text, Transformable};
+
+use battle::database::Database;
+use error::DeucalionError;
+use game_state::GameState;
+use ui::codes::{parse_control_codes, resolve_message, MessagePart, TextRun};
+use ui::{FONT_SIZE, TEXT_COLOR};
+
+/// A line of text, which may have control codes in it.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Label {
+    text: String,
+    parts: Vec<MessagePart>,
+    /// The colour of the text until a control code changes it.
+    pub color: Color,
+}
+
+impl Label {
+    /// Create a label. Fails if the text has a control code that can't be read.
+    pub fn new(text: &str) -> Result<Label, DeucalionError> {
+        Ok(Label {
+            text: String::from(text),
+            parts: parse_control_codes(text)?,
+            color: TEXT_COLOR,
+        })
+    }
+
+    /// The label's text, as it was given, control codes and all.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Change the label's text. If it can't be read, the label is left as it was.
+    pub fn set_text(&mut self, text: &str) -> Result<(), DeucalionError> {
+        self.parts = parse_control_codes(text)?;
+        self.text = String::from(text);
+        Ok(())
+    }
+
+    /// The label's text, with its control codes filled in, split into runs of one colour.
+    pub fn runs(&self, state: &GameState, database: &Database) -> Vec<TextRun> {
+        resolve_message(&self.parts, self.color, state, database)
+    }
+
+    /// The label's text, with its control codes filled in, without its colours.
+    pub fn plain_text(&self, state: &GameState, database: &Database) -> String {
+        self.runs(state, database)
+            .into_iter()
+            .map(|run| run.text)
+            .collect()
+    }
+
+    /// Draw the label with its top left corner at a point.
+    pub fn draw<T: RenderTarget>(
+        &self,
+        target: &mut T,
+        font: &Font,
+        (x, y): (f32, f32),
+        state: &GameState,
+        database: &Database,
+    ) {
+        let mut x = x;
+        for run in self.runs(state, database) {
+            let mut text = Text::new(&run.text, font, FONT_SIZE);
+            text.set_position((x, y));
+            text.set_fill_color(&run.color);
+            target.draw(&text);
+            // Carry on from the end of the run, trailing spaces and all.
+            x = text.find_character_pos(run.text.chars().count()).x;
+        }
+    }
+}
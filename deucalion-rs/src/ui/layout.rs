@@ -0,0 +1,189 @@
+//! Layouts: widget trees described by Lua tables, so that scripts can build screens of their
+//! own. Each widget is a table with its `kind`, and optionally an `id`, its position `x` and `y`
+//! within its parent, and the widgets inside it, in `children`:
+//!
+//! * `"window"`: a framed panel, `width` wide and either `height` pixels or tall enough for
+//!   `rows` lines of text.
+//! * `"label"`: a line of `text`, which may have the message control codes in it (see
+//!   `ui::codes`), starting in colour `color` of the palette.
+//! * `"list"`: a list of `items` to choose from, each its text or a table of its `text` and
+//!   whether it's `enabled`, showing `rows` at a time (all of them, unless it says otherwise),
+//!   with the `selected` one, counting from 1, selected to begin with.
+//! * `"gauge"`: a bar `width` wide, showing a `value` out of `max` with a `label` such as
+//!   `"HP"`. HP and MP gauges have colours of their own; others are in colour `color` of the
+//!   palette.
+//!
+//! For example:
+//!
+//! ```lua
+//! {
+//!     kind = "window", x = 8, y = 8, width = 200, rows = 4,
+//!     children = {
+//!         { kind = "label", text = "\\C[1]Where to?" },
+//!         { kind = "list", id = "places", y = 20, items = { "Town", "Castle", "Cave" } },
+//!     },
+//! }
+//! ```
+
+use hlua::AnyLuaValue;
+use sfml::graphics::Color;
+
+use config::schema::ConfigValue;
+use error::DeucalionError;
+use scripting::commands::non_negative_number;
+use scripting::convert::get_table_field;
+use ui::codes::PALETTE;
+use ui::gauge::{Gauge, HP_COLOR, MP_COLOR};
+use ui::label::Label;
+use ui::list::{List, ListItem};
+use ui::widget::{widget_rect, Widget, WidgetKind};
+use ui::window::Window;
+use ui::{HIGHLIGHT_COLOR, LINE_HEIGHT};
+
+/// Read a widget, and the widgets inside it, from a layout table.
+pub fn parse_layout(table: &AnyLuaValue) -> Result<Widget, DeucalionError> {
+    let kind = match get_table_field(table, "kind") {
+        AnyLuaValue::LuaString(kind) => kind,
+        _ => {
+            return Err(DeucalionError::from(String::from(
+                "each widget in a layout must have a kind",
+            )))
+        }
+    };
+    let id = match get_table_field(table, "id") {
+        AnyLuaValue::LuaNil => None,
+        AnyLuaValue::LuaString(id) => Some(id),
+        _ => return Err(DeucalionError::from(String::from("a widget's id must be a string"))),
+    };
+    // Say which widget was wrong, by its id if it has one.
+    let describe = |e: String| match id {
+        Some(ref id) => DeucalionError::from(format!("in {} '{}': {}", kind, id, e)),
+        None => DeucalionError::from(format!("in a {}: {}", kind, e)),
+    };
+    let x = optional_number(table, "x", 0).map_err(&describe)?;
+    let y = optional_number(table, "y", 0).map_err(&describe)?;
+
+    let (widget_kind, width, height) = match kind.as_str() {
+        "window" => {
+            let width = non_negative_number(table, "width").map_err(&describe)?;
+            let height = match get_table_field(table, "rows") {
+                AnyLuaValue::LuaNil => non_negative_number(table, "height").map_err(&describe)?,
+                _ => {
+                    let rows = non_negative_number(table, "rows").map_err(&describe)?;
+                    Window::with_rows(0, 0, width, rows).rect.size.height
+                }
+            };
+            (WidgetKind::Window, width, height)
+        }
+        "label" => {
+            let text = match get_table_field(table, "text") {
+                AnyLuaValue::LuaString(text) => text,
+                _ => return Err(describe(String::from("text must be a string"))),
+            };
+            let mut label = Label::new(&text).map_err(|e| describe(e.to_string()))?;
+            if let Some(color) = optional_color(table).map_err(&describe)? {
+                label.color = color;
+            }
+            let width = optional_number(table, "width", 0).map_err(&describe)?;
+            (WidgetKind::Label(label), width, LINE_HEIGHT)
+        }
+        "list" => {
+            let items = parse_list_items(table).map_err(&describe)?;
+            let count = items.len() as u32;
+            let rows = optional_number(table, "rows", count.max(1)).map_err(&describe)?;
+            let selected = optional_number(table, "selected", 1).map_err(&describe)?;
+            let mut list = List::new(items, rows as usize);
+            list.select(selected.saturating_sub(1) as usize);
+            let width = optional_number(table, "width", 0).map_err(&describe)?;
+            (WidgetKind::List(list), width, rows.saturating_mul(LINE_HEIGHT))
+        }
+        "gauge" => {
+            let label = match get_table_field(table, "label") {
+                AnyLuaValue::LuaNil => String::new(),
+                AnyLuaValue::LuaString(label) => label,
+                _ => return Err(describe(String::from("label must be a string"))),
+            };
+            let value = non_negative_number(table, "value").map_err(&describe)?;
+            let max = non_negative_number(table, "max").map_err(&describe)?;
+            let color = match optional_color(table).map_err(&describe)? {
+                Some(color) => color,
+                None if label == "HP" => HP_COLOR,
+                None if label == "MP" => MP_COLOR,
+                None => HIGHLIGHT_COLOR,
+            };
+            let width = optional_number(table, "width", 100).map_err(&describe)?;
+            (
+                WidgetKind::Gauge(Gauge::new(label, value, max, color)),
+                width,
+                LINE_HEIGHT,
+            )
+        }
+        _ => {
+            return Err(DeucalionError::from(format!(
+                "there is no kind of widget called '{}'",
+                kind
+            )))
+        }
+    };
+
+    let mut widget = Widget::new(widget_kind, widget_rect(x, y, width, height));
+    widget.id = id.clone();
+    match get_table_field(table, "children") {
+        AnyLuaValue::LuaNil => {}
+        children => {
+            let children = Vec::<AnyLuaValue>::from_lua(children)
+                .ok_or_else(|| describe(String::from("children must be a list of widgets")))?;
+            for child in children.iter() {
+                widget.children.push(parse_layout(child)?);
+            }
+        }
+    }
+    Ok(widget)
+}
+
+// Read a whole number that may be left out, in which case it's `default`.
+fn optional_number(table: &AnyLuaValue, name: &str, default: u32) -> Result<u32, String> {
+    match get_table_field(table, name) {
+        AnyLuaValue::LuaNil => Ok(default),
+        _ => non_negative_number(table, name),
+    }
+}
+
+// Read a colour from the palette, by its index, if one is given.
+fn optional_color(table: &AnyLuaValue) -> Result<Option<Color>, String> {
+    match get_table_field(table, "color") {
+        AnyLuaValue::LuaNil => Ok(None),
+        _ => match non_negative_number(table, "color") {
+            Ok(index) if (index as usize) < PALETTE.len() => Ok(Some(PALETTE[index as usize])),
+            _ => Err(format!("color must be from 0 to {}", PALETTE.len() - 1)),
+        },
+    }
+}
+
+// Read a list's items: each its text, or a table of its text and whether it's enabled.
+fn parse_list_items(table: &AnyLuaValue) -> Result<Vec<ListItem>, String> {
+    let entries = Vec::<AnyLuaValue>::from_lua(get_table_field(table, "items"))
+        .ok_or_else(|| String::from("items must be a list"))?;
+    let mut items = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let item = match entry {
+            AnyLuaValue::LuaString(text) => ListItem::new(text),
+            item @ AnyLuaValue::LuaArray(_) => {
+                match (get_table_field(&item, "text"), get_table_field(&item, "enabled")) {
+                    (AnyLuaValue::LuaString(text), AnyLuaValue::LuaNil) => ListItem::new(text),
+                    (AnyLuaValue::LuaString(text), AnyLuaValue::LuaBoolean(enabled)) => {
+                        ListItem::enabled_if(text, enabled)
+                    }
+                    _ => {
+                        return Err(String::from(
+                            "each item must have its text, and may say whether it's enabled",
+                        ))
+                    }
+                }
+            }
+            _ => return Err(String::from("items must be text or tables")),
+        };
+        items.push(item);
+    }
+    Ok(items)
+}
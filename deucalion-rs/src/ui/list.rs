@@ -0,0 +1,164 @@
+//! Lists: choices the player moves a selection through, scrolling when there are more than fit.
+
+use sfml::graphics::{Font, RenderTarget};
+
+use ui::window::Window;
+use ui::{self, DISABLED_COLOR, HIGHLIGHT_COLOR, LINE_HEIGHT, TEXT_COLOR};
+
+/// A choice in a list.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ListItem {
+    pub text: String,
+    /// Whether the choice can be chosen. Disabled choices can still be selected, to see what
+    /// they are, but are shown greyed out.
+    pub enabled: bool,
+}
+
+impl ListItem {
+    /// A choice that can be chosen.
+    pub fn new<S: Into<String>>(text: S) -> ListItem {
+        ListItem {
+            text: text.into(),
+            enabled: true,
+        }
+    }
+
+    /// A choice that can only be chosen if `enabled` is true.
+    pub fn enabled_if<S: Into<String>>(text: S, enabled: bool) -> ListItem {
+        ListItem {
+            text: text.into(),
+            enabled: enabled,
+        }
+    }
+}
+
+/// Choices, one of which is selected, of which as many as fit in `rows` are shown at a time.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct List {
+    items: Vec<ListItem>,
+    selected: usize,
+    // The first choice shown.
+    scroll: usize,
+    rows: usize,
+}
+
+impl List {
+    /// Create a list showing `rows` choices at a time, with the first one selected.
+    pub fn new(items: Vec<ListItem>, rows: usize) -> List {
+        List {
+            items: items,
+            selected: 0,
+            scroll: 0,
+            rows: rows.max(1),
+        }
+    }
+
+    /// Every choice in the list.
+    pub fn items(&self) -> &[ListItem] {
+        &self.items
+    }
+
+    /// Replace the choices, keeping the same one selected if it's still there, or the last one
+    /// if the list is now shorter.
+    pub fn set_items(&mut self, items: Vec<ListItem>) {
+        self.items = items;
+        let selected = self.selected.min(self.items.len().saturating_sub(1));
+        self.select(selected);
+    }
+
+    /// The index of the selected choice, or None if the list is empty.
+    pub fn selected(&self) -> Option<usize> {
+        if self.items.is_empty() {
+            None
+        } else {
+            Some(self.selected)
+        }
+    }
+
+    /// The selected choice, or None if the list is empty.
+    pub fn selected_item(&self) -> Option<&ListItem> {
+        self.items.get(self.selected)
+    }
+
+    /// Whether a choice that can be chosen is selected.
+    pub fn is_selected_enabled(&self) -> bool {
+        self.selected_item().map_or(false, |item| item.enabled)
+    }
+
+    /// Select a choice by index, scrolling to show it. Indices past the end are ignored.
+    pub fn select(&mut self, index: usize) {
+        if index >= self.items.len() {
+            self.selected = 0;
+            self.scroll = 0;
+            return;
+        }
+        self.selected = index;
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + self.rows {
+            self.scroll = self.selected + 1 - self.rows;
+        }
+    }
+
+    /// Move the selection up (negative) or down (positive), wrapping around.
+    pub fn move_selection(&mut self, delta: i32) {
+        let selected = wrap(self.selected, delta, self.items.len());
+        self.select(selected);
+    }
+
+    /// The indices of the choices currently shown.
+    pub fn visible(&self) -> ::std::ops::Range<usize> {
+        self.scroll..(self.scroll + self.rows).min(self.items.len())
+    }
+
+    /// Draw the shown choices in a window, starting at a row, with the selected one highlighted
+    /// if the list has focus.
+    pub fn draw<T: RenderTarget>(
+        &self,
+        target: &mut T,
+        font: &Font,
+        window: &Window,
+        first_row: u32,
+        focused: bool,
+    ) {
+        self.draw_at(target, font, window.line_position(0, first_row), focused);
+    }
+
+    /// Draw the shown choices one under another, the first with its top left corner at a point.
+    pub fn draw_at<T: RenderTarget>(
+        &self,
+        target: &mut T,
+        font: &Font,
+        (x, y): (f32, f32),
+        focused: bool,
+    ) {
+        for (row, index) in self.visible().enumerate() {
+            let item = &self.items[index];
+            let color = if !item.enabled {
+                &DISABLED_COLOR
+            } else if focused && index == self.selected {
+                &HIGHLIGHT_COLOR
+            } else {
+                &TEXT_COLOR
+            };
+            let marker = if focused && index == self.selected {
+                "> "
+            } else {
+                "  "
+            };
+            let line = format!("{}{}", marker, item.text);
+            let position = (x, y + (row as u32 * LINE_HEIGHT) as f32);
+            ui::draw_text(target, font, &line, position, color);
+        }
+    }
+}
+
+/// Move an index through `count` positions by `delta`, wrapping around either end. Returns 0
+/// when there's nothing to move through.
+pub fn wrap(index: usize, delta: i32, count: usize) -> usize {
+    if count == 0 {
+        return 0;
+    }
+    let count = count as i64;
+    ((index as i64 + i64::from(delta)) % count + count) as usize % count as usize
+}
@@ -0,0 +1,67 @@
+//! The widgets menus are built from: windows to frame them, drawn with the game's windowskin;
+//! lists for the player to choose from; labels, with the message control codes; and gauges of HP
+//! and MP. Each widget keeps its state apart from drawing it, so that menus can be tested without
+//! a window, and draws itself onto any render target with the engine's font.
+//!
+//! Widgets can be used one at a time, or kept together in a widget tree (see `ui::widget`),
+//! which moves the focus between its lists with the player's actions. Scripts describe trees of
+//! their own as layouts (see `ui::layout`).
+
+use sfml::graphics::{Color, Font, RenderTarget, Text, Transformable};
+
+pub mod codes;
+pub mod gauge;
+pub mod label;
+pub mod layout;
+pub mod list;
+pub mod skin;
+pub mod widget;
+pub mod window;
+#[cfg(test)]
+mod test_codes;
+#[cfg(test)]
+mod test_gauge;
+#[cfg(test)]
+mod test_layout;
+#[cfg(test)]
+mod test_list;
+#[cfg(test)]
+mod test_skin;
+#[cfg(test)]
+mod test_widget;
+#[cfg(test)]
+mod test_window;
+
+/// The size of text in menus.
+pub const FONT_SIZE: u32 = 16;
+
+/// How far apart lines of text in menus are.
+pub const LINE_HEIGHT: u32 = 20;
+
+/// The colour of text.
+pub const TEXT_COLOR: Color = Color::WHITE;
+
+/// The colour of the selected choice in a list, and of headings.
+pub const HIGHLIGHT_COLOR: Color = Color::YELLOW;
+
+/// The colour of choices that can't be chosen.
+pub const DISABLED_COLOR: Color = Color {
+    r: 128,
+    g: 128,
+    b: 128,
+    a: 255,
+};
+
+/// Draw a line of menu text with its top left corner at a point.
+pub fn draw_text<T: RenderTarget>(
+    target: &mut T,
+    font: &Font,
+    line: &str,
+    (x, y): (f32, f32),
+    color: &Color,
+) {
+    let mut text = Text::new(line, font, FONT_SIZE);
+    text.set_position((x, y));
+    text.set_fill_color(color);
+    target.draw(&text);
+}
@@ -0,0 +1,176 @@
+//! Windowskins: images windows are drawn with, in place of the plain frame.
+//!
+//! A windowskin is described by data/windowskins/<name>/info.lua, e.g.:
+//!
+//! ```lua
+//! IMAGE = "skin.png"  -- beside info.lua
+//! BORDER = 8
+//! ```
+//!
+//! The image is cut into nine slices, `BORDER` pixels in from each edge: the corners are drawn
+//! as they are, the edges are stretched along the window's sides, and the middle is stretched to
+//! fill the rest of it.
+
+use sfml::graphics::{IntRect, RenderTarget, Sprite, Texture, Transformable};
+
+use config::schema::{Field, Schema};
+use error::DeucalionError;
+use geom::{ScreenPoint, ScreenRect, ScreenSize};
+use resource::loading;
+use resource::ResourceKind;
+use scripting::budget::ScriptBudget;
+use scripting::execute_script_from_reader;
+use scripting::modules::get_module_roots;
+use scripting::sandbox::{get_default_script_libraries, get_sandboxed_environment};
+
+/// What a windowskin's info.lua says about it.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct WindowskinInfo {
+    /// The file name of the image, beside info.lua.
+    pub image: String,
+    /// How far the slices are cut in from each edge of the image, in pixels.
+    pub border: u32,
+}
+
+impl Default for WindowskinInfo {
+    fn default() -> WindowskinInfo {
+        WindowskinInfo {
+            image: String::new(),
+            border: 8,
+        }
+    }
+}
+
+impl WindowskinInfo {
+    /// Read a windowskin's info from data/windowskins/<name>/info.lua. Invalid values are
+    /// reported and replaced by their defaults, except for the image, which is required.
+    pub fn by_name(name: &str) -> Result<WindowskinInfo, DeucalionError> {
+        let path = loading::get_resource_relative_path_by_name(ResourceKind::Windowskin, name)?;
+        let mut environment =
+            get_sandboxed_environment(&get_default_script_libraries(), &get_module_roots(&[]));
+        let reader = loading::open_data_file(&path)?;
+        execute_script_from_reader(&mut environment, reader, &path, &ScriptBudget::for_config())?;
+        let (info, mut errors) =
+            get_windowskin_info_schema().read(&mut environment, WindowskinInfo::default());
+        if info.image.is_empty() {
+            return Err(errors.pop().unwrap_or_else(|| {
+                DeucalionError::from(format!("windowskin {} has no image", name))
+            }));
+        }
+        for e in errors.iter() {
+            warn!("Invalid value in the info of windowskin {}, using its default: {}", name, e);
+        }
+        Ok(info)
+    }
+}
+
+/// Describe how each field of a WindowskinInfo is read from info.lua.
+pub fn get_windowskin_info_schema() -> Schema<WindowskinInfo> {
+    Schema::new()
+        .field(Field::new("IMAGE", |w: &mut WindowskinInfo| &mut w.image).required())
+        .field(
+            Field::new("BORDER", |w: &mut WindowskinInfo| &mut w.border)
+                .check("above zero", |b| *b > 0),
+        )
+}
+
+/// A windowskin with its image loaded, ready to draw windows with.
+pub struct Windowskin {
+    pub info: WindowskinInfo,
+    pub texture: Texture,
+}
+
+impl Windowskin {
+    /// Load a windowskin and its image.
+    pub fn by_name(name: &str) -> Result<Windowskin, DeucalionError> {
+        let info = WindowskinInfo::by_name(name)?;
+        let mut image_path =
+            loading::get_resource_relative_path_by_name(ResourceKind::Windowskin, name)?;
+        image_path.set_file_name(&info.image);
+        let image_data = loading::read_data_file(&image_path)?;
+        let texture = match Texture::from_memory(&image_data, &IntRect::new(0, 0, 0, 0)) {
+            Some(texture) => texture,
+            None => {
+                return Err(DeucalionError::from(format!(
+                    "could not decode the image '{}' for windowskin '{}'",
+                    info.image, name
+                )))
+            }
+        };
+        info!("Successfully loaded the windowskin '{}'", name);
+        Ok(Windowskin {
+            info: info,
+            texture: texture,
+        })
+    }
+
+    /// Draw a window's frame and background over an area of the screen.
+    pub fn draw<T: RenderTarget>(&self, target: &mut T, rect: ScreenRect) {
+        let size = self.texture.size();
+        let image = ScreenSize::new(size.x, size.y);
+        for (source, destination) in get_nine_slices(image, self.info.border, rect) {
+            let mut sprite = Sprite::with_texture_and_rect(
+                &self.texture,
+                &IntRect::new(
+                    source.origin.x as i32,
+                    source.origin.y as i32,
+                    source.size.width as i32,
+                    source.size.height as i32,
+                ),
+            );
+            sprite.set_position((destination.origin.x as f32, destination.origin.y as f32));
+            sprite.set_scale((
+                destination.size.width as f32 / source.size.width as f32,
+                destination.size.height as f32 / source.size.height as f32,
+            ));
+            target.draw(&sprite);
+        }
+    }
+}
+
+/// Cut an image of a size into nine slices, `border` pixels in from each edge, and work out
+/// where each goes to cover `rect`. Returns each slice of the image with the area it's drawn
+/// over, leaving out any that would be empty. The border is narrowed to fit small images, and
+/// the corners are shrunk to fit small windows.
+pub fn get_nine_slices(
+    image: ScreenSize,
+    border: u32,
+    rect: ScreenRect,
+) -> Vec<(ScreenRect, ScreenRect)> {
+    let source_border = border.min(image.width / 2).min(image.height / 2);
+    let border = source_border
+        .min(rect.size.width / 2)
+        .min(rect.size.height / 2);
+    // The start and length of each column and row, in the image and on the screen.
+    let spans = |start: u32, length: u32, source_length: u32| {
+        [
+            ((0, source_border), (start, border)),
+            (
+                (source_border, source_length - source_border * 2),
+                (start + border, length - border * 2),
+            ),
+            (
+                (source_length - source_border, source_border),
+                (start + length - border, border),
+            ),
+        ]
+    };
+    let columns = spans(rect.origin.x, rect.size.width, image.width);
+    let rows = spans(rect.origin.y, rect.size.height, image.height);
+    let mut slices = Vec::with_capacity(9);
+    for &((source_y, source_height), (y, height)) in rows.iter() {
+        for &((source_x, source_width), (x, width)) in columns.iter() {
+            if source_width == 0 || source_height == 0 || width == 0 || height == 0 {
+                continue;
+            }
+            slices.push((
+                ScreenRect::new(
+                    ScreenPoint::new(source_x, source_y),
+                    ScreenSize::new(source_width, source_height),
+                ),
+                ScreenRect::new(ScreenPoint::new(x, y), ScreenSize::new(width, height)),
+            ));
+        }
+    }
+    slices
+}
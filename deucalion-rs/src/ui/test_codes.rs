@@ -0,0 +1,69 @@
+use battle::database::{Database, Item};
+use game_state::GameState;
+use ui::codes::*;
+use ui::{HIGHLIGHT_COLOR, TEXT_COLOR};
+
+#[test]
+// Text should be split at its control codes, with doubled backslashes kept as one.
+fn test_parse_control_codes() {
+    assert_eq!(
+        parse_control_codes(r"\C[1]\n[player]\C[0] has \G gold, \\ \I[potion]").unwrap(),
+        vec![
+            MessagePart::Color(1),
+            MessagePart::Name(String::from("player")),
+            MessagePart::Color(0),
+            MessagePart::Text(String::from(" has ")),
+            MessagePart::Gold,
+            MessagePart::Text(String::from(" gold, \\ ")),
+            MessagePart::Item(String::from("potion")),
+        ]
+    );
+    assert_eq!(parse_control_codes("").unwrap(), Vec::new());
+}
+
+#[test]
+// Unknown codes, colours outside the palette and missing brackets should be rejected.
+fn test_bad_control_codes() {
+    assert!(parse_control_codes(r"\Q").is_err());
+    assert!(parse_control_codes(r"\C[99]").is_err());
+    assert!(parse_control_codes(r"\C[red]").is_err());
+    assert!(parse_control_codes(r"\N player").is_err());
+    assert!(parse_control_codes(r"\N[player").is_err());
+    assert!(parse_control_codes("ends with \\").is_err());
+}
+
+#[test]
+// Names and gold should be filled in from the game state, in runs of one colour.
+fn test_resolve_message() {
+    let mut database = Database::default();
+    database.items.insert(
+        String::from("potion"),
+        Item {
+            name: String::from("Potion"),
+            ..Item::default()
+        },
+    );
+    let mut state = GameState::new();
+    state.join("player").unwrap();
+    state.give_gold(25);
+    let name = state.member("player").unwrap().name.clone();
+
+    let parts = parse_control_codes(r"\C[1]\N[player]\C[0] has \G gold and a \I[potion].").unwrap();
+    assert_eq!(
+        resolve_message(&parts, TEXT_COLOR, &state, &database),
+        vec![
+            TextRun {
+                text: name,
+                color: HIGHLIGHT_COLOR,
+            },
+            TextRun {
+                text: String::from(" has 25 gold and a Potion."),
+                color: TEXT_COLOR,
+            },
+        ]
+    );
+    // Characters who aren't in the party, and unknown items, are shown by their own names.
+    let parts = parse_control_codes(r"\N[stranger] \I[relic]").unwrap();
+    let runs = resolve_message(&parts, TEXT_COLOR, &state, &database);
+    assert_eq!(runs[0].text, "stranger relic");
+}
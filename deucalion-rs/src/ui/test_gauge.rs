@@ -0,0 +1,14 @@
+use ui::gauge::*;
+
+#[test]
+// Gauges should fill in proportion, never past full, and show something for any value at all.
+fn test_gauge_fill() {
+    let gauge = Gauge::hp(30, 40);
+    assert_eq!(gauge.text(), "HP 30/40");
+    assert_eq!(gauge.fraction(), 0.75);
+    assert_eq!(gauge.filled_width(100), 75);
+    assert_eq!(Gauge::mp(1, 1000).filled_width(100), 1);
+    assert_eq!(Gauge::mp(0, 10).filled_width(100), 0);
+    assert_eq!(Gauge::mp(50, 10).filled_width(100), 100);
+    assert_eq!(Gauge::hp(5, 0).fraction(), 0.0);
+}
@@ -0,0 +1,92 @@
+use hlua::AnyLuaValue;
+
+use battle::database::Database;
+use error::DeucalionError;
+use game_state::GameState;
+use scripting::budget::ScriptBudget;
+use scripting::sandbox::{get_default_script_libraries, ScriptEnvironments, ScriptOwner};
+use ui::gauge::HP_COLOR;
+use ui::layout::*;
+use ui::widget::{Widget, WidgetKind, WidgetTree};
+use ui::window::PADDING;
+use ui::LINE_HEIGHT;
+
+// Run a script in the game's environment and read the layout it returns.
+fn parse(script: &str) -> Result<Widget, DeucalionError> {
+    let mut environments =
+        ScriptEnvironments::new(&get_default_script_libraries(), &[], ScriptBudget::unlimited());
+    let value: AnyLuaValue = environments
+        .get(&ScriptOwner::Game)
+        .execute(script)
+        .unwrap();
+    parse_layout(&value)
+}
+
+#[test]
+// A layout should be read into a tree of widgets, placed and filled in as it says.
+fn test_parse_layout() {
+    let layout = parse(
+        r#"return {
+            kind = "window", x = 8, y = 8, width = 200, rows = 5,
+            children = {
+                { kind = "label", id = "title", text = "\\C[1]Where to, \\N[player]?" },
+                { kind = "list", id = "places", y = 20, selected = 2,
+                  items = { "Town", { text = "Castle", enabled = false }, "Cave" } },
+                { kind = "gauge", id = "hp", y = 80, label = "HP", value = 3, max = 4 },
+            },
+        }"#,
+    ).unwrap();
+    assert_eq!(layout.rect.size.height, LINE_HEIGHT * 5 + PADDING * 2);
+    assert_eq!(layout.children.len(), 3);
+
+    let tree = WidgetTree::new(layout);
+    assert_eq!(tree.focus(), Some("places"));
+    let places = tree.list("places").unwrap();
+    assert_eq!(places.items().len(), 3);
+    assert_eq!(places.selected(), Some(1));
+    assert!(!places.is_selected_enabled());
+    assert_eq!(tree.screen_rect("places").unwrap().origin.y, 8 + PADDING + 20);
+
+    let mut state = GameState::new();
+    state.join("player").unwrap();
+    let name = state.member("player").unwrap().name.clone();
+    match tree.find("title").unwrap().kind {
+        WidgetKind::Label(ref label) => assert_eq!(
+            label.plain_text(&state, &Database::default()),
+            format!("Where to, {}?", name)
+        ),
+        _ => panic!("title should be a label"),
+    }
+    match tree.find("hp").unwrap().kind {
+        WidgetKind::Gauge(ref gauge) => {
+            assert_eq!(gauge.text(), "HP 3/4");
+            assert_eq!(gauge.color, HP_COLOR);
+        }
+        _ => panic!("hp should be a gauge"),
+    }
+}
+
+#[test]
+// Mistakes should be reported, naming the widget they're in.
+fn test_bad_layouts() {
+    assert!(parse("return { kind = 'button' }").is_err());
+    assert!(parse("return { x = 5 }").is_err());
+    assert!(parse("return { kind = 'window', height = 40 }").is_err());
+    assert!(parse("return { kind = 'label', text = '\\\\Q' }").is_err());
+    assert!(parse("return { kind = 'list', items = { 5 } }").is_err());
+    assert!(parse("return { kind = 'gauge', value = -1, max = 4 }").is_err());
+    let error = parse(
+        "return { kind = 'window', width = 10, height = 10, \
+         children = { { kind = 'label', id = 'title', text = 5 } } }",
+    ).unwrap_err();
+    assert!(error.to_string().contains("label 'title'"));
+}
+
+#[test]
+// Too many rows to measure should make a widget as tall as can be, rather than overflowing.
+fn test_huge_rows() {
+    let window = parse("return { kind = 'window', width = 10, rows = 4294967295 }").unwrap();
+    assert_eq!(window.rect.size.height, u32::max_value());
+    let list = parse("return { kind = 'list', rows = 4294967295, items = { 'a' } }").unwrap();
+    assert_eq!(list.rect.size.height, u32::max_value());
+}
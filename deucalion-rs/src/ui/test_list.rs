@@ -0,0 +1,46 @@
+use ui::list::*;
+
+// A list of choices named after their positions, showing three at a time.
+fn list(count: usize) -> List {
+    List::new((0..count).map(|i| ListItem::new(i.to_string())).collect(), 3)
+}
+
+#[test]
+// Moving the selection past either end should wrap around, scrolling to show it.
+fn test_selection_wraps_and_scrolls() {
+    let mut list = list(5);
+    assert_eq!(list.selected(), Some(0));
+    assert_eq!(list.visible(), 0..3);
+    list.move_selection(-1);
+    assert_eq!(list.selected(), Some(4));
+    assert_eq!(list.visible(), 2..5);
+    list.move_selection(1);
+    assert_eq!(list.selected(), Some(0));
+    assert_eq!(list.visible(), 0..3);
+    list.move_selection(3);
+    assert_eq!(list.visible(), 1..4);
+}
+
+#[test]
+// Replacing the choices should keep the selection within them.
+fn test_set_items() {
+    let mut list = list(5);
+    list.select(4);
+    list.set_items(vec![ListItem::new("a"), ListItem::enabled_if("b", false)]);
+    assert_eq!(list.selected(), Some(1));
+    assert_eq!(list.visible(), 0..2);
+    assert!(!list.is_selected_enabled());
+    list.set_items(Vec::new());
+    assert_eq!(list.selected(), None);
+    assert_eq!(list.selected_item(), None);
+    list.move_selection(1);
+    assert_eq!(list.visible(), 0..0);
+}
+
+#[test]
+// Wrapping should work for moves of more than the count, and when there's nothing to move to.
+fn test_wrap() {
+    assert_eq!(wrap(1, 7, 3), 2);
+    assert_eq!(wrap(0, -4, 3), 2);
+    assert_eq!(wrap(0, 1, 0), 0);
+}
@@ -0,0 +1,28 @@
+use geom::{ScreenPoint, ScreenRect, ScreenSize};
+use ui::skin::*;
+
+fn rect(x: u32, y: u32, width: u32, height: u32) -> ScreenRect {
+    ScreenRect::new(ScreenPoint::new(x, y), ScreenSize::new(width, height))
+}
+
+#[test]
+// The corners should be drawn as they are, and the edges and middle stretched between them.
+fn test_nine_slices() {
+    let slices = get_nine_slices(ScreenSize::new(24, 24), 8, rect(10, 20, 100, 50));
+    assert_eq!(slices.len(), 9);
+    assert_eq!(slices[0], (rect(0, 0, 8, 8), rect(10, 20, 8, 8)));
+    assert_eq!(slices[1], (rect(8, 0, 8, 8), rect(18, 20, 84, 8)));
+    assert_eq!(slices[4], (rect(8, 8, 8, 8), rect(18, 28, 84, 34)));
+    assert_eq!(slices[8], (rect(16, 16, 8, 8), rect(102, 62, 8, 8)));
+}
+
+#[test]
+// Small windows should shrink the corners, and images too small for a middle should go without.
+fn test_nine_slices_small() {
+    let slices = get_nine_slices(ScreenSize::new(24, 24), 8, rect(0, 0, 10, 40));
+    assert_eq!(slices[0], (rect(0, 0, 8, 8), rect(0, 0, 5, 5)));
+    assert!(slices.iter().all(|&(_, destination)| destination.max_x() <= 10));
+
+    let slices = get_nine_slices(ScreenSize::new(16, 16), 8, rect(0, 0, 40, 40));
+    assert_eq!(slices.len(), 4);
+}
@@ -0,0 +1,136 @@
+use geom::{ScreenPoint, ScreenSize};
+use input::Action;
+use ui::gauge::Gauge;
+use ui::label::Label;
+use ui::list::{List, ListItem};
+use ui::widget::*;
+use ui::window::PADDING;
+use ui::LINE_HEIGHT;
+
+// A window with a title, two lists side by side, the second with a disabled choice, an HP
+// gauge, and a list without an id, which can't be focused.
+fn tree() -> WidgetTree {
+    let list = |items: Vec<ListItem>| WidgetKind::List(List::new(items, 3));
+    WidgetTree::new(
+        Widget::new(WidgetKind::Window, widget_rect(10, 20, 300, 200))
+            .with_id("window")
+            .with_child(
+                Widget::new(
+                    WidgetKind::Label(Label::new("Title").unwrap()),
+                    widget_rect(0, 0, 0, LINE_HEIGHT),
+                ).with_id("title"),
+            )
+            .with_child(
+                Widget::new(
+                    list(vec![ListItem::new("a"), ListItem::new("b")]),
+                    widget_rect(0, LINE_HEIGHT, 100, LINE_HEIGHT * 3),
+                ).with_id("left"),
+            )
+            .with_child(
+                Widget::new(
+                    list(vec![ListItem::enabled_if("c", false), ListItem::new("d")]),
+                    widget_rect(100, LINE_HEIGHT, 100, LINE_HEIGHT * 3),
+                ).with_id("right"),
+            )
+            .with_child(
+                Widget::new(
+                    WidgetKind::Gauge(Gauge::hp(10, 20)),
+                    widget_rect(0, LINE_HEIGHT * 4, 100, LINE_HEIGHT),
+                ).with_id("hp"),
+            )
+            .with_child(Widget::new(
+                list(vec![ListItem::new("e")]),
+                widget_rect(0, LINE_HEIGHT * 5, 100, LINE_HEIGHT),
+            )),
+    )
+}
+
+#[test]
+// Widgets should be found by id, and placed inside their window's padding.
+fn test_find_widgets() {
+    let tree = tree();
+    assert_eq!(tree.focusable(), vec!["left", "right"]);
+    assert_eq!(tree.focus(), Some("left"));
+    assert!(tree.find("title").is_some());
+    assert!(tree.find("nothing").is_none());
+    assert_eq!(tree.list("right").unwrap().items().len(), 2);
+    assert!(tree.list("title").is_none());
+
+    let rect = tree.screen_rect("right").unwrap();
+    assert_eq!(
+        rect.origin,
+        ScreenPoint::new(10 + PADDING + 100, 20 + PADDING + LINE_HEIGHT)
+    );
+    assert_eq!(rect.size, ScreenSize::new(100, LINE_HEIGHT * 3));
+    assert_eq!(tree.screen_rect("window").unwrap().origin, ScreenPoint::new(10, 20));
+}
+
+#[test]
+// Up and Down should move the selection in the focused list, and Left and Right the focus.
+fn test_focus_navigation() {
+    let mut tree = tree();
+    assert_eq!(tree.on_action(Action::Down), UiEvent::None);
+    assert_eq!(tree.list("left").unwrap().selected(), Some(1));
+    assert_eq!(tree.list("right").unwrap().selected(), Some(0));
+
+    tree.on_action(Action::Right);
+    assert_eq!(tree.focus(), Some("right"));
+    tree.on_action(Action::Right);
+    assert_eq!(tree.focus(), Some("left"));
+    tree.on_action(Action::Left);
+    assert_eq!(tree.focus(), Some("right"));
+
+    assert!(!tree.set_focus("title"));
+    assert!(tree.set_focus("left"));
+    assert_eq!(tree.focus(), Some("left"));
+}
+
+#[test]
+// Confirming should choose the selected choice, unless it's disabled, and cancelling back out.
+fn test_choose() {
+    let mut tree = tree();
+    tree.on_action(Action::Down);
+    assert_eq!(
+        tree.on_action(Action::Confirm),
+        UiEvent::Chosen {
+            list: String::from("left"),
+            index: 1,
+        }
+    );
+    tree.set_focus("right");
+    assert_eq!(tree.on_action(Action::Confirm), UiEvent::None);
+    tree.on_action(Action::Up);
+    assert_eq!(
+        tree.on_action(Action::Confirm),
+        UiEvent::Chosen {
+            list: String::from("right"),
+            index: 1,
+        }
+    );
+    assert_eq!(tree.on_action(Action::Cancel), UiEvent::Cancelled);
+
+    // With nothing to focus, there's nothing to choose.
+    let mut empty = WidgetTree::new(Widget::new(WidgetKind::Window, widget_rect(0, 0, 10, 10)));
+    assert_eq!(empty.focus(), None);
+    assert_eq!(empty.on_action(Action::Confirm), UiEvent::None);
+}
+
+#[test]
+// Widgets found by id should be changeable in place.
+fn test_change_widgets() {
+    let mut tree = tree();
+    tree.gauge_mut("hp").unwrap().value = 15;
+    tree.label_mut("title").unwrap().set_text("New title").unwrap();
+    assert!(tree.label_mut("title").unwrap().set_text(r"\Q").is_err());
+    tree.list_mut("left").unwrap().set_items(vec![ListItem::new("z")]);
+
+    match tree.find("hp").unwrap().kind {
+        WidgetKind::Gauge(ref gauge) => assert_eq!(gauge.text(), "HP 15/20"),
+        _ => panic!("hp should be a gauge"),
+    }
+    match tree.find("title").unwrap().kind {
+        WidgetKind::Label(ref label) => assert_eq!(label.text(), "New title"),
+        _ => panic!("title should be a label"),
+    }
+    assert_eq!(tree.list("left").unwrap().items(), &[ListItem::new("z")]);
+}
@@ -0,0 +1,29 @@
+use geom::{ScreenPoint, ScreenRect, ScreenSize};
+use ui::window::*;
+use ui::LINE_HEIGHT;
+
+#[test]
+// Lines of text should be laid out inside a window's padding.
+fn test_window_layout() {
+    let window = Window::with_rows(10, 20, 200, 3);
+    assert_eq!(window.rows(), 3);
+    assert_eq!(
+        window.inner(),
+        ScreenRect::new(
+            ScreenPoint::new(10 + PADDING, 20 + PADDING),
+            ScreenSize::new(200 - PADDING * 2, LINE_HEIGHT * 3),
+        )
+    );
+    assert_eq!(
+        window.line_position(4, 2),
+        ((10 + PADDING + 4) as f32, (20 + PADDING + LINE_HEIGHT * 2) as f32)
+    );
+}
+
+#[test]
+// A window too small for its padding should have no room for text, rather than overflowing.
+fn test_tiny_window() {
+    let window = Window::new(0, 0, 6, 4);
+    assert_eq!(window.inner().size, ScreenSize::new(2, 0));
+    assert_eq!(window.rows(), 0);
+}
@@ -0,0 +1,355 @@
+//! Widget trees: a whole screen of widgets, kept from frame to frame, with one of its lists
+//! focused. Scenes send the tree the player's actions and hear back what was chosen, and can look
+//! widgets up by id to change them, or, in tests, to see what they show.
+//!
+//! Each widget is placed relative to its parent: inside the padding of a window, and from the
+//! top left corner of anything else. Only lists with ids can be focused. Up and Down move the
+//! selection in the focused list, Left and Right move the focus between lists, in the order
+//! they're in the tree, Confirm chooses the selected choice if it can be chosen, and Cancel or
+//! Menu backs out.
+
+use sfml::graphics::{Font, RenderTarget};
+
+use battle::database::Database;
+use game_state::GameState;
+use geom::{ScreenPoint, ScreenRect, ScreenSize};
+use input::Action;
+use ui::gauge::Gauge;
+use ui::label::Label;
+use ui::list::{self, List};
+use ui::skin::Windowskin;
+use ui::window::Window;
+
+/// What a widget is.
+#[derive(PartialEq, Debug, Clone)]
+pub enum WidgetKind {
+    /// A framed panel, with its children inside it.
+    Window,
+    Label(Label),
+    List(List),
+    Gauge(Gauge),
+}
+
+/// A widget, and the widgets inside it.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Widget {
+    /// What the widget is looked up by, if anything needs to.
+    pub id: Option<String>,
+    /// Where the widget is, relative to where its parent puts what's inside it.
+    pub rect: ScreenRect,
+    pub kind: WidgetKind,
+    pub children: Vec<Widget>,
+}
+
+impl Widget {
+    /// Create a widget with nothing inside it.
+    pub fn new(kind: WidgetKind, rect: ScreenRect) -> Widget {
+        Widget {
+            id: None,
+            rect: rect,
+            kind: kind,
+            children: Vec::new(),
+        }
+    }
+
+    /// Give the widget an id.
+    pub fn with_id<S: Into<String>>(mut self, id: S) -> Widget {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Put a widget inside this one, after those already there.
+    pub fn with_child(mut self, child: Widget) -> Widget {
+        self.children.push(child);
+        self
+    }
+
+    /// Whether the widget has an id.
+    pub fn has_id(&self, id: &str) -> bool {
+        self.id.as_ref().map_or(false, |own| own == id)
+    }
+
+    /// Whether the widget can be focused: it's a list with an id.
+    pub fn is_focusable(&self) -> bool {
+        match self.kind {
+            WidgetKind::List(_) => self.id.is_some(),
+            _ => false,
+        }
+    }
+
+    /// Find this widget, or one inside it, by id.
+    pub fn find(&self, id: &str) -> Option<&Widget> {
+        if self.has_id(id) {
+            return Some(self);
+        }
+        self.children.iter().filter_map(|child| child.find(id)).next()
+    }
+
+    /// Find this widget, or one inside it, by id, to change.
+    pub fn find_mut(&mut self, id: &str) -> Option<&mut Widget> {
+        if self.has_id(id) {
+            return Some(self);
+        }
+        self.children
+            .iter_mut()
+            .filter_map(|child| child.find_mut(id))
+            .next()
+    }
+
+    // How far in from the widget's corner what's inside it goes.
+    fn content_offset(&self) -> (u32, u32) {
+        match self.kind {
+            WidgetKind::Window => {
+                let window = Window { rect: self.rect };
+                let inner = window.inner();
+                (
+                    inner.origin.x - self.rect.origin.x,
+                    inner.origin.y - self.rect.origin.y,
+                )
+            }
+            _ => (0, 0),
+        }
+    }
+
+    // Where the widget is on the screen, given where its parent puts what's inside it.
+    fn placed_at(&self, origin: ScreenPoint) -> ScreenRect {
+        ScreenRect::new(
+            ScreenPoint::new(origin.x + self.rect.origin.x, origin.y + self.rect.origin.y),
+            self.rect.size,
+        )
+    }
+
+    // Find where a widget is on the screen, given where this one's parent puts its children.
+    fn find_rect(&self, id: &str, origin: ScreenPoint) -> Option<ScreenRect> {
+        let rect = self.placed_at(origin);
+        if self.has_id(id) {
+            return Some(rect);
+        }
+        let (x, y) = self.content_offset();
+        let inside = ScreenPoint::new(rect.origin.x + x, rect.origin.y + y);
+        self.children
+            .iter()
+            .filter_map(|child| child.find_rect(id, inside))
+            .next()
+    }
+
+    // Add the ids of the focusable widgets, this one and those inside it, in order.
+    fn collect_focusable(&self, ids: &mut Vec<String>) {
+        if self.is_focusable() {
+            if let Some(ref id) = self.id {
+                ids.push(id.clone());
+            }
+        }
+        for child in self.children.iter() {
+            child.collect_focusable(ids);
+        }
+    }
+
+    /// Draw the widget, then what's inside it, given where its parent puts its children, with
+    /// the selection shown in the focused list.
+    pub fn draw<T: RenderTarget>(
+        &self,
+        target: &mut T,
+        context: &DrawContext,
+        origin: ScreenPoint,
+    ) {
+        let rect = self.placed_at(origin);
+        let position = (rect.origin.x as f32, rect.origin.y as f32);
+        match self.kind {
+            WidgetKind::Window => Window { rect: rect }.draw(target, context.skin),
+            WidgetKind::Label(ref label) => {
+                label.draw(target, context.font, position, context.state, context.database)
+            }
+            WidgetKind::List(ref list) => {
+                let focused = match (self.id.as_ref(), context.focus) {
+                    (Some(id), Some(focus)) => id == focus,
+                    _ => false,
+                };
+                list.draw_at(target, context.font, position, focused)
+            }
+            WidgetKind::Gauge(ref gauge) => gauge.draw(target, context.font, rect),
+        }
+        let (x, y) = self.content_offset();
+        let inside = ScreenPoint::new(rect.origin.x + x, rect.origin.y + y);
+        for child in self.children.iter() {
+            child.draw(target, context, inside);
+        }
+    }
+}
+
+/// What widgets are drawn with.
+pub struct DrawContext<'a> {
+    pub font: &'a Font,
+    pub skin: Option<&'a Windowskin>,
+    /// The id of the focused list.
+    pub focus: Option<&'a str>,
+    /// What labels fill in their control codes from.
+    pub state: &'a GameState,
+    pub database: &'a Database,
+}
+
+/// What happened when the player acted on a widget tree.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum UiEvent {
+    /// Nothing the scene needs to know about, such as the selection moving.
+    None,
+    /// The player chose a choice from a list, by the list's id and the choice's index.
+    Chosen { list: String, index: usize },
+    /// The player backed out.
+    Cancelled,
+}
+
+/// A screen of widgets, with one of its lists focused.
+#[derive(PartialEq, Debug, Clone)]
+pub struct WidgetTree {
+    pub root: Widget,
+    focus: Option<String>,
+}
+
+impl WidgetTree {
+    /// Create a tree with its first focusable list focused, if it has one.
+    pub fn new(root: Widget) -> WidgetTree {
+        let mut tree = WidgetTree {
+            root: root,
+            focus: None,
+        };
+        tree.focus = tree.focusable().into_iter().next();
+        tree
+    }
+
+    /// The ids of the lists that can be focused, in the order the focus moves through them.
+    pub fn focusable(&self) -> Vec<String> {
+        let mut ids = Vec::new();
+        self.root.collect_focusable(&mut ids);
+        ids
+    }
+
+    /// The id of the focused list, if any.
+    pub fn focus(&self) -> Option<&str> {
+        self.focus.as_ref().map(String::as_str)
+    }
+
+    /// Focus a list by id. Returns false, leaving the focus where it was, if there's no
+    /// focusable list with that id.
+    pub fn set_focus(&mut self, id: &str) -> bool {
+        if self.focusable().iter().any(|focusable| focusable == id) {
+            self.focus = Some(String::from(id));
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Find a widget by id.
+    pub fn find(&self, id: &str) -> Option<&Widget> {
+        self.root.find(id)
+    }
+
+    /// Find a widget by id, to change.
+    pub fn find_mut(&mut self, id: &str) -> Option<&mut Widget> {
+        self.root.find_mut(id)
+    }
+
+    /// Where a widget is on the screen, by id.
+    pub fn screen_rect(&self, id: &str) -> Option<ScreenRect> {
+        self.root.find_rect(id, ScreenPoint::origin())
+    }
+
+    /// Find a list by id.
+    pub fn list(&self, id: &str) -> Option<&List> {
+        match self.find(id).map(|widget| &widget.kind) {
+            Some(&WidgetKind::List(ref list)) => Some(list),
+            _ => None,
+        }
+    }
+
+    /// Find a list by id, to change.
+    pub fn list_mut(&mut self, id: &str) -> Option<&mut List> {
+        match self.find_mut(id).map(|widget| &mut widget.kind) {
+            Some(&mut WidgetKind::List(ref mut list)) => Some(list),
+            _ => None,
+        }
+    }
+
+    /// Find a label by id, to change.
+    pub fn label_mut(&mut self, id: &str) -> Option<&mut Label> {
+        match self.find_mut(id).map(|widget| &mut widget.kind) {
+            Some(&mut WidgetKind::Label(ref mut label)) => Some(label),
+            _ => None,
+        }
+    }
+
+    /// Find a gauge by id, to change.
+    pub fn gauge_mut(&mut self, id: &str) -> Option<&mut Gauge> {
+        match self.find_mut(id).map(|widget| &mut widget.kind) {
+            Some(&mut WidgetKind::Gauge(ref mut gauge)) => Some(gauge),
+            _ => None,
+        }
+    }
+
+    /// Act on the focused list.
+    pub fn on_action(&mut self, action: Action) -> UiEvent {
+        match action {
+            Action::Up | Action::Down => {
+                let delta = if action == Action::Up { -1 } else { 1 };
+                if let Some(focus) = self.focus.clone() {
+                    if let Some(list) = self.list_mut(&focus) {
+                        list.move_selection(delta);
+                    }
+                }
+                UiEvent::None
+            }
+            Action::Left | Action::Right => {
+                let delta = if action == Action::Left { -1 } else { 1 };
+                let focusable = self.focusable();
+                let current = match self.focus {
+                    Some(ref focus) => focusable.iter().position(|id| id == focus),
+                    None => None,
+                };
+                if let Some(current) = current {
+                    let next = list::wrap(current, delta, focusable.len());
+                    self.focus = Some(focusable[next].clone());
+                }
+                UiEvent::None
+            }
+            Action::Confirm => {
+                let focus = match self.focus.clone() {
+                    Some(focus) => focus,
+                    None => return UiEvent::None,
+                };
+                match self.list(&focus) {
+                    Some(list) if list.is_selected_enabled() => UiEvent::Chosen {
+                        index: list.selected().unwrap_or(0),
+                        list: focus.clone(),
+                    },
+                    _ => UiEvent::None,
+                }
+            }
+            Action::Cancel | Action::Menu => UiEvent::Cancelled,
+        }
+    }
+
+    /// Draw every widget.
+    pub fn draw<T: RenderTarget>(
+        &self,
+        target: &mut T,
+        font: &Font,
+        skin: Option<&Windowskin>,
+        state: &GameState,
+        database: &Database,
+    ) {
+        let context = DrawContext {
+            font: font,
+            skin: skin,
+            focus: self.focus(),
+            state: state,
+            database: database,
+        };
+        self.root.draw(target, &context, ScreenPoint::origin());
+    }
+}
+
+/// A rectangle at `(x, y)`, for placing widgets.
+pub fn widget_rect(x: u32, y: u32, width: u32, height: u32) -> ScreenRect {
+    ScreenRect::new(ScreenPoint::new(x, y), ScreenSize::new(width, height))
+}
@@ -0,0 +1,96 @@
+//! Windows: the framed panels menus are laid out in.
+
+use sfml::graphics::{Color, Font, RectangleShape, RenderTarget, Shape, Transformable};
+use sfml::system::Vector2f;
+
+use geom::{ScreenPoint, ScreenRect, ScreenSize};
+use ui::skin::Windowskin;
+use ui::{self, LINE_HEIGHT};
+
+/// How far a window's contents are inset from its edges.
+pub const PADDING: u32 = 8;
+
+/// How thick a window's border is.
+pub const BORDER_THICKNESS: f32 = 2.0;
+
+/// A framed panel on the screen, with lines of text inside it.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct Window {
+    /// Where the window is, border and all.
+    pub rect: ScreenRect,
+}
+
+impl Window {
+    /// Create a window with its top left corner at `(x, y)`.
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Window {
+        Window {
+            rect: ScreenRect::new(ScreenPoint::new(x, y), ScreenSize::new(width, height)),
+        }
+    }
+
+    /// Create a window just tall enough for a number of lines of text, or as tall as can be
+    /// if that's too tall to measure.
+    pub fn with_rows(x: u32, y: u32, width: u32, rows: u32) -> Window {
+        let height = rows.saturating_mul(LINE_HEIGHT).saturating_add(PADDING * 2);
+        Window::new(x, y, width, height)
+    }
+
+    /// The area inside the window's padding, where its contents go.
+    pub fn inner(&self) -> ScreenRect {
+        let padding = PADDING.min(self.rect.size.width / 2).min(self.rect.size.height / 2);
+        ScreenRect::new(
+            ScreenPoint::new(self.rect.origin.x + padding, self.rect.origin.y + padding),
+            ScreenSize::new(
+                self.rect.size.width - padding * 2,
+                self.rect.size.height - padding * 2,
+            ),
+        )
+    }
+
+    /// How many lines of text fit inside the window.
+    pub fn rows(&self) -> u32 {
+        self.inner().size.height / LINE_HEIGHT
+    }
+
+    /// The position of the start of a line of text inside the window, `column` pixels in from
+    /// the left of its contents.
+    pub fn line_position(&self, column: u32, row: u32) -> (f32, f32) {
+        let inner = self.inner();
+        (
+            (inner.origin.x + column) as f32,
+            (inner.origin.y + row * LINE_HEIGHT) as f32,
+        )
+    }
+
+    /// Draw the window's background and border, with the game's windowskin if it has one.
+    pub fn draw<T: RenderTarget>(&self, target: &mut T, skin: Option<&Windowskin>) {
+        if let Some(skin) = skin {
+            skin.draw(target, self.rect);
+            return;
+        }
+        let mut background = RectangleShape::with_size(Vector2f::new(
+            self.rect.size.width as f32 - BORDER_THICKNESS * 2.0,
+            self.rect.size.height as f32 - BORDER_THICKNESS * 2.0,
+        ));
+        background.set_position((
+            self.rect.origin.x as f32 + BORDER_THICKNESS,
+            self.rect.origin.y as f32 + BORDER_THICKNESS,
+        ));
+        background.set_fill_color(&Color::rgba(16, 24, 64, 224));
+        background.set_outline_color(&Color::WHITE);
+        background.set_outline_thickness(BORDER_THICKNESS);
+        target.draw(&background);
+    }
+
+    /// Draw a line of text inside the window.
+    pub fn draw_text<T: RenderTarget>(
+        &self,
+        target: &mut T,
+        font: &Font,
+        line: &str,
+        (column, row): (u32, u32),
+        color: &Color,
+    ) {
+        ui::draw_text(target, font, line, self.line_position(column, row), color);
+    }
+}
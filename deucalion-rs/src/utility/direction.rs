@@ -10,6 +10,27 @@ pub enum Direction {
 }
 
 impl Direction {
+    /// Parse a direction from the name scripts use for it, e.g. "up".
+    pub fn from_name(name: &str) -> Option<Direction> {
+        match name {
+            "up" => Some(Direction::Up),
+            "down" => Some(Direction::Down),
+            "left" => Some(Direction::Left),
+            "right" => Some(Direction::Right),
+            _ => None,
+        }
+    }
+
+    /// The name scripts use for this direction.
+    pub fn name(self) -> &'static str {
+        match self {
+            Direction::Up => "up",
+            Direction::Down => "down",
+            Direction::Left => "left",
+            Direction::Right => "right",
+        }
+    }
+
     /// Return the angle represented by the direction, in degrees, starting from Up, going
     /// clockwise
     pub fn to_angle(self) -> usize {
@@ -0,0 +1,165 @@
+//! Provides the EightDirection enum, for games with diagonal movement, and utilities for working
+//! with it. Games with only four directions of movement can ignore it and use `Direction`.
+
+use geom::WorldVector;
+use utility::direction::Direction;
+
+/// Represents the four cardinal directions and the four diagonals between them.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum EightDirection {
+    Up,
+    UpRight,
+    Right,
+    DownRight,
+    Down,
+    DownLeft,
+    Left,
+    UpLeft,
+}
+
+impl EightDirection {
+    /// Every direction, clockwise from Up.
+    pub fn all() -> &'static [EightDirection] {
+        static ALL: [EightDirection; 8] = [
+            EightDirection::Up,
+            EightDirection::UpRight,
+            EightDirection::Right,
+            EightDirection::DownRight,
+            EightDirection::Down,
+            EightDirection::DownLeft,
+            EightDirection::Left,
+            EightDirection::UpLeft,
+        ];
+        &ALL
+    }
+
+    /// Parse a direction from the name scripts use for it, e.g. "up_left".
+    pub fn from_name(name: &str) -> Option<EightDirection> {
+        EightDirection::all().iter().cloned().find(|d| d.name() == name)
+    }
+
+    /// The name scripts use for this direction. The cardinal directions have the same names as
+    /// their `Direction` counterparts.
+    pub fn name(self) -> &'static str {
+        match self {
+            EightDirection::Up => "up",
+            EightDirection::UpRight => "up_right",
+            EightDirection::Right => "right",
+            EightDirection::DownRight => "down_right",
+            EightDirection::Down => "down",
+            EightDirection::DownLeft => "down_left",
+            EightDirection::Left => "left",
+            EightDirection::UpLeft => "up_left",
+        }
+    }
+
+    /// Return the angle represented by the direction, in degrees, starting from Up, going
+    /// clockwise
+    pub fn to_angle(self) -> usize {
+        EightDirection::all()
+            .iter()
+            .position(|&d| d == self)
+            .expect("every direction is in EightDirection::all()") * 45
+    }
+
+    /// Return the direction nearest to an angle, in degrees clockwise from Up. Any angle is
+    /// accepted, including negative ones and those past 360.
+    pub fn from_angle(angle: f32) -> EightDirection {
+        let octant = ((angle / 45.0).round() as i32 % 8 + 8) % 8;
+        EightDirection::all()[octant as usize]
+    }
+
+    /// Return the direction nearest to that of a vector, or None for a zero vector.
+    pub fn from_vector(vector: WorldVector) -> Option<EightDirection> {
+        if vector.x == 0.0 && vector.y == 0.0 {
+            return None;
+        }
+        // World space has y pointing down, so Up is -y and clockwise is towards +x.
+        Some(EightDirection::from_angle(
+            vector.x.atan2(-vector.y).to_degrees(),
+        ))
+    }
+
+    /// Return the vector of length one pointing in this direction.
+    pub fn unit_vector(self) -> WorldVector {
+        let (x, y) = self.offset();
+        let length = if self.is_diagonal() {
+            ::std::f32::consts::SQRT_2
+        } else {
+            1.0
+        };
+        WorldVector::new(x as f32 / length, y as f32 / length)
+    }
+
+    /// Return the offset, in tiles, of the neighbouring tile in this direction.
+    pub fn offset(self) -> (i32, i32) {
+        let (horizontal, vertical) = self.components();
+        let x = match horizontal {
+            Some(Direction::Left) => -1,
+            Some(_) => 1,
+            None => 0,
+        };
+        let y = match vertical {
+            Some(Direction::Up) => -1,
+            Some(_) => 1,
+            None => 0,
+        };
+        (x, y)
+    }
+
+    /// Whether this is one of the diagonals.
+    pub fn is_diagonal(self) -> bool {
+        self.to_direction().is_none()
+    }
+
+    /// Return the inverse of the current direction, UpLeft->DownRight, Up->Down, etc.
+    pub fn reverse(self) -> EightDirection {
+        EightDirection::from_angle((self.to_angle() + 180) as f32)
+    }
+
+    /// Return the cardinal direction this is, or None for a diagonal.
+    pub fn to_direction(self) -> Option<Direction> {
+        match self {
+            EightDirection::Up => Some(Direction::Up),
+            EightDirection::Right => Some(Direction::Right),
+            EightDirection::Down => Some(Direction::Down),
+            EightDirection::Left => Some(Direction::Left),
+            _ => None,
+        }
+    }
+
+    /// Return the nearest cardinal direction. Diagonals give their horizontal part, which is the
+    /// way characters face when walking diagonally on four-direction spritesheets.
+    pub fn nearest_direction(self) -> Direction {
+        match self.components() {
+            (Some(horizontal), _) => horizontal,
+            (None, Some(vertical)) => vertical,
+            (None, None) => unreachable!("every direction has a component"),
+        }
+    }
+
+    /// Split the direction into its horizontal and vertical parts.
+    pub fn components(self) -> (Option<Direction>, Option<Direction>) {
+        match self {
+            EightDirection::Up => (None, Some(Direction::Up)),
+            EightDirection::UpRight => (Some(Direction::Right), Some(Direction::Up)),
+            EightDirection::Right => (Some(Direction::Right), None),
+            EightDirection::DownRight => (Some(Direction::Right), Some(Direction::Down)),
+            EightDirection::Down => (None, Some(Direction::Down)),
+            EightDirection::DownLeft => (Some(Direction::Left), Some(Direction::Down)),
+            EightDirection::Left => (Some(Direction::Left), None),
+            EightDirection::UpLeft => (Some(Direction::Left), Some(Direction::Up)),
+        }
+    }
+}
+
+impl From<Direction> for EightDirection {
+    fn from(direction: Direction) -> EightDirection {
+        match direction {
+            Direction::Up => EightDirection::Up,
+            Direction::Down => EightDirection::Down,
+            Direction::Left => EightDirection::Left,
+            Direction::Right => EightDirection::Right,
+        }
+    }
+}
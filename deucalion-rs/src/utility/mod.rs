@@ -2,3 +2,9 @@
 //! components of the deucalion system.
 
 pub mod direction;
+pub mod eight_direction;
+pub mod random;
+#[cfg(test)]
+mod test_eight_direction;
+#[cfg(test)]
+mod test_random;
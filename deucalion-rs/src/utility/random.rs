@@ -0,0 +1,76 @@
+//! A small, fast random number generator for game logic, such as wandering NPCs. It isn't
+//! suitable for anything that needs to be unpredictable to an attacker.
+//!
+//! Generators can be created with a fixed seed, so that tests and replays see the same sequence
+//! every time.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A xorshift64* random number generator.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    /// Create a generator that produces the same sequence for the same seed.
+    pub fn with_seed(seed: u64) -> Random {
+        // The state must never be zero, or every number generated would be zero.
+        Random {
+            state: if seed == 0 { 0x9E37_79B9_7F4A_7C15 } else { seed },
+        }
+    }
+
+    /// Create a generator seeded from the current time.
+    pub fn from_time() -> Random {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() ^ (u64::from(d.subsec_nanos()) << 32))
+            .unwrap_or(0);
+        Random::with_seed(seed)
+    }
+
+    /// Generate a number anywhere in the range of a u64.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Generate a number from 0 up to, but not including, `bound`, which must not be zero.
+    pub fn below(&mut self, bound: u32) -> u32 {
+        assert!(bound > 0, "Random::below needs a bound above zero");
+        // The top bits are the most random.
+        ((self.next_u64() >> 32) % u64::from(bound)) as u32
+    }
+
+    /// Generate a number from `minimum` to `maximum`, inclusive.
+    pub fn between(&mut self, minimum: i32, maximum: i32) -> i32 {
+        if maximum <= minimum {
+            return minimum;
+        }
+        let span = (i64::from(maximum) - i64::from(minimum) + 1) as u64;
+        (i64::from(minimum) + ((self.next_u64() >> 11) % span) as i64) as i32
+    }
+
+    /// Generate a number from 0 up to, but not including, 1.
+    pub fn fraction(&mut self) -> f64 {
+        // 53 bits fill the whole precision of an f64.
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Return true with the given probability, from 0 to 1.
+    pub fn chance(&mut self, probability: f64) -> bool {
+        self.fraction() < probability
+    }
+
+    /// Choose one of a list of items, or None if it's empty.
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            None
+        } else {
+            items.get(self.below(items.len() as u32) as usize)
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use utility::eight_direction::*;
+use utility::direction::Direction;
+use geom::WorldVector;
+
+#[test]
+fn test_angles_round_trip() {
+    for &direction in EightDirection::all() {
+        assert_eq!(EightDirection::from_angle(direction.to_angle() as f32), direction);
+    }
+    assert_eq!(EightDirection::UpLeft.to_angle(), 315);
+    // Angles are wrapped, and snapped to the nearest direction.
+    assert_eq!(EightDirection::from_angle(-50.0), EightDirection::UpLeft);
+    assert_eq!(EightDirection::from_angle(400.0), EightDirection::UpRight);
+}
+
+#[test]
+fn test_reverse() {
+    assert_eq!(EightDirection::UpLeft.reverse(), EightDirection::DownRight);
+    assert_eq!(EightDirection::Down.reverse(), EightDirection::Up);
+}
+
+#[test]
+// Cardinal directions convert both ways; diagonals only have a nearest cardinal direction.
+fn test_direction_conversions() {
+    for &direction in [Direction::Up, Direction::Down, Direction::Left, Direction::Right].iter() {
+        let eight = EightDirection::from(direction);
+        assert_eq!(eight.to_direction(), Some(direction));
+        assert_eq!(eight.to_angle(), direction.to_angle());
+        assert_eq!(eight.name(), direction.name());
+    }
+    assert_eq!(EightDirection::DownLeft.to_direction(), None);
+    assert_eq!(EightDirection::DownLeft.nearest_direction(), Direction::Left);
+}
+
+#[test]
+// Unit vectors should have length one, point the right way in world space (y down), and give
+// back the direction they came from.
+fn test_unit_vectors() {
+    for &direction in EightDirection::all() {
+        let vector = direction.unit_vector();
+        assert!((vector.length() - 1.0).abs() < 1e-6);
+        assert_eq!(EightDirection::from_vector(vector), Some(direction));
+    }
+    assert_eq!(EightDirection::Up.unit_vector(), WorldVector::new(0.0, -1.0));
+    assert_eq!(EightDirection::from_vector(WorldVector::new(0.0, 0.0)), None);
+    assert_eq!(
+        EightDirection::from_vector(WorldVector::new(10.0, 1.0)),
+        Some(EightDirection::Right)
+    );
+}
+
+#[test]
+fn test_names() {
+    for &direction in EightDirection::all() {
+        assert_eq!(EightDirection::from_name(direction.name()), Some(direction));
+    }
+    assert_eq!(EightDirection::from_name("sideways"), None);
+}
@@ -0,0 +1,32 @@
+use utility::random::*;
+
+#[test]
+// The same seed should give the same sequence.
+fn test_seeded_sequences_repeat() {
+    let mut a = Random::with_seed(42);
+    let mut b = Random::with_seed(42);
+    for _ in 0..100 {
+        assert_eq!(a.next_u64(), b.next_u64());
+    }
+    // Even a zero seed should give useful numbers.
+    assert!(Random::with_seed(0).next_u64() != 0);
+}
+
+#[test]
+// Numbers should stay in range, and every value in a small range should come up.
+fn test_ranges() {
+    let mut random = Random::with_seed(7);
+    let mut seen = [false; 5];
+    for _ in 0..1000 {
+        let n = random.between(-2, 2);
+        assert!(n >= -2 && n <= 2);
+        seen[(n + 2) as usize] = true;
+        assert!(random.below(3) < 3);
+        let f = random.fraction();
+        assert!(f >= 0.0 && f < 1.0);
+    }
+    assert!(seen.iter().all(|&s| s));
+    assert_eq!(random.between(4, 4), 4);
+    assert!(!random.chance(0.0));
+    assert!(random.chance(1.0));
+}
@@ -0,0 +1,238 @@
+//! Actors: the player and anything else that moves around a map.
+
+use geom::{WorldPoint, WorldRect, WorldSize, WorldVector};
+use utility::eight_direction::EightDirection;
+use world::collision::CollisionGrid;
+use world::movement::{MovementMode, MovementSettings};
+
+/// How fast actors move by default, in tiles per second.
+pub const DEFAULT_SPEED_IN_TILES: f32 = 4.0;
+
+// How close an actor must get to where it's walking to count as having arrived.
+const ARRIVAL_DISTANCE: f32 = 0.5;
+
+/// How walking towards a point is going.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum WalkResult {
+    /// The actor is on its way.
+    Walking,
+    /// The actor is there.
+    Arrived,
+    /// Something is in the way, so the actor hasn't moved.
+    Blocked,
+}
+
+/// Something that moves around a map.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Actor {
+    /// The top left of the actor's bounding box.
+    pub position: WorldPoint,
+    /// The size of the actor's bounding box, which is what collides with blocked tiles.
+    pub size: WorldSize,
+    /// How fast the actor moves at full speed, in world units per second.
+    pub speed: f32,
+    /// The way the actor is facing.
+    pub facing: EightDirection,
+    // Where the actor is stepping to, in grid movement.
+    step_target: Option<WorldPoint>,
+}
+
+impl Actor {
+    /// Create an actor at rest, facing down.
+    pub fn new(position: WorldPoint, size: WorldSize, speed: f32) -> Actor {
+        Actor {
+            position: position,
+            size: size,
+            speed: speed,
+            facing: EightDirection::Down,
+            step_target: None,
+        }
+    }
+
+    /// The actor's bounding box.
+    pub fn bounds(&self) -> WorldRect {
+        WorldRect::new(self.position, self.size)
+    }
+
+    /// Whether the actor is part way through a step from one tile to another.
+    pub fn is_stepping(&self) -> bool {
+        self.step_target.is_some()
+    }
+
+    /// Where the actor will be once it's finished the step it's taking, if any.
+    pub fn destination(&self) -> WorldPoint {
+        self.step_target.unwrap_or(self.position)
+    }
+
+    /// Put the actor somewhere, stopping any step it's taking.
+    pub fn place(&mut self, position: WorldPoint) {
+        self.position = position;
+        self.step_target = None;
+    }
+
+    /// Move the actor for one frame lasting `delta` seconds, according to movement input, which
+    /// has a length of at most one. In free movement the input's length sets the speed, so that
+    /// an analog stick pushed half way moves at half speed; in grid movement only its direction
+    /// matters.
+    pub fn update(
+        &mut self,
+        settings: &MovementSettings,
+        grid: &CollisionGrid,
+        input: WorldVector,
+        delta: f32,
+    ) {
+        let input = settings.restrict_input(input);
+        match settings.mode {
+            MovementMode::Free => {
+                self.move_freely(grid, input, delta);
+            }
+            MovementMode::Grid => {
+                let mut remaining = delta;
+                // Keep stepping while the input is held, carrying over the time left at the end of
+                //  each step so that walking doesn't stutter between tiles.
+                loop {
+                    if !self.is_stepping() {
+                        match EightDirection::from_vector(input) {
+                            Some(direction) => {
+                                if !self.start_step(grid, direction) {
+                                    break;
+                                }
+                            }
+                            None => break,
+                        }
+                    }
+                    remaining = self.advance_step(remaining);
+                    if remaining <= 0.0 {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Move by up to `input` times the actor's speed for `delta` seconds, stopping against blocked
+    /// tiles. Returns how far the actor actually moved.
+    pub fn move_freely(
+        &mut self,
+        grid: &CollisionGrid,
+        input: WorldVector,
+        delta: f32,
+    ) -> WorldVector {
+        if let Some(direction) = EightDirection::from_vector(input) {
+            self.facing = direction;
+        }
+        let motion = grid.sweep(&self.bounds(), input * self.speed * delta);
+        self.position = self.position + motion;
+        motion
+    }
+
+    /// Start a step to the neighbouring tile in a direction, turning to face it. Returns whether
+    /// the step was started: it isn't if the actor is already stepping, or the tile is blocked.
+    /// Diagonal steps also need both tiles beside them to be clear, so actors can't squeeze
+    /// between the corners of two walls.
+    pub fn start_step(&mut self, grid: &CollisionGrid, direction: EightDirection) -> bool {
+        if self.is_stepping() {
+            return false;
+        }
+        self.facing = direction;
+        let tile = grid.tile_size();
+        let (x, y) = direction.offset();
+        let bounds = self.bounds();
+        let clear = |dx: i32, dy: i32| {
+            let offset = WorldVector::new(dx as f32 * tile.width, dy as f32 * tile.height);
+            !grid.is_area_blocked(&bounds.translate(&offset))
+        };
+        if !clear(x, y) || (direction.is_diagonal() && !(clear(x, 0) && clear(0, y))) {
+            return false;
+        }
+        self.step_target = Some(
+            self.position + WorldVector::new(x as f32 * tile.width, y as f32 * tile.height),
+        );
+        true
+    }
+
+    /// Move towards the end of the current step for up to `delta` seconds. Returns the time left
+    /// over if the step finished, or zero.
+    pub fn advance_step(&mut self, delta: f32) -> f32 {
+        let target = match self.step_target {
+            Some(target) => target,
+            None => return 0.0,
+        };
+        let to_target = target - self.position;
+        let distance = to_target.length();
+        let travel = self.speed * delta;
+        if travel >= distance {
+            self.position = target;
+            self.step_target = None;
+            if self.speed > 0.0 {
+                delta - distance / self.speed
+            } else {
+                0.0
+            }
+        } else {
+            self.position = self.position + to_target * (travel / distance);
+            0.0
+        }
+    }
+
+    /// Walk for one frame towards a point, which the actor's position (its top left) will end
+    /// up at. In grid movement this steps from tile to tile, so the point should be the top left
+    /// of a tile; without diagonal movement, the actor walks along one axis and then the other.
+    pub fn walk_towards(
+        &mut self,
+        goal: WorldPoint,
+        settings: &MovementSettings,
+        grid: &CollisionGrid,
+        delta: f32,
+    ) -> WalkResult {
+        let offset = goal - self.position;
+        if !self.is_stepping() && offset.length() < ARRIVAL_DISTANCE {
+            return WalkResult::Arrived;
+        }
+        match settings.mode {
+            MovementMode::Grid => {
+                if !self.is_stepping() {
+                    let input = settings.restrict_input(offset);
+                    let direction = match EightDirection::from_vector(input) {
+                        Some(direction) => direction,
+                        None => return WalkResult::Arrived,
+                    };
+                    // If a diagonal is blocked, try going straight along either of its parts.
+                    let (horizontal, vertical) = direction.components();
+                    let started = self.start_step(grid, direction)
+                        || (direction.is_diagonal()
+                            && (self.start_step(grid, horizontal.unwrap().into())
+                                || self.start_step(grid, vertical.unwrap().into())));
+                    if !started {
+                        self.facing = direction;
+                        return WalkResult::Blocked;
+                    }
+                }
+                self.advance_step(delta);
+                WalkResult::Walking
+            }
+            MovementMode::Free => {
+                let reach = self.speed * delta;
+                if reach <= 0.0 {
+                    return WalkResult::Walking;
+                }
+                // Without diagonal movement, close the larger distance first.
+                let offset = if settings.diagonal {
+                    offset
+                } else if offset.x.abs() >= offset.y.abs() {
+                    WorldVector::new(offset.x, 0.0)
+                } else {
+                    WorldVector::new(0.0, offset.y)
+                };
+                // Slow down at the end, rather than overshooting.
+                let input = offset / offset.length().max(reach);
+                let moved = self.move_freely(grid, input, delta);
+                if moved.length() > 0.0 {
+                    WalkResult::Walking
+                } else {
+                    WalkResult::Blocked
+                }
+            }
+        }
+    }
+}
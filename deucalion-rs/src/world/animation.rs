@@ -0,0 +1,107 @@
+//! Playing animations on the map, on actors or at fixed positions, such as an emote over a
+//! character's head or an effect on a tile.
+
+use std::rc::Rc;
+
+use geom::WorldPoint;
+use resource::animation::{Animation, AnimationFrame};
+
+/// Plays an animation from its first frame, keeping track of which frame is showing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationPlayer {
+    animation: Rc<Animation>,
+    // The frame showing, and how long it's been showing for
+    index: usize,
+    elapsed: f32,
+    started: bool,
+    finished: bool,
+}
+
+impl AnimationPlayer {
+    /// Start playing an animation.
+    pub fn new(animation: Rc<Animation>) -> AnimationPlayer {
+        AnimationPlayer {
+            animation: animation,
+            index: 0,
+            elapsed: 0.0,
+            started: false,
+            finished: false,
+        }
+    }
+
+    /// The frame showing, or None if the animation has no frames.
+    pub fn current_frame(&self) -> Option<&AnimationFrame> {
+        self.animation.frames.get(self.index)
+    }
+
+    /// Whether a non-looping animation has reached the end of its last frame. Looping
+    /// animations never finish.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Play for `delta` seconds. Returns the events of every frame shown along the way, in
+    /// order, including the first frame's when the animation starts.
+    pub fn update(&mut self, delta: f32) -> Vec<String> {
+        let mut events = Vec::new();
+        if self.animation.frames.is_empty() {
+            self.finished = true;
+            return events;
+        }
+        if !self.started {
+            self.started = true;
+            self.push_event(&mut events);
+        }
+        // An animation with no length would never get past its frames.
+        if self.finished || self.animation.duration() <= 0.0 {
+            self.finished = !self.animation.looping;
+            return events;
+        }
+        self.elapsed += delta;
+        while self.elapsed >= self.animation.frames[self.index].duration {
+            self.elapsed -= self.animation.frames[self.index].duration;
+            if self.index + 1 < self.animation.frames.len() {
+                self.index += 1;
+            } else if self.animation.looping {
+                self.index = 0;
+            } else {
+                self.finished = true;
+                break;
+            }
+            self.push_event(&mut events);
+        }
+        events
+    }
+
+    fn push_event(&self, events: &mut Vec<String>) {
+        if let Some(ref event) = self.animation.frames[self.index].event {
+            events.push(event.clone());
+        }
+    }
+}
+
+/// What an animation is played on.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnimationTarget {
+    /// An actor, by name: "player", or the name of an NPC. The animation moves with them.
+    Actor(String),
+    /// A fixed point on the map.
+    Position(WorldPoint),
+}
+
+/// An animation playing on the map.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayingAnimation {
+    /// The name of the animation, which its events' scripts are found by.
+    pub name: String,
+    pub player: AnimationPlayer,
+    pub target: AnimationTarget,
+}
+
+/// Something that happened because an animation showed a frame with an event.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationEvent {
+    /// The name of the animation.
+    pub animation: String,
+    pub event: String,
+}
@@ -0,0 +1,317 @@
+//! Characters' info: who they are, and how they behave when placed on a map as NPCs.
+//!
+//! A character's info is read from data/characters/<name>/info.lua, where each value is a global,
+//! e.g. `BEHAVIOR = "wander"`. An NPC placed on a map can override any of them with a custom
+//! property of the same name in lower case, e.g. `behavior`, on its TMX object.
+
+use hlua::AnyLuaValue;
+use tiled;
+
+use battle::combatant::Stats;
+use config::schema::{ConfigValue, Field, Schema};
+use error::DeucalionError;
+use game_state::growth::{Curve, StatGrowth, DEFAULT_EXP_CURVE, DEFAULT_MAX_LEVEL};
+use geom::WorldPoint;
+use resource::loading;
+use resource::ResourceKind;
+use scripting::budget::ScriptBudget;
+use scripting::execute_script_from_reader;
+use scripting::modules::get_module_roots;
+use scripting::sandbox::{get_default_script_libraries, get_sandboxed_environment};
+use utility::eight_direction::EightDirection;
+use world::actor::DEFAULT_SPEED_IN_TILES;
+
+/// What an NPC does by itself.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum BehaviorKind {
+    /// Stay put.
+    Stand,
+    /// Walk about at random, staying within a radius of where the NPC started.
+    Wander,
+    /// Walk a fixed route.
+    Patrol,
+    /// Stay put, but turn to face the player when they come close.
+    FacePlayer,
+    /// Follow the player or another NPC around, or chase them.
+    Follow,
+}
+
+impl BehaviorKind {
+    /// Parse a behavior from the name used for it in info.lua and map properties.
+    pub fn from_name(name: &str) -> Option<BehaviorKind> {
+        match name {
+            "stand" => Some(BehaviorKind::Stand),
+            "wander" => Some(BehaviorKind::Wander),
+            "patrol" => Some(BehaviorKind::Patrol),
+            "face_player" => Some(BehaviorKind::FacePlayer),
+            "follow" => Some(BehaviorKind::Follow),
+            _ => None,
+        }
+    }
+
+    /// The name used for this behavior in info.lua and map properties.
+    pub fn name(self) -> &'static str {
+        match self {
+            BehaviorKind::Stand => "stand",
+            BehaviorKind::Wander => "wander",
+            BehaviorKind::Patrol => "patrol",
+            BehaviorKind::FacePlayer => "face_player",
+            BehaviorKind::Follow => "follow",
+        }
+    }
+}
+
+impl ConfigValue for BehaviorKind {
+    fn type_name() -> String {
+        String::from("one of stand, wander, patrol, face_player or follow")
+    }
+
+    fn from_lua(value: AnyLuaValue) -> Option<BehaviorKind> {
+        String::from_lua(value).and_then(|name| BehaviorKind::from_name(&name))
+    }
+}
+
+/// One step of a patrol route.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum RouteStep {
+    /// Move one tile in a direction.
+    Move(EightDirection),
+    /// Stand still for a number of seconds.
+    Wait(f32),
+    /// Walk in a straight line to a point, such as a point of a polyline drawn in Tiled.
+    To(WorldPoint),
+}
+
+impl RouteStep {
+    /// Parse a step as it's written in a route: a direction, like "up" or "down_left", or
+    /// "wait" followed by a number of seconds, like "wait 1.5".
+    pub fn from_name(name: &str) -> Option<RouteStep> {
+        let mut words = name.split_whitespace();
+        let step = match words.next()? {
+            "wait" => RouteStep::Wait(words.next()?.parse().ok().filter(|s: &f32| *s >= 0.0)?),
+            direction => RouteStep::Move(EightDirection::from_name(direction)?),
+        };
+        match words.next() {
+            Some(_) => None,
+            None => Some(step),
+        }
+    }
+}
+
+impl ConfigValue for RouteStep {
+    fn type_name() -> String {
+        String::from("route step (a direction, or \"wait <seconds>\")")
+    }
+
+    fn from_lua(value: AnyLuaValue) -> Option<RouteStep> {
+        String::from_lua(value).and_then(|name| RouteStep::from_name(&name))
+    }
+}
+
+/// A patrol route. In info.lua it's a list of steps, e.g. `{ "up", "up", "wait 2", "down" }`;
+/// since map properties can't hold lists, it may also be a single string with the steps
+/// separated by commas, e.g. "up, up, wait 2, down".
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct Route(pub Vec<RouteStep>);
+
+impl ConfigValue for Route {
+    fn type_name() -> String {
+        format!(
+            "list of {} values, or a string of them separated by commas",
+            RouteStep::type_name()
+        )
+    }
+
+    fn from_lua(value: AnyLuaValue) -> Option<Route> {
+        match value {
+            AnyLuaValue::LuaString(steps) => steps
+                .split(',')
+                .map(|step| RouteStep::from_name(step.trim()))
+                .collect::<Option<Vec<RouteStep>>>()
+                .map(Route),
+            value => Vec::<RouteStep>::from_lua(value).map(Route),
+        }
+    }
+}
+
+/// What an NPC does by itself, with the details each behavior needs.
+#[derive(PartialEq, Debug, Clone)]
+pub enum Behavior {
+    Stand,
+    /// Wander within `radius` tiles of where the NPC started, pausing for around `interval`
+    /// seconds between steps.
+    Wander { radius: u32, interval: f32 },
+    /// Walk a route, starting it again from the beginning at the end if `repeat` is set.
+    Patrol { route: Vec<RouteStep>, repeat: bool },
+    /// Face the player whenever they're within `distance` tiles.
+    FacePlayer { distance: u32 },
+    /// Walk towards `target`, which is "player" or the name of an NPC, until within `distance`
+    /// tiles of it. A distance of zero chases the target until it's caught.
+    Follow { target: String, distance: u32 },
+}
+
+/// The name a follow target uses for the player.
+pub const PLAYER_TARGET: &'static str = "player";
+
+/// The character the player plays as, whose info is data/characters/player/info.lua.
+pub const PLAYER_CHARACTER: &'static str = "player";
+
+/// A character's info.
+#[derive(PartialEq, Debug, Clone)]
+pub struct CharacterInfo {
+    /// The character's name, as shown to the player.
+    pub name: String,
+    pub description: String,
+    /// How fast the character moves, in tiles per second.
+    pub speed: f64,
+    pub behavior: BehaviorKind,
+    /// For wandering, how many tiles from the start the character may go.
+    pub wander_radius: u32,
+    /// For wandering, roughly how many seconds the character waits between steps.
+    pub wander_interval: f64,
+    /// For patrolling, the route.
+    pub route: Route,
+    /// For patrolling, whether to start the route again at its end.
+    pub route_repeat: bool,
+    /// For facing the player, how close, in tiles, the player must be.
+    pub face_distance: u32,
+    /// For following, who to follow: "player", or the name of an NPC on the same map.
+    pub follow_target: String,
+    /// For following, how close, in tiles, to stay.
+    pub follow_distance: u32,
+    /// How the character fights when they're in the player's party, at level 1, and at every
+    /// level for stats without a growth curve.
+    pub stats: Stats,
+    /// The skills the character can use in battle, by their names in the database.
+    pub skills: Vec<String>,
+    /// The level the character is at when they first join the party.
+    pub level: u32,
+    /// The highest level the character can reach.
+    pub max_level: u32,
+    /// The total EXP the character needs to reach each level; see `game_state::growth`.
+    pub exp_curve: Curve,
+    /// How the character's stats grow with their level.
+    pub growth: StatGrowth,
+}
+
+impl CharacterInfo {
+    /// The info of a character with nothing in its info.lua.
+    pub fn default_for(name: &str) -> CharacterInfo {
+        CharacterInfo {
+            name: String::from(name),
+            description: String::new(),
+            speed: f64::from(DEFAULT_SPEED_IN_TILES),
+            behavior: BehaviorKind::Stand,
+            wander_radius: 3,
+            wander_interval: 2.0,
+            route: Route::default(),
+            route_repeat: true,
+            face_distance: 3,
+            follow_target: String::from(PLAYER_TARGET),
+            follow_distance: 1,
+            stats: Stats::default(),
+            skills: Vec::new(),
+            level: 1,
+            max_level: DEFAULT_MAX_LEVEL,
+            exp_curve: Curve::Formula(String::from(DEFAULT_EXP_CURVE)),
+            growth: StatGrowth::default(),
+        }
+    }
+
+    /// Read a character's info from data/characters/<name>/info.lua. A character doesn't have to
+    /// have one. Invalid values are reported and replaced by their defaults.
+    pub fn by_name(name: &str) -> Result<CharacterInfo, DeucalionError> {
+        let path = loading::get_resource_relative_path_by_name(ResourceKind::Character, name)?;
+        let defaults = CharacterInfo::default_for(name);
+        if !loading::data_file_exists(&path) {
+            debug!("Character {} has no info at {}", name, path.display());
+            return Ok(defaults);
+        }
+        // Like the game's configuration, info.lua gets an environment of its own, which is
+        //  thrown away once it's been read.
+        let mut environment =
+            get_sandboxed_environment(&get_default_script_libraries(), &get_module_roots(&[]));
+        let reader = loading::open_data_file(&path)?;
+        execute_script_from_reader(&mut environment, reader, &path, &ScriptBudget::for_config())?;
+        let (info, errors) = get_character_info_schema().read(&mut environment, defaults);
+        for e in errors.iter() {
+            warn!("Invalid value in the info of character {}, using its default: {}", name, e);
+        }
+        Ok(info)
+    }
+
+    /// Override this info with the custom properties of a map object, named like the globals of
+    /// info.lua but in lower case. Invalid properties are reported and ignored.
+    pub fn with_properties(self, properties: &tiled::Properties) -> CharacterInfo {
+        let (info, errors) = get_character_info_schema().read_from(
+            |identifier| {
+                properties
+                    .get(&identifier.to_lowercase())
+                    .map(property_to_lua)
+            },
+            self,
+        );
+        for e in errors.iter() {
+            warn!("Invalid character property on a map, ignoring it: {}", e);
+        }
+        info
+    }
+
+    /// The behavior described by this info.
+    pub fn behavior(&self) -> Behavior {
+        match self.behavior {
+            BehaviorKind::Stand => Behavior::Stand,
+            BehaviorKind::Wander => Behavior::Wander {
+                radius: self.wander_radius,
+                interval: self.wander_interval as f32,
+            },
+            BehaviorKind::Patrol => Behavior::Patrol {
+                route: self.route.0.clone(),
+                repeat: self.route_repeat,
+            },
+            BehaviorKind::FacePlayer => Behavior::FacePlayer {
+                distance: self.face_distance,
+            },
+            BehaviorKind::Follow => Behavior::Follow {
+                target: self.follow_target.clone(),
+                distance: self.follow_distance,
+            },
+        }
+    }
+}
+
+/// Convert a map property to the Lua value the same setting would have in a script.
+pub fn property_to_lua(property: &tiled::PropertyValue) -> AnyLuaValue {
+    match *property {
+        tiled::PropertyValue::BoolValue(b) => AnyLuaValue::LuaBoolean(b),
+        tiled::PropertyValue::FloatValue(f) => AnyLuaValue::LuaNumber(f64::from(f)),
+        tiled::PropertyValue::IntValue(i) => AnyLuaValue::LuaNumber(f64::from(i)),
+        tiled::PropertyValue::ColorValue(c) => AnyLuaValue::LuaNumber(f64::from(c)),
+        tiled::PropertyValue::StringValue(ref s) => AnyLuaValue::LuaString(s.clone()),
+    }
+}
+
+/// Describe how each field of a CharacterInfo is read from info.lua.
+pub fn get_character_info_schema() -> Schema<CharacterInfo> {
+    Schema::new()
+        .field(Field::new("NAME", |c: &mut CharacterInfo| &mut c.name))
+        .field(Field::new("DESCRIPTION", |c: &mut CharacterInfo| &mut c.description))
+        .field(Field::new("SPEED", |c: &mut CharacterInfo| &mut c.speed).range(0.0, 100.0))
+        .field(Field::new("BEHAVIOR", |c: &mut CharacterInfo| &mut c.behavior))
+        .field(Field::new("WANDER_RADIUS", |c: &mut CharacterInfo| &mut c.wander_radius))
+        .field(
+            Field::new("WANDER_INTERVAL", |c: &mut CharacterInfo| &mut c.wander_interval)
+                .range(0.0, 3600.0),
+        )
+        .field(Field::new("ROUTE", |c: &mut CharacterInfo| &mut c.route))
+        .field(Field::new("ROUTE_REPEAT", |c: &mut CharacterInfo| &mut c.route_repeat))
+        .field(Field::new("FACE_DISTANCE", |c: &mut CharacterInfo| &mut c.face_distance))
+        .field(Field::new("FOLLOW_TARGET", |c: &mut CharacterInfo| &mut c.follow_target))
+        .field(Field::new("FOLLOW_DISTANCE", |c: &mut CharacterInfo| &mut c.follow_distance))
+        .field(Field::new("STATS", |c: &mut CharacterInfo| &mut c.stats))
+        .field(Field::new("SKILLS", |c: &mut CharacterInfo| &mut c.skills))
+        .field(Field::new("LEVEL", |c: &mut CharacterInfo| &mut c.level).range(1, 9999))
+        .field(Field::new("MAX_LEVEL", |c: &mut CharacterInfo| &mut c.max_level).range(1, 9999))
+        .field(Field::new("EXP_CURVE", |c: &mut CharacterInfo| &mut c.exp_curve))
+        .field(Field::new("GROWTH", |c: &mut CharacterInfo| &mut c.growth))
+}
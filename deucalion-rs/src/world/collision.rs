@@ -0,0 +1,233 @@
+//! Which tiles of a map can't be walked through, and moving boxes around without passing through
+//! them.
+//!
+//! A tile is blocked if a rectangle in the map's "walls" object group covers any part of it, or
+//! if the tile placed there on any layer has the custom property `passable` set to false in its
+//! tileset. Everything outside the map is blocked.
+
+use tiled;
+
+use geom::{WorldPoint, WorldRect, WorldSize, WorldVector};
+
+/// The name of the object group whose rectangles mark blocked tiles.
+pub const WALLS_OBJECT_GROUP: &'static str = "walls";
+
+/// The tile property that, set to false, makes a tile blocked wherever it's placed.
+pub const PASSABLE_PROPERTY: &'static str = "passable";
+
+// How close an edge must be to a tile boundary to count as touching it rather than overlapping,
+//  so that a box resting against a wall isn't treated as being inside it.
+const EPSILON: f32 = 1e-3;
+
+/// Which tiles of a map are blocked.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollisionGrid {
+    width: u32,
+    height: u32,
+    tile_size: WorldSize,
+    blocked: Vec<bool>,
+}
+
+impl CollisionGrid {
+    /// Create a grid of the given size in tiles, with nothing blocked.
+    pub fn new(width: u32, height: u32, tile_size: WorldSize) -> CollisionGrid {
+        CollisionGrid {
+            width: width,
+            height: height,
+            tile_size: tile_size,
+            blocked: vec![false; (width * height) as usize],
+        }
+    }
+
+    /// Read the collision data of a map.
+    pub fn from_map(map: &tiled::Map) -> CollisionGrid {
+        let tile_size = WorldSize::new(map.tile_width as f32, map.tile_height as f32);
+        let mut grid = CollisionGrid::new(map.width, map.height, tile_size);
+
+        // Tiles that are impassable wherever they're placed
+        let mut impassable_gids = Vec::new();
+        for tileset in map.tilesets.iter() {
+            for tile in tileset.tiles.iter() {
+                if let Some(&tiled::PropertyValue::BoolValue(false)) =
+                    tile.properties.get(PASSABLE_PROPERTY)
+                {
+                    impassable_gids.push(tileset.first_gid + tile.id);
+                }
+            }
+        }
+        if !impassable_gids.is_empty() {
+            for layer in map.layers.iter() {
+                for (y, row) in layer.tiles.iter().enumerate() {
+                    for (x, gid) in row.iter().enumerate() {
+                        if impassable_gids.contains(gid) {
+                            grid.set_blocked(x as i32, y as i32, true);
+                        }
+                    }
+                }
+            }
+        }
+
+        // Walls drawn over the map
+        for group in map.object_groups.iter() {
+            if group.name != WALLS_OBJECT_GROUP {
+                continue;
+            }
+            for object in group.objects.iter() {
+                match object.shape {
+                    tiled::ObjectShape::Rect { width, height } => grid.block_area(
+                        &WorldRect::new(
+                            WorldPoint::new(object.x, object.y),
+                            WorldSize::new(width, height),
+                        ),
+                    ),
+                    _ => warn!(
+                        "Ignoring wall {} ('{}'), which isn't a rectangle",
+                        object.id, object.name
+                    ),
+                }
+            }
+        }
+        grid
+    }
+
+    /// The size of the grid, in tiles.
+    pub fn size_in_tiles(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// The size of each tile, in world units.
+    pub fn tile_size(&self) -> WorldSize {
+        self.tile_size
+    }
+
+    /// The tile coordinates of the tile containing a point.
+    pub fn tile_at(&self, point: WorldPoint) -> (i32, i32) {
+        (
+            (point.x / self.tile_size.width).floor() as i32,
+            (point.y / self.tile_size.height).floor() as i32,
+        )
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if x < 0 || y < 0 || x as u32 >= self.width || y as u32 >= self.height {
+            None
+        } else {
+            Some(y as usize * self.width as usize + x as usize)
+        }
+    }
+
+    /// Whether the tile at the given tile coordinates is blocked. Tiles outside the map are.
+    pub fn is_blocked(&self, x: i32, y: i32) -> bool {
+        self.index(x, y).map(|i| self.blocked[i]).unwrap_or(true)
+    }
+
+    /// Block or unblock the tile at the given tile coordinates. Tiles outside the map are ignored.
+    pub fn set_blocked(&mut self, x: i32, y: i32, blocked: bool) {
+        if let Some(i) = self.index(x, y) {
+            self.blocked[i] = blocked;
+        }
+    }
+
+    /// Block every tile that an area overlaps.
+    pub fn block_area(&mut self, area: &WorldRect) {
+        let (columns, rows) = self.tiles_overlapped(area);
+        for y in rows.0..rows.1 + 1 {
+            for x in columns.0..columns.1 + 1 {
+                self.set_blocked(x, y, true);
+            }
+        }
+    }
+
+    /// The tile coordinates of every tile an area overlaps.
+    pub fn tiles_in_area(&self, area: &WorldRect) -> Vec<(i32, i32)> {
+        let (columns, rows) = self.tiles_overlapped(area);
+        let mut tiles = Vec::new();
+        for y in rows.0..rows.1 + 1 {
+            for x in columns.0..columns.1 + 1 {
+                tiles.push((x, y));
+            }
+        }
+        tiles
+    }
+
+    /// Whether any tile an area overlaps is blocked.
+    pub fn is_area_blocked(&self, area: &WorldRect) -> bool {
+        let (columns, rows) = self.tiles_overlapped(area);
+        (rows.0..rows.1 + 1).any(|y| (columns.0..columns.1 + 1).any(|x| self.is_blocked(x, y)))
+    }
+
+    // The inclusive ranges of columns and rows an area overlaps. Edges lying on a tile boundary
+    //  don't overlap the tile on the other side.
+    fn tiles_overlapped(&self, area: &WorldRect) -> ((i32, i32), (i32, i32)) {
+        (
+            overlapped_range(area.min_x(), area.max_x(), self.tile_size.width),
+            overlapped_range(area.min_y(), area.max_y(), self.tile_size.height),
+        )
+    }
+
+    /// Work out how far a box can actually go when trying to move by `motion`, stopping it
+    /// against blocked tiles instead of letting it pass through them, however far it moves.
+    ///
+    /// Horizontal movement is resolved first, then vertical, so a box moving diagonally into a
+    /// wall slides along it rather than stopping dead. A box that already overlaps a blocked tile
+    /// may move out of it.
+    pub fn sweep(&self, bounds: &WorldRect, motion: WorldVector) -> WorldVector {
+        let rows = overlapped_range(bounds.min_y(), bounds.max_y(), self.tile_size.height);
+        let x = sweep_axis(
+            bounds.min_x(),
+            bounds.max_x(),
+            motion.x,
+            self.tile_size.width,
+            |column| (rows.0..rows.1 + 1).any(|row| self.is_blocked(column, row)),
+        );
+
+        let moved = bounds.translate(&WorldVector::new(x, 0.0));
+        let columns = overlapped_range(moved.min_x(), moved.max_x(), self.tile_size.width);
+        let y = sweep_axis(
+            moved.min_y(),
+            moved.max_y(),
+            motion.y,
+            self.tile_size.height,
+            |row| (columns.0..columns.1 + 1).any(|column| self.is_blocked(column, row)),
+        );
+        WorldVector::new(x, y)
+    }
+}
+
+// The inclusive range of tiles that the span from `min` to `max` overlaps, along one axis.
+fn overlapped_range(min: f32, max: f32, tile: f32) -> (i32, i32) {
+    (
+        ((min + EPSILON) / tile).floor() as i32,
+        ((max - EPSILON) / tile).floor() as i32,
+    )
+}
+
+// Move the span from `min` to `max` by up to `delta` along one axis, stopping at the first line
+//  of tiles it would enter for which `blocked` is true. Returns how far it can move.
+fn sweep_axis<F>(min: f32, max: f32, delta: f32, tile: f32, blocked: F) -> f32
+where
+    F: Fn(i32) -> bool,
+{
+    if delta > 0.0 {
+        // The first line of tiles entered is the one after the leading edge's current one.
+        let first = ((max - EPSILON) / tile).floor() as i32 + 1;
+        let last = ((max + delta - EPSILON) / tile).floor() as i32;
+        for line in first..last + 1 {
+            if blocked(line) {
+                return (line as f32 * tile - max).max(0.0);
+            }
+        }
+        delta
+    } else if delta < 0.0 {
+        let first = ((min + EPSILON) / tile).floor() as i32 - 1;
+        let last = ((min + delta + EPSILON) / tile).floor() as i32;
+        for line in (last..first + 1).rev() {
+            if blocked(line) {
+                return ((line + 1) as f32 * tile - min).min(0.0);
+            }
+        }
+        delta
+    } else {
+        0.0
+    }
+}
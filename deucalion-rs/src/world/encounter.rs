@@ -0,0 +1,279 @@
+//! Random encounters: battles that start by themselves as the player walks about.
+//!
+//! Encounters happen in regions of a map, marked by objects of type "encounter", in any object
+//! group. A region's custom properties say what may be met there:
+//!
+//! - `troops`: the troops that may be met, separated by commas, each with an optional weight
+//!   after a colon, e.g. "slimes:3, bats". A troop with a weight of 3 is met three times as
+//!   often as one with the default weight of 1.
+//! - `min_steps` and `max_steps`: how many steps the player takes between encounters, chosen at
+//!   random from this range each time.
+//!
+//! Where regions overlap, the one last in the map's objects wins. A step is a move onto another
+//! tile, whether the player moves by the grid or freely.
+
+use std::cmp;
+
+use hlua::AnyLuaValue;
+use tiled;
+
+use config::schema::{ConfigValue, Field, Schema};
+use geom::{WorldPoint, WorldRect, WorldSize};
+use utility::random::Random;
+use world::character::property_to_lua;
+
+/// The type of the map objects that mark encounter regions.
+pub const ENCOUNTER_OBJECT_TYPE: &'static str = "encounter";
+
+/// The fewest steps between encounters, unless a region says otherwise.
+pub const DEFAULT_MIN_STEPS: u32 = 10;
+
+/// The most steps between encounters, unless a region says otherwise.
+pub const DEFAULT_MAX_STEPS: u32 = 30;
+
+/// A troop that may be met in a region, and how often.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct EncounterTroop {
+    /// The troop's name, as for `start_battle`.
+    pub troop: String,
+    /// How likely the troop is to be met, relative to the region's other troops.
+    pub weight: u32,
+}
+
+impl EncounterTroop {
+    /// Parse a troop from the form used in region properties: a name, optionally followed by a
+    /// colon and a weight above zero, e.g. "slimes:3".
+    pub fn from_name(name: &str) -> Option<EncounterTroop> {
+        let mut parts = name.splitn(2, ':');
+        let troop = parts.next().unwrap_or("").trim();
+        let weight = match parts.next() {
+            Some(weight) => weight.trim().parse().ok()?,
+            None => 1,
+        };
+        if troop.is_empty() || weight == 0 {
+            return None;
+        }
+        Some(EncounterTroop {
+            troop: String::from(troop),
+            weight: weight,
+        })
+    }
+}
+
+/// The troops that may be met in a region. In map properties, a string of them separated by
+/// commas, e.g. "slimes:3, bats".
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct EncounterTable(pub Vec<EncounterTroop>);
+
+impl EncounterTable {
+    /// Choose a troop at random, according to the troops' weights.
+    pub fn choose(&self, random: &mut Random) -> Option<&str> {
+        let total: u32 = self.0.iter().map(|entry| entry.weight).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut roll = random.below(total);
+        for entry in self.0.iter() {
+            if roll < entry.weight {
+                return Some(entry.troop.as_str());
+            }
+            roll -= entry.weight;
+        }
+        None
+    }
+}
+
+impl ConfigValue for EncounterTable {
+    fn type_name() -> String {
+        String::from("troops separated by commas, each with an optional \":<weight>\"")
+    }
+
+    fn from_lua(value: AnyLuaValue) -> Option<EncounterTable> {
+        let troops = String::from_lua(value)?
+            .split(',')
+            .map(EncounterTroop::from_name)
+            .collect::<Option<Vec<EncounterTroop>>>()?;
+        if troops.is_empty() {
+            return None;
+        }
+        Some(EncounterTable(troops))
+    }
+}
+
+/// A region of a map where encounters happen.
+#[derive(PartialEq, Debug, Clone)]
+pub struct EncounterRegion {
+    /// The name of the region's object, which `on_encounter` is given.
+    pub name: String,
+    pub area: WorldRect,
+    pub troops: EncounterTable,
+    pub min_steps: u32,
+    pub max_steps: u32,
+}
+
+impl Default for EncounterRegion {
+    fn default() -> EncounterRegion {
+        EncounterRegion {
+            name: String::new(),
+            area: WorldRect::new(WorldPoint::origin(), WorldSize::zero()),
+            troops: EncounterTable::default(),
+            min_steps: DEFAULT_MIN_STEPS,
+            max_steps: DEFAULT_MAX_STEPS,
+        }
+    }
+}
+
+/// Describe how each property of an encounter region is read from its map object.
+pub fn get_encounter_region_schema() -> Schema<EncounterRegion> {
+    Schema::new()
+        .field(Field::new("troops", |r: &mut EncounterRegion| &mut r.troops).required())
+        .field(
+            Field::new("min_steps", |r: &mut EncounterRegion| &mut r.min_steps).range(1, 10000),
+        )
+        .field(
+            Field::new("max_steps", |r: &mut EncounterRegion| &mut r.max_steps)
+                .default_from(|r| cmp::max(r.min_steps, DEFAULT_MAX_STEPS))
+                .range(1, 10000),
+        )
+}
+
+/// Find the encounter regions of a map. Regions with invalid properties are reported and left
+/// out.
+pub fn get_map_encounter_regions(map: &tiled::Map) -> Vec<EncounterRegion> {
+    let mut regions = Vec::new();
+    for object in map.object_groups
+        .iter()
+        .flat_map(|group| group.objects.iter())
+        .filter(|object| object.obj_type == ENCOUNTER_OBJECT_TYPE)
+    {
+        let (mut region, errors) = get_encounter_region_schema().read_from(
+            |identifier| object.properties.get(identifier).map(property_to_lua),
+            EncounterRegion::default(),
+        );
+        if !errors.is_empty() {
+            for e in errors.iter() {
+                error!("Invalid encounter region '{}', leaving it out: {}", object.name, e);
+            }
+            continue;
+        }
+        if region.max_steps < region.min_steps {
+            error!(
+                "Invalid encounter region '{}', leaving it out: max_steps is below min_steps",
+                object.name
+            );
+            continue;
+        }
+        // Tile objects are anchored at their bottom left; everything else at its top left.
+        let top = if object.gid != 0 {
+            object.y - object.height
+        } else {
+            object.y
+        };
+        region.name = object.name.clone();
+        region.area = WorldRect::new(
+            WorldPoint::new(object.x, top),
+            WorldSize::new(object.width, object.height),
+        );
+        regions.push(region);
+    }
+    regions
+}
+
+/// A step the player has taken in an encounter region.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct EncounterStep {
+    /// The index of the region the step was taken in.
+    pub region: usize,
+    /// Whether the region's count of steps has run out, so an encounter is due.
+    pub due: bool,
+}
+
+/// Counts the player's steps through a map's encounter regions.
+pub struct Encounters {
+    pub regions: Vec<EncounterRegion>,
+    random: Random,
+    // The tile the player was last seen on, if they've been seen since they were last moved by
+    //  a script or a warp.
+    tile: Option<(i32, i32)>,
+    // The region the player is in, and the steps left until an encounter there.
+    current: Option<usize>,
+    steps_left: u32,
+}
+
+impl Encounters {
+    /// Count steps through the given regions, rolling the steps between encounters with
+    /// `random`.
+    pub fn new(regions: Vec<EncounterRegion>, random: Random) -> Encounters {
+        Encounters {
+            regions: regions,
+            random: random,
+            tile: None,
+            current: None,
+            steps_left: 0,
+        }
+    }
+
+    /// The index of the region containing a point, if any.
+    pub fn region_at(&self, point: WorldPoint) -> Option<usize> {
+        self.regions
+            .iter()
+            .rposition(|region| region.area.contains(&point))
+    }
+
+    /// Note where the player is: `point` is the middle of the player, on tile `tile`. Returns
+    /// the step taken, if the player has moved onto another tile in an encounter region.
+    pub fn track(&mut self, point: WorldPoint, tile: (i32, i32)) -> Option<EncounterStep> {
+        let previous = self.tile;
+        self.tile = Some(tile);
+        if previous.is_none() || previous == Some(tile) {
+            return None;
+        }
+        let region = match self.region_at(point) {
+            Some(region) => region,
+            None => {
+                self.current = None;
+                return None;
+            }
+        };
+        if self.current != Some(region) {
+            self.current = Some(region);
+            self.roll_steps();
+        }
+        self.steps_left = self.steps_left.saturating_sub(1);
+        Some(EncounterStep {
+            region: region,
+            due: self.steps_left == 0,
+        })
+    }
+
+    /// Forget where the player was, so that moving them without walking, or walking them in a
+    /// cutscene, isn't counted as a step.
+    pub fn forget_position(&mut self) {
+        self.tile = None;
+    }
+
+    /// Start counting the steps to the next encounter again, such as after one has happened.
+    pub fn reset(&mut self) {
+        self.roll_steps();
+    }
+
+    /// Choose a troop from a region at random.
+    pub fn choose_troop(&mut self, region: usize) -> Option<String> {
+        let random = &mut self.random;
+        self.regions
+            .get(region)
+            .and_then(|region| region.troops.choose(random))
+            .map(String::from)
+    }
+
+    // Choose how many steps the player takes until the next encounter in the current region.
+    fn roll_steps(&mut self) {
+        let range = self.current
+            .and_then(|i| self.regions.get(i))
+            .map(|region| (region.min_steps, region.max_steps));
+        self.steps_left = match range {
+            Some((minimum, maximum)) => self.random.between(minimum as i32, maximum as i32) as u32,
+            None => 0,
+        };
+    }
+}
@@ -0,0 +1,348 @@
+//! The things on a map, such as the player and NPCs, and the collision data they move against.
+
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use error::DeucalionError;
+use geom::{WorldPoint, WorldRect, WorldVector};
+use resource::animation::Animation;
+use utility::random::Random;
+use world::actor::Actor;
+use world::animation::{AnimationEvent, AnimationPlayer, AnimationTarget, PlayingAnimation};
+use world::character::PLAYER_TARGET;
+use world::collision::CollisionGrid;
+use world::movement::MovementSettings;
+use world::npc::{Npc, Surroundings};
+use world::party::Party;
+use world::pathfinding::{PathFollower, PathResult};
+
+pub mod actor;
+pub mod animation;
+pub mod character;
+pub mod collision;
+pub mod encounter;
+pub mod movement;
+pub mod npc;
+pub mod party;
+pub mod pathfinding;
+#[cfg(test)]
+mod test_actor;
+#[cfg(test)]
+mod test_animation;
+#[cfg(test)]
+mod test_character;
+#[cfg(test)]
+mod test_collision;
+#[cfg(test)]
+mod test_encounter;
+#[cfg(test)]
+mod test_movement;
+#[cfg(test)]
+mod test_npc;
+#[cfg(test)]
+mod test_party;
+#[cfg(test)]
+mod test_pathfinding;
+#[cfg(test)]
+mod test_support;
+
+/// Everything on the current map.
+pub struct World {
+    pub collision: CollisionGrid,
+    pub movement: MovementSettings,
+    pub player: Actor,
+    /// The followers trailing behind the player.
+    pub party: Party,
+    pub npcs: Vec<Npc>,
+    pub random: Random,
+    /// The animations playing on the map.
+    pub animations: Vec<PlayingAnimation>,
+    // Where a script has told the player to walk to, if anywhere.
+    player_path: Option<PathFollower>,
+    // The events of animation frames shown since they were last taken.
+    animation_events: Vec<AnimationEvent>,
+}
+
+impl World {
+    /// Create a world with the player and NPCs on a map with the given collision data.
+    pub fn new(
+        collision: CollisionGrid,
+        movement: MovementSettings,
+        player: Actor,
+        npcs: Vec<Npc>,
+    ) -> World {
+        World {
+            collision: collision,
+            movement: movement,
+            player: player,
+            party: Party::new(),
+            npcs: npcs,
+            random: Random::from_time(),
+            animations: Vec::new(),
+            player_path: None,
+            animation_events: Vec::new(),
+        }
+    }
+
+    /// Move everything for one frame lasting `delta` seconds: the player according to their
+    /// movement input, and NPCs according to their behaviors, except for anyone a script is
+    /// walking somewhere. Actors don't walk through each other.
+    pub fn update(&mut self, player_input: WorldVector, delta: f32) {
+        let grid = self.occupancy(None);
+        match self.player_path.take() {
+            Some(mut path) => {
+                match path.update(
+                    &mut self.player,
+                    &self.movement,
+                    &grid,
+                    &self.collision,
+                    delta,
+                ) {
+                    PathResult::Walking => self.player_path = Some(path),
+                    PathResult::Arrived => {}
+                    PathResult::Unreachable => warn!(
+                        "The player can't reach tile {:?}, so they're giving up",
+                        path.goal()
+                    ),
+                }
+            }
+            None => self.player.update(&self.movement, &grid, player_input, delta),
+        }
+        self.party
+            .update(&self.player, &self.movement, &self.collision, delta);
+
+        // Every NPC moves against the same grid, with everyone blocked. Each NPC's own tiles are
+        //  freed while it moves, and its new ones blocked for the NPCs after it.
+        let mut grid = self.occupancy(None);
+        self.block_player_and_party(&mut grid);
+        for i in 0..self.npcs.len() {
+            self.free_npc_tiles(&mut grid, i);
+            {
+                let npcs = self.npc_bounds();
+                let surroundings = Surroundings {
+                    grid: &grid,
+                    walls: &self.collision,
+                    settings: &self.movement,
+                    player: self.player.bounds(),
+                    npcs: &npcs,
+                };
+                self.npcs[i].update(&surroundings, &mut self.random, delta);
+            }
+            grid.block_area(&self.npcs[i].actor.bounds());
+        }
+
+        for playing in self.animations.iter_mut() {
+            for event in playing.player.update(delta) {
+                self.animation_events.push(AnimationEvent {
+                    animation: playing.name.clone(),
+                    event: event,
+                });
+            }
+        }
+        self.animations.retain(|playing| !playing.player.is_finished());
+    }
+
+    /// Get the collision data with the tiles of every actor blocked, except the player and
+    /// their followers if `npc` is None, or the NPC at that index otherwise.
+    pub fn occupancy(&self, npc: Option<usize>) -> CollisionGrid {
+        let mut grid = self.collision.clone();
+        if npc.is_some() {
+            self.block_player_and_party(&mut grid);
+        }
+        for (i, other) in self.npcs.iter().enumerate() {
+            if Some(i) != npc {
+                grid.block_area(&other.actor.bounds());
+            }
+        }
+        grid
+    }
+
+    // Block the tiles of the player and their followers.
+    fn block_player_and_party(&self, grid: &mut CollisionGrid) {
+        grid.block_area(&self.player.bounds());
+        for follower in self.party.followers() {
+            grid.block_area(&follower.actor.bounds());
+        }
+    }
+
+    // Unblock the tiles of the NPC at index `npc` in a grid with every actor blocked, as if it
+    //  had been left out, except for walls and tiles that someone else is also on.
+    fn free_npc_tiles(&self, grid: &mut CollisionGrid, npc: usize) {
+        let mut others = vec![self.player.bounds()];
+        others.extend(self.party.followers().iter().map(|f| f.actor.bounds()));
+        others.extend(
+            self.npcs
+                .iter()
+                .enumerate()
+                .filter(|&(i, _)| i != npc)
+                .map(|(_, other)| other.actor.bounds()),
+        );
+        let occupied: HashSet<(i32, i32)> = others
+            .iter()
+            .flat_map(|area| grid.tiles_in_area(area))
+            .collect();
+        let own = grid.tiles_in_area(&self.npcs[npc].actor.bounds());
+        for (x, y) in own {
+            if !self.collision.is_blocked(x, y) && !occupied.contains(&(x, y)) {
+                grid.set_blocked(x, y, false);
+            }
+        }
+    }
+
+    // Where every NPC is, by name.
+    fn npc_bounds(&self) -> Vec<(String, WorldRect)> {
+        self.npcs
+            .iter()
+            .map(|npc| (npc.name.clone(), npc.actor.bounds()))
+            .collect()
+    }
+
+    /// Find an NPC by name.
+    pub fn npc(&self, name: &str) -> Option<&Npc> {
+        self.npcs.iter().find(|npc| npc.name == name)
+    }
+
+    /// Find an NPC by name, to change it.
+    pub fn npc_mut(&mut self, name: &str) -> Option<&mut Npc> {
+        self.npcs.iter_mut().find(|npc| npc.name == name)
+    }
+
+    /// Walk the player, if `actor` is "player", or the named NPC to the tile at the given tile
+    /// coordinates, going around anything in the way. While the player is being walked
+    /// somewhere, their movement input is ignored.
+    pub fn move_to(&mut self, actor: &str, tile: (i32, i32)) -> Result<(), DeucalionError> {
+        let (width, height) = self.collision.size_in_tiles();
+        if tile.0 < 0 || tile.1 < 0 || tile.0 as u32 >= width || tile.1 as u32 >= height {
+            return Err(DeucalionError::from(format!(
+                "tile ({}, {}) is outside the map",
+                tile.0, tile.1
+            )));
+        }
+        if actor == PLAYER_TARGET {
+            self.player_path = Some(PathFollower::new(tile));
+            return Ok(());
+        }
+        match self.npc_mut(actor) {
+            Some(npc) => {
+                npc.move_to(tile);
+                Ok(())
+            }
+            None => Err(no_such_npc(actor)),
+        }
+    }
+
+    /// Whether a script is walking the player somewhere.
+    pub fn is_player_moving_to(&self) -> bool {
+        self.player_path.is_some()
+    }
+
+    /// Start playing an animation, by name, on an actor or at a point. Looping animations play
+    /// until they're stopped.
+    pub fn play_animation(
+        &mut self,
+        name: &str,
+        animation: Rc<Animation>,
+        target: AnimationTarget,
+    ) -> Result<(), DeucalionError> {
+        if let AnimationTarget::Actor(ref actor) = target {
+            if actor != PLAYER_TARGET && self.npc(actor).is_none() {
+                return Err(no_such_npc(actor));
+            }
+        }
+        self.animations.push(PlayingAnimation {
+            name: String::from(name),
+            player: AnimationPlayer::new(animation),
+            target: target,
+        });
+        Ok(())
+    }
+
+    /// Stop every playing animation with the given name.
+    pub fn stop_animation(&mut self, name: &str) {
+        self.animations.retain(|playing| playing.name != name);
+    }
+
+    /// Find where an animation is being played: the middle of the actor it's on, or its point.
+    /// None if it's on an NPC that's no longer there.
+    pub fn get_animation_position(&self, target: &AnimationTarget) -> Option<WorldPoint> {
+        match *target {
+            AnimationTarget::Position(point) => Some(point),
+            AnimationTarget::Actor(ref actor) if actor == PLAYER_TARGET => {
+                Some(self.player.bounds().center())
+            }
+            AnimationTarget::Actor(ref actor) => {
+                self.npc(actor).map(|npc| npc.actor.bounds().center())
+            }
+        }
+    }
+
+    /// Remove and return the events of the animation frames shown since this was last called,
+    /// oldest first.
+    pub fn take_animation_events(&mut self) -> Vec<AnimationEvent> {
+        self.animation_events.drain(..).collect()
+    }
+
+    /// Put the player somewhere else on the map straight away, bringing their followers along.
+    pub fn warp_player(&mut self, position: WorldPoint) {
+        self.player.place(position);
+        self.player_path = None;
+        self.party.warp(&self.player);
+    }
+
+    /// Add a character to the end of the player's line of followers, starting on the player.
+    pub fn add_follower(&mut self, character: &str) -> Result<(), DeucalionError> {
+        if self.party.has_follower(character) {
+            return Err(DeucalionError::from(format!(
+                "{} is already following the player",
+                character
+            )));
+        }
+        self.party.add_follower(character, &self.player);
+        Ok(())
+    }
+
+    /// Remove a character from the player's line of followers.
+    pub fn remove_follower(&mut self, character: &str) -> Result<(), DeucalionError> {
+        if self.party.remove_follower(character) {
+            Ok(())
+        } else {
+            Err(DeucalionError::from(format!(
+                "{} isn't following the player",
+                character
+            )))
+        }
+    }
+
+    /// Pause the behavior of the named NPC, or of every NPC if no name is given.
+    pub fn pause_routes(&mut self, name: Option<&str>) -> Result<(), DeucalionError> {
+        self.for_npcs(name, Npc::pause)
+    }
+
+    /// Resume the behavior of the named NPC, or of every NPC if no name is given.
+    pub fn resume_routes(&mut self, name: Option<&str>) -> Result<(), DeucalionError> {
+        self.for_npcs(name, Npc::resume)
+    }
+
+    // Do something to the named NPC, or every NPC.
+    fn for_npcs(&mut self, name: Option<&str>, f: fn(&mut Npc)) -> Result<(), DeucalionError> {
+        match name {
+            Some(name) => match self.npc_mut(name) {
+                Some(npc) => {
+                    f(npc);
+                    Ok(())
+                }
+                None => Err(no_such_npc(name)),
+            },
+            None => {
+                for npc in self.npcs.iter_mut() {
+                    f(npc);
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn no_such_npc(name: &str) -> DeucalionError {
+    DeucalionError::from(format!("there is no NPC named '{}' on this map", name))
+}
@@ -0,0 +1,128 @@
+//! How actors move around maps: a tile at a time, or freely by the pixel, and whether they can
+//! move diagonally. The game chooses a default in game_config.lua, and each map may override it
+//! with custom properties in its TMX file.
+
+use hlua::AnyLuaValue;
+use tiled;
+
+use config::game_config::GameConfig;
+use config::schema::ConfigValue;
+use geom::WorldVector;
+
+/// The map property that overrides the game's movement mode.
+pub const MOVEMENT_MODE_PROPERTY: &'static str = "movement_mode";
+
+/// The map property that overrides whether the game allows diagonal movement.
+pub const DIAGONAL_MOVEMENT_PROPERTY: &'static str = "diagonal_movement";
+
+/// How actors move.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum MovementMode {
+    /// Actors step from tile to tile, and can only stop on a tile.
+    Grid,
+    /// Actors move by any amount in any direction, following analog input, and collide with
+    /// blocked tiles by their bounding boxes.
+    Free,
+}
+
+impl MovementMode {
+    /// Parse a movement mode from the name used for it in game_config.lua and map properties.
+    pub fn from_name(name: &str) -> Option<MovementMode> {
+        match name {
+            "grid" => Some(MovementMode::Grid),
+            "free" => Some(MovementMode::Free),
+            _ => None,
+        }
+    }
+
+    /// The name used for this movement mode in game_config.lua and map properties.
+    pub fn name(self) -> &'static str {
+        match self {
+            MovementMode::Grid => "grid",
+            MovementMode::Free => "free",
+        }
+    }
+}
+
+impl ConfigValue for MovementMode {
+    fn type_name() -> String {
+        String::from("one of grid or free")
+    }
+
+    fn from_lua(value: AnyLuaValue) -> Option<MovementMode> {
+        String::from_lua(value).and_then(|name| MovementMode::from_name(&name))
+    }
+}
+
+/// How actors move on a particular map.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct MovementSettings {
+    pub mode: MovementMode,
+    /// Whether actors may move diagonally. Without it, diagonal input is reduced to the nearest
+    /// cardinal direction.
+    pub diagonal: bool,
+}
+
+impl MovementSettings {
+    /// The game's default movement settings.
+    pub fn for_game(config: &GameConfig) -> MovementSettings {
+        MovementSettings {
+            mode: config.movement_mode,
+            diagonal: config.diagonal_movement,
+        }
+    }
+
+    /// The movement settings for a map, which are the game's, overridden by any of the map's
+    /// properties. Invalid properties are reported and ignored.
+    pub fn for_map(config: &GameConfig, map: &tiled::Map) -> MovementSettings {
+        MovementSettings::for_game(config).with_properties(&map.properties)
+    }
+
+    /// Override these settings with the movement properties of a map.
+    pub fn with_properties(self, properties: &tiled::Properties) -> MovementSettings {
+        let mut settings = self;
+        match properties.get(MOVEMENT_MODE_PROPERTY) {
+            Some(&tiled::PropertyValue::StringValue(ref name)) => {
+                match MovementMode::from_name(name) {
+                    Some(mode) => settings.mode = mode,
+                    None => warn!("Ignoring unknown movement mode '{}' in map properties", name),
+                }
+            }
+            Some(other) => warn!(
+                "Ignoring map property {} = {:?}, which should be a string",
+                MOVEMENT_MODE_PROPERTY, other
+            ),
+            None => {}
+        }
+        match properties.get(DIAGONAL_MOVEMENT_PROPERTY) {
+            Some(&tiled::PropertyValue::BoolValue(diagonal)) => settings.diagonal = diagonal,
+            Some(other) => warn!(
+                "Ignoring map property {} = {:?}, which should be a boolean",
+                DIAGONAL_MOVEMENT_PROPERTY, other
+            ),
+            None => {}
+        }
+        settings
+    }
+
+    /// Restrict movement input to what these settings allow: without diagonal movement, input
+    /// is snapped to the nearest cardinal direction, keeping its strength. Input is never longer
+    /// than one.
+    pub fn restrict_input(&self, input: WorldVector) -> WorldVector {
+        let length = input.length();
+        let strength = length.min(1.0);
+        if length == 0.0 {
+            WorldVector::new(0.0, 0.0)
+        } else if self.diagonal {
+            input * (strength / length)
+        } else {
+            // Snap to whichever axis the input is mostly along.
+            let snapped = if input.x.abs() >= input.y.abs() {
+                WorldVector::new(input.x.signum(), 0.0)
+            } else {
+                WorldVector::new(0.0, input.y.signum())
+            };
+            snapped * strength
+        }
+    }
+}
@@ -0,0 +1,402 @@
+//! NPCs: actors that move by themselves, following a `Behavior`.
+//!
+//! NPCs are placed on maps in Tiled as objects of type "npc", in any object group. The object's
+//! name identifies the NPC to scripts; its `character` property (or, without one, its name) says
+//! whose info.lua to read, and its other properties override that info (see `world::character`).
+//! A patrolling NPC drawn as a polyline with no `route` property walks along the line.
+
+use tiled;
+
+use geom::{WorldPoint, WorldRect, WorldSize, WorldVector};
+use utility::eight_direction::EightDirection;
+use utility::random::Random;
+use world::actor::{Actor, WalkResult};
+use world::character::{Behavior, CharacterInfo, RouteStep, PLAYER_TARGET};
+use world::collision::CollisionGrid;
+use world::movement::{MovementMode, MovementSettings};
+use world::pathfinding::{PathFollower, PathResult};
+
+/// The type of the map objects that are NPCs.
+pub const NPC_OBJECT_TYPE: &'static str = "npc";
+
+/// The map object property naming the character an NPC is.
+pub const CHARACTER_PROPERTY: &'static str = "character";
+
+/// What an NPC can see of the map while deciding what to do.
+pub struct Surroundings<'a> {
+    /// The map's collision data, with the tiles of every other actor blocked too.
+    pub grid: &'a CollisionGrid,
+    /// The map's own collision data.
+    pub walls: &'a CollisionGrid,
+    pub settings: &'a MovementSettings,
+    /// Where the player is.
+    pub player: WorldRect,
+    /// Where every NPC on the map is, by name.
+    pub npcs: &'a [(String, WorldRect)],
+}
+
+impl<'a> Surroundings<'a> {
+    /// Find where a follow target is: "player", or the name of an NPC.
+    pub fn find(&self, target: &str) -> Option<WorldRect> {
+        if target == PLAYER_TARGET {
+            Some(self.player)
+        } else {
+            self.npcs
+                .iter()
+                .find(|&&(ref name, _)| name == target)
+                .map(|&(_, bounds)| bounds)
+        }
+    }
+}
+
+/// An actor that moves by itself.
+#[derive(Debug, Clone)]
+pub struct Npc {
+    /// The name scripts know the NPC by, which is its object's name on the map.
+    pub name: String,
+    pub actor: Actor,
+    pub behavior: Behavior,
+    // Where the NPC started, which wandering stays near.
+    home: WorldPoint,
+    // Which step of a patrol route the NPC is on.
+    route_index: usize,
+    // How many more seconds the NPC is waiting for.
+    waiting: f32,
+    // Where the NPC is walking to, if anywhere.
+    goal: Option<WorldPoint>,
+    paused: bool,
+    // Where a script has told the NPC to walk to, if anywhere.
+    path: Option<PathFollower>,
+}
+
+impl Npc {
+    /// Create an NPC standing at a position.
+    pub fn new(name: &str, actor: Actor, behavior: Behavior) -> Npc {
+        Npc {
+            name: String::from(name),
+            home: actor.position,
+            actor: actor,
+            behavior: behavior,
+            route_index: 0,
+            waiting: 0.0,
+            goal: None,
+            paused: false,
+            path: None,
+        }
+    }
+
+    /// Stop following the behavior, such as for a cutscene. A step that's under way is finished,
+    /// so the NPC stops on a tile.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Carry on following the behavior from where it was paused.
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    /// Whether the NPC's behavior is paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Walk to the tile at the given tile coordinates, setting the behavior aside until there,
+    /// even while it's paused.
+    pub fn move_to(&mut self, tile: (i32, i32)) {
+        self.path = Some(PathFollower::new(tile));
+        // Whatever the NPC was walking to, it'll have to work it out again from wherever it ends
+        //  up.
+        self.goal = None;
+    }
+
+    /// Whether the NPC is walking somewhere a script told it to.
+    pub fn is_moving_to(&self) -> bool {
+        self.path.is_some()
+    }
+
+    /// Which step of its route a patrolling NPC is on.
+    pub fn route_index(&self) -> usize {
+        self.route_index
+    }
+
+    /// Follow the behavior for one frame lasting `delta` seconds.
+    pub fn update(&mut self, surroundings: &Surroundings, random: &mut Random, delta: f32) {
+        if let Some(mut path) = self.path.take() {
+            match path.update(
+                &mut self.actor,
+                surroundings.settings,
+                surroundings.grid,
+                surroundings.walls,
+                delta,
+            ) {
+                PathResult::Walking => self.path = Some(path),
+                PathResult::Arrived => {}
+                PathResult::Unreachable => warn!(
+                    "NPC {} can't reach tile {:?}, so it's giving up",
+                    self.name,
+                    path.goal()
+                ),
+            }
+            return;
+        }
+        if self.paused {
+            self.actor.advance_step(delta);
+            return;
+        }
+        // Behaviors are cheap to clone, and cloning lets the NPC change itself while following
+        //  one.
+        match self.behavior.clone() {
+            Behavior::Stand => {
+                self.actor.advance_step(delta);
+            }
+            Behavior::Wander { radius, interval } => {
+                self.wander(surroundings, random, radius, interval, delta)
+            }
+            Behavior::Patrol { ref route, repeat } => {
+                self.patrol(surroundings, route, repeat, delta)
+            }
+            Behavior::FacePlayer { distance } => {
+                self.actor.advance_step(delta);
+                if tile_distance(&self.actor.bounds(), &surroundings.player, surroundings.grid)
+                    <= distance
+                {
+                    self.face(surroundings.player, surroundings.settings);
+                }
+            }
+            Behavior::Follow {
+                ref target,
+                distance,
+            } => match surroundings.find(target) {
+                Some(target)
+                    if tile_distance(&self.actor.bounds(), &target, surroundings.grid)
+                        > distance =>
+                {
+                    let goal = position_at(
+                        target.center(),
+                        self.actor.size,
+                        surroundings.settings,
+                        surroundings.grid.tile_size(),
+                    );
+                    self.actor.walk_towards(
+                        goal,
+                        surroundings.settings,
+                        surroundings.grid,
+                        delta,
+                    );
+                }
+                Some(target) => {
+                    self.actor.advance_step(delta);
+                    if !self.actor.is_stepping() {
+                        self.face(target, surroundings.settings);
+                    }
+                }
+                None => {
+                    self.actor.advance_step(delta);
+                }
+            },
+        }
+    }
+
+    // Turn to face something.
+    fn face(&mut self, other: WorldRect, settings: &MovementSettings) {
+        let towards = other.center() - self.actor.bounds().center();
+        if let Some(direction) = EightDirection::from_vector(settings.restrict_input(towards)) {
+            self.actor.facing = direction;
+        }
+    }
+
+    fn wander(
+        &mut self,
+        surroundings: &Surroundings,
+        random: &mut Random,
+        radius: u32,
+        interval: f32,
+        delta: f32,
+    ) {
+        if let Some(goal) = self.goal {
+            match self.actor
+                .walk_towards(goal, surroundings.settings, surroundings.grid, delta)
+            {
+                WalkResult::Walking => return,
+                // Either way, rest a while before setting off again.
+                WalkResult::Arrived | WalkResult::Blocked => {
+                    self.goal = None;
+                    // Vary the rest, so wanderers don't all move in step.
+                    self.waiting = interval * (0.5 + random.fraction() as f32);
+                }
+            }
+        }
+        if self.waiting > 0.0 {
+            self.waiting -= delta;
+            return;
+        }
+        let directions: Vec<EightDirection> = EightDirection::all()
+            .iter()
+            .cloned()
+            .filter(|d| surroundings.settings.diagonal || !d.is_diagonal())
+            .collect();
+        let direction = *random
+            .choose(&directions)
+            .expect("there are always some directions");
+        let tile = surroundings.grid.tile_size();
+        let (x, y) = direction.offset();
+        let goal =
+            self.actor.position + WorldVector::new(x as f32 * tile.width, y as f32 * tile.height);
+        let from_home = goal - self.home;
+        if from_home.x.abs() <= radius as f32 * tile.width + 0.5
+            && from_home.y.abs() <= radius as f32 * tile.height + 0.5
+        {
+            self.goal = Some(goal);
+        } else {
+            self.waiting = interval;
+        }
+    }
+
+    fn patrol(
+        &mut self,
+        surroundings: &Surroundings,
+        route: &[RouteStep],
+        repeat: bool,
+        delta: f32,
+    ) {
+        if self.waiting > 0.0 {
+            self.waiting -= delta;
+            if self.waiting <= 0.0 {
+                self.next_route_step(route.len(), repeat);
+            }
+            return;
+        }
+        let step = match route.get(self.route_index) {
+            Some(&step) => step,
+            // The route is over
+            None => {
+                self.actor.advance_step(delta);
+                return;
+            }
+        };
+        if self.goal.is_none() {
+            let tile = surroundings.grid.tile_size();
+            self.goal = match step {
+                RouteStep::Move(direction) => {
+                    let (x, y) = direction.offset();
+                    Some(
+                        self.actor.position
+                            + WorldVector::new(x as f32 * tile.width, y as f32 * tile.height),
+                    )
+                }
+                RouteStep::To(point) => Some(point),
+                RouteStep::Wait(seconds) => {
+                    self.waiting = seconds;
+                    if seconds <= 0.0 {
+                        self.next_route_step(route.len(), repeat);
+                    }
+                    return;
+                }
+            };
+        }
+        let goal = self.goal.expect("the goal was just set");
+        // When blocked, keep trying the same step, so the NPC carries on once the way is clear.
+        if self.actor
+            .walk_towards(goal, surroundings.settings, surroundings.grid, delta)
+            == WalkResult::Arrived
+        {
+            self.next_route_step(route.len(), repeat);
+        }
+    }
+
+    fn next_route_step(&mut self, length: usize, repeat: bool) {
+        self.goal = None;
+        self.waiting = 0.0;
+        self.route_index += 1;
+        if self.route_index >= length && repeat {
+            self.route_index = 0;
+        }
+    }
+}
+
+// How many tiles apart two areas are, counting diagonal steps as one.
+fn tile_distance(a: &WorldRect, b: &WorldRect, grid: &CollisionGrid) -> u32 {
+    let tile = grid.tile_size();
+    let offset = b.center() - a.center();
+    let x = (offset.x.abs() / tile.width).round();
+    let y = (offset.y.abs() / tile.height).round();
+    x.max(y) as u32
+}
+
+/// Get the position an actor of the given size should have to stand at a point: in grid
+/// movement, on the tile containing it, and in free movement, centred on it.
+pub fn position_at(
+    point: WorldPoint,
+    size: WorldSize,
+    settings: &MovementSettings,
+    tile: WorldSize,
+) -> WorldPoint {
+    match settings.mode {
+        MovementMode::Grid => WorldPoint::new(
+            (point.x / tile.width).floor() * tile.width,
+            (point.y / tile.height).floor() * tile.height,
+        ),
+        MovementMode::Free => {
+            WorldPoint::new(point.x - size.width / 2.0, point.y - size.height / 2.0)
+        }
+    }
+}
+
+/// Create the NPCs placed on a map, reading each one's character info. NPCs whose info can't be
+/// read are reported and given the default info.
+pub fn get_map_npcs(map: &tiled::Map, settings: &MovementSettings) -> Vec<Npc> {
+    let tile = WorldSize::new(map.tile_width as f32, map.tile_height as f32);
+    let mut npcs = Vec::new();
+    for object in map.object_groups
+        .iter()
+        .flat_map(|group| group.objects.iter())
+        .filter(|object| object.obj_type == NPC_OBJECT_TYPE)
+    {
+        let character = match object.properties.get(CHARACTER_PROPERTY) {
+            Some(&tiled::PropertyValue::StringValue(ref character)) => character.clone(),
+            _ => object.name.clone(),
+        };
+        let info = CharacterInfo::by_name(&character)
+            .unwrap_or_else(|e| {
+                error!("Failed to read the info of character {}: {}", character, e);
+                CharacterInfo::default_for(&character)
+            })
+            .with_properties(&object.properties);
+
+        // Tile objects are anchored at their bottom left; everything else at its top left.
+        let top = if object.gid != 0 {
+            object.y - object.height
+        } else {
+            object.y
+        };
+        let mut behavior = info.behavior();
+        let point = match object.shape {
+            tiled::ObjectShape::Polyline { ref points } => {
+                // Patrol along the line, unless a route was given.
+                if let Behavior::Patrol { ref mut route, .. } = behavior {
+                    if route.is_empty() {
+                        *route = points
+                            .iter()
+                            .skip(1)
+                            .map(|&(x, y)| {
+                                let point = WorldPoint::new(object.x + x, object.y + y);
+                                RouteStep::To(position_at(point, tile, settings, tile))
+                            })
+                            .collect();
+                    }
+                }
+                WorldPoint::new(object.x, object.y)
+            }
+            _ => WorldPoint::new(object.x + object.width / 2.0, top + object.height / 2.0),
+        };
+        let actor = Actor::new(
+            position_at(point, tile, settings, tile),
+            tile,
+            info.speed as f32 * tile.width,
+        );
+        debug!("Placing NPC {} ({}) with behavior {:?}", object.name, character, behavior);
+        npcs.push(Npc::new(&object.name, actor, behavior));
+    }
+    npcs
+}
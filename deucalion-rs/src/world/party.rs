@@ -0,0 +1,170 @@
+//! The player's party, trailing behind them on the map.
+//!
+//! Followers walk in a line behind the leader, each one walking to where the one ahead of it
+//! was, by replaying the trail of places the leader has stepped from. When the party is warped,
+//! or taken to another map, everyone arrives on the leader and spreads out again as they walk.
+
+use std::collections::VecDeque;
+
+use geom::WorldPoint;
+use world::actor::Actor;
+use world::collision::CollisionGrid;
+use world::movement::MovementSettings;
+
+// How much shorter than a tile a move can be and still count as a whole step, allowing for
+//  rounding.
+const EPSILON: f32 = 1e-3;
+
+/// A party member walking behind the leader.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Follower {
+    /// Which character the follower is.
+    pub character: String,
+    pub actor: Actor,
+}
+
+/// The followers trailing behind the player.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Party {
+    followers: Vec<Follower>,
+    following: bool,
+    // The places the leader has stepped from, most recent first, one for each follower at most.
+    trail: VecDeque<WorldPoint>,
+    // Where the leader was when the last step was added to the trail.
+    anchor: Option<WorldPoint>,
+}
+
+impl Default for Party {
+    fn default() -> Party {
+        Party::new()
+    }
+}
+
+impl Party {
+    /// Create a party with no followers, which follows the leader once it has some.
+    pub fn new() -> Party {
+        Party {
+            followers: Vec::new(),
+            following: true,
+            trail: VecDeque::new(),
+            anchor: None,
+        }
+    }
+
+    /// The followers shown on the map, nearest the leader first. There are none while following
+    /// is turned off.
+    pub fn followers(&self) -> &[Follower] {
+        if self.following {
+            &self.followers[..]
+        } else {
+            &[]
+        }
+    }
+
+    /// Whether a character is one of the followers, whether or not they're shown.
+    pub fn has_follower(&self, character: &str) -> bool {
+        self.followers.iter().any(|f| f.character == character)
+    }
+
+    /// Add a character to the end of the line, starting on the leader.
+    pub fn add_follower(&mut self, character: &str, leader: &Actor) {
+        let mut actor = leader.clone();
+        actor.place(leader.position);
+        self.anchor.get_or_insert(leader.destination());
+        self.followers.push(Follower {
+            character: String::from(character),
+            actor: actor,
+        });
+    }
+
+    /// Remove a character from the line. Returns whether they were in it.
+    pub fn remove_follower(&mut self, character: &str) -> bool {
+        let count = self.followers.len();
+        self.followers.retain(|f| f.character != character);
+        self.trail.truncate(self.followers.len());
+        self.followers.len() != count
+    }
+
+    /// Whether the followers are shown and following the leader.
+    pub fn is_following(&self) -> bool {
+        self.following
+    }
+
+    /// Show or hide the followers. Followers shown again appear on the leader.
+    pub fn set_following(&mut self, following: bool, leader: &Actor) {
+        if following && !self.following {
+            self.warp(leader);
+        }
+        self.following = following;
+    }
+
+    /// Have every follower walk onto the leader, such as at the start of a cutscene. They spread
+    /// out behind the leader again once the leader walks on.
+    pub fn gather(&mut self) {
+        self.trail.clear();
+    }
+
+    /// Whether every follower is standing on the leader.
+    pub fn is_gathered(&self, leader: &Actor) -> bool {
+        self.followers
+            .iter()
+            .all(|f| !f.actor.is_stepping() && f.actor.position == leader.position)
+    }
+
+    /// Put every follower on the leader straight away, such as after the leader has been
+    /// warped somewhere, or taken to another map.
+    pub fn warp(&mut self, leader: &Actor) {
+        for follower in self.followers.iter_mut() {
+            follower.actor.place(leader.position);
+            follower.actor.facing = leader.facing;
+        }
+        self.trail.clear();
+        self.anchor = Some(leader.destination());
+    }
+
+    /// Move the followers for one frame lasting `delta` seconds, after the leader has moved.
+    /// Followers only collide with `walls`, not with each other or the leader.
+    pub fn update(
+        &mut self,
+        leader: &Actor,
+        settings: &MovementSettings,
+        walls: &CollisionGrid,
+        delta: f32,
+    ) {
+        if !self.following {
+            return;
+        }
+        let tile = walls.tile_size();
+        self.record(leader, tile.width.min(tile.height));
+        for i in 0..self.followers.len() {
+            let target = self.target(i);
+            let actor = &mut self.followers[i].actor;
+            // Followers keep pace with the leader, whatever their own speed.
+            actor.speed = leader.speed;
+            actor.walk_towards(target, settings, walls, delta);
+        }
+    }
+
+    // Add a step to the trail once the leader has set off, or moved, a whole step from where
+    //  the last one was added.
+    fn record(&mut self, leader: &Actor, spacing: f32) {
+        let destination = leader.destination();
+        let anchor = *self.anchor.get_or_insert(destination);
+        if (destination - anchor).length() >= spacing - EPSILON {
+            self.trail.push_front(anchor);
+            self.trail.truncate(self.followers.len());
+            self.anchor = Some(destination);
+        }
+    }
+
+    // Where a follower should walk to: their place on the trail, or the end of the trail if it's
+    //  shorter, or the leader if it's empty.
+    fn target(&self, i: usize) -> WorldPoint {
+        self.trail
+            .get(i)
+            .or_else(|| self.trail.back())
+            .cloned()
+            .or(self.anchor)
+            .unwrap_or(self.followers[i].actor.position)
+    }
+}
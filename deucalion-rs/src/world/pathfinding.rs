@@ -0,0 +1,278 @@
+//! Finding a way across a map, and walking actors along it, for scripted movement such as
+//! walking an NPC to a door.
+//!
+//! Paths are found with A* over the tiles of a `CollisionGrid`, moving in the four cardinal
+//! directions. Since the grid given can have the tiles of other actors blocked too, a path goes
+//! around whoever is standing in the way when it's found; if someone steps into it afterwards,
+//! the actor following it finds another.
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, VecDeque};
+
+use geom::WorldPoint;
+use utility::direction::Direction;
+use utility::eight_direction::EightDirection;
+use world::actor::{Actor, WalkResult};
+use world::collision::CollisionGrid;
+use world::movement::MovementSettings;
+use world::npc::position_at;
+
+/// How long, in seconds, an actor waits for someone to get out of its way before giving up on
+/// reaching where it's going.
+pub const PATIENCE: f32 = 5.0;
+
+// The order neighbours are explored in, so that paths of equal length are always chosen the same
+//  way.
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Right,
+    Direction::Down,
+    Direction::Left,
+];
+
+// A tile waiting to be explored, ordered so that BinaryHeap, which is a max-heap, gives the one
+//  with the lowest estimated total cost first, and among those, the one found first.
+#[derive(PartialEq, Eq)]
+struct Candidate {
+    estimate: u32,
+    order: u32,
+    index: usize,
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Candidate) -> Ordering {
+        other
+            .estimate
+            .cmp(&self.estimate)
+            .then_with(|| other.order.cmp(&self.order))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Candidate) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// How far a tile is from another, walking in the four cardinal directions with nothing in the way.
+fn manhattan_distance(a: (i32, i32), b: (i32, i32)) -> u32 {
+    ((a.0 - b.0).abs() + (a.1 - b.1).abs()) as u32
+}
+
+/// Find the shortest way from one tile to another, as the directions of each step, moving
+/// only through unblocked tiles. The start tile itself may be blocked, such as by the actor
+/// standing there. Returns None if there's no way there, including when the goal is blocked.
+pub fn find_path(
+    grid: &CollisionGrid,
+    start: (i32, i32),
+    goal: (i32, i32),
+) -> Option<Vec<Direction>> {
+    if start == goal {
+        return Some(Vec::new());
+    }
+    if grid.is_blocked(goal.0, goal.1) {
+        return None;
+    }
+    let (width, height) = grid.size_in_tiles();
+    let index = |(x, y): (i32, i32)| y as usize * width as usize + x as usize;
+    let tiles = (width * height) as usize;
+    if start.0 < 0 || start.1 < 0 || start.0 as u32 >= width || start.1 as u32 >= height {
+        return None;
+    }
+
+    // The cheapest known cost of reaching each tile, and the step that reached it
+    let mut costs: Vec<Option<u32>> = vec![None; tiles];
+    let mut came_by: Vec<Option<Direction>> = vec![None; tiles];
+    let mut open = BinaryHeap::new();
+    let mut order = 0;
+    costs[index(start)] = Some(0);
+    open.push(Candidate {
+        estimate: manhattan_distance(start, goal),
+        order: order,
+        index: index(start),
+    });
+
+    while let Some(candidate) = open.pop() {
+        let tile = (
+            (candidate.index % width as usize) as i32,
+            (candidate.index / width as usize) as i32,
+        );
+        if tile == goal {
+            return Some(trace_path(&came_by, start, goal, &index));
+        }
+        let cost = costs[candidate.index].expect("explored tiles have a cost");
+        // A tile can be queued more than once; skip the stale, more expensive entries.
+        if candidate.estimate > cost + manhattan_distance(tile, goal) {
+            continue;
+        }
+        for &direction in DIRECTIONS.iter() {
+            let (dx, dy) = EightDirection::from(direction).offset();
+            let next = (tile.0 + dx, tile.1 + dy);
+            if grid.is_blocked(next.0, next.1) {
+                continue;
+            }
+            let i = index(next);
+            if costs[i].map(|known| known <= cost + 1).unwrap_or(false) {
+                continue;
+            }
+            costs[i] = Some(cost + 1);
+            came_by[i] = Some(direction);
+            order += 1;
+            open.push(Candidate {
+                estimate: cost + 1 + manhattan_distance(next, goal),
+                order: order,
+                index: i,
+            });
+        }
+    }
+    None
+}
+
+// Follow the steps that reached the goal back to the start, and list them in order.
+fn trace_path<F>(
+    came_by: &[Option<Direction>],
+    start: (i32, i32),
+    goal: (i32, i32),
+    index: &F,
+) -> Vec<Direction>
+where
+    F: Fn((i32, i32)) -> usize,
+{
+    let mut path = Vec::new();
+    let mut tile = goal;
+    while tile != start {
+        let direction = came_by[index(tile)].expect("every tile on a path was reached by a step");
+        path.push(direction);
+        let (dx, dy) = EightDirection::from(direction.reverse()).offset();
+        tile = (tile.0 + dx, tile.1 + dy);
+    }
+    path.reverse();
+    path
+}
+
+/// How following a path is going.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum PathResult {
+    /// The actor is on its way, or waiting for someone to get out of its way.
+    Walking,
+    /// The actor is there.
+    Arrived,
+    /// There's no way there, or the way has been blocked for too long.
+    Unreachable,
+}
+
+/// Walks an actor to a tile, finding a path there and finding another whenever it's blocked.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathFollower {
+    goal: (i32, i32),
+    path: VecDeque<Direction>,
+    // Where the actor is walking to for the current step of the path.
+    step_target: Option<WorldPoint>,
+    // How long the actor has been waiting for the way to clear.
+    waited: f32,
+}
+
+impl PathFollower {
+    /// Start walking to the tile at the given tile coordinates.
+    pub fn new(goal: (i32, i32)) -> PathFollower {
+        PathFollower {
+            goal: goal,
+            path: VecDeque::new(),
+            step_target: None,
+            waited: 0.0,
+        }
+    }
+
+    /// The tile being walked to.
+    pub fn goal(&self) -> (i32, i32) {
+        self.goal
+    }
+
+    /// Move an actor along the path for one frame lasting `delta` seconds. `grid` should have
+    /// the tiles of every other actor blocked, and `walls` only the map's own collision data,
+    /// which tells whether it's worth waiting for the way to clear.
+    pub fn update(
+        &mut self,
+        actor: &mut Actor,
+        settings: &MovementSettings,
+        grid: &CollisionGrid,
+        walls: &CollisionGrid,
+        delta: f32,
+    ) -> PathResult {
+        // Finish a step under way before deciding anything, so the actor is on a tile.
+        if self.step_target.is_none() && actor.is_stepping() {
+            actor.advance_step(delta);
+            return PathResult::Walking;
+        }
+        if self.step_target.is_none() {
+            let here = grid.tile_at(actor.bounds().center());
+            if here == self.goal {
+                // Finish on the tile itself, which in free movement may be a little way off.
+                self.step_target = Some(tile_position(self.goal, actor, settings, grid));
+            } else {
+                if self.path.is_empty() {
+                    match find_path(grid, here, self.goal) {
+                        Some(path) => {
+                            self.path = path.into_iter().collect();
+                            self.waited = 0.0;
+                        }
+                        None => return self.wait(here, walls, delta),
+                    }
+                }
+                let direction = self.path.pop_front().expect("the path was just found");
+                let (dx, dy) = EightDirection::from(direction).offset();
+                let next = (here.0 + dx, here.1 + dy);
+                self.step_target = Some(tile_position(next, actor, settings, grid));
+            }
+        }
+
+        let target = self.step_target.expect("the step target was just set");
+        match actor.walk_towards(target, settings, grid, delta) {
+            WalkResult::Walking => PathResult::Walking,
+            WalkResult::Arrived => {
+                self.step_target = None;
+                if grid.tile_at(actor.bounds().center()) == self.goal {
+                    PathResult::Arrived
+                } else {
+                    PathResult::Walking
+                }
+            }
+            // Someone has stepped into the way; find another way next frame.
+            WalkResult::Blocked => {
+                self.step_target = None;
+                self.path.clear();
+                if grid.tile_at(actor.bounds().center()) == self.goal {
+                    PathResult::Arrived
+                } else {
+                    PathResult::Walking
+                }
+            }
+        }
+    }
+
+    // Wait for a blocked way to clear, unless there's no way there at all, or it's taking too
+    //  long.
+    fn wait(&mut self, here: (i32, i32), walls: &CollisionGrid, delta: f32) -> PathResult {
+        self.waited += delta;
+        if self.waited > PATIENCE || find_path(walls, here, self.goal).is_none() {
+            PathResult::Unreachable
+        } else {
+            PathResult::Walking
+        }
+    }
+}
+
+// Where an actor should be to stand on a tile.
+fn tile_position(
+    tile: (i32, i32),
+    actor: &Actor,
+    settings: &MovementSettings,
+    grid: &CollisionGrid,
+) -> WorldPoint {
+    let size = grid.tile_size();
+    let centre = WorldPoint::new(
+        (tile.0 as f32 + 0.5) * size.width,
+        (tile.1 as f32 + 0.5) * size.height,
+    );
+    position_at(centre, actor.size, settings, size)
+}
@@ -0,0 +1,73 @@
+use world::actor::*;
+use world::collision::CollisionGrid;
+use world::movement::{MovementMode, MovementSettings};
+use geom::{WorldPoint, WorldSize, WorldVector};
+use utility::eight_direction::EightDirection;
+
+fn settings(mode: MovementMode, diagonal: bool) -> MovementSettings {
+    MovementSettings {
+        mode: mode,
+        diagonal: diagonal,
+    }
+}
+
+// An actor the size of a tile, moving one tile per second, on an open 10x10 grid.
+fn actor_and_grid() -> (Actor, CollisionGrid) {
+    let size = WorldSize::new(16.0, 16.0);
+    (
+        Actor::new(WorldPoint::new(16.0, 16.0), size, 16.0),
+        CollisionGrid::new(10, 10, size),
+    )
+}
+
+#[test]
+// In grid movement, actors walk a whole tile and then stop if the input is released.
+fn test_grid_step() {
+    let (mut actor, grid) = actor_and_grid();
+    let grid_movement = settings(MovementMode::Grid, false);
+    actor.update(&grid_movement, &grid, WorldVector::new(1.0, 0.0), 0.5);
+    assert_eq!(actor.position, WorldPoint::new(24.0, 16.0));
+    assert!(actor.is_stepping());
+    actor.update(&grid_movement, &grid, WorldVector::new(0.0, 0.0), 1.0);
+    assert_eq!(actor.position, WorldPoint::new(32.0, 16.0));
+    assert!(!actor.is_stepping());
+    assert_eq!(actor.facing, EightDirection::Right);
+}
+
+#[test]
+// Blocked tiles can't be stepped onto, and diagonal steps can't cut corners.
+fn test_grid_step_blocked() {
+    let (mut actor, mut grid) = actor_and_grid();
+    grid.set_blocked(2, 1, true);
+    assert!(!actor.start_step(&grid, EightDirection::Right));
+    assert_eq!(actor.facing, EightDirection::Right);
+    assert!(!actor.start_step(&grid, EightDirection::DownRight));
+    assert!(actor.start_step(&grid, EightDirection::DownLeft));
+}
+
+#[test]
+// Without diagonal movement, diagonal input becomes a cardinal step.
+fn test_grid_step_without_diagonals() {
+    let (mut actor, grid) = actor_and_grid();
+    actor.update(
+        &settings(MovementMode::Grid, false),
+        &grid,
+        WorldVector::new(0.5, 0.6),
+        1.0,
+    );
+    assert_eq!(actor.position, WorldPoint::new(16.0, 32.0));
+}
+
+#[test]
+// In free movement, the input's strength sets the speed, and walls stop the actor.
+fn test_free_movement() {
+    let (mut actor, mut grid) = actor_and_grid();
+    let free = settings(MovementMode::Free, true);
+    actor.update(&free, &grid, WorldVector::new(0.5, 0.0), 1.0);
+    assert_eq!(actor.position, WorldPoint::new(24.0, 16.0));
+    assert!(!actor.is_stepping());
+
+    grid.set_blocked(3, 1, true);
+    actor.update(&free, &grid, WorldVector::new(1.0, 0.0), 10.0);
+    assert_eq!(actor.position, WorldPoint::new(32.0, 16.0));
+}
@@ -0,0 +1,129 @@
+use std::rc::Rc;
+
+use world::animation::*;
+use world::*;
+use world::character::Behavior;
+use world::npc::Npc;
+use world::test_support::*;
+use geom::{WorldPoint, WorldVector};
+use resource::animation::{Animation, AnimationFrame};
+
+fn frame(frame: u32, duration: f32, event: Option<&str>) -> AnimationFrame {
+    AnimationFrame {
+        frame: frame,
+        duration: duration,
+        event: event.map(String::from),
+    }
+}
+
+// Three quarter-second frames, with events on the first and last.
+fn animation(looping: bool) -> Rc<Animation> {
+    Rc::new(Animation {
+        frames: vec![
+            frame(0, 0.25, Some("start")),
+            frame(1, 0.25, None),
+            frame(2, 0.25, Some("end")),
+        ],
+        looping: looping,
+    })
+}
+
+fn world() -> World {
+    open_world(10, 10, vec![Npc::new("guard", actor_at(2, 3), Behavior::Stand)])
+}
+
+#[test]
+// Players should move through the frames in time, firing events as frames are shown.
+fn test_player_events() {
+    let mut player = AnimationPlayer::new(animation(false));
+    assert_eq!(player.update(0.1), vec![String::from("start")]);
+    assert_eq!(player.current_frame().unwrap().frame, 0);
+    assert!(player.update(0.2).is_empty());
+    assert_eq!(player.current_frame().unwrap().frame, 1);
+    // Frames can be skipped over in one go, but their events still fire.
+    assert_eq!(player.update(0.3), vec![String::from("end")]);
+    assert!(!player.is_finished());
+    assert!(player.update(0.5).is_empty());
+    assert!(player.is_finished());
+    assert_eq!(player.current_frame().unwrap().frame, 2);
+}
+
+#[test]
+// Looping animations should start over, firing the first frame's event again, and never finish.
+fn test_player_loops() {
+    let mut player = AnimationPlayer::new(animation(true));
+    player.update(0.0);
+    assert_eq!(
+        player.update(0.8),
+        vec![String::from("end"), String::from("start")]
+    );
+    assert_eq!(player.current_frame().unwrap().frame, 0);
+    assert!(!player.is_finished());
+}
+
+#[test]
+// Animations with no frames, or no length, shouldn't hang.
+fn test_player_degenerate() {
+    let mut player = AnimationPlayer::new(Rc::new(Animation {
+        frames: vec![],
+        looping: true,
+    }));
+    player.update(1.0);
+    assert!(player.is_finished());
+
+    let mut player = AnimationPlayer::new(Rc::new(Animation {
+        frames: vec![frame(0, 0.0, None)],
+        looping: true,
+    }));
+    player.update(1.0);
+    assert_eq!(player.current_frame().unwrap().frame, 0);
+}
+
+#[test]
+// The world should play animations on actors, pass on their events and drop finished ones.
+fn test_world_animations() {
+    let mut world = world();
+    world
+        .play_animation(
+            "alert",
+            animation(false),
+            AnimationTarget::Actor(String::from("guard")),
+        )
+        .unwrap();
+    assert!(world
+        .play_animation(
+            "alert",
+            animation(false),
+            AnimationTarget::Actor(String::from("nobody")),
+        )
+        .is_err());
+    assert_eq!(
+        world.get_animation_position(&world.animations[0].target),
+        Some(WorldPoint::new(40.0, 56.0))
+    );
+
+    world.update(WorldVector::new(0.0, 0.0), 0.6);
+    world.update(WorldVector::new(0.0, 0.0), 0.6);
+    let events: Vec<String> = world
+        .take_animation_events()
+        .into_iter()
+        .map(|e| e.event)
+        .collect();
+    assert_eq!(events, vec![String::from("start"), String::from("end")]);
+    assert!(world.animations.is_empty());
+    assert!(world.take_animation_events().is_empty());
+}
+
+#[test]
+// Stopping an animation should stop it, even if it loops.
+fn test_stop_animation() {
+    let mut world = world();
+    let target = AnimationTarget::Position(WorldPoint::new(8.0, 8.0));
+    world
+        .play_animation("torch", animation(true), target)
+        .unwrap();
+    world.update(WorldVector::new(0.0, 0.0), 5.0);
+    assert_eq!(world.animations.len(), 1);
+    world.stop_animation("torch");
+    assert!(world.animations.is_empty());
+}
@@ -0,0 +1,64 @@
+use world::character::*;
+use utility::eight_direction::EightDirection;
+use hlua::AnyLuaValue;
+use std::collections::HashMap;
+use config::schema::ConfigValue;
+use tiled::PropertyValue;
+
+#[test]
+// Routes can be lists of steps, or strings of them separated by commas.
+fn test_route_parsing() {
+    let expected = Route(vec![
+        RouteStep::Move(EightDirection::Up),
+        RouteStep::Wait(1.5),
+        RouteStep::Move(EightDirection::DownLeft),
+    ]);
+    let from_string =
+        Route::from_lua(AnyLuaValue::LuaString(String::from("up, wait 1.5, down_left")));
+    assert_eq!(from_string, Some(expected.clone()));
+    let from_list = Route::from_lua(AnyLuaValue::LuaArray(vec![
+        (AnyLuaValue::LuaNumber(2.0), AnyLuaValue::LuaString(String::from("wait 1.5"))),
+        (AnyLuaValue::LuaNumber(1.0), AnyLuaValue::LuaString(String::from("up"))),
+        (AnyLuaValue::LuaNumber(3.0), AnyLuaValue::LuaString(String::from("down_left"))),
+    ]));
+    assert_eq!(from_list, Some(expected));
+    assert_eq!(RouteStep::from_name("wait"), None);
+    assert_eq!(RouteStep::from_name("wait -1"), None);
+    assert_eq!(RouteStep::from_name("up up"), None);
+}
+
+#[test]
+// Map object properties override a character's info, and invalid ones are ignored.
+fn test_properties_override_info() {
+    let mut properties = HashMap::new();
+    properties.insert(
+        String::from("behavior"),
+        PropertyValue::StringValue(String::from("patrol")),
+    );
+    properties.insert(
+        String::from("route"),
+        PropertyValue::StringValue(String::from("left, right")),
+    );
+    properties.insert(String::from("route_repeat"), PropertyValue::BoolValue(false));
+    properties.insert(String::from("speed"), PropertyValue::IntValue(-3));
+    let info = CharacterInfo::default_for("guard").with_properties(&properties);
+    assert_eq!(info.speed, CharacterInfo::default_for("guard").speed);
+    assert_eq!(
+        info.behavior(),
+        Behavior::Patrol {
+            route: vec![
+                RouteStep::Move(EightDirection::Left),
+                RouteStep::Move(EightDirection::Right),
+            ],
+            repeat: false,
+        }
+    );
+}
+
+#[test]
+// Characters without an info.lua get the default info.
+fn test_missing_info_uses_defaults() {
+    let info = CharacterInfo::by_name("nobody_at_all").unwrap();
+    assert_eq!(info, CharacterInfo::default_for("nobody_at_all"));
+    assert_eq!(info.behavior(), Behavior::Stand);
+}
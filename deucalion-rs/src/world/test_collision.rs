@@ -0,0 +1,78 @@
+use world::collision::*;
+use geom::{WorldPoint, WorldRect, WorldSize, WorldVector};
+use resource::loading;
+use resource::ResourceKind;
+use tiled;
+
+// A 10x10 grid of 16 unit tiles with a wall down column 5.
+fn grid_with_wall() -> CollisionGrid {
+    let mut grid = CollisionGrid::new(10, 10, WorldSize::new(16.0, 16.0));
+    for y in 0..10 {
+        grid.set_blocked(5, y, true);
+    }
+    grid
+}
+
+fn tile_box(x: f32, y: f32) -> WorldRect {
+    WorldRect::new(WorldPoint::new(x, y), WorldSize::new(16.0, 16.0))
+}
+
+#[test]
+// Everything outside the map counts as blocked.
+fn test_outside_is_blocked() {
+    let grid = grid_with_wall();
+    assert!(!grid.is_blocked(0, 0));
+    assert!(grid.is_blocked(-1, 0));
+    assert!(grid.is_blocked(0, 10));
+}
+
+#[test]
+// A box should stop flush against a wall, however far it tries to go in one frame.
+fn test_sweep_stops_at_wall() {
+    let grid = grid_with_wall();
+    let motion = grid.sweep(&tile_box(40.0, 16.0), WorldVector::new(1000.0, 0.0));
+    assert_eq!(motion, WorldVector::new(24.0, 0.0));
+    // Moving away from the wall is unaffected.
+    let motion = grid.sweep(&tile_box(64.0, 16.0), WorldVector::new(-20.0, 0.0));
+    assert_eq!(motion, WorldVector::new(-20.0, 0.0));
+    // Approaching from the other side stops on the other face.
+    let motion = grid.sweep(&tile_box(120.0, 16.0), WorldVector::new(-100.0, 0.0));
+    assert_eq!(motion, WorldVector::new(-24.0, 0.0));
+}
+
+#[test]
+// Moving diagonally into a wall should slide along it.
+fn test_sweep_slides_along_wall() {
+    let grid = grid_with_wall();
+    let motion = grid.sweep(&tile_box(64.0, 32.0), WorldVector::new(8.0, 8.0));
+    assert_eq!(motion, WorldVector::new(0.0, 8.0));
+}
+
+#[test]
+// The edges of the map stop movement too.
+fn test_sweep_stops_at_map_edge() {
+    let grid = grid_with_wall();
+    let motion = grid.sweep(&tile_box(4.0, 4.0), WorldVector::new(-10.0, -10.0));
+    assert_eq!(motion, WorldVector::new(-4.0, -4.0));
+}
+
+#[test]
+// A box only touching a blocked tile's edge isn't in it, but one overlapping it by a little is.
+fn test_area_blocked() {
+    let grid = grid_with_wall();
+    assert!(!grid.is_area_blocked(&tile_box(64.0, 0.0)));
+    assert!(grid.is_area_blocked(&tile_box(65.0, 0.0)));
+}
+
+#[test]
+// map001 has an empty walls layer and no impassable tiles.
+fn test_collision_from_map() {
+    let (reader, path) =
+        loading::get_resource_reader_and_path_by_name(ResourceKind::Map, "map001").unwrap();
+    let map = tiled::parse_with_path(reader, &path).unwrap();
+    let grid = CollisionGrid::from_map(&map);
+    assert_eq!(grid.size_in_tiles(), (60, 40));
+    assert_eq!(grid.tile_size(), WorldSize::new(32.0, 32.0));
+    assert!(!grid.is_blocked(0, 0));
+    assert!(!grid.is_blocked(59, 39));
+}
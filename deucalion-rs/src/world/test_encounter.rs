@@ -0,0 +1,140 @@
+use hlua::AnyLuaValue;
+use tiled;
+
+use config::schema::ConfigValue;
+use geom::{WorldPoint, WorldRect, WorldSize};
+use resource::loading;
+use resource::ResourceKind;
+use utility::random::Random;
+use world::encounter::*;
+use world::test_support::TILE;
+
+fn table(troops: &str) -> Option<EncounterTable> {
+    EncounterTable::from_lua(AnyLuaValue::LuaString(String::from(troops)))
+}
+
+// A region covering tiles (x, y) to (x + size - 1, y + size - 1).
+fn region(name: &str, x: i32, y: i32, size: i32, steps: u32) -> EncounterRegion {
+    EncounterRegion {
+        name: String::from(name),
+        area: WorldRect::new(
+            WorldPoint::new(x as f32 * TILE, y as f32 * TILE),
+            WorldSize::new(size as f32 * TILE, size as f32 * TILE),
+        ),
+        troops: table(name).unwrap(),
+        min_steps: steps,
+        max_steps: steps,
+    }
+}
+
+// Where the middle of tile (x, y) is.
+fn centre(x: i32, y: i32) -> WorldPoint {
+    WorldPoint::new((x as f32 + 0.5) * TILE, (y as f32 + 0.5) * TILE)
+}
+
+fn walk(encounters: &mut Encounters, x: i32, y: i32) -> Option<EncounterStep> {
+    encounters.track(centre(x, y), (x, y))
+}
+
+#[test]
+// Troops are separated by commas, with optional weights above zero.
+fn test_encounter_table() {
+    assert_eq!(
+        table("slimes:3, bats"),
+        Some(EncounterTable(vec![
+            EncounterTroop {
+                troop: String::from("slimes"),
+                weight: 3,
+            },
+            EncounterTroop {
+                troop: String::from("bats"),
+                weight: 1,
+            },
+        ]))
+    );
+    assert_eq!(table("slimes:0"), None);
+    assert_eq!(table("slimes:lots"), None);
+    assert_eq!(table("slimes,, bats"), None);
+    assert_eq!(table(":3"), None);
+    assert_eq!(EncounterTable::from_lua(AnyLuaValue::LuaNumber(3.0)), None);
+}
+
+#[test]
+// Troops should be met about as often as their weights say.
+fn test_weighted_choice() {
+    let troops = table("slimes:3, bats").unwrap();
+    let mut random = Random::with_seed(5);
+    let slimes = (0..4000)
+        .filter(|_| troops.choose(&mut random) == Some("slimes"))
+        .count();
+    assert!(slimes > 2800 && slimes < 3200, "Slimes were met {} times", slimes);
+}
+
+#[test]
+// map001 has a field in the east where slimes are met.
+fn test_regions_from_map() {
+    let (reader, path) =
+        loading::get_resource_reader_and_path_by_name(ResourceKind::Map, "map001").unwrap();
+    let map = tiled::parse_with_path(reader, &path).unwrap();
+    let regions = get_map_encounter_regions(&map);
+    assert_eq!(regions.len(), 1);
+    assert_eq!(regions[0].name, "east_field");
+    assert_eq!(
+        regions[0].area,
+        WorldRect::new(WorldPoint::new(1280.0, 0.0), WorldSize::new(640.0, 640.0))
+    );
+    assert_eq!(regions[0].troops, table("slimes").unwrap());
+    assert_eq!((regions[0].min_steps, regions[0].max_steps), (8, 20));
+}
+
+#[test]
+// Steps should only count in a region, once per tile, with an encounter due when they run out.
+fn test_counting_steps() {
+    let mut encounters = Encounters::new(vec![region("slimes", 0, 0, 10, 3)], Random::with_seed(1));
+    // The first sighting of the player isn't a step.
+    assert_eq!(walk(&mut encounters, 0, 0), None);
+    assert_eq!(walk(&mut encounters, 0, 0), None);
+    let step = |due| {
+        Some(EncounterStep {
+            region: 0,
+            due: due,
+        })
+    };
+    assert_eq!(walk(&mut encounters, 1, 0), step(false));
+    assert_eq!(walk(&mut encounters, 2, 0), step(false));
+    assert_eq!(walk(&mut encounters, 3, 0), step(true));
+    // Until the count starts again, every step is due.
+    assert_eq!(walk(&mut encounters, 4, 0), step(true));
+    encounters.reset();
+    assert_eq!(walk(&mut encounters, 5, 0), step(false));
+
+    // Outside the region, nothing counts.
+    assert_eq!(walk(&mut encounters, 12, 0), None);
+    assert_eq!(walk(&mut encounters, 13, 0), None);
+
+    // Moving the player without walking isn't a step.
+    encounters.forget_position();
+    assert_eq!(walk(&mut encounters, 5, 5), None);
+    assert_eq!(walk(&mut encounters, 5, 6), step(false));
+}
+
+#[test]
+// Where regions overlap, the last one wins, and entering another region starts the count again.
+fn test_overlapping_regions() {
+    let regions = vec![region("slimes", 0, 0, 10, 2), region("bats", 5, 0, 2, 5)];
+    let mut encounters = Encounters::new(regions, Random::with_seed(1));
+    assert_eq!(encounters.region_at(centre(1, 1)), Some(0));
+    assert_eq!(encounters.region_at(centre(5, 1)), Some(1));
+    assert_eq!(encounters.region_at(centre(20, 1)), None);
+
+    walk(&mut encounters, 3, 1);
+    assert_eq!(walk(&mut encounters, 4, 1).map(|s| s.due), Some(false));
+    assert_eq!(
+        walk(&mut encounters, 5, 1),
+        Some(EncounterStep {
+            region: 1,
+            due: false,
+        })
+    );
+    assert_eq!(encounters.choose_troop(1), Some(String::from("bats")));
+}
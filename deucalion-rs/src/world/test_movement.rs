@@ -0,0 +1,56 @@
+use world::movement::*;
+use geom::WorldVector;
+use std::collections::HashMap;
+use tiled::PropertyValue;
+
+fn grid_settings() -> MovementSettings {
+    MovementSettings {
+        mode: MovementMode::Grid,
+        diagonal: false,
+    }
+}
+
+#[test]
+// Map properties override the game's settings, and invalid ones are ignored.
+fn test_map_properties_override() {
+    let mut properties = HashMap::new();
+    properties.insert(
+        String::from(MOVEMENT_MODE_PROPERTY),
+        PropertyValue::StringValue(String::from("free")),
+    );
+    properties.insert(String::from(DIAGONAL_MOVEMENT_PROPERTY), PropertyValue::IntValue(1));
+    let settings = grid_settings().with_properties(&properties);
+    assert_eq!(settings.mode, MovementMode::Free);
+    assert!(!settings.diagonal);
+
+    let settings = grid_settings().with_properties(&HashMap::new());
+    assert_eq!(settings, grid_settings());
+}
+
+#[test]
+// Without diagonal movement, input snaps to the axis it's mostly along, keeping its strength.
+fn test_restrict_input() {
+    let input = WorldVector::new(0.3, -0.4);
+    let restricted = grid_settings().restrict_input(input);
+    assert_eq!(restricted.x, 0.0);
+    assert!((restricted.y + 0.5).abs() < 1e-6);
+    let diagonal = MovementSettings {
+        mode: MovementMode::Free,
+        diagonal: true,
+    };
+    assert_eq!(diagonal.restrict_input(input), input);
+    // Input is never stronger than one, so keys pressed together aren't faster than one.
+    let restricted = diagonal.restrict_input(WorldVector::new(1.0, 1.0));
+    assert!((restricted.length() - 1.0).abs() < 1e-6);
+    assert_eq!(
+        grid_settings().restrict_input(WorldVector::new(0.0, 0.0)),
+        WorldVector::new(0.0, 0.0)
+    );
+}
+
+#[test]
+fn test_movement_mode_names() {
+    assert_eq!(MovementMode::from_name("free"), Some(MovementMode::Free));
+    assert_eq!(MovementMode::from_name(MovementMode::Grid.name()), Some(MovementMode::Grid));
+    assert_eq!(MovementMode::from_name("teleport"), None);
+}
@@ -0,0 +1,161 @@
+use world::*;
+use world::character::{Behavior, RouteStep};
+use world::npc::Npc;
+use world::test_support::*;
+use utility::eight_direction::EightDirection;
+use utility::random::Random;
+
+// An open 20x20 grid map with the player in the corner and one NPC.
+fn world_with(npc: Npc) -> World {
+    let mut world = open_world(20, 20, vec![npc]);
+    world.random = Random::with_seed(1);
+    world
+}
+
+// Run the world for a number of seconds, a tenth of a second at a time.
+fn run(world: &mut World, seconds: u32) {
+    for _ in 0..seconds * 10 {
+        world.update(still(), 0.1);
+    }
+}
+
+#[test]
+// A patrol route should be walked step by step, and start again at the end.
+fn test_patrol_repeats() {
+    let route = vec![
+        RouteStep::Move(EightDirection::Right),
+        RouteStep::Wait(1.0),
+        RouteStep::Move(EightDirection::Left),
+    ];
+    let mut world = world_with(Npc::new(
+        "guard",
+        actor_at(5, 5),
+        Behavior::Patrol {
+            route: route,
+            repeat: true,
+        },
+    ));
+    // Each step is a tile, which takes a second to walk; the NPC notices it's arrived on the
+    //  frame after.
+    world.update(still(), 1.0);
+    assert_eq!(world.npcs[0].actor.position, tile(6, 5));
+    world.update(still(), 0.1);
+    assert_eq!(world.npcs[0].route_index(), 1);
+    world.update(still(), 0.1);
+    world.update(still(), 1.0);
+    assert_eq!(world.npcs[0].route_index(), 2);
+    world.update(still(), 1.0);
+    assert_eq!(world.npcs[0].actor.position, tile(5, 5));
+    world.update(still(), 0.1);
+    assert_eq!(world.npcs[0].route_index(), 0);
+}
+
+#[test]
+// A paused NPC should stay put, and carry on its route from the same step when resumed.
+fn test_pause_and_resume() {
+    let mut world = world_with(Npc::new(
+        "guard",
+        actor_at(5, 5),
+        Behavior::Patrol {
+            route: vec![
+                RouteStep::Move(EightDirection::Down),
+                RouteStep::Move(EightDirection::Down),
+            ],
+            repeat: false,
+        },
+    ));
+    world.update(still(), 1.0);
+    world.pause_routes(Some("guard")).unwrap();
+    run(&mut world, 3);
+    assert_eq!(world.npcs[0].actor.position, tile(5, 6));
+    assert!(world.npcs[0].is_paused());
+
+    world.resume_routes(None).unwrap();
+    run(&mut world, 3);
+    assert_eq!(world.npcs[0].actor.position, tile(5, 7));
+    // Unknown NPCs are an error.
+    assert!(world.pause_routes(Some("nobody")).is_err());
+}
+
+#[test]
+// Wandering NPCs shouldn't stray further than their radius.
+fn test_wander_stays_in_radius() {
+    let mut world = world_with(Npc::new(
+        "villager",
+        actor_at(10, 10),
+        Behavior::Wander {
+            radius: 2,
+            interval: 0.1,
+        },
+    ));
+    let mut moved = false;
+    for _ in 0..600 {
+        world.update(still(), 0.1);
+        let position = world.npcs[0].actor.position;
+        assert!((position.x - tile(10, 10).x).abs() <= 2.0 * TILE);
+        assert!((position.y - tile(10, 10).y).abs() <= 2.0 * TILE);
+        moved = moved || position != tile(10, 10);
+    }
+    assert!(moved);
+}
+
+#[test]
+// NPCs that face the player should only turn when the player is close.
+fn test_face_player() {
+    let mut world = world_with(Npc::new(
+        "shopkeeper",
+        actor_at(3, 0),
+        Behavior::FacePlayer { distance: 2 },
+    ));
+    world.update(still(), 0.1);
+    assert_eq!(world.npcs[0].actor.facing, EightDirection::Down);
+    world.player.position = tile(2, 0);
+    world.update(still(), 0.1);
+    assert_eq!(world.npcs[0].actor.facing, EightDirection::Left);
+}
+
+#[test]
+// Followers should walk up to their target and stop at their distance, without walking into it.
+fn test_follow_player() {
+    let mut world = world_with(Npc::new(
+        "dog",
+        actor_at(0, 6),
+        Behavior::Follow {
+            target: String::from("player"),
+            distance: 1,
+        },
+    ));
+    run(&mut world, 10);
+    assert_eq!(world.npcs[0].actor.position, tile(0, 1));
+    assert_eq!(world.npcs[0].actor.facing, EightDirection::Up);
+    // Chasing gets as close as possible, but still can't walk through the player.
+    world.npcs[0].behavior = Behavior::Follow {
+        target: String::from("player"),
+        distance: 0,
+    };
+    run(&mut world, 2);
+    assert_eq!(world.npcs[0].actor.position, tile(0, 1));
+}
+
+#[test]
+// NPCs shouldn't walk through each other, but should still be able to leave their own tiles.
+fn test_npcs_block_each_other() {
+    let mut world = open_world(
+        20,
+        20,
+        vec![
+            Npc::new("walker", actor_at(2, 2), Behavior::Stand),
+            Npc::new("guard", actor_at(3, 2), Behavior::Stand),
+        ],
+    );
+    world.move_to("walker", (4, 2)).unwrap();
+    for _ in 0..100 {
+        world.update(still(), 0.1);
+        assert!(!world.npcs[0]
+            .actor
+            .bounds()
+            .intersects(&world.npcs[1].actor.bounds()));
+    }
+    assert_eq!(world.npcs[0].actor.position, tile(4, 2));
+    assert_eq!(world.npcs[1].actor.position, tile(3, 2));
+}
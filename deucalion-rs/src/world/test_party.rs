@@ -0,0 +1,125 @@
+use world::*;
+use world::character::Behavior;
+use world::npc::Npc;
+use world::test_support::*;
+use geom::{WorldPoint, WorldVector};
+
+// An open 10x10 grid map with the player in the corner, followed by Anna then Bob.
+fn world_with_party(npcs: Vec<Npc>) -> World {
+    let mut world = open_world(10, 10, npcs);
+    world.add_follower("anna").unwrap();
+    world.add_follower("bob").unwrap();
+    world
+}
+
+fn right() -> WorldVector {
+    WorldVector::new(1.0, 0.0)
+}
+
+fn down() -> WorldVector {
+    WorldVector::new(0.0, 1.0)
+}
+
+fn follower_positions(world: &World) -> Vec<WorldPoint> {
+    world
+        .party
+        .followers()
+        .iter()
+        .map(|f| f.actor.position)
+        .collect()
+}
+
+#[test]
+// Followers should walk in a line behind the leader, each stepping along with them.
+fn test_followers_trail_behind() {
+    let mut world = world_with_party(vec![]);
+    for _ in 0..3 {
+        world.update(right(), 1.0);
+    }
+    assert_eq!(world.player.position, tile(3, 0));
+    assert_eq!(follower_positions(&world), vec![tile(2, 0), tile(1, 0)]);
+
+    // Half way through the leader's next step, the followers are half way through theirs.
+    for _ in 0..5 {
+        world.update(right(), 0.1);
+    }
+    let positions = follower_positions(&world);
+    assert!((positions[0].x - 2.5 * TILE).abs() < 0.1);
+    assert!((positions[1].x - 1.5 * TILE).abs() < 0.1);
+}
+
+#[test]
+// Followers should go round corners the way the leader did, rather than cutting across.
+fn test_followers_replay_steps() {
+    let mut world = world_with_party(vec![]);
+    world.update(right(), 1.0);
+    world.update(right(), 1.0);
+    world.update(down(), 1.0);
+    world.update(down(), 1.0);
+    assert_eq!(world.player.position, tile(2, 2));
+    assert_eq!(follower_positions(&world), vec![tile(2, 1), tile(2, 0)]);
+}
+
+#[test]
+// Gathered followers should walk onto the leader, and come out again as the leader walks on.
+fn test_gather() {
+    let mut world = world_with_party(vec![]);
+    for _ in 0..3 {
+        world.update(down(), 1.0);
+    }
+    world.party.gather();
+    for _ in 0..30 {
+        world.update(still(), 0.1);
+    }
+    assert!(world.party.is_gathered(&world.player));
+    assert_eq!(follower_positions(&world), vec![tile(0, 3), tile(0, 3)]);
+
+    world.update(right(), 1.0);
+    world.update(right(), 1.0);
+    assert_eq!(follower_positions(&world), vec![tile(1, 3), tile(0, 3)]);
+}
+
+#[test]
+// Warping the player should bring their followers along straight away.
+fn test_warp() {
+    let mut world = world_with_party(vec![]);
+    for _ in 0..3 {
+        world.update(right(), 1.0);
+    }
+    world.warp_player(tile(7, 7));
+    assert_eq!(world.player.position, tile(7, 7));
+    assert_eq!(follower_positions(&world), vec![tile(7, 7), tile(7, 7)]);
+    // The old trail is forgotten, so nobody walks back to where they were.
+    world.update(still(), 1.0);
+    assert_eq!(follower_positions(&world), vec![tile(7, 7), tile(7, 7)]);
+}
+
+#[test]
+// Hidden followers shouldn't be shown or get in anyone's way, and should reappear on the leader.
+fn test_toggle_following() {
+    let mut world = world_with_party(vec![Npc::new("guard", actor_at(5, 5), Behavior::Stand)]);
+    world.update(right(), 1.0);
+    world.update(right(), 1.0);
+    assert!(world.occupancy(Some(0)).is_blocked(1, 0));
+
+    world.party.set_following(false, &world.player);
+    assert!(world.party.followers().is_empty());
+    assert!(!world.occupancy(Some(0)).is_blocked(1, 0));
+    world.update(down(), 1.0);
+
+    world.party.set_following(true, &world.player);
+    assert_eq!(follower_positions(&world), vec![tile(2, 1), tile(2, 1)]);
+    // The player walks through their own followers.
+    assert!(!world.occupancy(None).is_blocked(2, 1));
+}
+
+#[test]
+// Characters can only be in the line once.
+fn test_add_and_remove_followers() {
+    let mut world = world_with_party(vec![]);
+    assert!(world.add_follower("anna").is_err());
+    world.remove_follower("anna").unwrap();
+    assert!(world.remove_follower("anna").is_err());
+    assert_eq!(world.party.followers().len(), 1);
+    assert_eq!(world.party.followers()[0].character, "bob");
+}
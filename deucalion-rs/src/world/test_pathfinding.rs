@@ -0,0 +1,162 @@
+use world::pathfinding::*;
+use world::*;
+use world::actor::Actor;
+use world::character::Behavior;
+use world::collision::CollisionGrid;
+use world::npc::Npc;
+use world::test_support::*;
+use geom::{WorldSize, WorldVector};
+use utility::direction::Direction;
+use utility::eight_direction::EightDirection;
+
+// Make a grid from rows of text, where '#' is a blocked tile.
+fn grid(rows: &[&str]) -> CollisionGrid {
+    let mut grid = CollisionGrid::new(
+        rows[0].len() as u32,
+        rows.len() as u32,
+        WorldSize::new(TILE, TILE),
+    );
+    for (y, row) in rows.iter().enumerate() {
+        for (x, c) in row.chars().enumerate() {
+            grid.set_blocked(x as i32, y as i32, c == '#');
+        }
+    }
+    grid
+}
+
+// Follow a path, checking every tile on the way is open, and return where it ends.
+fn walk(grid: &CollisionGrid, start: (i32, i32), path: &[Direction]) -> (i32, i32) {
+    path.iter().fold(start, |(x, y), &direction| {
+        let (dx, dy) = EightDirection::from(direction).offset();
+        assert!(!grid.is_blocked(x + dx, y + dy), "the path went through a wall");
+        (x + dx, y + dy)
+    })
+}
+
+// Follow a path for up to a number of frames of a tenth of a second each, until it's finished.
+fn follow(
+    follower: &mut PathFollower,
+    actor: &mut Actor,
+    grid: &CollisionGrid,
+    walls: &CollisionGrid,
+    frames: u32,
+) -> PathResult {
+    for _ in 0..frames {
+        match follower.update(actor, &grid_settings(), grid, walls, 0.1) {
+            PathResult::Walking => {}
+            result => return result,
+        }
+        assert!(!grid.is_area_blocked(&actor.bounds()), "the actor walked into a wall");
+    }
+    PathResult::Walking
+}
+
+#[test]
+// With nothing in the way, the path should go straight there.
+fn test_straight_path() {
+    let grid = grid(&["....", "....", "...."]);
+    assert_eq!(
+        find_path(&grid, (0, 1), (3, 1)),
+        Some(vec![Direction::Right, Direction::Right, Direction::Right])
+    );
+    assert_eq!(find_path(&grid, (2, 2), (2, 2)), Some(vec![]));
+}
+
+#[test]
+// Paths should find the shortest way around walls.
+fn test_path_around_walls() {
+    let grid = grid(&[
+        "......",
+        ".####.",
+        ".#....",
+        ".#.##.",
+        "...#..",
+    ]);
+    let path = find_path(&grid, (2, 2), (4, 4)).unwrap();
+    assert_eq!(walk(&grid, (2, 2), &path), (4, 4));
+    assert_eq!(path.len(), 6);
+
+    let path = find_path(&grid, (2, 4), (2, 0)).unwrap();
+    assert_eq!(walk(&grid, (2, 4), &path), (2, 0));
+    assert_eq!(path.len(), 8);
+}
+
+#[test]
+// There's no path to a blocked tile, somewhere walled off, or outside the map.
+fn test_no_path() {
+    let grid = grid(&["..#..", "..#..", "..#.."]);
+    assert_eq!(find_path(&grid, (0, 0), (4, 2)), None);
+    assert_eq!(find_path(&grid, (0, 0), (2, 1)), None);
+    assert_eq!(find_path(&grid, (0, 0), (-1, 0)), None);
+}
+
+#[test]
+// A follower should walk an actor to its goal, tile by tile.
+fn test_follow_path() {
+    let grid = grid(&["....", ".##.", "...."]);
+    let mut actor = actor_at(0, 2);
+    let mut follower = PathFollower::new((3, 0));
+    assert_eq!(follow(&mut follower, &mut actor, &grid, &grid, 100), PathResult::Arrived);
+    assert_eq!(actor.position, tile(3, 0));
+}
+
+#[test]
+// When someone steps into the way, the follower should find another way.
+fn test_replan_when_blocked() {
+    let walls = grid(&[".....", ".....", "....."]);
+    let mut occupied = walls.clone();
+    let mut actor = actor_at(0, 1);
+    let mut follower = PathFollower::new((4, 1));
+    // The way is straight along the middle row, until someone stands in it.
+    assert_eq!(follow(&mut follower, &mut actor, &occupied, &walls, 11), PathResult::Walking);
+    assert_eq!(actor.position, tile(1, 1));
+    occupied.set_blocked(3, 1, true);
+    assert_eq!(
+        follow(&mut follower, &mut actor, &occupied, &walls, 100),
+        PathResult::Arrived
+    );
+    assert_eq!(actor.position, tile(4, 1));
+}
+
+#[test]
+// Followers should wait a while for someone to get out of the way, but give up straight away if
+// there's no way at all.
+fn test_unreachable() {
+    let walls = grid(&["....."]);
+    let mut occupied = walls.clone();
+    occupied.set_blocked(2, 0, true);
+    let mut actor = actor_at(0, 0);
+    let mut follower = PathFollower::new((4, 0));
+    assert_eq!(follow(&mut follower, &mut actor, &occupied, &walls, 40), PathResult::Walking);
+    assert_eq!(
+        follow(&mut follower, &mut actor, &occupied, &walls, 100),
+        PathResult::Unreachable
+    );
+
+    let walls = grid(&["..#.."]);
+    let mut follower = PathFollower::new((4, 0));
+    assert_eq!(follow(&mut follower, &mut actor, &walls, &walls, 1), PathResult::Unreachable);
+}
+
+#[test]
+// Scripts should be able to walk NPCs and the player about, whatever the player is pressing.
+fn test_world_move_to() {
+    let mut world = World::new(
+        grid(&["......", "......", "......", "......"]),
+        grid_settings(),
+        actor_at(0, 0),
+        vec![Npc::new("guard", actor_at(2, 2), Behavior::Stand)],
+    );
+    world.move_to("guard", (5, 3)).unwrap();
+    world.move_to("player", (0, 3)).unwrap();
+    for _ in 0..100 {
+        world.update(WorldVector::new(1.0, 0.0), 0.1);
+    }
+    assert!(!world.npcs[0].is_moving_to());
+    assert!(!world.is_player_moving_to());
+    assert_eq!(world.npcs[0].actor.position, tile(5, 3));
+    assert_eq!(world.player.position, tile(0, 3));
+
+    assert!(world.move_to("nobody", (1, 1)).is_err());
+    assert!(world.move_to("guard", (6, 0)).is_err());
+}
@@ -0,0 +1,44 @@
+//! Helpers shared by the world's tests.
+
+use geom::{WorldPoint, WorldSize, WorldVector};
+use world::actor::Actor;
+use world::collision::CollisionGrid;
+use world::movement::{MovementMode, MovementSettings};
+use world::npc::Npc;
+use world::World;
+
+/// The width and height of a tile.
+pub const TILE: f32 = 16.0;
+
+/// Where the top left of tile (x, y) is.
+pub fn tile(x: i32, y: i32) -> WorldPoint {
+    WorldPoint::new(x as f32 * TILE, y as f32 * TILE)
+}
+
+/// An actor the size of a tile, moving one tile per second.
+pub fn actor_at(x: i32, y: i32) -> Actor {
+    Actor::new(tile(x, y), WorldSize::new(TILE, TILE), TILE)
+}
+
+/// Grid movement, without diagonals.
+pub fn grid_settings() -> MovementSettings {
+    MovementSettings {
+        mode: MovementMode::Grid,
+        diagonal: false,
+    }
+}
+
+/// An open map of the given size in tiles, with the player in the corner and the given NPCs.
+pub fn open_world(width: u32, height: u32, npcs: Vec<Npc>) -> World {
+    World::new(
+        CollisionGrid::new(width, height, WorldSize::new(TILE, TILE)),
+        grid_settings(),
+        actor_at(0, 0),
+        npcs,
+    )
+}
+
+/// No movement input.
+pub fn still() -> WorldVector {
+    WorldVector::new(0.0, 0.0)
+}